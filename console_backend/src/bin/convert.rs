@@ -0,0 +1,125 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::Context;
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use strum::VariantNames;
+
+use console_backend::{
+    cli_options::{csv_logger, log_path_template, sbp_json_option},
+    convert::{convert_file, ConvertFormat, ConvertOptions},
+    log_path::LogPathTemplate,
+    output::{CsvLogger, SbpJsonOption},
+    types::Result,
+};
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "warn");
+    }
+    env_logger::init();
+    let opts = Opts::parse();
+    let output_dir = match opts.output_dir {
+        Some(dir) => dir,
+        None => opts.input.with_extension(""),
+    };
+    let size = std::fs::metadata(&opts.input)
+        .with_context(|| format!("could not open {}", opts.input.display()))?
+        .len();
+    let pb = ProgressBar::new(size);
+    pb.enable_steady_tick(Duration::from_millis(1000));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] [{bar}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
+            .progress_chars("=> "),
+    );
+    let csv_loggers = if opts.csv_loggers.is_empty() {
+        CsvLogger::all()
+    } else {
+        opts.csv_loggers
+    };
+    let convert_opts = ConvertOptions {
+        output_dir: output_dir.clone(),
+        formats: opts.formats,
+        csv_loggers,
+        sbp_json_options: if opts.sbp_json_options.is_empty() {
+            SbpJsonOption::defaults()
+        } else {
+            opts.sbp_json_options
+        },
+        path_template: opts.path_template,
+    };
+    convert_file(&opts.input, convert_opts, {
+        let pb = pb.clone();
+        move |n| pb.inc(n)
+    })?;
+    pb.finish();
+    println!("Wrote converted files to {}", output_dir.display());
+    Ok(())
+}
+
+/// Convert an SBP log into CSV, SBP-JSON, track, RINEX, NMEA and SQLite files
+#[derive(Parser)]
+#[clap(
+    name = "swift-convert",
+    version = include_str!("../version.txt"),
+    override_usage = "\
+    swift-convert [OPTIONS] <INPUT>
+
+    Examples:
+        - Write all CSV logs into ./swift-gnss-20220101-120000/:
+            swift-convert swift-gnss-20220101-120000.sbp
+        - Write the position log, a KML track and RINEX into ./out/:
+            swift-convert swift-gnss-20220101-120000.sbp --output-dir out \\
+                --formats csv,kml,rinex --csv-loggers POSITION
+    "
+)]
+struct Opts {
+    /// The binary SBP log to convert
+    input: PathBuf,
+
+    /// Directory to write to, <INPUT> without its extension by default
+    #[clap(long, short)]
+    output_dir: Option<PathBuf>,
+
+    /// Formats to write, comma separated
+    #[clap(long, value_parser = convert_format, value_delimiter = ',', default_value = "CSV")]
+    formats: Vec<ConvertFormat>,
+
+    /// CSV logs to write, comma separated. Defaults to all logs
+    #[clap(long, value_parser = csv_logger, value_delimiter = ',')]
+    csv_loggers: Vec<CsvLogger>,
+
+    /// Layout of the SBP-JSON log, comma separated. Any of COMPACT, PAYLOAD, HOST_TIME and
+    /// PIKSI_TOOLS, defaults to PAYLOAD
+    #[clap(long, value_parser = sbp_json_option, value_delimiter = ',')]
+    sbp_json_options: Vec<SbpJsonOption>,
+
+    /// Layout of the converted files, e.g. {logdir}/{product_id}/{serial}/{conn}.sbp where
+    /// {logdir} is the output directory and {conn} the name of <INPUT>
+    #[clap(long, value_parser = log_path_template)]
+    path_template: Option<LogPathTemplate>,
+}
+
+fn convert_format(s: &str) -> std::result::Result<ConvertFormat, String> {
+    ConvertFormat::from_str(&s.to_uppercase())
+        .map_err(|_| format!("possible values: {:?}", ConvertFormat::VARIANTS))
+}
@@ -24,13 +24,17 @@ use console_backend::{
     client_sender::ChannelSender,
     connection::ConnectionManager,
     log_panel::setup_logging,
+    log_recovery::recover_logs,
+    log_schedule::start_log_schedule,
     server_recv_thread::server_recv_thread,
     shared_state::SharedState,
     utils::{refresh_connection_frontend, refresh_loggingbar},
 };
 use crossbeam::channel;
+use std::io::BufRead;
 
 fn main() -> Result<()> {
+    let started = Local::now();
     let opt = CliOptions::from_filtered_cli();
     if opt.serial.serial.is_none() && opt.tcp.tcp.is_none() && opt.file.file.is_none() {
         eprintln!(
@@ -51,9 +55,12 @@ Usage:
     let conn_manager = ConnectionManager::new(client_send.clone(), shared_state.clone());
     handle_cli(opt, &conn_manager, shared_state.clone());
     setup_logging(client_send.clone(), shared_state.clone());
+    recover_logs(&shared_state, started);
     refresh_connection_frontend(&client_send, &shared_state);
     refresh_loggingbar(&client_send, &shared_state);
-    server_recv_thread(conn_manager, client_send, server_recv, shared_state);
+    start_log_schedule(&client_send, &shared_state);
+    server_recv_thread(conn_manager, client_send, server_recv, shared_state.clone());
+    annotation_thread(shared_state);
 
     let mut msg_count: usize = 0;
     while client_recv.recv().is_ok() {
@@ -65,3 +72,19 @@ Usage:
     }
     Ok(())
 }
+
+/// Each line typed on stdin is written into the active logs as an annotation,
+/// an empty line adds a plain marker.
+fn annotation_thread(shared_state: SharedState) {
+    eprintln!("Type a note and press Enter to annotate the logs.");
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let text = match line.trim() {
+                "" => String::from("marker"),
+                text => text.to_string(),
+            };
+            shared_state.add_session_annotation(text);
+        }
+    });
+}
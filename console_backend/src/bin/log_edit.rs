@@ -0,0 +1,213 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+
+use console_backend::{
+    cli_options::{byte_size, sbp_json_option},
+    log_edit::{format_for_path, merge, split, trim, SplitBy, TimeBound},
+    output::{SbpJsonEncoder, SbpJsonOption, SbpLogging},
+    types::Result,
+};
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let json = if opts.sbp_json_options.is_empty() {
+        SbpJsonEncoder::default()
+    } else {
+        SbpJsonEncoder::new(&opts.sbp_json_options)
+    };
+    match opts.command {
+        Command::Trim {
+            input,
+            output,
+            start,
+            end,
+            format,
+        } => {
+            let format = format.unwrap_or_else(|| format_for_path(&output));
+            let frames = trim(open(&input)?, &output, format, &json, start, end)?;
+            println!("Wrote {frames} frames to {}", output.display());
+        }
+        Command::Split {
+            input,
+            output_dir,
+            size,
+            duration,
+            by_sender,
+            by_msg_type,
+            format,
+        } => {
+            let by = match (size, duration, by_sender, by_msg_type) {
+                (Some(size), None, false, false) => SplitBy::Size(size),
+                (None, Some(duration), false, false) => SplitBy::Duration(duration),
+                (None, None, true, false) => SplitBy::Sender,
+                (None, None, false, true) => SplitBy::MsgType,
+                _ => anyhow::bail!(
+                    "exactly one of --size, --duration, --by-sender or --by-msg-type is required"
+                ),
+            };
+            let output_dir = output_dir.unwrap_or_else(|| input.with_extension(""));
+            let stem = input
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| String::from("split"));
+            let paths = split(
+                open(&input)?,
+                &output_dir,
+                &stem,
+                by,
+                format.unwrap_or(SbpLogging::SBP),
+                &json,
+            )?;
+            println!("Wrote {} files to {}", paths.len(), output_dir.display());
+        }
+        Command::Merge {
+            inputs,
+            output,
+            format,
+        } => {
+            let format = format.unwrap_or_else(|| format_for_path(&output));
+            let readers = inputs
+                .iter()
+                .map(|input| open(input))
+                .collect::<Result<Vec<_>>>()?;
+            let frames = merge(readers, &output, format, &json)?;
+            println!("Wrote {frames} frames to {}", output.display());
+        }
+    }
+    Ok(())
+}
+
+fn open(path: &Path) -> Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+    Ok(BufReader::new(file))
+}
+
+/// Trim, split and merge binary SBP logs
+#[derive(Parser)]
+#[clap(
+    name = "swift-log-edit",
+    version = include_str!("../version.txt"),
+    override_usage = "\
+    swift-log-edit <COMMAND> [OPTIONS]
+
+    Examples:
+        - Keep ten minutes of GPS week 2200:
+            swift-log-edit trim in.sbp out.sbp --start 2200:3600 --end 2200:4200
+        - Keep a wall-clock range and write SBP-JSON:
+            swift-log-edit trim in.sbp out.sbp.json \\
+                --start 2022-01-01T12:00:00Z --end 2022-01-01T12:10:00Z
+        - Split into 100MB files, then into one file per sender:
+            swift-log-edit split in.sbp --size 100M
+            swift-log-edit split in.sbp --by-sender
+        - Merge a rover and a base station log:
+            swift-log-edit merge rover.sbp base.sbp --output merged.sbp
+    "
+)]
+struct Opts {
+    #[clap(subcommand)]
+    command: Command,
+
+    /// Layout of SBP-JSON output, comma separated. Any of COMPACT, PAYLOAD, HOST_TIME and
+    /// PIKSI_TOOLS, defaults to PAYLOAD
+    #[clap(long, global = true, value_parser = sbp_json_option, value_delimiter = ',')]
+    sbp_json_options: Vec<SbpJsonOption>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Keep the frames between two GPS (WN:TOW) or UTC (RFC 3339) times
+    Trim {
+        /// The binary SBP log to trim
+        input: PathBuf,
+
+        /// Where to write, as SBP-JSON if it ends in .json
+        output: PathBuf,
+
+        /// Drop frames before this time
+        #[clap(long)]
+        start: Option<TimeBound>,
+
+        /// Drop frames from this time on
+        #[clap(long)]
+        end: Option<TimeBound>,
+
+        /// Output format, inferred from <OUTPUT> by default
+        #[clap(long, value_parser = sbp_format)]
+        format: Option<SbpLogging>,
+    },
+
+    /// Split a log by size, GPS time, sender ID or message type
+    Split {
+        /// The binary SBP log to split
+        input: PathBuf,
+
+        /// Directory to write to, <INPUT> without its extension by default
+        #[clap(long, short)]
+        output_dir: Option<PathBuf>,
+
+        /// Maximum size of each file, e.g. 500K, 100M or 1G
+        #[clap(long, value_parser = byte_size)]
+        size: Option<u64>,
+
+        /// Seconds of GPS time in each file
+        #[clap(long)]
+        duration: Option<f64>,
+
+        /// Write one file per sender ID
+        #[clap(long)]
+        by_sender: bool,
+
+        /// Write one file per message type
+        #[clap(long)]
+        by_msg_type: bool,
+
+        /// Output format, SBP by default
+        #[clap(long, value_parser = sbp_format)]
+        format: Option<SbpLogging>,
+    },
+
+    /// Merge logs into one ordered by rover GPS time
+    Merge {
+        /// The binary SBP logs to merge
+        #[clap(required = true, num_args = 2..)]
+        inputs: Vec<PathBuf>,
+
+        /// Where to write, as SBP-JSON if it ends in .json
+        #[clap(long, short)]
+        output: PathBuf,
+
+        /// Output format, inferred from <OUTPUT> by default
+        #[clap(long, value_parser = sbp_format)]
+        format: Option<SbpLogging>,
+    },
+}
+
+fn sbp_format(s: &str) -> std::result::Result<SbpLogging, String> {
+    let s = s.to_uppercase().replace('-', "_");
+    SbpLogging::from_str(&s).map_err(|_| String::from("possible values: SBP, SBP_JSON"))
+}
@@ -0,0 +1,64 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use anyhow::Context;
+use clap::Parser;
+
+use console_backend::{rinex::convert_sbp, types::Result};
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let obs = opts.obs.unwrap_or_else(|| opts.input.with_extension("obs"));
+    let nav = opts.nav.unwrap_or_else(|| opts.input.with_extension("nav"));
+    let input = File::open(&opts.input)
+        .with_context(|| format!("could not open {}", opts.input.display()))?;
+    let (epochs, ephemerides) = convert_sbp(BufReader::new(input), &obs, &nav)?;
+    println!("Wrote {epochs} epochs to {}", obs.display());
+    println!("Wrote {ephemerides} ephemerides to {}", nav.display());
+    Ok(())
+}
+
+/// Convert the observations and ephemerides in an SBP log to RINEX 3.04
+#[derive(Parser)]
+#[clap(
+    name = "swift-rinex",
+    version = include_str!("../version.txt"),
+    override_usage = "\
+    swift-rinex [OPTIONS] <INPUT>
+
+    Examples:
+        - Convert a log to swift-gnss-20220101-120000.obs and .nav:
+            swift-rinex swift-gnss-20220101-120000.sbp
+        - Choose the output files:
+            swift-rinex swift-gnss-20220101-120000.sbp --obs base.22o --nav base.22p
+    "
+)]
+struct Opts {
+    /// The binary SBP log to read
+    input: PathBuf,
+
+    /// The RINEX observation file to write, <INPUT> with an .obs extension by default
+    #[clap(long)]
+    obs: Option<PathBuf>,
+
+    /// The RINEX navigation file to write, <INPUT> with a .nav extension by default
+    #[clap(long)]
+    nav: Option<PathBuf>,
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{fs::File, io, path::PathBuf};
+
+use anyhow::Context;
+use clap::Parser;
+
+use console_backend::{sqlite_log::export_query, types::Result};
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let query = match (opts.query, opts.table) {
+        (Some(query), None) => query,
+        (None, Some(table)) => {
+            let mut query = format!("SELECT * FROM {table}");
+            if let Some(filter) = opts.filter {
+                query.push_str(&format!(" WHERE {filter}"));
+            }
+            query + " ORDER BY gps_time"
+        }
+        _ => anyhow::bail!("exactly one of --query or --table is required"),
+    };
+    let rows = match opts.output {
+        Some(ref output) => {
+            let file = File::create(output)
+                .with_context(|| format!("could not create {}", output.display()))?;
+            export_query(&opts.database, &query, file)?
+        }
+        None => export_query(&opts.database, &query, io::stdout().lock())?,
+    };
+    if let Some(output) = opts.output {
+        println!("Wrote {rows} rows to {}", output.display());
+    }
+    Ok(())
+}
+
+/// Export query results from a --sqlite-log database as CSV
+#[derive(Parser)]
+#[clap(
+    name = "swift-sqlite-export",
+    version = include_str!("../version.txt"),
+    override_usage = "\
+    swift-sqlite-export <DATABASE> [OPTIONS]
+
+    Tables: position, velocity, baseline, dops, tracking_cn0 and status keyed by
+    gps_time, plus the modes and signals they reference.
+
+    Examples:
+        - Every Float RTK epoch with more than 0.5m horizontal accuracy:
+            swift-sqlite-export soak.sqlite --output float.csv --query \\
+                \"SELECT p.* FROM position p JOIN modes m ON p.mode = m.id \\
+                 WHERE p.h_accuracy > 0.5 AND m.name = 'Float RTK'\"
+        - All DOPs above 2.0 to stdout:
+            swift-sqlite-export soak.sqlite --table dops --filter \"pdop > 2.0\"
+    "
+)]
+struct Opts {
+    /// The database written with --sqlite-log
+    database: PathBuf,
+
+    /// SQL query to export
+    #[clap(long, short)]
+    query: Option<String>,
+
+    /// Export a whole table ordered by GPS time, instead of --query
+    #[clap(long, short)]
+    table: Option<String>,
+
+    /// Condition on the rows of --table, e.g. "h_accuracy > 0.5"
+    #[clap(long, requires = "table")]
+    filter: Option<String>,
+
+    /// CSV file to write, stdout by default
+    #[clap(long, short)]
+    output: Option<PathBuf>,
+}
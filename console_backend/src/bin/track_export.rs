@@ -0,0 +1,79 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{fs::File, io::BufReader, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Context};
+use clap::Parser;
+use strum::VariantNames;
+
+use console_backend::{
+    track_export::{export_track, TrackFormat},
+    types::Result,
+};
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let format = match opts.format {
+        Some(format) => format,
+        None => TrackFormat::from_path(&opts.output).ok_or_else(|| {
+            anyhow!(
+                "unable to infer a track format from {}, use --format",
+                opts.output.display()
+            )
+        })?,
+    };
+    let input = File::open(&opts.input)
+        .with_context(|| format!("could not open {}", opts.input.display()))?;
+    let points = export_track(BufReader::new(input), &opts.output, format)?;
+    println!("Wrote {points} points to {}", opts.output.display());
+    Ok(())
+}
+
+/// Export the position solutions in an SBP log as a GPX, KML or GeoJSON track
+#[derive(Parser)]
+#[clap(
+    name = "swift-track-export",
+    version = include_str!("../version.txt"),
+    override_usage = "\
+    swift-track-export [OPTIONS] <INPUT> <OUTPUT>
+
+    Examples:
+        - Export a drive test to Google Earth:
+            swift-track-export swift-gnss-20220101-120000.sbp drive.kml
+        - Export a drive test to QGIS:
+            swift-track-export swift-gnss-20220101-120000.sbp drive.geojson
+    "
+)]
+struct Opts {
+    /// The binary SBP log to read
+    input: PathBuf,
+
+    /// The track file to write
+    output: PathBuf,
+
+    /// The track format, inferred from the extension of <OUTPUT> by default
+    #[clap(long, value_parser = track_format)]
+    format: Option<TrackFormat>,
+}
+
+fn track_format(s: &str) -> std::result::Result<TrackFormat, String> {
+    TrackFormat::from_str(&s.to_uppercase())
+        .map_err(|_| format!("possible values: {:?}", TrackFormat::VARIANTS))
+}
@@ -17,16 +17,25 @@
 // IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use std::{num::ParseIntError, path::PathBuf, str::FromStr};
+use std::{convert::TryInto, num::ParseIntError, path::PathBuf, str::FromStr, time::Duration};
 
+use chrono::NaiveTime;
 use clap::{ArgAction, Args, Parser};
 use log::{debug, error};
 
 use crate::common_constants::LogLevel;
 use crate::constants::AVAILABLE_BAUDRATES;
-use crate::output::CsvLogging;
+use crate::correction_source::{Corrections, InputType, LocalSource};
+use crate::credentials::{CredentialKey, CredentialStore};
+use crate::log_path::LogPathTemplate;
+use crate::log_schedule::{LogSchedule, Recurrence};
+use crate::nmea::{NmeaSentence, NmeaTarget};
+use crate::ntrip_profile::{self, NtripProfile};
+use crate::output::{CsvLogger, CsvLogging, SbpJsonOption};
 use crate::shared_state::SharedState;
+use crate::track_export::TrackFormat;
 use crate::types::{FlowControl, RealtimeDelay};
+use crate::utils::OkOrLog;
 use crate::{
     common_constants::{SbpLogging, Tabs},
     connection::ConnectionManager,
@@ -60,6 +69,11 @@ pub struct CliOptions {
     #[clap(long, value_parser = sbp_logger)]
     pub sbp_log: Option<SbpLogging>,
 
+    /// Layout of SBP-JSON logs, comma separated. Any of COMPACT, PAYLOAD, HOST_TIME and
+    /// PIKSI_TOOLS, defaults to PAYLOAD.
+    #[clap(long, value_parser = sbp_json_option, value_delimiter = ',')]
+    pub sbp_json_options: Vec<SbpJsonOption>,
+
     /// Set SBP log filename.
     #[clap(long)]
     pub sbp_log_filename: Option<PathBuf>,
@@ -81,6 +95,12 @@ pub struct CliOptions {
     #[clap(long)]
     pub log_dirname: Option<String>,
 
+    /// Layout of each session's logs, e.g. {logdir}/{product_id}/{serial}/{date}/{time}_{conn}.sbp.
+    /// Also accepts {firmware}. CSV, track, RINEX and metadata files are written next to the
+    /// SBP log.
+    #[clap(long, value_parser = log_path_template)]
+    pub log_path_template: Option<LogPathTemplate>,
+
     /// Create a log file containing console debug information.
     #[clap(long)]
     pub log_console: bool,
@@ -97,6 +117,74 @@ pub struct CliOptions {
     #[clap(long)]
     pub csv_log: bool,
 
+    /// Restrict CSV logging to these loggers, comma separated. Defaults to all loggers.
+    #[clap(long, value_parser = csv_logger, value_delimiter = ',')]
+    pub csv_loggers: Vec<CsvLogger>,
+
+    /// Export the position solution as tracks in these formats while CSV logging, comma separated.
+    #[clap(long, value_parser = track_format, value_delimiter = ',')]
+    pub track_formats: Vec<TrackFormat>,
+
+    /// Write RINEX observation and navigation files while CSV logging.
+    #[clap(long)]
+    pub rinex_log: bool,
+
+    /// Output NMEA generated from the solution, may be repeated. One of file:<path>,
+    /// tcp:[<address>:]<port>, udp:<host>:<port> or serial:<device>[@<baudrate>].
+    #[clap(long, value_parser = nmea_target)]
+    pub nmea_output: Vec<NmeaTarget>,
+
+    /// Write the position, velocity, baseline, DOPs, tracking C/N0 and status to this
+    /// SQLite database, query it with swift-sqlite-export.
+    #[clap(long)]
+    pub sqlite_log: Option<PathBuf>,
+
+    /// Only keep this much history in the --sqlite-log database, e.g. 12h or 7d.
+    #[clap(long, value_parser = duration, requires = "sqlite_log")]
+    pub sqlite_retention: Option<Duration>,
+
+    /// NMEA sentences to output, comma separated. Defaults to all sentences.
+    #[clap(long, value_parser = nmea_sentence, value_delimiter = ',')]
+    pub nmea_sentences: Vec<NmeaSentence>,
+
+    /// Seconds between flushing open logs to disk, 0 to only flush when a log is closed.
+    /// Defaults to 1 second.
+    #[clap(long, value_parser = sync_interval)]
+    pub log_sync_interval: Option<f64>,
+
+    /// Save the received corrections and their host timestamps next to each SBP log,
+    /// replayable with --corrections file:<path>.
+    #[clap(long)]
+    pub record_corrections: bool,
+
+    /// Start SBP logging at this UTC time, HH:MM[:SS].
+    #[clap(long, value_parser = schedule_time)]
+    pub log_start: Option<NaiveTime>,
+
+    /// Stop SBP logging at this UTC time, HH:MM[:SS].
+    #[clap(long, value_parser = schedule_time)]
+    pub log_stop: Option<NaiveTime>,
+
+    /// Repeat the --log-start/--log-stop window, once or daily.
+    #[clap(long, value_parser = recurrence)]
+    pub log_recurrence: Option<Recurrence>,
+
+    /// Stop each scheduled SBP log after this long, e.g. 45m or 4h.
+    #[clap(long, value_parser = duration)]
+    pub log_max_duration: Option<Duration>,
+
+    /// Stop each scheduled SBP log once it reaches this size, e.g. 500M or 2G.
+    #[clap(long, value_parser = byte_size)]
+    pub log_max_size: Option<u64>,
+
+    /// YAML file with the logging schedule, options given on the command line take precedence.
+    #[clap(long)]
+    pub log_schedule: Option<PathBuf>,
+
+    /// Add an annotation to the metadata sidecar of each log, may be repeated.
+    #[clap(long)]
+    pub log_annotation: Vec<String>,
+
     /// Show CSV logging button.
     #[clap(long)]
     pub show_csv_log: bool,
@@ -117,6 +205,65 @@ pub struct CliOptions {
     #[clap(long)]
     pub enable_ntrip: bool,
 
+    /// Start the NTRIP client with this caster url and mountpoint whenever the device
+    /// connects, without using the NTRIP tab.
+    #[clap(long)]
+    pub ntrip_url: Option<String>,
+
+    /// NTRIP caster username.
+    #[clap(long)]
+    pub ntrip_user: Option<String>,
+
+    /// File holding the NTRIP caster password.
+    #[clap(long)]
+    pub ntrip_password_file: Option<PathBuf>,
+
+    /// Name of a saved credential used for the NTRIP caster username and password.
+    #[clap(long)]
+    pub ntrip_credential: Option<String>,
+
+    /// Seconds between GGA sentences sent to the NTRIP caster, 0 to only send one with
+    /// the request. Defaults to 10.
+    #[clap(long)]
+    pub ntrip_gga_period: Option<u64>,
+
+    /// Write NTRIP corrections to the device as rtcm or sbp. Defaults to rtcm.
+    #[clap(long, value_parser = ntrip_output)]
+    pub ntrip_output: Option<String>,
+
+    /// Static position reported to the NTRIP caster, <lat>,<lon>,<alt> in degrees and
+    /// meters. Defaults to the device's position.
+    #[clap(long, value_parser = ntrip_position, allow_hyphen_values = true)]
+    pub ntrip_position: Option<[f64; 3]>,
+
+    /// YAML file with the NTRIP client settings, options given on the command line take
+    /// precedence.
+    #[clap(long)]
+    pub ntrip_profile: Option<PathBuf>,
+
+    /// Unlock the saved credentials with the contents of this file. Otherwise the
+    /// passphrase in SWIFTNAV_CREDENTIALS_PASSPHRASE is used if set.
+    #[clap(long)]
+    pub credentials_key_file: Option<PathBuf>,
+
+    /// Write corrections to the device from file:<path>, tcp:<host>:<port>, udp:<port> or
+    /// serial:<device>[@<baudrate>] instead of an NTRIP caster. Files are replayed at the
+    /// pace they were recorded.
+    #[clap(
+        long,
+        value_parser = corrections_source,
+        conflicts_with_all = ["ntrip_url", "ntrip_profile"]
+    )]
+    pub corrections: Option<LocalSource>,
+
+    /// Format of the corrections source, rtcm or sbp.
+    #[clap(long, default_value = "rtcm", value_parser = corrections_input)]
+    pub corrections_input: InputType,
+
+    /// Write the corrections source to the device as rtcm or sbp.
+    #[clap(long, default_value = "rtcm", value_parser = ntrip_output)]
+    pub corrections_output: String,
+
     /// Path to a yaml file containing known piksi settings.
     #[clap(long)]
     pub settings_yaml: Option<PathBuf>,
@@ -282,6 +429,110 @@ pub fn sbp_logger(s: &str) -> Result<SbpLogging, String> {
     SbpLogging::from_str(s).map_err(|_| format!("possible values: {:?}", SbpLogging::VARIANTS))
 }
 
+pub fn csv_logger(s: &str) -> Result<CsvLogger, String> {
+    CsvLogger::from_str(s).map_err(|_| format!("possible values: {:?}", CsvLogger::VARIANTS))
+}
+
+pub fn sbp_json_option(s: &str) -> Result<SbpJsonOption, String> {
+    SbpJsonOption::from_str(&s.to_uppercase())
+        .map_err(|_| format!("possible values: {:?}", SbpJsonOption::VARIANTS))
+}
+
+pub fn track_format(s: &str) -> Result<TrackFormat, String> {
+    TrackFormat::from_str(s).map_err(|_| format!("possible values: {:?}", TrackFormat::VARIANTS))
+}
+
+pub fn nmea_target(s: &str) -> Result<NmeaTarget, String> {
+    NmeaTarget::from_str(s).map_err(|e| e.to_string())
+}
+
+pub fn ntrip_output(s: &str) -> Result<String, String> {
+    ntrip_profile::output_type(s)
+        .map(|_| s.to_lowercase())
+        .map_err(|e| e.to_string())
+}
+
+/// A latitude, longitude and altitude, `<lat>,<lon>,<alt>`.
+pub fn ntrip_position(s: &str) -> Result<[f64; 3], String> {
+    let values: Vec<f64> = s
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid position {s}, {e}"))?;
+    values
+        .try_into()
+        .map_err(|_| format!("expected <lat>,<lon>,<alt>, got {s}"))
+}
+
+pub fn corrections_source(s: &str) -> Result<LocalSource, String> {
+    LocalSource::from_str(s).map_err(|e| e.to_string())
+}
+
+pub fn corrections_input(s: &str) -> Result<InputType, String> {
+    InputType::from_str(s).map_err(|_| format!("possible values: {:?}", InputType::VARIANTS))
+}
+
+pub fn log_path_template(s: &str) -> Result<LogPathTemplate, String> {
+    LogPathTemplate::from_str(s).map_err(|e| e.to_string())
+}
+
+pub fn nmea_sentence(s: &str) -> Result<NmeaSentence, String> {
+    NmeaSentence::from_str(&s.to_uppercase())
+        .map_err(|_| format!("possible values: {:?}", NmeaSentence::VARIANTS))
+}
+
+pub fn sync_interval(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs >= 0.0 && secs.is_finite() => Ok(secs),
+        _ => Err(String::from("expected a non-negative number of seconds")),
+    }
+}
+
+/// A size in bytes with an optional K, M or G suffix, e.g. 500K, 100M or 1G.
+pub fn byte_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (num, scale) = match s.char_indices().last() {
+        Some((idx, 'K' | 'k')) => (&s[..idx], 1 << 10),
+        Some((idx, 'M' | 'm')) => (&s[..idx], 1 << 20),
+        Some((idx, 'G' | 'g')) => (&s[..idx], 1 << 30),
+        _ => (s, 1),
+    };
+    num.parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .and_then(|n| n.checked_mul(scale))
+        .ok_or_else(|| format!("expected a size such as 500K, 100M or 1G, got {s}"))
+}
+
+/// A duration in seconds with an optional s, m, h or d suffix, e.g. 90, 45m, 4h or 7d.
+pub fn duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (num, scale) = match s.char_indices().last() {
+        Some((idx, 's')) => (&s[..idx], 1.0),
+        Some((idx, 'm')) => (&s[..idx], 60.0),
+        Some((idx, 'h')) => (&s[..idx], 3600.0),
+        Some((idx, 'd')) => (&s[..idx], 86400.0),
+        _ => (s, 1.0),
+    };
+    num.parse::<f64>()
+        .ok()
+        .filter(|n| *n > 0.0 && n.is_finite())
+        .and_then(|n| Duration::try_from_secs_f64(n * scale).ok())
+        .ok_or_else(|| format!("expected a duration such as 90, 45m, 4h or 7d, got {s}"))
+}
+
+/// A UTC time of day, HH:MM or HH:MM:SS.
+pub fn schedule_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .map_err(|_| format!("expected a UTC time such as 02:00 or 02:00:30, got {s}"))
+}
+
+pub fn recurrence(s: &str) -> Result<Recurrence, String> {
+    Recurrence::from_str(&s.to_lowercase())
+        .map_err(|_| format!("possible values: {:?}", Recurrence::VARIANTS))
+}
+
 pub fn tabs(s: &str) -> Result<Tabs, String> {
     Tabs::from_str(s).map_err(|_| format!("possible values: {:?}", Tabs::VARIANTS))
 }
@@ -297,6 +548,41 @@ pub fn log_level(s: &str) -> Result<LogLevel, String> {
 /// - `conn_manager`: The Server state to start a specific connection.
 /// - `shared_state`: The shared state for validating another connection is not already running.
 pub fn handle_cli(opt: CliOptions, conn_manager: &ConnectionManager, shared_state: SharedState) {
+    if let Some(key) = CredentialKey::from_env_or(opt.credentials_key_file) {
+        if let Some(credentials) = CredentialStore::open(&key).ok_or_log(|e| error!("{e}")) {
+            shared_state.set_credentials(credentials);
+        }
+    }
+    // before connecting, so the client starts with the first connection
+    let ntrip_profile = opt
+        .ntrip_profile
+        .and_then(|path| NtripProfile::from_file(&path).ok_or_log(|e| error!("{e}")))
+        .unwrap_or_default();
+    let ntrip = NtripProfile {
+        url: opt.ntrip_url,
+        user: opt.ntrip_user,
+        password_file: opt.ntrip_password_file,
+        credential: opt.ntrip_credential,
+        gga_period: opt.ntrip_gga_period,
+        output: opt.ntrip_output,
+        position: opt.ntrip_position,
+    }
+    .or(ntrip_profile);
+    let corrections = if let Some(source) = opt.corrections {
+        let input = opt.corrections_input;
+        ntrip_profile::output_type(&opt.corrections_output)
+            .map(|output| Some(Corrections::local(source, input, output)))
+    } else {
+        ntrip
+            .options(shared_state.lock().credentials.as_ref())
+            .map(|options| options.map(Corrections::ntrip))
+    };
+    if let Some(corrections) = corrections.ok_or_log(|e| error!("{e}")).flatten() {
+        shared_state
+            .lock()
+            .ntrip_tab
+            .set_headless(Some(corrections));
+    }
     if let Some(serial) = opt.serial.serial {
         let serialport = serial.display().to_string();
         conn_manager.connect_to_serial(serialport, opt.serial.baudrate, opt.serial.flow_control);
@@ -314,6 +600,45 @@ pub fn handle_cli(opt: CliOptions, conn_manager: &ConnectionManager, shared_stat
     if let Some(folder) = opt.log_dirname {
         shared_state.set_logging_directory(PathBuf::from(folder));
     }
+    if opt.log_path_template.is_some() {
+        shared_state.set_log_path_template(opt.log_path_template);
+    }
+    let base_schedule = opt
+        .log_schedule
+        .and_then(|path| LogSchedule::from_file(&path).ok_or_log(|e| error!("{e}")))
+        .unwrap_or_default();
+    let schedule = LogSchedule {
+        start: opt.log_start.or(base_schedule.start),
+        stop: opt.log_stop.or(base_schedule.stop),
+        recurrence: opt.log_recurrence.unwrap_or(base_schedule.recurrence),
+        max_duration: opt.log_max_duration.or(base_schedule.max_duration),
+        max_size: opt.log_max_size.or(base_schedule.max_size),
+    };
+    if !schedule.is_empty() {
+        shared_state.set_log_schedule(Some(schedule));
+    }
+    shared_state.set_record_corrections(opt.record_corrections);
+    if let Some(secs) = opt.log_sync_interval {
+        shared_state.set_log_sync_interval((secs > 0.0).then(|| Duration::from_secs_f64(secs)));
+    }
+    if !opt.csv_loggers.is_empty() {
+        shared_state.set_csv_loggers(opt.csv_loggers);
+    }
+    if !opt.track_formats.is_empty() {
+        shared_state.set_track_formats(opt.track_formats);
+    }
+    shared_state.set_rinex_logging(opt.rinex_log);
+    if let Some(ref path) = opt.sqlite_log {
+        shared_state.start_sqlite_log(path, opt.sqlite_retention);
+    }
+    if !opt.nmea_output.is_empty() {
+        let sentences = if opt.nmea_sentences.is_empty() {
+            NmeaSentence::all()
+        } else {
+            opt.nmea_sentences
+        };
+        shared_state.start_nmea_output(&opt.nmea_output, sentences);
+    }
     shared_state.lock().logging_bar.csv_logging = CsvLogging::from(opt.csv_log);
     if let Some(log_level) = opt.log_level {
         shared_state.set_log_level(log_level);
@@ -325,9 +650,15 @@ pub fn handle_cli(opt: CliOptions, conn_manager: &ConnectionManager, shared_stat
     if opt.log_stdout {
         shared_state.set_log_stdout(true);
     }
+    if !opt.sbp_json_options.is_empty() {
+        shared_state.set_sbp_json_options(opt.sbp_json_options);
+    }
     if let Some(path) = opt.sbp_log_filename {
         shared_state.set_sbp_logging_filename(Some(path));
     }
+    for annotation in opt.log_annotation {
+        shared_state.add_session_annotation(annotation);
+    }
     if let Some(sbp_log) = opt.sbp_log {
         shared_state.set_sbp_logging(true);
         shared_state.set_sbp_logging_format(
@@ -336,3 +667,24 @@ pub fn handle_cli(opt: CliOptions, conn_manager: &ConnectionManager, shared_stat
     }
     log::logger().flush();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_size_test() {
+        assert_eq!(byte_size("500K"), Ok(500 << 10));
+        assert_eq!(byte_size("1G"), Ok(1 << 30));
+        assert!(byte_size("0").is_err());
+        assert!(byte_size("18446744073709551615G").is_err());
+    }
+
+    #[test]
+    fn duration_test() {
+        assert_eq!(duration("45m"), Ok(Duration::from_secs(2700)));
+        assert_eq!(duration("1.5"), Ok(Duration::from_millis(1500)));
+        assert!(duration("-1h").is_err());
+        assert!(duration("1e30h").is_err());
+    }
+}
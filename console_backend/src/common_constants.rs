@@ -85,6 +85,54 @@ pub enum CsvLogging {
     ON,
 }
 
+#[derive(Clone, Debug, Display, EnumString, EnumVariantNames, Eq, Hash, PartialEq)]
+pub enum CsvLogger {
+    #[strum(serialize = "POSITION")]
+    POSITION,
+    #[strum(serialize = "VELOCITY")]
+    VELOCITY,
+    #[strum(serialize = "BASELINE")]
+    BASELINE,
+    #[strum(serialize = "DOPS")]
+    DOPS,
+    #[strum(serialize = "TRACKING_CN0")]
+    TRACKING_CN0,
+    #[strum(serialize = "OBSERVATIONS")]
+    OBSERVATIONS,
+    #[strum(serialize = "IMU")]
+    IMU,
+    #[strum(serialize = "MAG")]
+    MAG,
+    #[strum(serialize = "INS")]
+    INS,
+    #[strum(serialize = "PROTECTION_LEVEL")]
+    PROTECTION_LEVEL,
+    #[strum(serialize = "ANNOTATIONS")]
+    ANNOTATIONS,
+}
+
+#[derive(Clone, Debug, Display, EnumString, EnumVariantNames, Eq, Hash, PartialEq)]
+pub enum TrackFormat {
+    #[strum(serialize = "GPX")]
+    GPX,
+    #[strum(serialize = "KML")]
+    KML,
+    #[strum(serialize = "GEOJSON")]
+    GEOJSON,
+}
+
+#[derive(Clone, Debug, Display, EnumString, EnumVariantNames, Eq, Hash, PartialEq)]
+pub enum SbpJsonOption {
+    #[strum(serialize = "COMPACT")]
+    COMPACT,
+    #[strum(serialize = "PAYLOAD")]
+    PAYLOAD,
+    #[strum(serialize = "HOST_TIME")]
+    HOST_TIME,
+    #[strum(serialize = "PIKSI_TOOLS")]
+    PIKSI_TOOLS,
+}
+
 #[derive(Clone, Debug, Display, EnumString, EnumVariantNames, Eq, Hash, PartialEq)]
 pub enum LogLevel {
     #[strum(serialize = "ERROR")]
@@ -183,6 +231,16 @@ pub enum Keys {
     SBP_LOGGING_FORMAT_INDEX,
     #[strum(serialize = "CSV_LOGGING")]
     CSV_LOGGING,
+    #[strum(serialize = "CSV_LOGGERS")]
+    CSV_LOGGERS,
+    #[strum(serialize = "TRACK_FORMATS")]
+    TRACK_FORMATS,
+    #[strum(serialize = "RINEX_LOGGING")]
+    RINEX_LOGGING,
+    #[strum(serialize = "LOG_SCHEDULE")]
+    LOG_SCHEDULE,
+    #[strum(serialize = "SBP_JSON_OPTIONS")]
+    SBP_JSON_OPTIONS,
     #[strum(serialize = "SBP_LOGGING_LABELS")]
     SBP_LOGGING_LABELS,
     #[strum(serialize = "LOG_LEVEL_LABELS")]
@@ -33,6 +33,7 @@ use crossbeam::channel::Sender;
 use log::{error, info};
 
 use crate::client_sender::BoxedClientSender;
+use crate::common_constants::ConnectionType;
 use crate::constants::*;
 use crate::process_messages::{process_messages, Messages};
 use crate::shared_state::{ConnectionState, SharedState};
@@ -186,6 +187,9 @@ fn conn_manager_thd(
                         },
                         &client_sender,
                     );
+                    if !conn.is_file() {
+                        shared_state.start_headless_ntrip(msg_sender.clone(), &client_sender);
+                    }
                     refresh_connection_frontend(&client_sender, &shared_state);
                     pm_thd = Some(process_messages_thd(
                         messages,
@@ -208,9 +212,13 @@ fn conn_manager_thd(
                         shared_state.reset_logging();
                     }
                     refresh_loggingbar(&client_sender, &shared_state);
+                    shared_state.stop_headless_ntrip();
                     shared_state.set_connection(ConnectionState::Disconnected, &client_sender);
                     refresh_connection_frontend(&client_sender, &shared_state);
                     join(&mut pm_thd);
+                    if let Err(e) = shared_state.end_sqlite_log() {
+                        error!("Issue closing SQLite log, {e}");
+                    }
                     info!("Disconnected successfully.");
                 }
             };
@@ -222,6 +230,9 @@ fn conn_manager_thd(
         join(&mut status_thd);
         join(&mut pm_thd);
         join(&mut reconnect_thd);
+        if let Err(e) = shared_state.end_sqlite_log() {
+            error!("Issue closing SQLite log, {e}");
+        }
     })
 }
 
@@ -307,6 +318,14 @@ impl Connection {
         }
     }
 
+    pub fn connection_type(&self) -> ConnectionType {
+        match self {
+            Connection::Tcp(_) => ConnectionType::Tcp,
+            Connection::File(_) => ConnectionType::File,
+            Connection::Serial(_) => ConnectionType::Serial,
+        }
+    }
+
     pub fn close_when_done(&self) -> bool {
         match self {
             Connection::File(conn) => conn.close_when_done(),
@@ -26,6 +26,8 @@ use crate::updater::swift_version::SwiftVersion;
 
 pub(crate) const NANOSECONDS_PER_SECOND: f64 = 1.0e+9;
 pub(crate) const SECONDS_PER_NANOSECOND: f64 = 1.0e-9;
+// GPS - UTC offset, valid since 2017-01-01.
+pub(crate) const GPS_UTC_LEAP_SECONDS: i64 = 18;
 
 pub(crate) const APPLICATION_QUALIFIER: &str = "com.swift-nav";
 pub(crate) const APPLICATION_ORGANIZATION: &str = "swift-nav";
@@ -36,14 +38,46 @@ pub(crate) const APPLICATION_NAME: &str = "swift_navigation_console";
 #[allow(dead_code)]
 pub(crate) const LOG_WRITER_BUFFER_MESSAGE_COUNT: usize = 50;
 pub(crate) const LOG_FILENAME: &str = "swift-console-%Y%m%d-%H%M%S.log";
+pub(crate) const LOG_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+pub(crate) const LOG_SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
+// Time between trimming the SQLite log to its retention period.
+pub(crate) const SQLITE_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+// The SQLite log commits after this many messages or this long, whichever comes first.
+pub(crate) const SQLITE_COMMIT_MESSAGES: usize = 1000;
+pub(crate) const SQLITE_COMMIT_INTERVAL: Duration = Duration::from_secs(5);
+// Logs touched more recently than this may still be open in another console.
+pub(crate) const LOG_RECOVERY_QUIET_PERIOD: Duration = Duration::from_secs(10);
 
 // Main Tab constants.
 pub(crate) const WRITE_TO_DEVICE_SENDER_ID: u16 = 1337;
 pub(crate) const VEL_TIME_STR_FILEPATH: &str = "velocity_log_%Y%m%d-%H%M%S.csv";
 pub(crate) const POS_LLH_TIME_STR_FILEPATH: &str = "position_log_%Y%m%d-%H%M%S.csv";
 pub(crate) const BASELINE_TIME_STR_FILEPATH: &str = "baseline_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const DOPS_TIME_STR_FILEPATH: &str = "dops_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const TRACKING_CN0_TIME_STR_FILEPATH: &str = "tracking_cn0_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const OBSERVATION_TIME_STR_FILEPATH: &str = "observation_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const IMU_TIME_STR_FILEPATH: &str = "imu_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const MAG_TIME_STR_FILEPATH: &str = "mag_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const INS_TIME_STR_FILEPATH: &str = "ins_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const PROTECTION_LEVEL_TIME_STR_FILEPATH: &str =
+    "protection_level_log_%Y%m%d-%H%M%S.csv";
+pub(crate) const ANNOTATIONS_TIME_STR_FILEPATH: &str = "annotations_log_%Y%m%d-%H%M%S.csv";
+// Annotation markers are MsgLog records from the console tagged with this prefix.
+pub(crate) const ANNOTATION_TAG: &str = "[annotation]";
+pub(crate) const ANNOTATION_SENDER_ID: u16 = WRITE_TO_DEVICE_SENDER_ID;
+// Settings snapshot frames written at the start of each SBP log.
+pub(crate) const SETTINGS_SNAPSHOT_SENDER_ID: u16 = WRITE_TO_DEVICE_SENDER_ID;
 pub(crate) const SBP_FILEPATH: &str = "swift-gnss-%Y%m%d-%H%M%S.sbp";
 pub(crate) const SBP_JSON_FILEPATH: &str = "swift-gnss-%Y%m%d-%H%M%S.sbp.json";
+pub(crate) const GPX_TIME_STR_FILEPATH: &str = "track_log_%Y%m%d-%H%M%S.gpx";
+pub(crate) const KML_TIME_STR_FILEPATH: &str = "track_log_%Y%m%d-%H%M%S.kml";
+pub(crate) const GEOJSON_TIME_STR_FILEPATH: &str = "track_log_%Y%m%d-%H%M%S.geojson";
+pub(crate) const RINEX_OBS_TIME_STR_FILEPATH: &str = "rinex_log_%Y%m%d-%H%M%S.obs";
+pub(crate) const RINEX_NAV_TIME_STR_FILEPATH: &str = "rinex_log_%Y%m%d-%H%M%S.nav";
+pub(crate) const CSV_METADATA_TIME_STR_FILEPATH: &str = "csv_log_%Y%m%d-%H%M%S.metadata.json";
+pub(crate) const SESSION_METADATA_EXTENSION: &str = ".metadata.json";
+pub(crate) const CORRECTIONS_EXTENSION: &str = ".corrections";
+pub(crate) const CORRECTIONS_TIMESTAMPS_EXTENSION: &str = ".times.csv";
 pub(crate) const DEFAULT_LOG_DIRECTORY: &str = "SwiftNav";
 pub(crate) const DEFAULT_IP_ADDRESS: &str = "192.168.0.222";
 pub(crate) const DEFAULT_PORT: u16 = 55555;
@@ -67,6 +101,7 @@ pub(crate) const AVAILABLE_FLOWS: &[&str] = &[
 ];
 pub(crate) const READER_TIMEOUT: Duration = Duration::from_secs(2);
 pub(crate) const CONNECTION_HISTORY_FILENAME: &str = "connection_history.yaml";
+pub(crate) const CREDENTIALS_FILENAME: &str = "credentials.enc";
 pub(crate) const MAX_CONNECTION_HISTORY: i32 = 15;
 
 // Tracking Signals Tab constants.
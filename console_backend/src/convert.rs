@@ -0,0 +1,418 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Offline conversion of SBP logs into the console's output formats.
+//!
+//! The log is run through the same tabs as a live connection, without any realtime
+//! delay, so the files written match those produced by logging from the GUI.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use sbp::{Sbp, SbpMessage};
+use strum_macros::{Display, EnumString, EnumVariantNames};
+
+use crate::client_sender::{ChannelSender, ClientSender};
+use crate::common_constants::SbpLogging;
+use crate::connection::Connection;
+use crate::constants::SETTINGS_SNAPSHOT_SENDER_ID;
+use crate::log_path::{LogPathContext, LogPathTemplate};
+use crate::output::{CsvLogger, CsvLogging, SbpJsonOption};
+use crate::process_messages::{process_messages, Messages};
+use crate::session_metadata::DeviceInfo;
+use crate::shared_state::SharedState;
+use crate::track_export::TrackFormat;
+use crate::types::{MsgSender, RealtimeDelay, Result};
+
+#[derive(Debug, Clone, Copy, Display, EnumString, EnumVariantNames, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ConvertFormat {
+    /// The CSV logs selected with [`ConvertOptions::csv_loggers`].
+    CSV,
+    /// The log as SBP-JSON.
+    JSON,
+    GPX,
+    KML,
+    GEOJSON,
+    /// RINEX observation and navigation files.
+    RINEX,
+    /// NMEA 0183 sentences generated from the solution.
+    NMEA,
+    /// The solution as an SQLite database.
+    SQLITE,
+}
+
+impl ConvertFormat {
+    fn track_format(&self) -> Option<TrackFormat> {
+        match self {
+            ConvertFormat::GPX => Some(TrackFormat::GPX),
+            ConvertFormat::KML => Some(TrackFormat::KML),
+            ConvertFormat::GEOJSON => Some(TrackFormat::GEOJSON),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Directory the converted files are written to.
+    pub output_dir: PathBuf,
+    pub formats: Vec<ConvertFormat>,
+    /// CSV logs to write when converting to [`ConvertFormat::CSV`].
+    pub csv_loggers: Vec<CsvLogger>,
+    /// Layout of the SBP-JSON log when converting to [`ConvertFormat::JSON`].
+    pub sbp_json_options: Vec<SbpJsonOption>,
+    /// Layout of the converted files under [`ConvertOptions::output_dir`], which fills
+    /// `{logdir}`. Device fields come from the settings snapshot at the start of the log.
+    pub path_template: Option<LogPathTemplate>,
+}
+
+/// Reports the number of bytes read through it.
+struct ProgressReader<R, F> {
+    inner: R,
+    on_progress: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        (self.on_progress)(n as u64);
+        Ok(n)
+    }
+}
+
+/// Convert an SBP log into the formats in `opts`.
+///
+/// # Parameters
+/// - `input`: The binary SBP log to convert.
+/// - `opts`: Where to write the converted files, and which formats to write.
+/// - `on_progress`: Called with the number of bytes read from `input` as it is read.
+pub fn convert_file<F>(input: &Path, opts: ConvertOptions, on_progress: F) -> Result<()>
+where
+    F: FnMut(u64) + Send + 'static,
+{
+    let mut output_dir = opts.output_dir.clone();
+    let mut json_filename = input
+        .with_extension("sbp.json")
+        .file_name()
+        .map(PathBuf::from);
+    if let Some(template) = &opts.path_template {
+        let ctx = log_path_context(input, opts.output_dir.clone())?;
+        output_dir = template.log_dir(&ctx);
+        json_filename = template
+            .sbp_path(&ctx, SbpLogging::SBP_JSON)
+            .file_name()
+            .map(PathBuf::from);
+    }
+    fs::create_dir_all(&output_dir)?;
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let shared_state = SharedState::new();
+    if opts.formats.contains(&ConvertFormat::NMEA) {
+        shared_state.start_nmea_file(&output_dir.join(format!("{stem}.nmea")));
+    }
+    if opts.formats.contains(&ConvertFormat::SQLITE) {
+        shared_state.start_sqlite_log(&output_dir.join(format!("{stem}.sqlite")), None);
+    }
+    shared_state.set_logging_directory(output_dir);
+
+    let csv_loggers = if opts.formats.contains(&ConvertFormat::CSV) {
+        opts.csv_loggers.clone()
+    } else {
+        Vec::new()
+    };
+    let track_formats: Vec<TrackFormat> = opts
+        .formats
+        .iter()
+        .filter_map(ConvertFormat::track_format)
+        .collect();
+    let rinex = opts.formats.contains(&ConvertFormat::RINEX);
+    let csv_logging = !csv_loggers.is_empty() || !track_formats.is_empty() || rinex;
+    shared_state.set_csv_loggers(csv_loggers);
+    shared_state.set_track_formats(track_formats);
+    shared_state.set_rinex_logging(rinex);
+    shared_state.set_csv_logging(CsvLogging::from(csv_logging));
+
+    if opts.formats.contains(&ConvertFormat::JSON) {
+        shared_state.set_sbp_logging_filename(json_filename);
+        shared_state.set_sbp_logging_format(SbpLogging::SBP_JSON);
+        shared_state.set_sbp_json_options(opts.sbp_json_options.clone());
+        shared_state.set_sbp_logging(true);
+    }
+
+    // nothing is displayed, so drop everything that would be sent to the frontend
+    let (client_send, _client_recv) = crossbeam::channel::unbounded();
+    let client_sender = ChannelSender::new(client_send);
+    client_sender.set_connected(false);
+
+    let reader = ProgressReader {
+        inner: File::open(input)?,
+        on_progress,
+    };
+    let (messages, _stop_token) = Messages::new(reader);
+    let conn = Connection::file(
+        input.display().to_string(),
+        RealtimeDelay::Off,
+        /*close_when_done=*/ true,
+    );
+    process_messages(
+        messages,
+        MsgSender::new(io::sink()),
+        conn,
+        shared_state.clone(),
+        Box::new(client_sender),
+    )?;
+
+    shared_state.close_csv_logs()?;
+    shared_state.finish_nmea_output();
+    shared_state.end_sqlite_log()?;
+    shared_state.end_sbp_session()?;
+    Ok(())
+}
+
+// Messages read while looking for the settings snapshot before giving up.
+const SNAPSHOT_SCAN_LIMIT: usize = 10_000;
+
+/// Fill a log path template from the log itself rather than a live connection.
+fn log_path_context(input: &Path, logdir: PathBuf) -> Result<LogPathContext> {
+    let device = logged_device(input)?;
+    let time = fs::metadata(input)?
+        .modified()
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|_| Local::now());
+    Ok(LogPathContext {
+        logdir,
+        product_id: device.product_id,
+        serial: device.serial_number,
+        firmware: device.firmware_version,
+        conn: input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned()),
+        time,
+    })
+}
+
+/// Device details from the settings snapshot written at the start of the log.
+fn logged_device(input: &Path) -> Result<DeviceInfo> {
+    let mut device = DeviceInfo::default();
+    let mut in_snapshot = false;
+    for msg in sbp::iter_messages(File::open(input)?)
+        .take(SNAPSHOT_SCAN_LIMIT)
+        .flatten()
+    {
+        let msg = match msg {
+            Sbp::MsgSettingsReadByIndexResp(msg)
+                if msg.sender_id() == Some(SETTINGS_SNAPSHOT_SENDER_ID) =>
+            {
+                msg
+            }
+            _ if in_snapshot => break,
+            _ => continue,
+        };
+        in_snapshot = true;
+        let setting = msg.setting.to_string();
+        let mut fields = setting.split('\0');
+        let (Some(_group), Some(name), Some(value)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let value = Some(value.to_string());
+        match name {
+            "firmware_version" => device.firmware_version = value,
+            "product_id" => device.product_id = value,
+            "serial_number" => device.serial_number = value,
+            _ => {}
+        }
+    }
+    Ok(device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite_log::export_query;
+    use crate::tabs::settings_tab::settings_snapshot;
+    use indexmap::IndexMap;
+    use sbp::messages::navigation::{MsgGpsTime, MsgPosLlh, MsgUtcTime};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn find_file(dir: &Path, extension: &str) -> PathBuf {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.to_string_lossy().ends_with(extension))
+            .unwrap_or_else(|| panic!("no {extension} file written"))
+    }
+
+    /// Ten epochs of UTC time and position.
+    fn drive_log(data: &mut Vec<u8>) {
+        for i in 0..10 {
+            let tow = 1000 * (i + 1);
+            sbp::to_writer(
+                &mut *data,
+                &MsgUtcTime {
+                    sender_id: Some(1337),
+                    flags: 1,
+                    tow,
+                    year: 2022,
+                    month: 1,
+                    day: 1,
+                    hours: 0,
+                    minutes: 0,
+                    seconds: i as u8,
+                    ns: 0,
+                },
+            )
+            .unwrap();
+            sbp::to_writer(
+                &mut *data,
+                &MsgPosLlh {
+                    sender_id: Some(1337),
+                    tow,
+                    lat: 37.0,
+                    lon: -122.0,
+                    height: 10.0,
+                    h_accuracy: 0,
+                    v_accuracy: 0,
+                    n_sats: 12,
+                    flags: 1,
+                },
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn convert_file_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let input = tmp_dir.path().join("drive.sbp");
+        let mut data = Vec::new();
+        drive_log(&mut data);
+        fs::write(&input, &data).unwrap();
+
+        let output_dir = tmp_dir.path().join("converted");
+        let read = Arc::new(AtomicU64::new(0));
+        let opts = ConvertOptions {
+            output_dir: output_dir.clone(),
+            formats: vec![ConvertFormat::CSV, ConvertFormat::JSON, ConvertFormat::GPX],
+            csv_loggers: vec![CsvLogger::POSITION],
+            sbp_json_options: vec![SbpJsonOption::COMPACT, SbpJsonOption::PIKSI_TOOLS],
+            path_template: None,
+        };
+        convert_file(&input, opts, {
+            let read = read.clone();
+            move |n| {
+                read.fetch_add(n, Ordering::Relaxed);
+            }
+        })
+        .unwrap();
+
+        assert_eq!(read.load(Ordering::Relaxed), data.len() as u64);
+        let position_log = fs::read_to_string(find_file(&output_dir, ".csv")).unwrap();
+        assert_eq!(position_log.lines().count(), 11);
+        let gpx = fs::read_to_string(find_file(&output_dir, ".gpx")).unwrap();
+        assert_eq!(gpx.matches("<trkpt").count(), 10);
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+        let json = fs::read_to_string(output_dir.join("drive.sbp.json")).unwrap();
+        assert_eq!(json.matches("\"msg_type\"").count(), 20);
+        assert_eq!(json.matches("\"delta\"").count(), 20);
+        assert!(!json.contains("\"crc\""));
+    }
+
+    #[test]
+    fn convert_to_nmea_and_sqlite() {
+        let tmp_dir = TempDir::new().unwrap();
+        let input = tmp_dir.path().join("drive.sbp");
+        let mut data = Vec::new();
+        // the SQLite log needs the GPS week to place rows in time
+        sbp::to_writer(
+            &mut data,
+            &MsgGpsTime {
+                sender_id: Some(1337),
+                wn: 2190,
+                tow: 1000,
+                ns_residual: 0,
+                flags: 1,
+            },
+        )
+        .unwrap();
+        drive_log(&mut data);
+        fs::write(&input, &data).unwrap();
+
+        let output_dir = tmp_dir.path().join("converted");
+        let opts = ConvertOptions {
+            output_dir: output_dir.clone(),
+            formats: vec![ConvertFormat::NMEA, ConvertFormat::SQLITE],
+            csv_loggers: Vec::new(),
+            sbp_json_options: SbpJsonOption::defaults(),
+            path_template: None,
+        };
+        convert_file(&input, opts, |_| {}).unwrap();
+
+        let nmea = fs::read_to_string(output_dir.join("drive.nmea")).unwrap();
+        assert_eq!(nmea.matches("$GNGGA,").count(), 10);
+        let mut out = Vec::new();
+        let rows = export_query(
+            output_dir.join("drive.sqlite"),
+            "SELECT gps_time FROM position",
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(rows, 10);
+    }
+
+    #[test]
+    fn convert_with_path_template() {
+        let tmp_dir = TempDir::new().unwrap();
+        let input = tmp_dir.path().join("drive.sbp");
+        let settings = IndexMap::from([(
+            "system_info".to_string(),
+            IndexMap::from([
+                ("product_id".to_string(), "Piksi Multi".to_string()),
+                ("serial_number".to_string(), "1234".to_string()),
+            ]),
+        )]);
+        let mut data = Vec::new();
+        for msg in settings_snapshot(&settings) {
+            sbp::to_writer(&mut data, &msg).unwrap();
+        }
+        drive_log(&mut data);
+        fs::write(&input, &data).unwrap();
+
+        let output_dir = tmp_dir.path().join("converted");
+        let opts = ConvertOptions {
+            output_dir: output_dir.clone(),
+            formats: vec![ConvertFormat::CSV, ConvertFormat::JSON],
+            csv_loggers: vec![CsvLogger::POSITION],
+            sbp_json_options: SbpJsonOption::defaults(),
+            path_template: Some("{logdir}/{product_id}/{serial}/{conn}.sbp".parse().unwrap()),
+        };
+        convert_file(&input, opts, |_| {}).unwrap();
+
+        let log_dir = output_dir.join("Piksi_Multi").join("1234");
+        assert!(log_dir.join("drive.sbp.json").exists());
+        find_file(&log_dir, ".csv");
+    }
+}
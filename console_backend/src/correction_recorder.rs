@@ -0,0 +1,183 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//! Recording of the raw corrections written to the device.
+//!
+//! Corrections are saved as they were received next to the SBP log, along with a
+//! CSV of host timestamps. Replaying the recording with a file correction source
+//! reproduces when each chunk arrived, including any outages of the caster.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use chrono::Utc;
+use log::{error, info};
+
+use crate::constants::{CORRECTIONS_EXTENSION, CORRECTIONS_TIMESTAMPS_EXTENSION};
+use crate::correction_source::InputType;
+use crate::types::Result;
+
+const TIMESTAMPS_HEADER: &str = "host_time_ms,bytes";
+
+/// Corrections recording next to the SBP log `log`, e.g. `<log>.corrections.rtcm`.
+pub fn corrections_path(log: &Path, input: InputType) -> PathBuf {
+    let mut name = log.as_os_str().to_owned();
+    name.push(CORRECTIONS_EXTENSION);
+    name.push(".");
+    name.push(input.to_string());
+    PathBuf::from(name)
+}
+
+/// Host timestamps of the corrections recording `corrections`.
+pub fn timestamps_path(corrections: &Path) -> PathBuf {
+    let mut name = corrections.as_os_str().to_owned();
+    name.push(CORRECTIONS_TIMESTAMPS_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Read the host time in milliseconds and the length of each recorded chunk.
+pub fn read_timestamps(path: &Path) -> Result<Vec<(i64, usize)>> {
+    let file = File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+    let mut chunks = Vec::new();
+    for line in BufReader::new(file).lines().skip(1) {
+        let line = line?;
+        let (time, len) = line
+            .split_once(',')
+            .with_context(|| format!("invalid corrections timestamp \"{line}\""))?;
+        chunks.push((time.parse()?, len.parse()?));
+    }
+    Ok(chunks)
+}
+
+struct CorrectionsLog {
+    data: File,
+    timestamps: File,
+}
+
+impl CorrectionsLog {
+    fn create(path: &Path) -> Result<Self> {
+        let data =
+            File::create(path).with_context(|| format!("could not create {}", path.display()))?;
+        let mut timestamps = File::create(timestamps_path(path))?;
+        writeln!(timestamps, "{TIMESTAMPS_HEADER}")?;
+        info!("Recording corrections to {}", path.display());
+        Ok(CorrectionsLog { data, timestamps })
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.data.write_all(data)?;
+        writeln!(
+            self.timestamps,
+            "{},{}",
+            Utc::now().timestamp_millis(),
+            data.len()
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+enum Recording {
+    #[default]
+    Off,
+    /// Waiting for corrections to know the recording's format.
+    Pending(PathBuf),
+    On(CorrectionsLog),
+}
+
+/// Records the corrections stream while an SBP log is open, shared by every
+/// correction source.
+#[derive(Clone, Default)]
+pub struct CorrectionRecorder(Arc<Mutex<Recording>>);
+
+impl std::fmt::Debug for CorrectionRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CorrectionRecorder").finish_non_exhaustive()
+    }
+}
+
+impl CorrectionRecorder {
+    /// Record the corrections received from now on next to the SBP log `log`. The
+    /// files are created with the first corrections.
+    pub fn start(&self, log: &Path) {
+        *self.0.lock().unwrap() = Recording::Pending(log.to_path_buf());
+    }
+
+    pub fn stop(&self) {
+        *self.0.lock().unwrap() = Recording::Off;
+    }
+
+    pub fn record(&self, input: InputType, data: &[u8]) {
+        let mut recording = self.0.lock().unwrap();
+        if let Recording::Pending(log) = &*recording {
+            *recording = match CorrectionsLog::create(&corrections_path(log, input)) {
+                Ok(log) => Recording::On(log),
+                Err(e) => {
+                    error!("Issue recording corrections, {e}");
+                    Recording::Off
+                }
+            };
+        }
+        if let Recording::On(log) = &mut *recording {
+            if let Err(e) = log.write(data) {
+                error!("Issue recording corrections, {e}");
+                *recording = Recording::Off;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn record_corrections() {
+        let tmp_dir = TempDir::new().unwrap();
+        let log = tmp_dir.path().join("swift-gnss-20220101-000000.sbp");
+        let recorder = CorrectionRecorder::default();
+        recorder.record(InputType::RTCM, &[0xD3]);
+        recorder.start(&log);
+        recorder.record(InputType::RTCM, &[0xD3, 0x00]);
+        recorder.record(InputType::RTCM, &[0x13, 0x3E, 0xD0]);
+        recorder.stop();
+        recorder.record(InputType::RTCM, &[0xD3]);
+
+        let path = corrections_path(&log, InputType::RTCM);
+        assert_eq!(
+            path,
+            tmp_dir
+                .path()
+                .join("swift-gnss-20220101-000000.sbp.corrections.rtcm")
+        );
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            vec![0xD3, 0x00, 0x13, 0x3E, 0xD0]
+        );
+        let chunks = read_timestamps(&timestamps_path(&path)).unwrap();
+        assert_eq!(
+            chunks.iter().map(|(_, len)| *len).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(chunks[0].0 <= chunks[1].0);
+    }
+}
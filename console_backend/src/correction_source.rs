@@ -0,0 +1,492 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//! Sources of the corrections written to the device.
+//!
+//! Besides an NTRIP caster, corrections can be replayed from a recorded file at
+//! realtime pace or read from a local radio over TCP, UDP or a serial port. Every
+//! source feeds the same [MessageConverter](crate::ntrip_output::MessageConverter),
+//! so the output type and the correction statistics don't depend on where the
+//! corrections come from.
+
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use crossbeam::channel::Sender;
+use strum_macros::{Display, EnumString, EnumVariantNames};
+
+use crate::constants::READER_TIMEOUT;
+use crate::correction_recorder::{read_timestamps, timestamps_path};
+use crate::process_messages::Messages;
+use crate::rtcm::{gps_time_now, MsmHeader, RtcmFramer};
+use crate::tabs::advanced_tab::ntrip_tab::{
+    sleep_while_running, Backoff, ConnectionState, ConnectionStatus, NtripOptions, OutputType,
+    STALLED_AFTER,
+};
+use crate::types::{ArcBool, Result};
+
+/// How long a read waits before checking whether the source was stopped.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_BAUDRATE: u32 = 115200;
+/// Gaps between epochs longer than this aren't waited for when replaying a file.
+const MAX_REPLAY_GAP_MS: i64 = 60_000;
+
+/// Format of the corrections read from a source.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumVariantNames)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+pub enum InputType {
+    #[default]
+    RTCM,
+    SBP,
+}
+
+/// Where corrections are read from.
+#[derive(Debug, Clone)]
+pub enum CorrectionSource {
+    Ntrip(NtripOptions),
+    Local(LocalSource),
+}
+
+impl CorrectionSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CorrectionSource::Ntrip(_) => "NTRIP",
+            CorrectionSource::Local(_) => "Corrections",
+        }
+    }
+}
+
+/// A correction source that doesn't need the rover solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalSource {
+    /// Recorded corrections, replayed at the pace they were recorded.
+    File(PathBuf),
+    /// A TCP server streaming corrections.
+    Tcp { host: String, port: u16 },
+    /// Corrections sent as datagrams to a local port.
+    Udp { port: u16 },
+    /// A radio on another serial port.
+    Serial { device: String, baudrate: u32 },
+}
+
+impl FromStr for LocalSource {
+    type Err = anyhow::Error;
+
+    /// Parse `file:<path>`, `tcp:<host>:<port>`, `udp:<port>` or
+    /// `serial:<device>[@<baudrate>]`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, rest) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected <file|tcp|udp|serial>:<address>, got {s}"))?;
+        let source = match kind {
+            "file" => LocalSource::File(PathBuf::from(rest)),
+            "tcp" => {
+                let (host, port) = rest
+                    .rsplit_once(':')
+                    .ok_or_else(|| anyhow!("expected tcp:<host>:<port>, got {s}"))?;
+                LocalSource::Tcp {
+                    host: host.to_string(),
+                    port: port
+                        .parse()
+                        .with_context(|| format!("invalid port in {s}"))?,
+                }
+            }
+            "udp" => LocalSource::Udp {
+                port: rest
+                    .parse()
+                    .with_context(|| format!("invalid port in {s}"))?,
+            },
+            "serial" => {
+                let (device, baudrate) = match rest.rsplit_once('@') {
+                    Some((device, baudrate)) => (
+                        device,
+                        baudrate
+                            .parse()
+                            .with_context(|| format!("invalid baudrate in {s}"))?,
+                    ),
+                    None => (rest, DEFAULT_BAUDRATE),
+                };
+                LocalSource::Serial {
+                    device: device.to_string(),
+                    baudrate,
+                }
+            }
+            _ => bail!("unknown corrections source {kind}, expected file, tcp, udp or serial"),
+        };
+        Ok(source)
+    }
+}
+
+impl std::fmt::Display for LocalSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LocalSource::File(path) => write!(f, "file {}", path.display()),
+            LocalSource::Tcp { host, port } => write!(f, "tcp {host}:{port}"),
+            LocalSource::Udp { port } => write!(f, "udp port {port}"),
+            LocalSource::Serial { device, baudrate } => write!(f, "serial {device} @{baudrate}"),
+        }
+    }
+}
+
+impl LocalSource {
+    /// Send corrections to `tx` until stopped, reconnecting with a backoff if a stream
+    /// drops. A file is replayed once, with the host timestamps of a recording if it
+    /// has them.
+    pub(crate) fn run(
+        &self,
+        input: InputType,
+        is_running: &ArcBool,
+        tx: Sender<Vec<u8>>,
+        status: &ConnectionStatus,
+    ) -> Result<()> {
+        if let LocalSource::File(path) = self {
+            status.set(ConnectionState::Streaming, &self.to_string());
+            let rdr = self.open()?;
+            let timestamps = timestamps_path(path);
+            if timestamps.exists() {
+                let chunks = read_timestamps(&timestamps)?;
+                return replay_recording(rdr, &chunks, is_running, &tx);
+            }
+            return replay(rdr, input, is_running, &tx);
+        }
+        let mut backoff = Backoff::default();
+        loop {
+            status.set(ConnectionState::Connecting, &self.to_string());
+            let result = self
+                .open()
+                .and_then(|rdr| forward(rdr, is_running, &tx, status, &self.to_string()));
+            if !is_running.get() {
+                return Ok(());
+            }
+            let e = result.err().unwrap_or_else(|| anyhow!("{self} closed"));
+            if status.get() != ConnectionState::Connecting {
+                backoff.reset();
+            }
+            let delay = backoff.next_delay();
+            status.set(
+                ConnectionState::Backoff,
+                &format!("{e}, reconnecting in {} s", delay.as_secs()),
+            );
+            if !sleep_while_running(is_running, delay) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn open(&self) -> Result<Box<dyn Read + Send>> {
+        let rdr: Box<dyn Read + Send> = match self {
+            LocalSource::File(path) => Box::new(
+                File::open(path).with_context(|| format!("could not open {}", path.display()))?,
+            ),
+            LocalSource::Tcp { host, port } => {
+                let address = (host.as_str(), *port)
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| anyhow!("could not resolve {host}"))?;
+                let stream = TcpStream::connect_timeout(&address, READER_TIMEOUT)?;
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                Box::new(stream)
+            }
+            LocalSource::Udp { port } => {
+                let socket = UdpSocket::bind(("0.0.0.0", *port))?;
+                socket.set_read_timeout(Some(READ_TIMEOUT))?;
+                Box::new(Datagrams(socket))
+            }
+            LocalSource::Serial { device, baudrate } => Box::new(
+                serialport::new(device.as_str(), *baudrate)
+                    .timeout(READ_TIMEOUT)
+                    .open()?,
+            ),
+        };
+        Ok(rdr)
+    }
+}
+
+/// Reads one datagram at a time from a socket.
+struct Datagrams(UdpSocket);
+
+impl Read for Datagrams {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+}
+
+/// Pass everything read to `tx`, reporting the stream as stalled while nothing arrives.
+fn forward(
+    mut rdr: Box<dyn Read + Send>,
+    is_running: &ArcBool,
+    tx: &Sender<Vec<u8>>,
+    status: &ConnectionStatus,
+    name: &str,
+) -> Result<()> {
+    let mut buf = vec![0u8; 4096];
+    let mut last_data = Instant::now();
+    status.set(ConnectionState::Streaming, name);
+    while is_running.get() {
+        match rdr.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                last_data = Instant::now();
+                status.set(ConnectionState::Streaming, name);
+                if tx.send(buf[..n].to_vec()).is_err() {
+                    return Ok(());
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if last_data.elapsed() >= STALLED_AFTER {
+                    status.set(
+                        ConnectionState::Stalled,
+                        &format!("no corrections for {} s", last_data.elapsed().as_secs()),
+                    );
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Replay a recorded file, waiting between epochs as long as they were apart.
+fn replay(
+    rdr: Box<dyn Read + Send>,
+    input: InputType,
+    is_running: &ArcBool,
+    tx: &Sender<Vec<u8>>,
+) -> Result<()> {
+    match input {
+        InputType::SBP => {
+            let (messages, _stop_token) = Messages::with_realtime_delay(rdr);
+            for (frame, _) in messages {
+                if !is_running.get() || tx.send(frame.as_bytes().to_vec()).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        InputType::RTCM => replay_rtcm(rdr, is_running, tx),
+    }
+}
+
+/// Replay a corrections recording, sending each chunk as long after the first as it
+/// was received.
+fn replay_recording<R: Read>(
+    mut rdr: R,
+    chunks: &[(i64, usize)],
+    is_running: &ArcBool,
+    tx: &Sender<Vec<u8>>,
+) -> Result<()> {
+    let Some(&(first, _)) = chunks.first() else {
+        return Ok(());
+    };
+    let start = Instant::now();
+    for &(time, len) in chunks {
+        let due = start + Duration::from_millis((time - first).max(0) as u64);
+        if !sleep_while_running(is_running, due.saturating_duration_since(Instant::now())) {
+            break;
+        }
+        let mut data = vec![0u8; len];
+        rdr.read_exact(&mut data)?;
+        if tx.send(data).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn replay_rtcm<R: Read>(mut rdr: R, is_running: &ArcBool, tx: &Sender<Vec<u8>>) -> Result<()> {
+    let mut framer = RtcmFramer::default();
+    let mut pace = Pace::default();
+    let mut buf = vec![0u8; 4096];
+    while is_running.get() {
+        let n = rdr.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for frame in framer.push(&buf[..n]) {
+            if let Some(header) = MsmHeader::decode(&frame, gps_time_now()) {
+                if !pace.wait(header.tow_ms, is_running) {
+                    return Ok(());
+                }
+            }
+            if tx.send(frame.as_bytes().to_vec()).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keeps a replay as far apart in wall time as the epochs it contains.
+#[derive(Debug, Default)]
+struct Pace {
+    last: Option<(i64, Instant)>,
+}
+
+impl Pace {
+    /// Wait until the epoch at `tow_ms` is due, false if stopped while waiting.
+    fn wait(&mut self, tow_ms: i64, is_running: &ArcBool) -> bool {
+        let now = Instant::now();
+        match self.last {
+            // another message of the same epoch
+            Some((last_tow, _)) if tow_ms == last_tow => true,
+            Some((last_tow, last_at))
+                if tow_ms > last_tow && tow_ms - last_tow <= MAX_REPLAY_GAP_MS =>
+            {
+                let due = last_at + Duration::from_millis((tow_ms - last_tow) as u64);
+                self.last = Some((tow_ms, due));
+                sleep_while_running(is_running, due.saturating_duration_since(now))
+            }
+            // first epoch, a gap in the recording or a week rollover
+            _ => {
+                self.last = Some((tow_ms, now));
+                true
+            }
+        }
+    }
+}
+
+/// The source and formats of a corrections stream.
+#[derive(Debug, Clone)]
+pub struct Corrections {
+    pub source: CorrectionSource,
+    pub input: InputType,
+    pub output: OutputType,
+}
+
+impl Corrections {
+    /// Corrections from an NTRIP caster, written as its configured output type.
+    pub fn ntrip(options: NtripOptions) -> Self {
+        let output = options.output_type.clone().unwrap_or(OutputType::RTCM);
+        Corrections {
+            source: CorrectionSource::Ntrip(options),
+            input: InputType::RTCM,
+            output,
+        }
+    }
+
+    pub fn local(source: LocalSource, input: InputType, output: OutputType) -> Self {
+        Corrections {
+            source: CorrectionSource::Local(source),
+            input,
+            output,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_sender::TestSender;
+    use crate::correction_recorder::{corrections_path, CorrectionRecorder};
+    use crossbeam::channel;
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_sources() {
+        assert_eq!(
+            LocalSource::from_str("file:/tmp/base.rtcm").unwrap(),
+            LocalSource::File(PathBuf::from("/tmp/base.rtcm"))
+        );
+        assert_eq!(
+            LocalSource::from_str("tcp:192.168.0.222:55555").unwrap(),
+            LocalSource::Tcp {
+                host: "192.168.0.222".to_string(),
+                port: 55555
+            }
+        );
+        assert_eq!(
+            LocalSource::from_str("udp:2101").unwrap(),
+            LocalSource::Udp { port: 2101 }
+        );
+        assert_eq!(
+            LocalSource::from_str("serial:/dev/ttyUSB1").unwrap(),
+            LocalSource::Serial {
+                device: "/dev/ttyUSB1".to_string(),
+                baudrate: DEFAULT_BAUDRATE
+            }
+        );
+        assert_eq!(
+            LocalSource::from_str("serial:COM4@57600").unwrap(),
+            LocalSource::Serial {
+                device: "COM4".to_string(),
+                baudrate: 57600
+            }
+        );
+        assert!(LocalSource::from_str("tcp:localhost").is_err());
+        assert!(LocalSource::from_str("udp:port").is_err());
+        assert!(LocalSource::from_str("http://caster:2101/MOUNT").is_err());
+        assert!(LocalSource::from_str("/tmp/base.rtcm").is_err());
+    }
+
+    #[test]
+    fn forward_until_closed() {
+        let (tx, rx) = channel::unbounded();
+        let status = ConnectionStatus::new("Corrections", TestSender::boxed());
+        let rdr = Box::new(Cursor::new(vec![0xD3, 0x00, 0x00]));
+        forward(rdr, &ArcBool::new_with(true), &tx, &status, "test").unwrap();
+        assert_eq!(rx.try_recv().unwrap(), vec![0xD3, 0x00, 0x00]);
+        assert_eq!(status.get(), ConnectionState::Streaming);
+    }
+
+    #[test]
+    fn replay_recorded_corrections() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let log = tmp_dir.path().join("test.sbp");
+        let recorder = CorrectionRecorder::default();
+        recorder.start(&log);
+        recorder.record(InputType::RTCM, &[0xD3, 0x00]);
+        std::thread::sleep(Duration::from_millis(100));
+        recorder.record(InputType::RTCM, &[0x13]);
+        recorder.stop();
+
+        let (tx, rx) = channel::unbounded();
+        let status = ConnectionStatus::new("Corrections", TestSender::boxed());
+        let source = LocalSource::File(corrections_path(&log, InputType::RTCM));
+        let start = Instant::now();
+        source
+            .run(InputType::RTCM, &ArcBool::new_with(true), tx, &status)
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![vec![0xD3, 0x00], vec![0x13]]
+        );
+    }
+
+    #[test]
+    fn pace_epochs() {
+        let running = ArcBool::new_with(true);
+        let mut pace = Pace::default();
+        let start = Instant::now();
+        assert!(pace.wait(1000, &running));
+        assert!(pace.wait(1000, &running));
+        assert!(pace.wait(1200, &running));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        // a gap or rollover restarts the pace without waiting
+        let start = Instant::now();
+        assert!(pace.wait(500_000, &running));
+        assert!(pace.wait(0, &running));
+        assert!(start.elapsed() < Duration::from_millis(200));
+    }
+}
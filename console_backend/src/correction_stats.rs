@@ -0,0 +1,367 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//! Analytics of the RTCM 3 correction stream received from a caster.
+//!
+//! Counts and rates of each message type, the base station and its position,
+//! the constellations covered by MSM observations, how old the latest
+//! observations are and gaps in the stream. SBP corrections only get the bytes,
+//! message counts, rates and gaps. Sent to the frontend as a `CorrectionsStatus`
+//! message.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use capnp::message::Builder;
+
+use crate::client_sender::BoxedClientSender;
+use crate::rtcm::{gps_time_now, station_position, MsmHeader, RtcmFrame};
+use crate::utils::serialize_capnproto_builder;
+
+/// Window over which message rates are measured.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Silence between frames counted as a gap in the stream.
+const GAP_THRESHOLD: Duration = Duration::from_secs(3);
+const MS_PER_WEEK: i64 = 604_800_000;
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_E2: f64 = 6.694_379_990_141_316e-3;
+/// Reported for values that haven't been received yet.
+const UNKNOWN: f64 = -1.0;
+
+#[derive(Debug)]
+struct MessageStats {
+    count: u64,
+    last_seen: Instant,
+    recent: VecDeque<Instant>,
+}
+
+impl MessageStats {
+    fn new(now: Instant) -> Self {
+        MessageStats {
+            count: 0,
+            last_seen: now,
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, now: Instant) {
+        self.count += 1;
+        self.last_seen = now;
+        self.recent.push_back(now);
+        while self
+            .recent
+            .front()
+            .is_some_and(|&t| now.duration_since(t) > RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Messages per second over the rate window, or since `start` if that is shorter.
+    fn rate(&self, start: Instant, now: Instant) -> f64 {
+        let window = RATE_WINDOW
+            .min(now.duration_since(start))
+            .max(Duration::from_secs(1));
+        let recent = self
+            .recent
+            .iter()
+            .filter(|&&t| now.duration_since(t) <= window)
+            .count();
+        recent as f64 / window.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConstellationStats {
+    msm: u8,
+    satellites: u32,
+    signals: u32,
+    last_seen: Instant,
+}
+
+#[derive(Debug)]
+pub struct CorrectionStats {
+    start: Instant,
+    messages: BTreeMap<u16, MessageStats>,
+    station_id: Option<u16>,
+    base_position: Option<[f64; 3]>,
+    constellations: BTreeMap<&'static str, ConstellationStats>,
+    /// Age of the latest MSM epoch when it was received.
+    latency_ms: Option<i64>,
+    last_frame: Option<Instant>,
+    gaps: u64,
+    longest_gap: Duration,
+    crc_errors: u64,
+    bytes: u64,
+}
+
+impl Default for CorrectionStats {
+    fn default() -> Self {
+        CorrectionStats::new(Instant::now())
+    }
+}
+
+impl CorrectionStats {
+    fn new(start: Instant) -> Self {
+        CorrectionStats {
+            start,
+            messages: BTreeMap::new(),
+            station_id: None,
+            base_position: None,
+            constellations: BTreeMap::new(),
+            latency_ms: None,
+            last_frame: None,
+            gaps: 0,
+            longest_gap: Duration::ZERO,
+            crc_errors: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Record a frame received now.
+    pub fn push(&mut self, frame: &RtcmFrame) {
+        self.update(frame, Instant::now(), gps_time_now());
+    }
+
+    /// Record an SBP message of `msg_type` received now.
+    pub fn push_sbp(&mut self, msg_type: u16) {
+        self.count(msg_type, Instant::now());
+    }
+
+    /// Count `len` bytes of the stream as received.
+    pub fn add_bytes(&mut self, len: usize) {
+        self.bytes += len as u64;
+    }
+
+    /// Number of candidate frames the framer dropped because of a bad CRC.
+    pub fn set_crc_errors(&mut self, crc_errors: u64) {
+        self.crc_errors = crc_errors;
+    }
+
+    /// Count a message of `message_type`, noting any gap since the previous one.
+    fn count(&mut self, message_type: u16, now: Instant) {
+        if let Some(last) = self.last_frame {
+            let gap = now.duration_since(last);
+            if gap > GAP_THRESHOLD {
+                self.gaps += 1;
+                self.longest_gap = self.longest_gap.max(gap);
+            }
+        }
+        self.last_frame = Some(now);
+        self.messages
+            .entry(message_type)
+            .or_insert_with(|| MessageStats::new(now))
+            .push(now);
+    }
+
+    fn update(&mut self, frame: &RtcmFrame, now: Instant, gps_now: (u16, i64)) {
+        self.count(frame.message_type(), now);
+        if let Some(station_id) = frame.station_id() {
+            self.station_id = Some(station_id);
+        }
+        if let Some((_, position)) = station_position(frame) {
+            self.base_position = Some(position);
+        }
+        if let Some(header) = MsmHeader::decode(frame, gps_now) {
+            self.constellations.insert(
+                header.constellation,
+                ConstellationStats {
+                    msm: header.msm,
+                    satellites: header.satellites,
+                    signals: header.signals,
+                    last_seen: now,
+                },
+            );
+            let age = (gps_now.1 - header.tow_ms + MS_PER_WEEK / 2).rem_euclid(MS_PER_WEEK)
+                - MS_PER_WEEK / 2;
+            self.latency_ms = Some(age);
+        }
+    }
+
+    pub fn send(&self, client_sender: &BoxedClientSender) {
+        self.send_at(client_sender, Instant::now());
+    }
+
+    fn send_at(&self, client_sender: &BoxedClientSender, now: Instant) {
+        let age = |t: Instant| now.duration_since(t).as_secs_f64();
+        let mut builder = Builder::new_default();
+        let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
+        let mut status = msg.init_corrections_status();
+
+        let mut messages = status.reborrow().init_messages(self.messages.len() as u32);
+        for (i, (&message_type, stats)) in self.messages.iter().enumerate() {
+            let mut entry = messages.reborrow().get(i as u32);
+            entry.set_message_type(message_type);
+            entry.set_count(stats.count);
+            entry.set_rate(stats.rate(self.start, now));
+            entry.set_age(age(stats.last_seen));
+        }
+        let mut constellations = status
+            .reborrow()
+            .init_constellations(self.constellations.len() as u32);
+        for (i, (&name, stats)) in self.constellations.iter().enumerate() {
+            let mut entry = constellations.reborrow().get(i as u32);
+            entry.set_name(name);
+            entry.set_msm(stats.msm);
+            entry.set_satellites(stats.satellites);
+            entry.set_signals(stats.signals);
+            entry.set_age(age(stats.last_seen));
+        }
+
+        status.set_station_id(self.station_id.map_or(-1, i32::from));
+        let mut base_position = status.reborrow().init_base_position();
+        match self.base_position {
+            Some(ecef) => {
+                let (lat, lon, alt) = ecef_to_llh(ecef);
+                let mut pos = base_position.init_pos();
+                pos.set_lat(lat);
+                pos.set_lon(lon);
+                pos.set_alt(alt);
+            }
+            None => base_position.set_none(()),
+        }
+        status.set_latency(
+            self.latency_ms
+                .map_or(UNKNOWN, |latency| latency as f64 / 1000.0),
+        );
+        status.set_age(self.last_frame.map_or(UNKNOWN, age));
+        status.set_gaps(self.gaps);
+        status.set_longest_gap(self.longest_gap.as_secs_f64());
+        status.set_crc_errors(self.crc_errors);
+        status.set_bytes(self.bytes);
+        client_sender.send_data(serialize_capnproto_builder(builder));
+    }
+}
+
+/// WGS84 latitude and longitude in degrees and height in meters of an ECEF position.
+fn ecef_to_llh([x, y, z]: [f64; 3]) -> (f64, f64, f64) {
+    let p = x.hypot(y);
+    let lon = y.atan2(x);
+    let mut lat = z.atan2(p * (1.0 - WGS84_E2));
+    let mut height = 0.0;
+    for _ in 0..5 {
+        let n = WGS84_A / (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt();
+        height = p / lat.cos() - n;
+        lat = z.atan2(p * (1.0 - WGS84_E2 * n / (n + height)));
+    }
+    (lat.to_degrees(), lon.to_degrees(), height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtcm::{crc24q, RtcmFramer};
+    use sbp::messages::observation::MsgObs;
+    use sbp::messages::ConcreteMessage;
+
+    /// Station 2003 position message from the RTCM 10403.3 standard.
+    const MSG_1005: [u8; 25] = [
+        0xd3, 0x00, 0x13, 0x3e, 0xd7, 0xd3, 0x02, 0x02, 0x98, 0x0e, 0xde, 0xef, 0x34, 0xb4, 0xbd,
+        0x62, 0xac, 0x09, 0x41, 0x98, 0x6f, 0x33, 0x36, 0x0b, 0x98,
+    ];
+
+    /// An MSM header without satellite data, which is all the stats look at.
+    fn msm_frame(message_type: u16, station_id: u16, epoch: u32, sats: u32) -> RtcmFrame {
+        let fields: [(usize, u64); 8] = [
+            (12, message_type as u64),
+            (12, station_id as u64),
+            (30, epoch as u64),
+            (1, 0),
+            (18, 0),
+            (64, (1u64 << sats) - 1),
+            (32, 0b11 << 30),
+            (7, 0),
+        ];
+        let mut payload = Vec::new();
+        let mut len = 0;
+        for (bits, value) in fields {
+            for i in (0..bits).rev() {
+                if len % 8 == 0 {
+                    payload.push(0);
+                }
+                payload[len / 8] |= ((value >> i & 1) as u8) << (7 - len % 8);
+                len += 1;
+            }
+        }
+        let mut frame = vec![0xD3, 0, payload.len() as u8];
+        frame.extend(payload);
+        let crc = crc24q(&frame);
+        frame.extend(&crc.to_be_bytes()[1..]);
+        RtcmFramer::default().push(&frame).remove(0)
+    }
+
+    #[test]
+    fn message_rates_and_gaps() {
+        let start = Instant::now();
+        let mut stats = CorrectionStats::new(start);
+        let gps_now = (2200, 345_601_500);
+        for i in 0..10 {
+            let now = start + Duration::from_secs(i);
+            stats.update(&msm_frame(1077, 42, 345_600_000, 8), now, gps_now);
+            stats.update(&msm_frame(1087, 42, 345_600_000, 6), now, gps_now);
+        }
+        // a five second outage
+        let later = start + Duration::from_secs(14);
+        stats.update(&msm_frame(1127, 42, 345_600_000, 10), later, gps_now);
+
+        assert_eq!(stats.messages[&1077].count, 10);
+        // six of the ten were in the last ten seconds
+        assert!((stats.messages[&1077].rate(start, later) - 0.6).abs() < 1e-9);
+        assert_eq!(stats.messages[&1127].count, 1);
+        assert_eq!(stats.station_id, Some(42));
+        assert_eq!(stats.gaps, 1);
+        assert_eq!(stats.longest_gap, Duration::from_secs(5));
+        assert_eq!(stats.latency_ms, Some(1500));
+
+        let names: Vec<_> = stats.constellations.keys().copied().collect();
+        assert_eq!(names, ["BeiDou", "GLONASS", "GPS"]);
+        let gps = stats.constellations["GPS"];
+        assert_eq!((gps.msm, gps.satellites, gps.signals), (7, 8, 2));
+    }
+
+    #[test]
+    fn latency_across_week_rollover() {
+        let start = Instant::now();
+        let mut stats = CorrectionStats::new(start);
+        stats.update(&msm_frame(1077, 0, 604_799_000, 4), start, (2201, 500));
+        assert_eq!(stats.latency_ms, Some(1500));
+    }
+
+    #[test]
+    fn sbp_messages() {
+        let mut stats = CorrectionStats::default();
+        stats.add_bytes(120);
+        stats.push_sbp(MsgObs::MESSAGE_TYPE);
+        stats.push_sbp(MsgObs::MESSAGE_TYPE);
+        assert_eq!(stats.bytes, 120);
+        assert_eq!(stats.messages[&MsgObs::MESSAGE_TYPE].count, 2);
+        assert!(stats.constellations.is_empty());
+    }
+
+    #[test]
+    fn base_station_position() {
+        let mut stats = CorrectionStats::default();
+        let frames = RtcmFramer::default().push(&MSG_1005);
+        stats.push(&frames[0]);
+        assert_eq!(stats.station_id, Some(2003));
+        let (lat, lon, height) = ecef_to_llh(stats.base_position.unwrap());
+        assert!((lat - 38.804_759).abs() < 1e-6, "{lat}");
+        assert!((lon + 77.064_774).abs() < 1e-6, "{lon}");
+        assert!((height - 114.561).abs() < 1e-3, "{height}");
+    }
+}
@@ -27,22 +27,40 @@ pub mod client_sender;
 pub mod common_constants;
 pub mod connection;
 pub mod constants;
+pub mod convert;
+pub mod correction_recorder;
+pub mod correction_source;
+pub mod correction_stats;
+pub mod credentials;
 pub mod errors;
 pub mod fft_monitor;
 pub mod fileio;
 pub mod fusion_status_flags;
+pub mod log_edit;
 pub mod log_panel;
+pub mod log_path;
+pub mod log_recovery;
+pub mod log_schedule;
+pub mod nmea;
 pub mod ntrip_output;
+pub mod ntrip_profile;
+pub mod ntrip_sourcetable;
 pub mod output;
 pub mod piksi_tools_constants;
 pub mod process_messages;
+pub mod rinex;
+pub mod rtcm;
+pub mod sbp_framing;
 #[cfg(not(test))]
 #[cfg(all(not(feature = "benches"), not(feature = "tests"), feature = "pyo3"))]
 pub mod server;
 pub mod server_recv_thread;
+pub mod session_metadata;
 pub mod shared_state;
+pub mod sqlite_log;
 pub mod status_bar;
 pub mod tabs;
+pub mod track_export;
 pub mod types;
 pub mod updater;
 pub mod utils;
@@ -91,7 +109,7 @@ struct Tabs {
     pub advanced_spectrum_analyzer: Mutex<AdvancedSpectrumAnalyzerTab>,
     pub status_bar: Mutex<StatusBar>,
     pub update: Mutex<UpdateTab>,
-    pub settings: Option<SettingsTab>, // settings only enabled on TCP / Serial
+    pub settings: Option<SettingsTab>, // read-only for files, rebuilt from the log's snapshot
     pub shared_state: SharedState,
 }
 
@@ -162,6 +180,20 @@ impl Tabs {
         tabs.settings = Some(SettingsTab::new(shared_state, client_sender, msg_sender));
         tabs
     }
+
+    fn with_read_only_settings(
+        shared_state: SharedState,
+        client_sender: BoxedClientSender,
+        msg_sender: MsgSender,
+    ) -> Self {
+        let mut tabs = Self::new(
+            shared_state.clone(),
+            client_sender.clone(),
+            msg_sender.clone(),
+        );
+        tabs.settings = Some(SettingsTab::new(shared_state, client_sender, msg_sender).read_only());
+        tabs
+    }
 }
 
 #[cfg(test)]
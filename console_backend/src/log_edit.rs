@@ -0,0 +1,576 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Frame level editing of SBP logs: trim, split and merge.
+//!
+//! Frames are copied as they were read, so binary output keeps every message byte for
+//! byte, SBP-JSON output is written by the same [`SbpJsonEncoder`] as the SBP-JSON
+//! logger. Each frame is tagged with the last rover GPS time seen before it (via
+//! `with_rover_time`) and the last wall-clock time reported by `MsgUtcTime`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use chrono::DateTime;
+use log::{debug, error};
+use sbp::messages::navigation::MsgUtcTime;
+use sbp::time::GpsTime;
+use sbp::{DeserializeError, Frame, Sbp, SbpIterExt, SbpMessage};
+
+use crate::output::{SbpJsonEncoder, SbpLogging};
+use crate::types::{Result, UtcDateTime};
+use crate::utils::date_conv::utc_time_from_msg;
+
+const SECONDS_PER_WEEK: f64 = 604800.0;
+
+/// A frame along with the latest times known when it was read.
+#[derive(Debug)]
+pub struct TimedFrame {
+    pub frame: Frame,
+    pub gps_time: Option<GpsTime>,
+    pub utc_time: Option<UtcDateTime>,
+}
+
+impl TimedFrame {
+    /// Seconds since the start of GPS time.
+    pub fn gps_seconds(&self) -> Option<f64> {
+        self.gps_time
+            .map(|t| t.wn() as f64 * SECONDS_PER_WEEK + t.tow())
+    }
+}
+
+/// Read the frames of an SBP log, skipping any that fail to decode.
+pub fn timed_frames<R: Read>(reader: R) -> impl Iterator<Item = TimedFrame> {
+    let mut gps_time = None;
+    let mut utc_time = None;
+    sbp::iter_frames(reader)
+        .with_rover_time()
+        .map_while(move |(frame, time)| {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(DeserializeError::IoError(e)) => {
+                    error!("{e}");
+                    return None;
+                }
+                Err(e) => {
+                    debug!("{e}");
+                    return Some(None);
+                }
+            };
+            if let Some(Ok(time)) = time {
+                gps_time = Some(time);
+            }
+            if frame.msg_type() == Some(MsgUtcTime::MESSAGE_TYPE) {
+                if let Ok(Sbp::MsgUtcTime(msg)) = frame.to_sbp() {
+                    if msg.flags & 0x7 != 0 {
+                        utc_time = Some(utc_time_from_msg(&msg));
+                    }
+                }
+            }
+            Some(Some(TimedFrame {
+                frame,
+                gps_time,
+                utc_time,
+            }))
+        })
+        .flatten()
+}
+
+/// Output format for a path, SBP-JSON for `.json` files and binary SBP otherwise.
+pub fn format_for_path(path: &Path) -> SbpLogging {
+    match path.extension() {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => SbpLogging::SBP_JSON,
+        _ => SbpLogging::SBP,
+    }
+}
+
+fn extension(format: &SbpLogging) -> &'static str {
+    match format {
+        SbpLogging::SBP => "sbp",
+        SbpLogging::SBP_JSON => "sbp.json",
+    }
+}
+
+/// Buffered writer for edited logs.
+pub struct FrameWriter {
+    format: SbpLogging,
+    json: SbpJsonEncoder,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    frames: usize,
+    bytes: u64,
+}
+
+impl FrameWriter {
+    pub fn new(path: impl Into<PathBuf>, format: SbpLogging) -> Result<Self> {
+        let path = path.into();
+        let file =
+            File::create(&path).with_context(|| format!("could not create {}", path.display()))?;
+        Ok(Self {
+            format,
+            json: SbpJsonEncoder::default(),
+            path,
+            writer: BufWriter::new(file),
+            frames: 0,
+            bytes: 0,
+        })
+    }
+
+    pub fn with_json_encoder(mut self, json: SbpJsonEncoder) -> Self {
+        self.json = json;
+        self
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> Result<()> {
+        match self.format {
+            SbpLogging::SBP => self.writer.write_all(frame.as_bytes())?,
+            SbpLogging::SBP_JSON => {
+                let msg = match frame.to_sbp() {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        error!("(SBP-JSON) skipping frame that cannot be parsed: {e}");
+                        return Ok(());
+                    }
+                };
+                match self.json.encode(&msg) {
+                    Ok(bytes) => self.writer.write_all(&bytes)?,
+                    Err(e) => error!("{e}"),
+                }
+            }
+        }
+        self.frames += 1;
+        self.bytes += frame.as_bytes().len() as u64;
+        Ok(())
+    }
+
+    /// Number of frames written so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Size of the frames written so far, in binary SBP bytes.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn finish(mut self) -> Result<PathBuf> {
+        self.writer.flush()?;
+        Ok(self.path)
+    }
+}
+
+/// A trim bound, either a GPS time or a UTC wall-clock time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeBound {
+    Gps { wn: i16, tow: f64 },
+    Utc(UtcDateTime),
+}
+
+impl TimeBound {
+    /// Whether the frame is known to be at or after this bound.
+    fn reached(&self, frame: &TimedFrame) -> bool {
+        match self {
+            TimeBound::Gps { wn, tow } => frame
+                .gps_seconds()
+                .is_some_and(|t| t >= *wn as f64 * SECONDS_PER_WEEK + tow),
+            TimeBound::Utc(bound) => frame.utc_time.is_some_and(|t| t >= *bound),
+        }
+    }
+}
+
+impl FromStr for TimeBound {
+    type Err = String;
+
+    /// Parses `WN:TOW` as a GPS time or an RFC 3339 timestamp as a UTC time.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some((wn, tow)) = s.split_once(':') {
+            if let (Ok(wn), Ok(tow)) = (wn.parse(), tow.parse()) {
+                return Ok(TimeBound::Gps { wn, tow });
+            }
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|t| TimeBound::Utc(t.into()))
+            .map_err(|_| {
+                format!("expected WN:TOW or an RFC 3339 time (e.g. 2022-01-01T12:00:00Z), got {s}")
+            })
+    }
+}
+
+/// Copy the frames between `start` (inclusive) and `end` (exclusive) to `output`.
+///
+/// Frames read before the start bound can be placed in time are dropped. Reading stops
+/// at the first frame at or after the end bound.
+pub fn trim<R: Read>(
+    reader: R,
+    output: &Path,
+    format: SbpLogging,
+    json: &SbpJsonEncoder,
+    start: Option<TimeBound>,
+    end: Option<TimeBound>,
+) -> Result<usize> {
+    let mut writer = FrameWriter::new(output, format)?.with_json_encoder(json.clone());
+    let mut started = start.is_none();
+    for frame in timed_frames(reader) {
+        if end.is_some_and(|end| end.reached(&frame)) {
+            break;
+        }
+        if !started {
+            started = start.map_or(true, |start| start.reached(&frame));
+        }
+        if started {
+            writer.write(&frame.frame)?;
+        }
+    }
+    let frames = writer.frames();
+    writer.finish()?;
+    Ok(frames)
+}
+
+/// How [split] divides a log.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitBy {
+    /// Start a new file before exceeding this many bytes of binary SBP.
+    Size(u64),
+    /// Start a new file every this many seconds of GPS time.
+    Duration(f64),
+    /// One file per sender ID.
+    Sender,
+    /// One file per message type.
+    MsgType,
+}
+
+/// Split a log into several files in `output_dir` named after `stem`.
+///
+/// Returns the paths written in the order they were created.
+pub fn split<R: Read>(
+    reader: R,
+    output_dir: &Path,
+    stem: &str,
+    by: SplitBy,
+    format: SbpLogging,
+    json: &SbpJsonEncoder,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(output_dir)?;
+    let ext = extension(&format);
+    let part_path = |part: usize| output_dir.join(format!("{stem}-{part:03}.{ext}"));
+    let new_writer = |path: PathBuf| -> Result<FrameWriter> {
+        Ok(FrameWriter::new(path, format.clone())?.with_json_encoder(json.clone()))
+    };
+    let mut paths = Vec::new();
+    match by {
+        SplitBy::Size(size) => {
+            let mut part = 0;
+            let mut writer = new_writer(part_path(part))?;
+            for frame in timed_frames(reader) {
+                let len = frame.frame.as_bytes().len() as u64;
+                if writer.frames() > 0 && writer.bytes() + len > size {
+                    paths.push(writer.finish()?);
+                    part += 1;
+                    writer = new_writer(part_path(part))?;
+                }
+                writer.write(&frame.frame)?;
+            }
+            paths.push(writer.finish()?);
+        }
+        SplitBy::Duration(secs) => {
+            if secs <= 0.0 {
+                return Err(anyhow!("split duration must be positive"));
+            }
+            let mut part = 0;
+            let mut part_start = None;
+            let mut writer = new_writer(part_path(part))?;
+            for frame in timed_frames(reader) {
+                if let Some(t) = frame.gps_seconds() {
+                    let start = *part_start.get_or_insert(t);
+                    if t - start >= secs {
+                        paths.push(writer.finish()?);
+                        part += 1;
+                        part_start = Some(start + ((t - start) / secs).floor() * secs);
+                        writer = new_writer(part_path(part))?;
+                    }
+                }
+                writer.write(&frame.frame)?;
+            }
+            paths.push(writer.finish()?);
+        }
+        SplitBy::Sender | SplitBy::MsgType => {
+            let mut writers: HashMap<Option<u16>, FrameWriter> = HashMap::new();
+            for frame in timed_frames(reader) {
+                let key = match by {
+                    SplitBy::Sender => frame.frame.sender_id(),
+                    _ => frame.frame.msg_type(),
+                };
+                if !writers.contains_key(&key) {
+                    let name = match (by, key) {
+                        (SplitBy::Sender, Some(id)) => format!("sender-{id}"),
+                        (_, Some(_)) => msg_type_name(&frame.frame),
+                        (_, None) => String::from("unknown"),
+                    };
+                    let path = output_dir.join(format!("{stem}-{name}.{ext}"));
+                    paths.push(path.clone());
+                    writers.insert(key, new_writer(path)?);
+                }
+                if let Some(writer) = writers.get_mut(&key) {
+                    writer.write(&frame.frame)?;
+                }
+            }
+            for (_, writer) in writers {
+                writer.finish()?;
+            }
+        }
+    }
+    Ok(paths)
+}
+
+fn msg_type_name(frame: &Frame) -> String {
+    match frame.to_sbp() {
+        Ok(Sbp::Unknown(_)) | Err(_) => format!("0x{:04X}", frame.msg_type().unwrap_or_default()),
+        Ok(msg) => msg.message_name().to_string(),
+    }
+}
+
+/// Merge several logs into one, ordered by rover GPS time.
+///
+/// Each input is assumed to already be in time order. Frames without a known time are
+/// written as soon as they are reached and ties go to the earlier input.
+pub fn merge<R: Read>(
+    readers: Vec<R>,
+    output: &Path,
+    format: SbpLogging,
+    json: &SbpJsonEncoder,
+) -> Result<usize> {
+    let mut writer = FrameWriter::new(output, format)?.with_json_encoder(json.clone());
+    let mut inputs: Vec<_> = readers
+        .into_iter()
+        .map(|reader| timed_frames(reader).peekable())
+        .collect();
+    loop {
+        let mut next: Option<(usize, f64)> = None;
+        for (idx, input) in inputs.iter_mut().enumerate() {
+            let Some(frame) = input.peek() else {
+                continue;
+            };
+            let t = frame.gps_seconds().unwrap_or(f64::NEG_INFINITY);
+            if next.map_or(true, |(_, best)| t < best) {
+                next = Some((idx, t));
+            }
+        }
+        let Some((idx, _)) = next else {
+            break;
+        };
+        if let Some(frame) = inputs[idx].next() {
+            writer.write(&frame.frame)?;
+        }
+    }
+    let frames = writer.frames();
+    writer.finish()?;
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use sbp::messages::logging::MsgLog;
+    use sbp::messages::navigation::MsgGpsTime;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::output::SbpJsonOption;
+
+    fn msg_gps_time(sender_id: u16, tow: u32) -> Sbp {
+        MsgGpsTime {
+            sender_id: Some(sender_id),
+            wn: 2200,
+            tow,
+            ns_residual: 0,
+            flags: 1,
+        }
+        .into()
+    }
+
+    fn msg_log(sender_id: u16) -> Sbp {
+        MsgLog {
+            sender_id: Some(sender_id),
+            level: 1,
+            text: String::from("hello").into(),
+        }
+        .into()
+    }
+
+    fn log_data(msgs: &[Sbp]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for msg in msgs {
+            sbp::to_writer(&mut data, msg).unwrap();
+        }
+        data
+    }
+
+    fn read_frames(path: &Path) -> Vec<TimedFrame> {
+        timed_frames(File::open(path).unwrap()).collect()
+    }
+
+    fn epochs(sender_id: u16, tows: &[u32]) -> Vec<Sbp> {
+        tows.iter()
+            .flat_map(|&tow| [msg_gps_time(sender_id, tow), msg_log(sender_id)])
+            .collect()
+    }
+
+    #[test]
+    fn time_bound_from_str() {
+        assert_eq!(
+            "2200:3600.5".parse::<TimeBound>().unwrap(),
+            TimeBound::Gps {
+                wn: 2200,
+                tow: 3600.5
+            }
+        );
+        assert!(matches!(
+            "2022-01-01T12:00:00Z".parse::<TimeBound>().unwrap(),
+            TimeBound::Utc(_)
+        ));
+        assert!("yesterday".parse::<TimeBound>().is_err());
+    }
+
+    #[test]
+    fn trim_by_gps_time() {
+        let tmp_dir = TempDir::new().unwrap();
+        let output = tmp_dir.path().join("trimmed.sbp");
+        let data = log_data(&epochs(1, &[1000, 2000, 3000, 4000]));
+        let start = "2200:2".parse().ok();
+        let end = "2200:4".parse().ok();
+        let json = SbpJsonEncoder::default();
+        let frames = trim(
+            Cursor::new(data),
+            &output,
+            SbpLogging::SBP,
+            &json,
+            start,
+            end,
+        )
+        .unwrap();
+        assert_eq!(frames, 4);
+        let tows: Vec<_> = read_frames(&output)
+            .iter()
+            .map(|f| f.gps_time.unwrap().tow())
+            .collect();
+        assert_eq!(tows, vec![2.0, 2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn trim_to_json_with_encoder_options() {
+        let tmp_dir = TempDir::new().unwrap();
+        let output = tmp_dir.path().join("trimmed.sbp.json");
+        let data = log_data(&epochs(1, &[1000, 2000]));
+        let json = SbpJsonEncoder::new(&[SbpJsonOption::COMPACT]);
+        let frames = trim(
+            Cursor::new(data),
+            &output,
+            SbpLogging::SBP_JSON,
+            &json,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(frames, 4);
+        let lines: Vec<serde_json::Value> = fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        let expected: serde_json::Value =
+            serde_json::from_slice(&json.encode(&msg_gps_time(1, 1000)).unwrap()).unwrap();
+        assert_eq!(lines[0], expected);
+        assert!(lines.iter().all(|line| line.get("crc").is_none()));
+    }
+
+    #[test]
+    fn split_by_size_and_sender() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut msgs = epochs(1, &[1000, 2000]);
+        msgs.extend(epochs(2, &[3000]));
+        let data = log_data(&msgs);
+
+        let paths = split(
+            Cursor::new(data.clone()),
+            tmp_dir.path(),
+            "log",
+            SplitBy::Size(data.len() as u64 / 2),
+            SbpLogging::SBP,
+            &SbpJsonEncoder::default(),
+        )
+        .unwrap();
+        assert!(paths.len() >= 2);
+        let total: u64 = paths.iter().map(|p| fs::metadata(p).unwrap().len()).sum();
+        assert_eq!(total, data.len() as u64);
+        assert!(paths
+            .iter()
+            .all(|p| fs::metadata(p).unwrap().len() <= data.len() as u64 / 2));
+
+        let paths = split(
+            Cursor::new(data),
+            tmp_dir.path(),
+            "log",
+            SplitBy::Sender,
+            SbpLogging::SBP,
+            &SbpJsonEncoder::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                tmp_dir.path().join("log-sender-1.sbp"),
+                tmp_dir.path().join("log-sender-2.sbp")
+            ]
+        );
+        assert_eq!(read_frames(&paths[0]).len(), 4);
+        assert_eq!(read_frames(&paths[1]).len(), 2);
+    }
+
+    #[test]
+    fn merge_in_time_order() {
+        let tmp_dir = TempDir::new().unwrap();
+        let output = tmp_dir.path().join("merged.sbp");
+        let first = log_data(&epochs(1, &[1000, 3000]));
+        let second = log_data(&epochs(2, &[2000, 4000]));
+        let frames = merge(
+            vec![Cursor::new(first), Cursor::new(second)],
+            &output,
+            SbpLogging::SBP,
+            &SbpJsonEncoder::default(),
+        )
+        .unwrap();
+        assert_eq!(frames, 8);
+        let senders: Vec<_> = read_frames(&output)
+            .iter()
+            .map(|f| f.frame.sender_id().unwrap())
+            .collect();
+        assert_eq!(senders, vec![1, 1, 2, 2, 1, 1, 2, 2]);
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//! Templated log paths.
+//!
+//! A template such as `{logdir}/{product_id}/{serial}/{date}/{time}_{conn}.sbp`
+//! places every log of a session in a directory laid out per device and
+//! connection. The SBP log takes its name from the template while CSV, track,
+//! RINEX and sidecar files keep their usual names next to it.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Local};
+
+use crate::common_constants::SbpLogging;
+use crate::shared_state::SharedState;
+use crate::types::Result;
+
+const PLACEHOLDERS: [&str; 7] = [
+    "logdir",
+    "product_id",
+    "serial",
+    "firmware",
+    "conn",
+    "date",
+    "time",
+];
+const UNKNOWN_VALUE: &str = "unknown";
+const DATE_FORMAT: &str = "%Y%m%d";
+const TIME_FORMAT: &str = "%H%M%S";
+
+/// Values substituted into a [LogPathTemplate].
+#[derive(Debug, Clone)]
+pub struct LogPathContext {
+    pub logdir: PathBuf,
+    pub product_id: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+    pub conn: Option<String>,
+    pub time: DateTime<Local>,
+}
+
+impl LogPathContext {
+    /// Build the context from the connected device and the current connection.
+    pub fn new(shared_state: &SharedState, logdir: PathBuf) -> Self {
+        let device = shared_state.device_info();
+        let conn = shared_state.connection();
+        LogPathContext {
+            logdir,
+            product_id: device.product_id,
+            serial: device.serial_number,
+            firmware: device.firmware_version,
+            conn: conn.is_connected().then(|| conn.name()),
+            time: Local::now(),
+        }
+    }
+
+    fn value(&self, placeholder: &str) -> String {
+        match placeholder {
+            "logdir" => self.logdir.display().to_string(),
+            "product_id" => sanitize(self.product_id.as_deref()),
+            "serial" => sanitize(self.serial.as_deref()),
+            "firmware" => sanitize(self.firmware.as_deref()),
+            "conn" => sanitize(self.conn.as_deref().map(last_component)),
+            "date" => self.time.format(DATE_FORMAT).to_string(),
+            "time" => self.time.format(TIME_FORMAT).to_string(),
+            _ => unreachable!("placeholders are checked when the template is parsed"),
+        }
+    }
+}
+
+/// A log path with `{placeholder}` fields filled in when logging starts.
+///
+/// Supported placeholders are `{logdir}`, `{product_id}`, `{serial}`,
+/// `{firmware}`, `{conn}`, `{date}` and `{time}`. A template that does not
+/// start from `{logdir}` and is relative is placed under the logging directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogPathTemplate(String);
+
+impl LogPathTemplate {
+    /// Fill in the template.
+    pub fn render(&self, ctx: &LogPathContext) -> PathBuf {
+        let mut rendered = String::new();
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}').expect("validated template");
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(&ctx.value(&rest[start + 1..end]));
+            rest = &rest[end + 1..];
+        }
+        rendered.push_str(rest);
+        let path = PathBuf::from(rendered);
+        if path.is_relative() && !self.0.contains("{logdir}") {
+            ctx.logdir.join(path)
+        } else {
+            path
+        }
+    }
+
+    /// Path of the SBP log, with the extension matching the logging format.
+    pub fn sbp_path(&self, ctx: &LogPathContext, logging: SbpLogging) -> PathBuf {
+        let path = self.render(ctx);
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let stem = [".sbp.json", ".json", ".sbp"]
+            .iter()
+            .find_map(|ext| name.strip_suffix(ext))
+            .unwrap_or(&name);
+        let extension = match logging {
+            SbpLogging::SBP => "sbp",
+            SbpLogging::SBP_JSON => "sbp.json",
+        };
+        path.with_file_name(format!("{stem}.{extension}"))
+    }
+
+    /// Directory the session's logs are written to.
+    pub fn log_dir(&self, ctx: &LogPathContext) -> PathBuf {
+        let path = self.render(ctx);
+        path.parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| ctx.logdir.clone())
+    }
+}
+
+impl FromStr for LogPathTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rest = s;
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                bail!("unmatched '}}' in log path template");
+            }
+            let end = start
+                + rest[start..]
+                    .find('}')
+                    .ok_or_else(|| anyhow!("unmatched '{{' in log path template"))?;
+            let placeholder = &rest[start + 1..end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "unknown placeholder {{{}}}, expected one of {}",
+                    placeholder,
+                    PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", ")
+                );
+            }
+            rest = &rest[end + 1..];
+        }
+        if s.is_empty() || s.ends_with(['/', '\\']) {
+            bail!("log path template must end with a file name");
+        }
+        Ok(LogPathTemplate(s.to_string()))
+    }
+}
+
+impl fmt::Display for LogPathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Keep the last path component of connection names such as `/dev/ttyUSB0`.
+fn last_component(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
+}
+
+/// Make a value safe to use as a single path component.
+fn sanitize(value: Option<&str>) -> String {
+    let value: String = value
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let value = value.trim_matches(['_', '.']);
+    if value.is_empty() {
+        UNKNOWN_VALUE.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> LogPathContext {
+        LogPathContext {
+            logdir: PathBuf::from("/logs"),
+            product_id: Some("Piksi Multi".into()),
+            serial: Some("1234".into()),
+            firmware: None,
+            conn: Some("192.168.0.222:55555".into()),
+            time: Local.with_ymd_and_hms(2023, 5, 4, 13, 2, 1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn render_template() {
+        let template: LogPathTemplate = "{logdir}/{product_id}/{serial}/{date}/{time}_{conn}.sbp"
+            .parse()
+            .unwrap();
+        let ctx = context();
+        assert_eq!(
+            template.sbp_path(&ctx, SbpLogging::SBP),
+            PathBuf::from("/logs/Piksi_Multi/1234/20230504/130201_192.168.0.222_55555.sbp")
+        );
+        assert_eq!(
+            template.sbp_path(&ctx, SbpLogging::SBP_JSON),
+            PathBuf::from("/logs/Piksi_Multi/1234/20230504/130201_192.168.0.222_55555.sbp.json")
+        );
+        assert_eq!(
+            template.log_dir(&ctx),
+            PathBuf::from("/logs/Piksi_Multi/1234/20230504")
+        );
+
+        let template: LogPathTemplate = "{firmware}/{conn}".parse().unwrap();
+        let ctx = LogPathContext {
+            conn: Some("/dev/ttyUSB0".into()),
+            ..ctx
+        };
+        assert_eq!(
+            template.sbp_path(&ctx, SbpLogging::SBP),
+            PathBuf::from("/logs/unknown/ttyUSB0.sbp")
+        );
+    }
+
+    #[test]
+    fn reject_invalid_templates() {
+        assert!("{logdir}/{device}.sbp".parse::<LogPathTemplate>().is_err());
+        assert!("{logdir}/{serial.sbp".parse::<LogPathTemplate>().is_err());
+        assert!("{logdir}/serial}.sbp".parse::<LogPathTemplate>().is_err());
+        assert!("{logdir}/{serial}/".parse::<LogPathTemplate>().is_err());
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Repair of logs left behind by a console that crashed or lost power.
+//!
+//! Every logging session writes a metadata sidecar when it starts and stamps its
+//! `stop_time` when it ends, so a sidecar without a stop time marks a session that
+//! never finished. Its logs are cut back to the last complete SBP frame, or the last
+//! complete line for SBP-JSON, CSV and RINEX, and the sidecar is marked as recovered.
+//! Track exports are cut back to their last complete segment and closed.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use glob::{glob, Pattern};
+use log::{error, info, warn};
+
+use crate::constants::{LOG_RECOVERY_QUIET_PERIOD, SESSION_METADATA_EXTENSION};
+use crate::sbp_framing;
+use crate::shared_state::SharedState;
+use crate::track_export::TrackFormat;
+use crate::types::Result;
+
+// Only the end of a log is examined, SBP frames are at most 263 bytes.
+const TAIL_LEN: u64 = 64 * 1024;
+
+/// A log that was cut back to its last complete record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairedLog {
+    pub path: PathBuf,
+    pub removed_bytes: u64,
+}
+
+/// Repair the logs of unfinished sessions in the logging directory on a background
+/// thread and report what was done in the log panel.
+///
+/// # Parameters
+/// - `shared_state`: The shared state to read the logging directory from.
+/// - `started`: When this console started, sessions started since are left alone.
+pub fn recover_logs(shared_state: &SharedState, started: DateTime<Local>) {
+    let dir = shared_state.logging_directory();
+    thread::spawn(move || match recover_sessions(&dir, started) {
+        Ok(repaired) => {
+            for log in repaired {
+                warn!(
+                    "Recovered {} from an interrupted logging session, removed {} trailing bytes",
+                    log.path.display(),
+                    log.removed_bytes
+                );
+            }
+        }
+        Err(e) => error!("Issue recovering logs in {}, {e}", dir.display()),
+    });
+}
+
+/// Repair the logs of every unfinished session started before `before`, found
+/// anywhere under `dir` since templated log paths place sessions in subdirectories.
+///
+/// # Returns
+/// - The logs that were cut, logs which were already complete are not included.
+pub fn recover_sessions(dir: &Path, before: DateTime<Local>) -> Result<Vec<RepairedLog>> {
+    let mut repaired = Vec::new();
+    let pattern = Path::new(&Pattern::escape(&dir.to_string_lossy()))
+        .join(format!("**/*{SESSION_METADATA_EXTENSION}"));
+    for sidecar in glob(&pattern.to_string_lossy())? {
+        let sidecar = match sidecar {
+            Ok(sidecar) => sidecar,
+            Err(e) => {
+                warn!("Skipping unreadable {}, {e}", e.path().display());
+                continue;
+            }
+        };
+        let mut metadata = match read_metadata(&sidecar) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!(
+                    "Skipping unreadable session metadata {}, {e}",
+                    sidecar.display()
+                );
+                continue;
+            }
+        };
+        let Some(logs) = unfinished_session_logs(&metadata, before) else {
+            continue;
+        };
+        if logs.iter().any(|log| recently_modified(log)) {
+            continue;
+        }
+        for log in logs.iter().filter(|log| log.exists()) {
+            match repair_log(log) {
+                Ok(None) => {}
+                Ok(Some(removed_bytes)) => repaired.push(RepairedLog {
+                    path: log.clone(),
+                    removed_bytes,
+                }),
+                Err(e) => error!("Issue repairing {}, {e}", log.display()),
+            }
+        }
+        metadata["recovered_at"] = serde_json::to_value(Local::now())?;
+        serde_json::to_writer_pretty(File::create(&sidecar)?, &metadata)?;
+        info!("Closed interrupted logging session {}", sidecar.display());
+    }
+    Ok(repaired)
+}
+
+fn read_metadata(path: &Path) -> Result<serde_json::Value> {
+    Ok(serde_json::from_reader(File::open(path)?)?)
+}
+
+/// The logs of a session that started before `before`, never stopped and hasn't
+/// been recovered yet.
+fn unfinished_session_logs(
+    metadata: &serde_json::Value,
+    before: DateTime<Local>,
+) -> Option<Vec<PathBuf>> {
+    if !metadata["stop_time"].is_null() || !metadata["recovered_at"].is_null() {
+        return None;
+    }
+    let start_time = DateTime::parse_from_rfc3339(metadata["start_time"].as_str()?).ok()?;
+    if start_time >= before {
+        return None;
+    }
+    let logs = metadata["logs"]
+        .as_array()?
+        .iter()
+        .filter_map(|log| log.as_str().map(PathBuf::from))
+        .collect();
+    Some(logs)
+}
+
+fn recently_modified(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < LOG_RECOVERY_QUIET_PERIOD)
+}
+
+/// Cut a log back to its last complete record.
+///
+/// # Returns
+/// - The number of bytes removed, or `None` if the log was left as it was.
+pub fn repair_log(path: &Path) -> Result<Option<u64>> {
+    let name = path.to_string_lossy();
+    let track = [
+        (".gpx", TrackFormat::GPX),
+        (".kml", TrackFormat::KML),
+        (".geojson", TrackFormat::GEOJSON),
+    ]
+    .iter()
+    .find(|(ext, _)| name.ends_with(ext));
+    if let Some((_, format)) = track {
+        return repair_track(path, format.clone());
+    }
+    let binary = name.ends_with(".sbp");
+    let text = [".json", ".csv", ".obs", ".nav"]
+        .iter()
+        .any(|ext| name.ends_with(ext));
+    if !binary && !text {
+        return Ok(None);
+    }
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let len = file.metadata()?.len();
+    let tail_start = len.saturating_sub(TAIL_LEN);
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(tail_start))?;
+    file.read_to_end(&mut tail)?;
+    let complete = if binary {
+        sbp_framing::complete_len(&tail)
+    } else {
+        complete_lines_len(&tail)
+    };
+    let complete = match complete {
+        Some(complete) => tail_start + complete as u64,
+        // nothing complete in the whole file, so there is nothing worth keeping
+        None if tail_start == 0 => 0,
+        // nothing complete at the end of a large file, better not to guess
+        None => return Ok(None),
+    };
+    if complete == len {
+        return Ok(None);
+    }
+    file.set_len(complete)?;
+    file.sync_all()?;
+    Ok(Some(len - complete))
+}
+
+/// Cut a track export back to its last complete segment and close the document.
+fn repair_track(path: &Path, format: TrackFormat) -> Result<Option<u64>> {
+    let data = fs::read(path)?;
+    if data.ends_with(format.trailer().as_bytes()) {
+        return Ok(None);
+    }
+    let Some(complete) = format.complete_len(&data) else {
+        // not even the header was written, so there is nothing worth keeping
+        fs::remove_file(path)?;
+        return Ok(Some(data.len() as u64));
+    };
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.set_len(complete as u64)?;
+    file.seek(SeekFrom::End(0))?;
+    file.write_all(format.trailer().as_bytes())?;
+    file.sync_all()?;
+    Ok(Some((data.len() - complete) as u64))
+}
+
+/// Length up to and including the last newline.
+fn complete_lines_len(data: &[u8]) -> Option<usize> {
+    data.iter().rposition(|&b| b == b'\n').map(|idx| idx + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use sbp::messages::logging::MsgLog;
+    use sbp::Sbp;
+    use tempfile::TempDir;
+
+    use crate::track_export::{TrackPoint, TrackWriter};
+    use crate::types::GnssModes;
+
+    fn msg_log() -> Sbp {
+        MsgLog {
+            sender_id: Some(1337),
+            level: 6,
+            text: String::from("hello").into(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn repair_sbp_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("test.sbp");
+        let mut data = Vec::new();
+        sbp::to_writer(&mut data, &msg_log()).unwrap();
+        sbp::to_writer(&mut data, &msg_log()).unwrap();
+        let complete = data.len();
+        sbp::to_writer(&mut data, &msg_log()).unwrap();
+        data.truncate(complete + 5);
+        fs::write(&path, &data).unwrap();
+
+        assert_eq!(repair_log(&path).unwrap(), Some(5));
+        assert_eq!(fs::metadata(&path).unwrap().len(), complete as u64);
+        let msgs = sbp::iter_messages(File::open(&path).unwrap()).count();
+        assert_eq!(msgs, 2);
+        assert_eq!(repair_log(&path).unwrap(), None);
+    }
+
+    fn track_point(secs: u32, mode: GnssModes) -> TrackPoint {
+        TrackPoint {
+            time: Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, secs).unwrap(),
+            lat: 37.77,
+            lon: -122.39,
+            height: 10.0,
+            h_accuracy: 0.5,
+            v_accuracy: 0.8,
+            n_sats: 12,
+            mode,
+        }
+    }
+
+    /// A track with two complete segments and a third cut short, and the track
+    /// with only the first two segments written by a clean finish.
+    fn crashed_track(dir: &Path, format: &TrackFormat) -> (PathBuf, String) {
+        let write = |path: &Path, points: &[TrackPoint]| {
+            let mut track = TrackWriter::new(path, format.clone()).unwrap();
+            for point in points {
+                track.write_point(point.clone()).unwrap();
+            }
+            track.finish().unwrap();
+            fs::read(path).unwrap()
+        };
+        let ext = format.to_string().to_lowercase();
+        let mut points = vec![
+            track_point(0, GnssModes::Spp),
+            track_point(1, GnssModes::Spp),
+            track_point(2, GnssModes::Fixed),
+            track_point(3, GnssModes::Fixed),
+        ];
+        let expected = write(&dir.join(format!("expected.{ext}")), &points);
+        points.push(track_point(4, GnssModes::Float));
+        points.push(track_point(5, GnssModes::Float));
+        points.push(track_point(6, GnssModes::Dgnss));
+        let crashed = dir.join(format!("crashed.{ext}"));
+        let mut data = write(&crashed, &points);
+        // lose the trailer and the end of the Float segment
+        data.truncate(expected.len() - format.trailer().len() + 40);
+        fs::write(&crashed, data).unwrap();
+        (crashed, String::from_utf8(expected).unwrap())
+    }
+
+    #[test]
+    fn repair_track_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        for format in TrackFormat::all() {
+            let (crashed, expected) = crashed_track(tmp_dir.path(), &format);
+            assert!(repair_log(&crashed).unwrap().is_some(), "{format}");
+            assert_eq!(fs::read_to_string(&crashed).unwrap(), expected, "{format}");
+            assert_eq!(repair_log(&crashed).unwrap(), None);
+        }
+        let geojson = fs::read_to_string(tmp_dir.path().join("crashed.geojson")).unwrap();
+        let geojson: serde_json::Value = serde_json::from_str(&geojson).unwrap();
+        assert_eq!(geojson["features"].as_array().unwrap().len(), 6);
+    }
+
+    /// An unfinished session in `dir` with a CSV log whose last line was cut short.
+    fn unfinished_session(dir: &Path) -> (PathBuf, PathBuf) {
+        fs::create_dir_all(dir).unwrap();
+        let csv = dir.join("position_log.csv");
+        let mut file = File::create(&csv).unwrap();
+        write!(file, "pc_time,tow(sec)\n1,2\n3,").unwrap();
+        let sidecar = dir.join("csv_log.metadata.json");
+        let start_time = Local::now() - Duration::minutes(5);
+        let metadata = serde_json::json!({
+            "logs": [csv],
+            "start_time": start_time,
+            "stop_time": null,
+        });
+        fs::write(&sidecar, metadata.to_string()).unwrap();
+        let modified = SystemTime::now() - LOG_RECOVERY_QUIET_PERIOD * 2;
+        File::options()
+            .write(true)
+            .open(&csv)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        (csv, sidecar)
+    }
+
+    #[test]
+    fn recover_sessions_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let (csv, sidecar) = unfinished_session(tmp_dir.path());
+
+        let repaired = recover_sessions(tmp_dir.path(), Local::now()).unwrap();
+        assert_eq!(
+            repaired,
+            vec![RepairedLog {
+                path: csv.clone(),
+                removed_bytes: 2
+            }]
+        );
+        assert_eq!(fs::read_to_string(&csv).unwrap(), "pc_time,tow(sec)\n1,2\n");
+        let metadata: serde_json::Value =
+            serde_json::from_reader(File::open(&sidecar).unwrap()).unwrap();
+        assert!(!metadata["recovered_at"].is_null());
+        // already recovered
+        assert!(recover_sessions(tmp_dir.path(), Local::now())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn recover_templated_sessions_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let logdir = tmp_dir.path().join("logs [field]");
+        // as laid out by `{logdir}/{product_id}/{serial}/{date}/{time}_{conn}.sbp`
+        let (first, _) = unfinished_session(&logdir.join("Piksi Multi/1234/2022-01-01"));
+        let (second, _) = unfinished_session(&logdir.join("Piksi Multi/5678/2022-01-02"));
+
+        let mut repaired: Vec<PathBuf> = recover_sessions(&logdir, Local::now())
+            .unwrap()
+            .into_iter()
+            .map(|log| log.path)
+            .collect();
+        repaired.sort();
+        assert_eq!(repaired, vec![first, second]);
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Unattended SBP logging sessions started and stopped on a schedule.
+//!
+//! A schedule has optional UTC start and stop times, which happen once or every day,
+//! and optional limits on the duration and size of each session. Without a start time
+//! logging begins right away, e.g. "log for 45 minutes then stop".
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use log::info;
+use serde::Deserialize;
+use strum_macros::{Display, EnumString, EnumVariantNames};
+
+use crate::cli_options::{byte_size, duration, schedule_time};
+use crate::client_sender::BoxedClientSender;
+use crate::constants::LOG_SCHEDULE_INTERVAL;
+use crate::shared_state::SharedState;
+use crate::types::Result;
+use crate::utils::refresh_loggingbar;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Display, EnumString, EnumVariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Recurrence {
+    #[default]
+    Once,
+    Daily,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogSchedule {
+    /// UTC time to start logging, right away if not set.
+    pub start: Option<NaiveTime>,
+    /// UTC time to stop logging.
+    pub stop: Option<NaiveTime>,
+    pub recurrence: Recurrence,
+    pub max_duration: Option<Duration>,
+    /// Stop once the SBP log reaches this many bytes.
+    pub max_size: Option<u64>,
+}
+
+/// A schedule as written in a YAML config file, e.g.
+///
+/// ```yaml
+/// start: "02:00"
+/// stop: "06:00"
+/// recurrence: daily
+/// max_size: 2G
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScheduleFile {
+    start: Option<serde_yaml::Value>,
+    stop: Option<serde_yaml::Value>,
+    recurrence: Option<Recurrence>,
+    max_duration: Option<serde_yaml::Value>,
+    max_size: Option<serde_yaml::Value>,
+}
+
+impl LogSchedule {
+    pub fn is_empty(&self) -> bool {
+        self.start.is_none()
+            && self.stop.is_none()
+            && self.max_duration.is_none()
+            && self.max_size.is_none()
+    }
+
+    /// Read a schedule from a YAML config file.
+    pub fn from_file(path: &Path) -> Result<LogSchedule> {
+        let file =
+            fs::File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+        let config: ScheduleFile = serde_yaml::from_reader(file)?;
+        Ok(LogSchedule {
+            start: parse_field(config.start, schedule_time)?,
+            stop: parse_field(config.stop, schedule_time)?,
+            recurrence: config.recurrence.unwrap_or_default(),
+            max_duration: parse_field(config.max_duration, duration)?,
+            max_size: parse_field(config.max_size, byte_size)?,
+        })
+    }
+
+    /// When a session started at `start` should end, if ever.
+    fn session_end(&self, start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let stop = self.stop.map(|stop| next_occurrence(stop, start));
+        let limit = self
+            .max_duration
+            .and_then(|d| chrono::Duration::from_std(d).ok())
+            .map(|d| start + d);
+        match (stop, limit) {
+            (Some(stop), Some(limit)) => Some(stop.min(limit)),
+            (stop, limit) => stop.or(limit),
+        }
+    }
+}
+
+/// Parse a config file value with the same validator as its command line option.
+fn parse_field<T>(
+    value: Option<serde_yaml::Value>,
+    parser: fn(&str) -> std::result::Result<T, String>,
+) -> Result<Option<T>> {
+    let value = match value {
+        None => return Ok(None),
+        Some(serde_yaml::Value::String(s)) => s,
+        Some(serde_yaml::Value::Number(n)) => n.to_string(),
+        Some(other) => return Err(anyhow!("unexpected value {other:?}")),
+    };
+    parser(&value).map(Some).map_err(|e| anyhow!(e))
+}
+
+/// The first time of day `time` strictly after `after`.
+fn next_occurrence(time: NaiveTime, after: DateTime<Utc>) -> DateTime<Utc> {
+    let today = Utc.from_utc_datetime(&after.date_naive().and_time(time));
+    if today > after {
+        today
+    } else {
+        today + chrono::Duration::days(1)
+    }
+}
+
+/// The last time of day `time` at or before `at`.
+fn last_occurrence(time: NaiveTime, at: DateTime<Utc>) -> DateTime<Utc> {
+    let today = Utc.from_utc_datetime(&at.date_naive().and_time(time));
+    if today <= at {
+        today
+    } else {
+        today - chrono::Duration::days(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduleState {
+    Waiting { start: DateTime<Utc> },
+    Logging { until: Option<DateTime<Utc>> },
+    Done,
+}
+
+impl fmt::Display for ScheduleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+        match self {
+            ScheduleState::Waiting { start } => {
+                write!(f, "Scheduled to log from {}", start.format(FORMAT))
+            }
+            ScheduleState::Logging { until: Some(until) } => {
+                write!(f, "Scheduled logging until {}", until.format(FORMAT))
+            }
+            ScheduleState::Logging { until: None } => write!(f, "Scheduled logging"),
+            ScheduleState::Done => write!(f, "Scheduled logging finished"),
+        }
+    }
+}
+
+/// Decides when a [`LogSchedule`] should be logging.
+#[derive(Debug)]
+pub struct LogScheduler {
+    schedule: LogSchedule,
+    state: ScheduleState,
+}
+
+impl LogScheduler {
+    /// Start following `schedule`, picking up a session that should already be running.
+    pub fn new(schedule: LogSchedule, now: DateTime<Utc>) -> LogScheduler {
+        let state = match schedule.start {
+            None => ScheduleState::Logging {
+                until: schedule.session_end(now),
+            },
+            Some(start) => match schedule.session_end(last_occurrence(start, now)) {
+                Some(until) if now < until => ScheduleState::Logging { until: Some(until) },
+                _ => ScheduleState::Waiting {
+                    start: next_occurrence(start, now),
+                },
+            },
+        };
+        LogScheduler { schedule, state }
+    }
+
+    pub fn state(&self) -> ScheduleState {
+        self.state
+    }
+
+    /// Advance the schedule.
+    ///
+    /// # Parameters
+    /// - `now`: The current time.
+    /// - `log_size`: Size of the SBP log being written, in bytes.
+    pub fn update(&mut self, now: DateTime<Utc>, log_size: u64) -> ScheduleState {
+        self.state = match self.state {
+            ScheduleState::Waiting { start } if now >= start => ScheduleState::Logging {
+                until: self.schedule.session_end(start),
+            },
+            ScheduleState::Logging { until }
+                if until.is_some_and(|until| now >= until)
+                    || self.schedule.max_size.is_some_and(|max| log_size >= max) =>
+            {
+                match (self.schedule.recurrence, self.schedule.start) {
+                    (Recurrence::Daily, Some(start)) => ScheduleState::Waiting {
+                        start: next_occurrence(start, now),
+                    },
+                    _ => ScheduleState::Done,
+                }
+            }
+            state => state,
+        };
+        self.state
+    }
+}
+
+/// Follow the schedule set from the command line or config file, if any, on a
+/// background thread. SBP logging is switched on and off as the schedule changes
+/// state, so it can still be toggled by hand in between.
+pub fn start_log_schedule(client_sender: &BoxedClientSender, shared_state: &SharedState) {
+    let Some(schedule) = shared_state.log_schedule() else {
+        return;
+    };
+    let client_sender = client_sender.clone();
+    let shared_state = shared_state.clone();
+    thread::spawn(move || {
+        let mut scheduler = LogScheduler::new(schedule, Utc::now());
+        let mut logging = false;
+        let mut status = None;
+        loop {
+            let log_size = shared_state
+                .sbp_logging_filepath()
+                .and_then(|path| fs::metadata(path).ok())
+                .map_or(0, |metadata| metadata.len());
+            let state = scheduler.update(Utc::now(), log_size);
+            let should_log = matches!(state, ScheduleState::Logging { .. });
+            if should_log != logging {
+                info!("{state}");
+                shared_state.set_sbp_logging(should_log);
+                logging = should_log;
+            }
+            let state_str = state.to_string();
+            if status.as_ref() != Some(&state_str) {
+                shared_state.set_log_schedule_status(Some(state_str.clone()));
+                refresh_loggingbar(&client_sender, &shared_state);
+                status = Some(state_str);
+            }
+            if state == ScheduleState::Done {
+                break;
+            }
+            thread::sleep(LOG_SCHEDULE_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, 1, 1, h, m, 0).unwrap()
+    }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn duration_limited_test() {
+        let schedule = LogSchedule {
+            max_duration: Some(Duration::from_secs(45 * 60)),
+            ..Default::default()
+        };
+        let mut scheduler = LogScheduler::new(schedule, utc(10, 0));
+        let until = Some(utc(10, 45));
+        assert_eq!(scheduler.state(), ScheduleState::Logging { until });
+        assert_eq!(
+            scheduler.update(utc(10, 44), 0),
+            ScheduleState::Logging { until }
+        );
+        assert_eq!(scheduler.update(utc(10, 45), 0), ScheduleState::Done);
+    }
+
+    #[test]
+    fn daily_window_test() {
+        let schedule = LogSchedule {
+            start: Some(time(2, 0)),
+            stop: Some(time(6, 0)),
+            recurrence: Recurrence::Daily,
+            max_size: Some(1000),
+            ..Default::default()
+        };
+        let next_day = |dt: DateTime<Utc>| dt + chrono::Duration::days(1);
+
+        // started inside the window picks up the running session
+        let scheduler = LogScheduler::new(schedule.clone(), utc(3, 0));
+        let until = Some(utc(6, 0));
+        assert_eq!(scheduler.state(), ScheduleState::Logging { until });
+
+        let mut scheduler = LogScheduler::new(schedule, utc(12, 0));
+        let start = next_day(utc(2, 0));
+        assert_eq!(scheduler.state(), ScheduleState::Waiting { start });
+        assert_eq!(
+            scheduler.update(start, 0),
+            ScheduleState::Logging {
+                until: Some(next_day(utc(6, 0)))
+            }
+        );
+        // stopping early on size waits for the next night
+        assert_eq!(
+            scheduler.update(next_day(utc(3, 0)), 1000),
+            ScheduleState::Waiting {
+                start: next_day(next_day(utc(2, 0)))
+            }
+        );
+    }
+
+    #[test]
+    fn schedule_file_test() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let path = tmp_dir.path().join("schedule.yaml");
+        fs::write(
+            &path,
+            "start: \"02:00\"\nstop: 06:00:30\nrecurrence: daily\nmax_duration: 45m\nmax_size: 1000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            LogSchedule::from_file(&path).unwrap(),
+            LogSchedule {
+                start: Some(time(2, 0)),
+                stop: Some(NaiveTime::from_hms_opt(6, 0, 30).unwrap()),
+                recurrence: Recurrence::Daily,
+                max_duration: Some(Duration::from_secs(45 * 60)),
+                max_size: Some(1000),
+            }
+        );
+        fs::write(&path, "start: noon\n").unwrap();
+        assert!(LogSchedule::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn window_across_midnight_test() {
+        let schedule = LogSchedule {
+            start: Some(time(22, 0)),
+            stop: Some(time(2, 0)),
+            ..Default::default()
+        };
+        let mut scheduler = LogScheduler::new(schedule, utc(21, 0));
+        assert!(matches!(
+            scheduler.update(utc(22, 0), 0),
+            ScheduleState::Logging { until: Some(until) } if until == utc(2, 0) + chrono::Duration::days(1)
+        ));
+    }
+}
@@ -0,0 +1,1131 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! NMEA 0183 output generated from the SBP solution.
+//!
+//! [`NmeaGenerator`] turns `PosLLH`, `VelNED`, `Dops`, `MsgObs`, `MsgSvAzEl` and
+//! `MsgUtcTime` into GGA, RMC, GSA, GSV, VTG, GST and ZDA sentences and [`NmeaOutput`] writes
+//! them to any number of files, TCP clients, UDP targets or serial ports. [`Gga`] is
+//! also used for the position sent to NTRIP casters.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use chrono::{Datelike, Timelike};
+use crossbeam::channel::{self, Sender, TrySendError};
+use log::{debug, error, info};
+use sbp::messages::observation::{MsgObs, MsgSvAzEl};
+use sbp::Sbp;
+use serialport::SerialPort;
+use strum_macros::{Display, EnumString, EnumVariantNames};
+
+use crate::types::{Dops, GnssModes, PosLLH, Result, SignalCodes, UtcDateTime, VelNED};
+use crate::utils::date_conv::utc_time_from_msg;
+
+const NMEA_DEFAULT_BAUDRATE: u32 = 115200;
+const NMEA_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+/// Epochs queued for the writer thread before new ones are dropped.
+const NMEA_QUEUE_LEN: usize = 16;
+const NMEA_GSV_SATS_PER_SENTENCE: usize = 4;
+const NMEA_GSA_SATS_PER_SENTENCE: usize = 12;
+const NMEA_SBAS_PRN_OFFSET: u16 = 87;
+const NMEA_GLO_ID_OFFSET: u16 = 64;
+const NMEA_QZSS_PRN_OFFSET: u16 = 192;
+const MS_TO_KNOTS: f64 = 1.943_844_5;
+const MS_TO_KPH: f64 = 3.6;
+const OBS_PSEUDORANGE_VALID: u8 = 0x01;
+const OBS_RAIM_EXCLUDED: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, Display, EnumString, EnumVariantNames, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum NmeaSentence {
+    GGA,
+    RMC,
+    GSA,
+    GSV,
+    VTG,
+    GST,
+    ZDA,
+}
+
+impl NmeaSentence {
+    pub fn all() -> Vec<NmeaSentence> {
+        vec![
+            NmeaSentence::GGA,
+            NmeaSentence::RMC,
+            NmeaSentence::GSA,
+            NmeaSentence::GSV,
+            NmeaSentence::VTG,
+            NmeaSentence::GST,
+            NmeaSentence::ZDA,
+        ]
+    }
+}
+
+/// Where NMEA sentences are sent.
+///
+/// Parsed from `file:<path>`, `tcp:[<address>:]<port>`, `udp:<host>:<port>` or
+/// `serial:<device>[@<baudrate>]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NmeaTarget {
+    File(PathBuf),
+    /// Address to listen on for TCP clients.
+    Tcp(String),
+    Udp(String),
+    Serial {
+        device: String,
+        baudrate: u32,
+    },
+}
+
+impl FromStr for NmeaTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, value) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected <file|tcp|udp|serial>:<target>, got {s}"))?;
+        if value.is_empty() {
+            return Err(anyhow!("missing {kind} target"));
+        }
+        match kind {
+            "file" => Ok(NmeaTarget::File(PathBuf::from(value))),
+            "tcp" if value.parse::<u16>().is_ok() => {
+                Ok(NmeaTarget::Tcp(format!("0.0.0.0:{value}")))
+            }
+            "tcp" => Ok(NmeaTarget::Tcp(value.to_string())),
+            "udp" => Ok(NmeaTarget::Udp(value.to_string())),
+            "serial" => {
+                let (device, baudrate) = match value.rsplit_once('@') {
+                    Some((device, baudrate)) => (
+                        device,
+                        baudrate
+                            .parse()
+                            .with_context(|| format!("invalid baudrate {baudrate}"))?,
+                    ),
+                    None => (value, NMEA_DEFAULT_BAUDRATE),
+                };
+                Ok(NmeaTarget::Serial {
+                    device: device.to_string(),
+                    baudrate,
+                })
+            }
+            _ => Err(anyhow!("unknown NMEA output type {kind}")),
+        }
+    }
+}
+
+impl fmt::Display for NmeaTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NmeaTarget::File(path) => write!(f, "file:{}", path.display()),
+            NmeaTarget::Tcp(address) => write!(f, "tcp:{address}"),
+            NmeaTarget::Udp(address) => write!(f, "udp:{address}"),
+            NmeaTarget::Serial { device, baudrate } => write!(f, "serial:{device}@{baudrate}"),
+        }
+    }
+}
+
+enum NmeaSink {
+    File(BufWriter<File>),
+    Tcp {
+        listener: TcpListener,
+        clients: Vec<TcpStream>,
+    },
+    Udp {
+        socket: UdpSocket,
+        address: String,
+    },
+    Serial(Box<dyn SerialPort>),
+}
+
+impl fmt::Debug for NmeaSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NmeaSink::File(_) => write!(f, "NmeaSink::File"),
+            NmeaSink::Tcp { clients, .. } => write!(f, "NmeaSink::Tcp({} clients)", clients.len()),
+            NmeaSink::Udp { address, .. } => write!(f, "NmeaSink::Udp({address})"),
+            NmeaSink::Serial(_) => write!(f, "NmeaSink::Serial"),
+        }
+    }
+}
+
+impl NmeaSink {
+    fn open(target: &NmeaTarget) -> Result<NmeaSink> {
+        let sink = match target {
+            NmeaTarget::File(path) => NmeaSink::File(BufWriter::new(File::create(path)?)),
+            NmeaTarget::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                NmeaSink::Tcp {
+                    listener,
+                    clients: Vec::new(),
+                }
+            }
+            NmeaTarget::Udp(address) => NmeaSink::Udp {
+                socket: UdpSocket::bind("0.0.0.0:0")?,
+                address: address.clone(),
+            },
+            NmeaTarget::Serial { device, baudrate } => NmeaSink::Serial(
+                serialport::new(device, *baudrate)
+                    .timeout(NMEA_WRITE_TIMEOUT)
+                    .open()?,
+            ),
+        };
+        Ok(sink)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            NmeaSink::File(file) => {
+                file.write_all(buf)?;
+                file.flush()
+            }
+            NmeaSink::Tcp { listener, clients } => {
+                loop {
+                    match listener.accept() {
+                        Ok((client, address)) => {
+                            info!("NMEA client connected from {address}");
+                            client.set_nonblocking(false)?;
+                            client.set_write_timeout(Some(NMEA_WRITE_TIMEOUT))?;
+                            clients.push(client);
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                        Err(e) => return Err(e),
+                    }
+                }
+                // clients that can't keep up or have gone away are dropped
+                clients.retain_mut(|client| client.write_all(buf).is_ok());
+                Ok(())
+            }
+            NmeaSink::Udp { socket, address } => {
+                socket.send_to(buf, address.as_str())?;
+                Ok(())
+            }
+            NmeaSink::Serial(port) => port.write_all(buf),
+        }
+    }
+}
+
+/// The solution of one epoch, waiting for its velocity.
+#[derive(Debug, Clone, Copy)]
+struct NmeaEpoch {
+    time: UtcDateTime,
+    tow: f64,
+    mode: GnssModes,
+    lat: f64,
+    lon: f64,
+    height: f64,
+    h_accuracy: f64,
+    v_accuracy: f64,
+    n_sats: u8,
+}
+
+/// Velocity over ground in m/s and true course in degrees.
+#[derive(Debug, Clone, Copy)]
+struct NmeaVelocity {
+    speed: f64,
+    course: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct NmeaDops {
+    pdop: f64,
+    hdop: f64,
+    vdop: f64,
+}
+
+/// The fields of a GGA sentence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gga {
+    pub time: UtcDateTime,
+    pub mode: GnssModes,
+    pub lat: f64,
+    pub lon: f64,
+    /// Height above the ellipsoid in meters.
+    pub height: f64,
+    /// Height above the geoid in meters, when the receiver reports it.
+    pub orthometric_height: Option<f64>,
+    pub n_sats: u8,
+    pub hdop: Option<f64>,
+    /// Age of the differential corrections in seconds.
+    pub age_corrections: Option<f64>,
+}
+
+impl Gga {
+    /// The sentence, without a line ending.
+    pub fn sentence(&self) -> String {
+        let quality = match self.mode {
+            GnssModes::NoFix => 0,
+            GnssModes::Spp => 1,
+            GnssModes::Dgnss | GnssModes::Sbas => 2,
+            GnssModes::Fixed => 4,
+            GnssModes::Float => 5,
+            GnssModes::Dr => 6,
+        };
+        let hdop = self.hdop.map(|h| format!("{h:.1}")).unwrap_or_default();
+        // Without the receiver's geoid the ellipsoidal height is reported with no separation.
+        let (altitude, separation) = match self.orthometric_height {
+            Some(orthometric) => (orthometric, self.height - orthometric),
+            None => (self.height, 0.0),
+        };
+        let altitude = fix_field(self.mode, format!("{altitude:.3}"));
+        let age = self
+            .age_corrections
+            .filter(|_| {
+                matches!(
+                    self.mode,
+                    GnssModes::Dgnss | GnssModes::Float | GnssModes::Fixed
+                )
+            })
+            .map(|age| format!("{age:.1}"))
+            .unwrap_or_default();
+        sentence(format!(
+            "GNGGA,{},{},{},{quality},{:02},{hdop},{altitude},M,{separation:.3},M,{age},",
+            time_field(&self.time),
+            lat_field(self.mode, self.lat),
+            lon_field(self.mode, self.lon),
+            self.n_sats
+        ))
+    }
+}
+
+/// Talker ids used for the satellites in GSV sentences.
+#[derive(Debug, Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+enum Talker {
+    Gps,
+    Glonass,
+    Galileo,
+    Beidou,
+    Qzss,
+}
+
+const TALKERS: [Talker; 5] = [
+    Talker::Gps,
+    Talker::Glonass,
+    Talker::Galileo,
+    Talker::Beidou,
+    Talker::Qzss,
+];
+
+impl Talker {
+    /// The talker and NMEA satellite id of a satellite.
+    fn sat(code: u8, sat: u8) -> Option<(Talker, u16)> {
+        let code = SignalCodes::from(code);
+        let sat = sat as u16;
+        let sat = if code.code_is_gps() {
+            (Talker::Gps, sat)
+        } else if code.code_is_sbas() {
+            (Talker::Gps, sat.saturating_sub(NMEA_SBAS_PRN_OFFSET))
+        } else if code.code_is_glo() {
+            (Talker::Glonass, sat + NMEA_GLO_ID_OFFSET)
+        } else if code.code_is_galileo() {
+            (Talker::Galileo, sat)
+        } else if code.code_is_bds() {
+            (Talker::Beidou, sat)
+        } else if code.code_is_qzss() {
+            (Talker::Qzss, sat.saturating_sub(NMEA_QZSS_PRN_OFFSET))
+        } else {
+            return None;
+        };
+        Some(sat)
+    }
+
+    fn id(&self) -> &'static str {
+        match self {
+            Talker::Gps => "GP",
+            Talker::Glonass => "GL",
+            Talker::Galileo => "GA",
+            Talker::Beidou => "GB",
+            Talker::Qzss => "GQ",
+        }
+    }
+}
+
+/// Generates NMEA sentences from SBP messages.
+///
+/// The solution messages of an epoch are collected until the `VelNED` matching the
+/// `PosLLH` arrives, or the next `PosLLH` if velocity isn't being output.
+#[derive(Debug)]
+pub struct NmeaGenerator {
+    sentences: Vec<NmeaSentence>,
+    utc_time: Option<UtcDateTime>,
+    dops: Option<NmeaDops>,
+    sats: BTreeMap<(Talker, u16), (i8, u16)>,
+    /// Satellites with a usable pseudorange in the latest observations of the rover.
+    used: BTreeSet<(Talker, u16)>,
+    rover: Option<u16>,
+    age_corrections: Option<f64>,
+    /// Time of week and height above the geoid of the latest `MsgPosLlhAcc`.
+    orthometric_height: Option<(f64, f64)>,
+    pending: Option<NmeaEpoch>,
+    last_epoch: Option<NmeaEpoch>,
+}
+
+impl NmeaGenerator {
+    pub fn new(sentences: Vec<NmeaSentence>) -> NmeaGenerator {
+        NmeaGenerator {
+            sentences,
+            utc_time: None,
+            dops: None,
+            sats: BTreeMap::new(),
+            used: BTreeSet::new(),
+            rover: None,
+            age_corrections: None,
+            orthometric_height: None,
+            pending: None,
+            last_epoch: None,
+        }
+    }
+
+    /// UTC time reported by the receiver, once it has a valid GPS time.
+    pub fn utc_time(&self) -> Option<UtcDateTime> {
+        self.utc_time
+    }
+
+    /// GGA fields of the latest solution, with the latest DOPs and age of corrections.
+    pub fn last_gga(&self) -> Option<Gga> {
+        self.last_epoch.map(|epoch| self.gga_fields(&epoch))
+    }
+
+    /// Update the generator with `msg`.
+    ///
+    /// # Returns
+    /// - The sentences of any epoch completed by `msg`, without line endings.
+    pub fn handle_msg(&mut self, msg: &Sbp) -> Vec<String> {
+        match msg {
+            Sbp::MsgUtcTime(msg) => {
+                self.utc_time = (msg.flags & 0x7 != 0).then(|| utc_time_from_msg(msg));
+            }
+            Sbp::MsgDops(msg) => self.handle_dops(Dops::MsgDops(msg.clone())),
+            Sbp::MsgDopsDepA(msg) => self.handle_dops(Dops::MsgDopsDepA(msg.clone())),
+            Sbp::MsgSvAzEl(msg) => self.handle_sv_az_el(msg),
+            Sbp::MsgObs(msg) => self.handle_obs(msg),
+            Sbp::MsgAgeCorrections(msg) => {
+                // 0xFFFF when no corrections have been received
+                self.age_corrections = (msg.age != u16::MAX).then(|| msg.age as f64 * 0.1);
+            }
+            Sbp::MsgPosLlhAcc(msg) => {
+                self.orthometric_height = Some((msg.tow as f64, msg.orthometric_height));
+            }
+            Sbp::MsgPosLlh(msg) => return self.handle_pos_llh(PosLLH::MsgPosLlh(msg.clone())),
+            Sbp::MsgPosLlhDepA(msg) => {
+                return self.handle_pos_llh(PosLLH::MsgPosLlhDepA(msg.clone()))
+            }
+            Sbp::MsgVelNed(msg) => return self.handle_vel_ned(VelNED::MsgVelNed(msg.clone())),
+            Sbp::MsgVelNedDepA(msg) => {
+                return self.handle_vel_ned(VelNED::MsgVelNedDepA(msg.clone()))
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn handle_dops(&mut self, msg: Dops) {
+        let fields = msg.fields();
+        self.dops = (fields.flags != 0).then(|| NmeaDops {
+            pdop: fields.pdop as f64 * 0.01,
+            hdop: fields.hdop as f64 * 0.01,
+            vdop: fields.vdop as f64 * 0.01,
+        });
+    }
+
+    fn handle_sv_az_el(&mut self, msg: &MsgSvAzEl) {
+        self.sats.clear();
+        for azel in msg.azel.iter().filter(|azel| (0..=90).contains(&azel.el)) {
+            if let Some(sat) = Talker::sat(azel.sid.code, azel.sid.sat) {
+                self.sats.insert(sat, (azel.el, azel.az as u16 * 2));
+            }
+        }
+    }
+
+    fn handle_obs(&mut self, msg: &MsgObs) {
+        // base station observations are forwarded on the same link
+        if self.rover.is_some() && msg.sender_id != self.rover {
+            return;
+        }
+        // the lower nibble is the index of this message within the epoch
+        if msg.header.n_obs & 0x0F == 0 {
+            self.used.clear();
+        }
+        let used = msg.obs.iter().filter(|obs| {
+            obs.flags & OBS_PSEUDORANGE_VALID != 0 && obs.flags & OBS_RAIM_EXCLUDED == 0
+        });
+        for obs in used {
+            if let Some(sat) = Talker::sat(obs.sid.code, obs.sid.sat) {
+                self.used.insert(sat);
+            }
+        }
+    }
+
+    fn handle_pos_llh(&mut self, msg: PosLLH) -> Vec<String> {
+        let sentences = self.flush(None);
+        let mode = msg.mode();
+        let fields = msg.fields();
+        self.rover = match &msg {
+            PosLLH::MsgPosLlh(msg) => msg.sender_id,
+            PosLLH::MsgPosLlhDepA(msg) => msg.sender_id,
+        };
+        self.pending = self.utc_time.map(|time| NmeaEpoch {
+            time,
+            tow: fields.tow,
+            mode: GnssModes::from(mode.min(GnssModes::Sbas as u8)),
+            lat: fields.lat,
+            lon: fields.lon,
+            height: fields.height,
+            h_accuracy: fields.h_accuracy,
+            v_accuracy: fields.v_accuracy,
+            n_sats: fields.n_sats,
+        });
+        if self.pending.is_some() {
+            self.last_epoch = self.pending;
+        }
+        sentences
+    }
+
+    fn handle_vel_ned(&mut self, msg: VelNED) -> Vec<String> {
+        let fields = msg.fields();
+        if !matches!(self.pending, Some(epoch) if epoch.tow == fields.tow) {
+            return Vec::new();
+        }
+        let velocity = (fields.flags & 0x7 != 0).then(|| {
+            let (n, e) = (fields.n as f64 / 1e3, fields.e as f64 / 1e3);
+            NmeaVelocity {
+                speed: n.hypot(e),
+                course: e.atan2(n).to_degrees().rem_euclid(360.0),
+            }
+        });
+        self.flush(velocity)
+    }
+
+    fn flush(&mut self, velocity: Option<NmeaVelocity>) -> Vec<String> {
+        let epoch = match self.pending.take() {
+            Some(epoch) => epoch,
+            None => return Vec::new(),
+        };
+        let mut sentences = Vec::new();
+        for sentence in self.sentences.iter() {
+            match sentence {
+                NmeaSentence::GGA => sentences.push(self.gga(&epoch)),
+                NmeaSentence::RMC => sentences.push(rmc(&epoch, velocity)),
+                NmeaSentence::GSA => sentences.extend(self.gsa(&epoch)),
+                NmeaSentence::GSV => sentences.extend(self.gsv()),
+                NmeaSentence::VTG => sentences.push(vtg(&epoch, velocity)),
+                NmeaSentence::GST => sentences.push(gst(&epoch)),
+                NmeaSentence::ZDA => sentences.push(zda(&epoch)),
+            }
+        }
+        sentences
+    }
+
+    fn gga(&self, epoch: &NmeaEpoch) -> String {
+        self.gga_fields(epoch).sentence()
+    }
+
+    fn gga_fields(&self, epoch: &NmeaEpoch) -> Gga {
+        Gga {
+            time: epoch.time,
+            mode: epoch.mode,
+            lat: epoch.lat,
+            lon: epoch.lon,
+            height: epoch.height,
+            orthometric_height: self
+                .orthometric_height
+                .filter(|(tow, _)| *tow == epoch.tow)
+                .map(|(_, height)| height),
+            n_sats: epoch.n_sats,
+            hdop: self.dops.map(|d| d.hdop),
+            age_corrections: self.age_corrections,
+        }
+    }
+
+    /// One GSA sentence for every twelve satellites of each constellation used.
+    fn gsa(&self, epoch: &NmeaEpoch) -> Vec<String> {
+        let fix = if epoch.mode == GnssModes::NoFix { 1 } else { 3 };
+        let (pdop, hdop, vdop) = self
+            .dops
+            .map(|d| {
+                (
+                    format!("{:.1}", d.pdop),
+                    format!("{:.1}", d.hdop),
+                    format!("{:.1}", d.vdop),
+                )
+            })
+            .unwrap_or_default();
+        let mut groups = Vec::new();
+        for talker in TALKERS {
+            let ids: Vec<u16> = self
+                .used
+                .iter()
+                .filter(|(sat_talker, _)| *sat_talker == talker)
+                .map(|(_, id)| *id)
+                .collect();
+            groups.extend(ids.chunks(NMEA_GSA_SATS_PER_SENTENCE).map(<[u16]>::to_vec));
+        }
+        if groups.is_empty() {
+            groups.push(Vec::new());
+        }
+        groups
+            .iter()
+            .map(|ids| {
+                let ids: String = (0..NMEA_GSA_SATS_PER_SENTENCE)
+                    .map(|i| match ids.get(i) {
+                        Some(id) => format!("{id:02},"),
+                        None => String::from(","),
+                    })
+                    .collect();
+                sentence(format!("GNGSA,A,{fix},{ids}{pdop},{hdop},{vdop}"))
+            })
+            .collect()
+    }
+
+    fn gsv(&self) -> Vec<String> {
+        let mut sentences = Vec::new();
+        for talker in TALKERS {
+            let sats: Vec<_> = self
+                .sats
+                .iter()
+                .filter(|((sat_talker, _), _)| *sat_talker == talker)
+                .collect();
+            let in_view = sats.len();
+            let total = in_view.div_ceil(NMEA_GSV_SATS_PER_SENTENCE);
+            for (i, chunk) in sats.chunks(NMEA_GSV_SATS_PER_SENTENCE).enumerate() {
+                let sats: String = chunk
+                    .iter()
+                    .map(|((_, id), (el, az))| format!(",{id:02},{el:02},{az:03},"))
+                    .collect();
+                sentences.push(sentence(format!(
+                    "{}GSV,{total},{},{in_view:02}{sats}",
+                    talker.id(),
+                    i + 1
+                )));
+            }
+        }
+        sentences
+    }
+}
+
+fn rmc(epoch: &NmeaEpoch, velocity: Option<NmeaVelocity>) -> String {
+    let status = if epoch.mode == GnssModes::NoFix {
+        'V'
+    } else {
+        'A'
+    };
+    let (speed, course) = velocity
+        .map(|v| {
+            (
+                format!("{:.3}", v.speed * MS_TO_KNOTS),
+                format!("{:.1}", v.course),
+            )
+        })
+        .unwrap_or_default();
+    sentence(format!(
+        "GNRMC,{},{status},{},{},{speed},{course},{},,,{}",
+        time_field(&epoch.time),
+        lat_field(epoch.mode, epoch.lat),
+        lon_field(epoch.mode, epoch.lon),
+        epoch.time.format("%d%m%y"),
+        mode_indicator(epoch.mode)
+    ))
+}
+
+fn vtg(epoch: &NmeaEpoch, velocity: Option<NmeaVelocity>) -> String {
+    let (course, knots, kph) = velocity
+        .map(|v| {
+            (
+                format!("{:.1}", v.course),
+                format!("{:.3}", v.speed * MS_TO_KNOTS),
+                format!("{:.3}", v.speed * MS_TO_KPH),
+            )
+        })
+        .unwrap_or_default();
+    sentence(format!(
+        "GNVTG,{course},T,,M,{knots},N,{kph},K,{}",
+        mode_indicator(epoch.mode)
+    ))
+}
+
+fn gst(epoch: &NmeaEpoch) -> String {
+    // PosLLH only carries the horizontal accuracy, split it evenly between axes
+    let horizontal = epoch.h_accuracy / std::f64::consts::SQRT_2;
+    sentence(format!(
+        "GNGST,{},,,,,{},{},{}",
+        time_field(&epoch.time),
+        fix_field(epoch.mode, format!("{horizontal:.3}")),
+        fix_field(epoch.mode, format!("{horizontal:.3}")),
+        fix_field(epoch.mode, format!("{:.3}", epoch.v_accuracy))
+    ))
+}
+
+fn zda(epoch: &NmeaEpoch) -> String {
+    let t = &epoch.time;
+    sentence(format!(
+        "GNZDA,{},{:02},{:02},{:04},00,00",
+        time_field(t),
+        t.day(),
+        t.month(),
+        t.year()
+    ))
+}
+
+fn mode_indicator(mode: GnssModes) -> char {
+    match mode {
+        GnssModes::NoFix => 'N',
+        GnssModes::Spp => 'A',
+        GnssModes::Dgnss | GnssModes::Sbas => 'D',
+        GnssModes::Float => 'F',
+        GnssModes::Fixed => 'R',
+        GnssModes::Dr => 'E',
+    }
+}
+
+/// `value` when there is a fix, otherwise an empty field.
+fn fix_field(mode: GnssModes, value: String) -> String {
+    if mode == GnssModes::NoFix {
+        String::new()
+    } else {
+        value
+    }
+}
+
+fn time_field(time: &UtcDateTime) -> String {
+    format!(
+        "{:02}{:02}{:02}.{:02}",
+        time.hour(),
+        time.minute(),
+        time.second(),
+        time.nanosecond().min(999_999_999) / 10_000_000
+    )
+}
+
+fn lat_field(mode: GnssModes, lat: f64) -> String {
+    let hemisphere = if lat < 0.0 { 'S' } else { 'N' };
+    fix_field(mode, degrees_minutes(lat, 2, hemisphere))
+        + if mode == GnssModes::NoFix { "," } else { "" }
+}
+
+fn lon_field(mode: GnssModes, lon: f64) -> String {
+    let hemisphere = if lon < 0.0 { 'W' } else { 'E' };
+    fix_field(mode, degrees_minutes(lon, 3, hemisphere))
+        + if mode == GnssModes::NoFix { "," } else { "" }
+}
+
+/// Format an angle as the `[d]ddmm.mmmmmmm,H` fields used by NMEA.
+fn degrees_minutes(value: f64, width: usize, hemisphere: char) -> String {
+    let minutes = (value.abs() * 60.0 * 1e7).round() / 1e7;
+    let degrees = (minutes / 60.0).floor();
+    let minutes = minutes - degrees * 60.0;
+    format!("{:0width$}{minutes:010.7},{hemisphere}", degrees as u16)
+}
+
+/// Wrap `body` with the leading `$` and trailing checksum.
+fn sentence(body: String) -> String {
+    let checksum = body.bytes().fold(0, |sum, c| sum ^ c);
+    format!("${body}*{checksum:02X}")
+}
+
+/// Writes NMEA sentences generated from the solution to each of its targets.
+///
+/// The sentences are written on a separate thread so slow TCP clients or serial
+/// ports never hold up message processing, epochs are dropped if it falls behind
+/// unless the output is [`NmeaOutput::lossless`].
+#[derive(Debug)]
+pub struct NmeaOutput {
+    generator: NmeaGenerator,
+    writer: Option<Sender<String>>,
+    thread: Option<JoinHandle<()>>,
+    lossless: bool,
+}
+
+impl NmeaOutput {
+    /// Open each of `targets`, targets which fail to open are logged and skipped.
+    pub fn new(targets: &[NmeaTarget], sentences: Vec<NmeaSentence>) -> NmeaOutput {
+        let sinks: Vec<_> = targets
+            .iter()
+            .filter_map(|target| match NmeaSink::open(target) {
+                Ok(sink) => {
+                    info!("Writing NMEA to {target}");
+                    Some((target.clone(), sink))
+                }
+                Err(e) => {
+                    error!("Unable to open NMEA output {target}, error {e}");
+                    None
+                }
+            })
+            .collect();
+        let (writer, thread) = if sinks.is_empty() {
+            (None, None)
+        } else {
+            let (writer, thread) = spawn_writer(sinks);
+            (Some(writer), Some(thread))
+        };
+        NmeaOutput {
+            generator: NmeaGenerator::new(sentences),
+            writer,
+            thread,
+            lossless: false,
+        }
+    }
+
+    /// Wait for the writer instead of dropping epochs, for converting logs offline.
+    pub fn lossless(mut self) -> Self {
+        self.lossless = true;
+        self
+    }
+
+    /// Close the output once every sentence queued has been written.
+    pub fn finish(mut self) {
+        self.writer = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writer.is_none()
+    }
+
+    pub fn handle_msg(&mut self, msg: &Sbp) {
+        let sentences = self.generator.handle_msg(msg);
+        if sentences.is_empty() {
+            return;
+        }
+        let buf: String = sentences.iter().map(|s| format!("{s}\r\n")).collect();
+        if let Some(writer) = &self.writer {
+            if self.lossless {
+                if writer.send(buf).is_err() {
+                    self.writer = None;
+                }
+                return;
+            }
+            match writer.try_send(buf) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => debug!("NMEA output is behind, dropping epoch"),
+                // every target has been closed
+                Err(TrySendError::Disconnected(_)) => self.writer = None,
+            }
+        }
+    }
+}
+
+/// Write each epoch received to `sinks` until the [`NmeaOutput`] is dropped or every
+/// sink has failed.
+fn spawn_writer(mut sinks: Vec<(NmeaTarget, NmeaSink)>) -> (Sender<String>, JoinHandle<()>) {
+    let (tx, rx) = channel::bounded::<String>(NMEA_QUEUE_LEN);
+    let thread = thread::spawn(move || {
+        for buf in rx.iter() {
+            sinks.retain_mut(|(target, sink)| match sink.write(buf.as_bytes()) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Unable to write NMEA to {target}, error {e}, closing output");
+                    false
+                }
+            });
+            if sinks.is_empty() {
+                break;
+            }
+        }
+    });
+    (tx, thread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sbp::messages::{
+        gnss::{CarrierPhase, GnssSignal, GpsTime},
+        navigation::{MsgAgeCorrections, MsgDops, MsgPosLlh, MsgPosLlhAcc, MsgUtcTime, MsgVelNed},
+        observation::{Doppler, ObservationHeader, PackedObsContent, SvAzEl},
+    };
+
+    fn checksum_ok(sentence: &str) -> bool {
+        let (body, checksum) = sentence[1..].split_once('*').unwrap();
+        let expected = body.bytes().fold(0, |sum, c| sum ^ c);
+        u8::from_str_radix(checksum, 16).unwrap() == expected
+    }
+
+    fn epoch_msgs(tow: u32) -> Vec<Sbp> {
+        vec![
+            Sbp::MsgUtcTime(MsgUtcTime {
+                sender_id: Some(1337),
+                flags: 1,
+                tow,
+                year: 2022,
+                month: 3,
+                day: 4,
+                hours: 12,
+                minutes: 34,
+                seconds: 56,
+                ns: 500_000_000,
+            }),
+            Sbp::MsgPosLlh(MsgPosLlh {
+                sender_id: Some(1337),
+                tow,
+                lat: 37.5,
+                lon: -122.25,
+                height: 10.0,
+                h_accuracy: 1414,
+                v_accuracy: 2000,
+                n_sats: 14,
+                flags: 4,
+            }),
+            Sbp::MsgVelNed(MsgVelNed {
+                sender_id: Some(1337),
+                tow,
+                n: 0,
+                e: 1000,
+                d: 0,
+                h_accuracy: 0,
+                v_accuracy: 0,
+                n_sats: 14,
+                flags: 1,
+            }),
+        ]
+    }
+
+    #[test]
+    fn nmea_target_test() {
+        assert_eq!(
+            NmeaTarget::from_str("tcp:2101").unwrap(),
+            NmeaTarget::Tcp("0.0.0.0:2101".into())
+        );
+        assert_eq!(
+            NmeaTarget::from_str("serial:/dev/ttyUSB0@9600").unwrap(),
+            NmeaTarget::Serial {
+                device: "/dev/ttyUSB0".into(),
+                baudrate: 9600
+            }
+        );
+        assert_eq!(
+            NmeaTarget::from_str("serial:COM3").unwrap().to_string(),
+            "serial:COM3@115200"
+        );
+        assert!(NmeaTarget::from_str("udp:").is_err());
+        assert!(NmeaTarget::from_str("ftp:host").is_err());
+    }
+
+    #[test]
+    fn nmea_generator_test() {
+        let mut generator = NmeaGenerator::new(NmeaSentence::all());
+        generator.handle_msg(&Sbp::MsgDops(MsgDops {
+            sender_id: Some(1337),
+            tow: 1000,
+            gdop: 200,
+            pdop: 150,
+            tdop: 100,
+            hdop: 90,
+            vdop: 120,
+            flags: 4,
+        }));
+        generator.handle_msg(&Sbp::MsgSvAzEl(MsgSvAzEl {
+            sender_id: Some(1337),
+            azel: (1..=5)
+                .map(|sat| SvAzEl {
+                    sid: GnssSignal { sat, code: 0 },
+                    az: 90,
+                    el: 45,
+                })
+                .chain(std::iter::once(SvAzEl {
+                    sid: GnssSignal { sat: 3, code: 3 },
+                    az: 10,
+                    el: 30,
+                }))
+                .collect(),
+        }));
+        // sat 3 is excluded by RAIM and sat 5 has no pseudorange
+        let obs = [
+            (1, 0, 0x01),
+            (2, 0, 0x0F),
+            (3, 0, 0x81),
+            (5, 0, 0x00),
+            (3, 3, 0x01),
+        ];
+        generator.handle_msg(&Sbp::MsgObs(MsgObs {
+            sender_id: Some(1337),
+            header: ObservationHeader {
+                t: GpsTime {
+                    tow: 1000,
+                    ns_residual: 0,
+                    wn: 2200,
+                },
+                n_obs: 0x10,
+            },
+            obs: obs
+                .iter()
+                .map(|&(sat, code, flags)| PackedObsContent {
+                    p: 0,
+                    l: CarrierPhase { i: 0, f: 0 },
+                    d: Doppler { i: 0, f: 0 },
+                    cn0: 180,
+                    lock: 0,
+                    flags,
+                    sid: GnssSignal { sat, code },
+                })
+                .collect(),
+        }));
+        let mut sentences = Vec::new();
+        for msg in epoch_msgs(1000) {
+            sentences.extend(generator.handle_msg(&msg));
+        }
+        assert!(sentences.iter().all(|s| checksum_ok(s)));
+        let find = |prefix: &str| {
+            sentences
+                .iter()
+                .find(|s| s.starts_with(prefix))
+                .unwrap()
+                .split('*')
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            find("$GNGGA"),
+            "$GNGGA,123456.50,3730.0000000,N,12215.0000000,W,4,14,0.9,10.000,M,0.000,M,,"
+        );
+        assert_eq!(
+            find("$GNRMC"),
+            "$GNRMC,123456.50,A,3730.0000000,N,12215.0000000,W,1.944,90.0,040322,,,R"
+        );
+        assert_eq!(find("$GNVTG"), "$GNVTG,90.0,T,,M,1.944,N,3.600,K,R");
+        let gsa: Vec<&str> = sentences
+            .iter()
+            .filter(|s| s.starts_with("$GNGSA"))
+            .map(|s| s.split('*').next().unwrap())
+            .collect();
+        assert_eq!(
+            gsa,
+            [
+                "$GNGSA,A,3,01,02,,,,,,,,,,,1.5,0.9,1.2",
+                "$GNGSA,A,3,67,,,,,,,,,,,,1.5,0.9,1.2"
+            ]
+        );
+        assert_eq!(find("$GNGST"), "$GNGST,123456.50,,,,,1.000,1.000,2.000");
+        assert_eq!(find("$GNZDA"), "$GNZDA,123456.50,04,03,2022,00,00");
+        let gsv: Vec<&String> = sentences.iter().filter(|s| s.contains("GSV")).collect();
+        assert_eq!(gsv.len(), 3);
+        assert!(gsv[0].starts_with("$GPGSV,2,1,05,01,45,180,,02,45,180,"));
+        assert!(gsv[2].starts_with("$GLGSV,1,1,01,67,30,020,"));
+    }
+
+    #[test]
+    fn gsa_without_observations_test() {
+        let mut generator = NmeaGenerator::new(vec![NmeaSentence::GSA]);
+        let mut sentences = Vec::new();
+        for msg in epoch_msgs(1000).into_iter().chain(epoch_msgs(2000)) {
+            sentences.extend(generator.handle_msg(&msg));
+        }
+        assert_eq!(sentences.len(), 2);
+        assert!(sentences[0].starts_with("$GNGSA,A,3,,,,,,,,,,,,,,,*"));
+    }
+
+    #[test]
+    fn last_gga_test() {
+        let mut generator = NmeaGenerator::new(Vec::new());
+        assert!(generator.last_gga().is_none());
+        for msg in epoch_msgs(1000) {
+            assert!(generator.handle_msg(&msg).is_empty());
+        }
+        generator.handle_msg(&Sbp::MsgAgeCorrections(MsgAgeCorrections {
+            sender_id: Some(1337),
+            tow: 1000,
+            age: 17,
+        }));
+        let gga = generator.last_gga().unwrap();
+        assert_eq!(gga.mode, GnssModes::Fixed);
+        assert_eq!(gga.age_corrections, Some(1.7));
+        assert_eq!(
+            gga.sentence().split('*').next().unwrap(),
+            "$GNGGA,123456.50,3730.0000000,N,12215.0000000,W,4,14,,10.000,M,0.000,M,1.7,"
+        );
+        let no_fix = Gga {
+            mode: GnssModes::NoFix,
+            ..gga
+        };
+        assert!(checksum_ok(&no_fix.sentence()));
+        assert!(no_fix
+            .sentence()
+            .starts_with("$GNGGA,123456.50,,,,,0,14,,,M,0.000,M,,*"));
+    }
+
+    #[test]
+    fn gga_geoid_separation_test() {
+        let mut generator = NmeaGenerator::new(Vec::new());
+        let pos_llh_acc = |tow| {
+            Sbp::MsgPosLlhAcc(MsgPosLlhAcc {
+                sender_id: Some(1337),
+                tow,
+                lat: 37.5,
+                lon: -122.25,
+                height: 10.0,
+                orthometric_height: 42.25,
+                h_accuracy: 1.0,
+                v_accuracy: 2.0,
+                ct_accuracy: 1.0,
+                at_accuracy: 1.0,
+                h_ellipse: 0.0,
+                confidence_and_geoid: 0,
+                n_sats: 14,
+                flags: 4,
+            })
+        };
+        for msg in epoch_msgs(1000) {
+            generator.handle_msg(&msg);
+        }
+        generator.handle_msg(&pos_llh_acc(1000));
+        let gga = generator.last_gga().unwrap();
+        assert_eq!(gga.orthometric_height, Some(42.25));
+        assert!(gga.sentence().starts_with(
+            "$GNGGA,123456.50,3730.0000000,N,12215.0000000,W,4,14,,42.250,M,-32.250,M,,*"
+        ));
+        // the geoid height of another epoch isn't used
+        for msg in epoch_msgs(2000) {
+            generator.handle_msg(&msg);
+        }
+        let gga = generator.last_gga().unwrap();
+        assert_eq!(gga.orthometric_height, None);
+        assert!(gga.sentence().contains(",10.000,M,0.000,M,"));
+    }
+
+    #[test]
+    fn nmea_udp_output_test() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let target = NmeaTarget::Udp(socket.local_addr().unwrap().to_string());
+        let mut output = NmeaOutput::new(&[target], vec![NmeaSentence::GGA]);
+        assert!(!output.is_empty());
+        for msg in epoch_msgs(1000) {
+            output.handle_msg(&msg);
+        }
+        let mut buf = [0; 256];
+        let len = socket.recv(&mut buf).unwrap();
+        let received = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(received.starts_with("$GNGGA,123456.50,"));
+        assert!(received.ends_with("\r\n"));
+    }
+}
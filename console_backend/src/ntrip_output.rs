@@ -1,103 +1,155 @@
+use crate::correction_recorder::CorrectionRecorder;
+use crate::correction_source::InputType;
+use crate::correction_stats::CorrectionStats;
+use crate::rtcm::{RtcmConverter, RtcmFrame, RtcmFramer};
+use crate::sbp_framing::SbpFramer;
 use crate::tabs::advanced_tab::ntrip_tab::OutputType;
 use crate::types::Result;
-use crate::utils::pythonhome_dir;
-use anyhow::Context;
+use anyhow::bail;
 use crossbeam::channel::Receiver;
-use log::{error, info};
+use log::error;
 use std::io::Write;
-use std::process::{Command, Stdio};
-use std::{io, thread};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
 pub struct MessageConverter {
     in_rx: Receiver<Vec<u8>>,
+    input_type: InputType,
     output_type: OutputType,
+    stats: Option<Arc<Mutex<CorrectionStats>>>,
+    recorder: Option<CorrectionRecorder>,
 }
 
 impl MessageConverter {
     pub fn new(in_rx: Receiver<Vec<u8>>, output_type: OutputType) -> Self {
-        Self { in_rx, output_type }
+        Self {
+            in_rx,
+            input_type: InputType::RTCM,
+            output_type,
+            stats: None,
+            recorder: None,
+        }
+    }
+
+    /// Read corrections of `input_type`, RTCM 3 by default.
+    pub fn with_input(mut self, input_type: InputType) -> Self {
+        self.input_type = input_type;
+        self
+    }
+
+    /// Record analytics of the correction stream in `stats`.
+    pub fn with_stats(mut self, stats: Arc<Mutex<CorrectionStats>>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Save the corrections as received with `recorder`.
+    pub fn with_recorder(mut self, recorder: CorrectionRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 
     pub fn start<W: Write + Send + 'static>(&mut self, out: W) -> Result<()> {
-        match self.output_type {
-            OutputType::RTCM => self.output_rtcm(out),
-            OutputType::SBP => self.output_sbp(out),
+        match (self.input_type, &self.output_type) {
+            (InputType::RTCM, OutputType::RTCM) => self.output_rtcm(out),
+            (InputType::RTCM, OutputType::SBP) => self.output_sbp(out),
+            (InputType::SBP, OutputType::SBP) => self.output_raw(out),
+            (InputType::SBP, OutputType::RTCM) => bail!("SBP corrections can't be written as RTCM"),
         }
     }
 
-    /// Just redirects directly to writer
-    fn output_rtcm<W: Write + Send + 'static>(&mut self, mut out: W) -> Result<()> {
+    /// Writes SBP corrections as they are, recording the bytes and messages received
+    fn output_raw<W: Write + Send + 'static>(&mut self, mut out: W) -> Result<()> {
         let in_rx = self.in_rx.clone();
-        thread::spawn(move || loop {
-            if let Ok(data) = in_rx.recv() {
-                if let Err(e) = out.write(&data) {
+        let recorder = self.recorder.clone();
+        let input_type = self.input_type;
+        let stats = self.stats.clone();
+        thread::spawn(move || {
+            let mut framer = SbpFramer::default();
+            while let Ok(data) = in_rx.recv() {
+                if let Some(recorder) = &recorder {
+                    recorder.record(input_type, &data);
+                }
+                if let Err(e) = out.write_all(&data) {
                     error!("failed to write to device {e}");
                 }
+                if let Some(stats) = &stats {
+                    let msg_types = framer.push(&data);
+                    let mut stats = stats.lock().unwrap();
+                    stats.add_bytes(data.len());
+                    for msg_type in msg_types {
+                        stats.push_sbp(msg_type);
+                    }
+                }
             }
         });
         Ok(())
     }
 
-    /// Runs rtcm3tosbp converter
-    fn output_sbp<W: Write + Send + 'static>(&mut self, mut out: W) -> Result<()> {
-        let mut child = if cfg!(target_os = "windows") {
-            let mut cmd = Command::new("cmd");
-            let rtcm = pythonhome_dir()?
-                .join("binaries")
-                .join("windows")
-                .join("rtcm3tosbp.exe")
-                .to_string_lossy()
-                .to_string();
-            info!("running rtcm3tosbp from \"{}\"", rtcm);
-            cmd.args(["/C", &rtcm]);
-            cmd
-        } else if cfg!(target_os = "macos") {
-            let mut cmd = Command::new("sh");
-            let rtcm = pythonhome_dir()?
-                .join("binaries")
-                .join("mac")
-                .join("rtcm3tosbp")
-                .to_string_lossy()
-                .to_string();
-            info!("running rtcm3tosbp from \"{}\"", rtcm);
-            cmd.args(["-c", &rtcm]);
-            cmd
-        } else {
-            let mut cmd = Command::new("sh");
-            let rtcm = pythonhome_dir()?
-                .join("binaries")
-                .join("linux")
-                .join("rtcm3tosbp")
-                .to_string_lossy()
-                .to_string();
-            info!("running rtcm3tosbp from \"{}\"", rtcm);
-            cmd.args(["-c", &rtcm]);
-            cmd
-        };
-        let mut child = child
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .context("rtcm converter process failed")?;
-
-        let mut child_in = child.stdin.take().context("rtcm3tosbp stdin missing")?;
-        let mut child_out = child.stdout.take().context("rtcm3tosbp stdout missing")?;
+    /// Just redirects directly to writer
+    fn output_rtcm<W: Write + Send + 'static>(&mut self, mut out: W) -> Result<()> {
         let in_rx = self.in_rx.clone();
-
+        let recorder = self.recorder.clone();
+        let input_type = self.input_type;
+        let stats = self.stats.clone();
         thread::spawn(move || {
-            if let Err(e) = io::copy(&mut child_out, &mut out) {
-                error!("failed to write to device {e}");
+            let mut framer = RtcmFramer::default();
+            while let Ok(data) = in_rx.recv() {
+                if let Some(recorder) = &recorder {
+                    recorder.record(input_type, &data);
+                }
+                if let Err(e) = out.write_all(&data) {
+                    error!("failed to write to device {e}");
+                }
+                if let Some(stats) = &stats {
+                    let frames = framer.push(&data);
+                    record_frames(stats, &data, &frames, &framer);
+                }
             }
         });
+        Ok(())
+    }
+
+    /// Converts RTCM 3 to SBP before writing to the device
+    fn output_sbp<W: Write + Send + 'static>(&mut self, mut out: W) -> Result<()> {
+        let in_rx = self.in_rx.clone();
+        let recorder = self.recorder.clone();
+        let input_type = self.input_type;
+        let stats = self.stats.clone();
         thread::spawn(move || {
+            let mut framer = RtcmFramer::default();
+            let mut converter = RtcmConverter::new();
             while let Ok(data) = in_rx.recv() {
-                if let Err(e) = child_in.write_all(&data) {
-                    error!("failed to write to rtcm3tosbp {e}")
+                if let Some(recorder) = &recorder {
+                    recorder.record(input_type, &data);
+                }
+                let frames = framer.push(&data);
+                if let Some(stats) = &stats {
+                    record_frames(stats, &data, &frames, &framer);
+                }
+                for frame in frames {
+                    for msg in converter.convert(&frame) {
+                        if let Err(e) = sbp::to_writer(&mut out, &msg) {
+                            error!("failed to write to device {e}");
+                        }
+                    }
                 }
             }
         });
-        thread::spawn(move || child.wait());
         Ok(())
     }
 }
+
+fn record_frames(
+    stats: &Mutex<CorrectionStats>,
+    data: &[u8],
+    frames: &[RtcmFrame],
+    framer: &RtcmFramer,
+) {
+    let mut stats = stats.lock().unwrap();
+    stats.add_bytes(data.len());
+    for frame in frames {
+        stats.push(frame);
+    }
+    stats.set_crc_errors(framer.crc_errors());
+}
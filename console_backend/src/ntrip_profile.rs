@@ -0,0 +1,226 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! NTRIP client settings for unattended use, from the command line or a YAML profile.
+//!
+//! The client is started on each device connection, so corrections keep flowing
+//! after the device reconnects without anyone pressing Start in the NTRIP tab.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use serde::Deserialize;
+
+use crate::credentials::{CredentialStore, UNLOCK_HELP};
+use crate::tabs::advanced_tab::ntrip_tab::{NtripOptions, OutputType};
+use crate::types::Result;
+
+/// Seconds between GGA sentences when none is given, as in the NTRIP tab.
+const DEFAULT_GGA_PERIOD: u64 = 10;
+
+/// NTRIP client settings, written in a YAML profile as e.g.
+///
+/// ```yaml
+/// url: na.skylark.swiftnav.com:2101/CRS
+/// user: rover1
+/// password_file: /etc/swift/ntrip-password
+/// # or the name of a credential saved in the credentials store
+/// # credential: skylark
+/// gga_period: 10
+/// output: rtcm
+/// position: [37.771, -122.403, -5.5]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NtripProfile {
+    pub url: Option<String>,
+    pub user: Option<String>,
+    /// File holding the password, so it stays out of the profile and process list.
+    pub password_file: Option<PathBuf>,
+    /// Name of a saved credential, used for the user and password when they aren't set.
+    pub credential: Option<String>,
+    pub gga_period: Option<u64>,
+    /// Write corrections to the device as `rtcm` or `sbp`.
+    pub output: Option<String>,
+    /// Static latitude, longitude and altitude, the device's position if not set.
+    pub position: Option<[f64; 3]>,
+}
+
+impl NtripProfile {
+    /// Read a profile from a YAML file.
+    pub fn from_file(path: &Path) -> Result<NtripProfile> {
+        let file =
+            fs::File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+        serde_yaml::from_reader(file)
+            .with_context(|| format!("invalid ntrip profile {}", path.display()))
+    }
+
+    /// These settings, with any missing ones taken from `other`.
+    pub fn or(self, other: NtripProfile) -> NtripProfile {
+        NtripProfile {
+            url: self.url.or(other.url),
+            user: self.user.or(other.user),
+            password_file: self.password_file.or(other.password_file),
+            credential: self.credential.or(other.credential),
+            gga_period: self.gga_period.or(other.gga_period),
+            output: self.output.or(other.output),
+            position: self.position.or(other.position),
+        }
+    }
+
+    /// Client options for these settings, `None` if no caster url is set. A named
+    /// credential is looked up in `credentials`.
+    pub fn options(&self, credentials: Option<&CredentialStore>) -> Result<Option<NtripOptions>> {
+        let Some(url) = self.url.clone() else {
+            return Ok(None);
+        };
+        let credential = match (&self.credential, credentials) {
+            (Some(name), Some(credentials)) => Some(credentials.get(name)?.clone()),
+            (Some(name), None) => bail!("can't use credential \"{name}\", {UNLOCK_HELP}"),
+            (None, _) => None,
+        };
+        let user = self
+            .user
+            .clone()
+            .or_else(|| credential.as_ref().map(|c| c.username.clone()));
+        let password = match (&self.password_file, credential) {
+            (Some(path), _) => read_password(path)?,
+            (None, Some(credential)) => credential.password.expose().to_string(),
+            (None, None) => String::new(),
+        };
+        let output = self.output.as_deref().unwrap_or("rtcm");
+        output_type(output)?;
+        Ok(Some(NtripOptions::new(
+            url,
+            user.unwrap_or_default(),
+            password,
+            self.position.map(|[lat, lon, alt]| (lat, lon, alt)),
+            self.gga_period.unwrap_or(DEFAULT_GGA_PERIOD),
+            &output.to_uppercase(),
+            false,
+        )))
+    }
+}
+
+/// Parse an `rtcm` or `sbp` corrections output.
+pub fn output_type(s: &str) -> Result<OutputType> {
+    OutputType::from_str(&s.to_uppercase())
+        .map_err(|_| anyhow!("expected an ntrip output of rtcm or sbp, got {s}"))
+}
+
+/// Read the password from the first line of `path`.
+fn read_password(path: &Path) -> Result<String> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("could not read ntrip password file {}", path.display()))?;
+    Ok(contents.lines().next().unwrap_or_default().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::{Credential, CredentialKey, Secret};
+    use crate::tabs::advanced_tab::ntrip_tab::PositionMode;
+
+    #[test]
+    fn profile_test() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let password_file = tmp_dir.path().join("password");
+        fs::write(&password_file, "s3cret\n").unwrap();
+        let profile_file = tmp_dir.path().join("ntrip.yaml");
+        fs::write(
+            &profile_file,
+            format!(
+                "url: caster.example.com:2101/CRS\nuser: rover1\npassword_file: {}\n\
+                 output: sbp\nposition: [37.5, -122.25, 10.0]\n",
+                password_file.display()
+            ),
+        )
+        .unwrap();
+        let profile = NtripProfile::from_file(&profile_file).unwrap();
+        let cli = NtripProfile {
+            url: Some("other.example.com:2101/MP".to_string()),
+            gga_period: Some(5),
+            ..Default::default()
+        };
+        let options = cli.or(profile).options(None).unwrap().unwrap();
+        assert_eq!(options.url, "other.example.com:2101/MP");
+        assert_eq!(options.username.as_deref(), Some("rover1"));
+        assert_eq!(
+            options.password.as_ref().map(Secret::expose),
+            Some("s3cret")
+        );
+        assert_eq!(options.nmea_period, 5);
+        assert!(matches!(options.output_type, Some(OutputType::SBP)));
+        assert!(matches!(
+            options.pos_mode,
+            PositionMode::Static { lat, .. } if lat == 37.5
+        ));
+
+        assert!(NtripProfile::default().options(None).unwrap().is_none());
+        let bad_output = NtripProfile {
+            url: Some("caster.example.com/CRS".to_string()),
+            output: Some("rinex".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_output.options(None).is_err());
+        fs::write(
+            &profile_file,
+            "url: caster.example.com/CRS\nmountpoint: CRS\n",
+        )
+        .unwrap();
+        assert!(NtripProfile::from_file(&profile_file).is_err());
+    }
+
+    #[test]
+    fn profile_credential() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let key = CredentialKey::Passphrase(Secret::new("passphrase"));
+        let mut store =
+            CredentialStore::open_at(tmp_dir.path().join("credentials.enc"), &key).unwrap();
+        store
+            .insert(
+                "skylark",
+                Credential {
+                    username: "rover1".to_string(),
+                    password: Secret::new("s3cret"),
+                },
+            )
+            .unwrap();
+        let profile = NtripProfile {
+            url: Some("caster.example.com:2101/CRS".to_string()),
+            credential: Some("skylark".to_string()),
+            ..Default::default()
+        };
+        let options = profile.options(Some(&store)).unwrap().unwrap();
+        assert_eq!(options.username.as_deref(), Some("rover1"));
+        assert_eq!(
+            options.password.as_ref().map(Secret::expose),
+            Some("s3cret")
+        );
+        assert!(!format!("{options:?}").contains("s3cret"));
+        assert!(profile.options(None).is_err());
+        let unknown = NtripProfile {
+            credential: Some("rtk2go".to_string()),
+            ..profile
+        };
+        assert!(unknown.options(Some(&store)).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//! NTRIP caster sourcetables.
+//!
+//! The sourcetable is requested with a GET on the caster root and lists the
+//! caster's mountpoints (STR), other casters (CAS) and networks (NET). It is
+//! used to browse mountpoints and to pick the nearest one to the rover.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use capnp::message::Builder;
+use curl::easy::{Easy, HttpVersion, List};
+
+use crate::client_sender::BoxedClientSender;
+use crate::types::Result;
+use crate::utils::serialize_capnproto_builder;
+
+const SOURCETABLE_TIMEOUT: Duration = Duration::from_secs(10);
+const EARTH_RADIUS_KM: f64 = 6371.0;
+const DEFAULT_SCHEME: &str = "http://";
+
+/// A mountpoint from a STR record.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mountpoint {
+    pub name: String,
+    pub identifier: String,
+    pub format: String,
+    pub format_details: String,
+    pub nav_systems: Vec<String>,
+    pub network: String,
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Whether the caster needs NMEA GGA from the client.
+    pub nmea: bool,
+    /// Whether corrections come from a network rather than a single base.
+    pub network_solution: bool,
+    pub authentication: String,
+    pub bitrate: Option<u32>,
+}
+
+impl Mountpoint {
+    fn parse(fields: &[&str]) -> Option<Mountpoint> {
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .map(|f| f.trim().to_string())
+                .unwrap_or_default()
+        };
+        let name = field(1);
+        if name.is_empty() {
+            return None;
+        }
+        Some(Mountpoint {
+            name,
+            identifier: field(2),
+            format: field(3),
+            format_details: field(4),
+            nav_systems: field(6)
+                .split('+')
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            network: field(7),
+            country: field(8),
+            lat: field(9).parse().unwrap_or_default(),
+            lon: field(10).parse().unwrap_or_default(),
+            nmea: field(11) == "1",
+            network_solution: field(12) == "1",
+            authentication: field(15),
+            bitrate: field(17).parse().ok(),
+        })
+    }
+
+    /// RTCM 3 is the only format that can be forwarded or converted to SBP.
+    pub fn is_rtcm3(&self) -> bool {
+        let format = self.format.to_uppercase().replace(' ', "");
+        format.starts_with("RTCM3")
+    }
+
+    /// Whether the sourcetable gives a location for the mountpoint.
+    pub fn has_location(&self) -> bool {
+        self.lat != 0.0 || self.lon != 0.0
+    }
+
+    /// Great circle distance in kilometers.
+    pub fn distance_km(&self, lat: f64, lon: f64) -> f64 {
+        distance_km((self.lat, self.lon), (lat, lon))
+    }
+}
+
+/// Another caster from a CAS record.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Caster {
+    pub host: String,
+    pub port: Option<u16>,
+    pub identifier: String,
+    pub operator: String,
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// A network from a NET record.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Network {
+    pub identifier: String,
+    pub operator: String,
+    pub authentication: String,
+    pub web: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sourcetable {
+    pub mountpoints: Vec<Mountpoint>,
+    pub casters: Vec<Caster>,
+    pub networks: Vec<Network>,
+}
+
+impl Sourcetable {
+    /// Parse a sourcetable, records that can't be parsed are skipped.
+    pub fn parse(text: &str) -> Sourcetable {
+        let mut table = Sourcetable::default();
+        for line in text.lines() {
+            let fields: Vec<&str> = line.trim_end().split(';').collect();
+            let field = |i: usize| {
+                fields
+                    .get(i)
+                    .map(|f| f.trim().to_string())
+                    .unwrap_or_default()
+            };
+            match fields[0] {
+                "STR" => table.mountpoints.extend(Mountpoint::parse(&fields)),
+                "CAS" => table.casters.push(Caster {
+                    host: field(1),
+                    port: field(2).parse().ok(),
+                    identifier: field(3),
+                    operator: field(4),
+                    country: field(6),
+                    lat: field(7).parse().unwrap_or_default(),
+                    lon: field(8).parse().unwrap_or_default(),
+                }),
+                "NET" => table.networks.push(Network {
+                    identifier: field(1),
+                    operator: field(2),
+                    authentication: field(3),
+                    web: field(5),
+                }),
+                "ENDSOURCETABLE" => break,
+                _ => {}
+            }
+        }
+        table
+    }
+
+    /// Request the sourcetable from the caster of `url`.
+    pub fn fetch(url: &str, username: Option<&str>, password: Option<&str>) -> Result<Sourcetable> {
+        let mut curl = Easy::new();
+        let mut headers = List::new();
+        headers.append("Ntrip-Version: Ntrip/2.0")?;
+        curl.http_headers(headers)?;
+        curl.useragent("NTRIP ntrip-client/1.0")?;
+        curl.url(&caster_root(url))?;
+        curl.http_version(HttpVersion::Any)?;
+        curl.http_09_allowed(true)?;
+        curl.timeout(SOURCETABLE_TIMEOUT)?;
+        if let Some(username) = username {
+            curl.username(username)?;
+        }
+        if let Some(password) = password {
+            curl.password(password)?;
+        }
+        let mut body = Vec::new();
+        {
+            let mut transfer = curl.transfer();
+            transfer.write_function(|data| {
+                body.extend_from_slice(data);
+                Ok(data.len())
+            })?;
+            transfer
+                .perform()
+                .context("ntrip sourcetable request failed")?;
+        }
+        let code = curl.response_code()?;
+        if code >= 400 {
+            bail!("ntrip sourcetable request failed with HTTP status {code}");
+        }
+        Ok(Sourcetable::parse(&String::from_utf8_lossy(&body)))
+    }
+
+    /// The nearest RTCM 3 mountpoint to a position.
+    pub fn nearest(&self, lat: f64, lon: f64) -> Option<&Mountpoint> {
+        self.mountpoints
+            .iter()
+            .filter(|mountpoint| mountpoint.is_rtcm3() && mountpoint.has_location())
+            .min_by(|a, b| a.distance_km(lat, lon).total_cmp(&b.distance_km(lat, lon)))
+    }
+}
+
+/// Great circle distance in kilometers between two latitude, longitude pairs in degrees.
+pub fn distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// Split a caster url into `scheme://host[:port]` and the mountpoint.
+fn split_url(url: &str) -> (String, &str) {
+    let url = url.trim();
+    let (scheme, rest) = match url.find("://") {
+        Some(i) => url.split_at(i + 3),
+        None => (DEFAULT_SCHEME, url),
+    };
+    let (host, mountpoint) = rest.split_once('/').unwrap_or((rest, ""));
+    (format!("{scheme}{host}"), mountpoint)
+}
+
+/// The caster root, where the sourcetable is served.
+pub fn caster_root(url: &str) -> String {
+    format!("{}/", split_url(url).0)
+}
+
+/// The mountpoint part of a mountpoint url.
+pub fn mountpoint_name(url: &str) -> &str {
+    split_url(url).1
+}
+
+/// The url of a mountpoint on the caster of `url`.
+pub fn mountpoint_url(url: &str, mountpoint: &str) -> String {
+    format!("{}/{mountpoint}", split_url(url).0)
+}
+
+/// Send the mountpoints to the frontend, with their distance from `position` when known.
+pub fn send_sourcetable(
+    client_sender: &BoxedClientSender,
+    table: &Sourcetable,
+    position: Option<(f64, f64)>,
+    selected: Option<&str>,
+    error: Option<&str>,
+) {
+    let mut builder = Builder::new_default();
+    let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
+    let mut status = msg.init_ntrip_sourcetable();
+    status.set_selected(selected.unwrap_or_default());
+    status.set_error(error.unwrap_or_default());
+    let mut mountpoints = status
+        .reborrow()
+        .init_mountpoints(table.mountpoints.len() as u32);
+    for (i, mountpoint) in table.mountpoints.iter().enumerate() {
+        let mut entry = mountpoints.reborrow().get(i as u32);
+        entry.set_name(&mountpoint.name);
+        entry.set_identifier(&mountpoint.identifier);
+        entry.set_format(&mountpoint.format);
+        entry.set_nav_systems(&mountpoint.nav_systems.join("+"));
+        entry.set_lat(mountpoint.lat);
+        entry.set_lon(mountpoint.lon);
+        entry.set_nmea(mountpoint.nmea);
+        let distance = position
+            .filter(|_| mountpoint.has_location())
+            .map(|(lat, lon)| mountpoint.distance_km(lat, lon));
+        entry.set_distance(distance.unwrap_or(-1.0));
+    }
+    client_sender.send_data(serialize_capnproto_builder(builder));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCETABLE: &str = "SOURCETABLE 200 OK\r\n\
+        Server: NTRIP Caster\r\n\
+        \r\n\
+        CAS;caster.example.com;2101;Example;Example Inc;0;USA;37.77;-122.42;;0;\r\n\
+        NET;EXNET;Example Inc;B;N;https://example.com;;;\r\n\
+        STR;SF;San Francisco;RTCM 3.2;1004(1),1005(10),1077(1);2;GPS+GLO+GAL;EXNET;USA;37.77;-122.42;1;0;sNTRIP;none;B;N;9600;\r\n\
+        STR;LA;Los Angeles;RTCM 3.3;1077(1);2;GPS+GAL;EXNET;USA;34.05;-118.24;0;0;sNTRIP;none;B;N;;\r\n\
+        STR;RAW;Raw data;RAW;;0;GPS;EXNET;USA;37.80;-122.40;0;0;sNTRIP;none;B;N;0;\r\n\
+        STR;;missing name;RTCM 3.2;;0;GPS;EXNET;USA;0;0;0;0;sNTRIP;none;B;N;0;\r\n\
+        ENDSOURCETABLE\r\n";
+
+    #[test]
+    fn parse_sourcetable() {
+        let table = Sourcetable::parse(SOURCETABLE);
+        assert_eq!(table.mountpoints.len(), 3);
+        assert_eq!(table.casters.len(), 1);
+        assert_eq!(table.networks.len(), 1);
+
+        let sf = &table.mountpoints[0];
+        assert_eq!(sf.name, "SF");
+        assert_eq!(sf.format, "RTCM 3.2");
+        assert_eq!(sf.nav_systems, ["GPS", "GLO", "GAL"]);
+        assert_eq!((sf.lat, sf.lon), (37.77, -122.42));
+        assert!(sf.nmea);
+        assert!(!sf.network_solution);
+        assert_eq!(sf.bitrate, Some(9600));
+        assert!(!table.mountpoints[2].is_rtcm3());
+
+        assert_eq!(table.casters[0].host, "caster.example.com");
+        assert_eq!(table.casters[0].port, Some(2101));
+        assert_eq!(table.networks[0].web, "https://example.com");
+    }
+
+    #[test]
+    fn nearest_mountpoint() {
+        let table = Sourcetable::parse(SOURCETABLE);
+        // the RAW mountpoint is closer but can't be used
+        assert_eq!(table.nearest(37.80, -122.40).unwrap().name, "SF");
+        assert_eq!(table.nearest(33.0, -117.0).unwrap().name, "LA");
+        let km = distance_km((37.77, -122.42), (34.05, -118.24));
+        assert!((km - 559.0).abs() < 1.0, "{km}");
+    }
+
+    #[test]
+    fn caster_urls() {
+        assert_eq!(
+            caster_root("na.skylark.swiftnav.com:2101"),
+            "http://na.skylark.swiftnav.com:2101/"
+        );
+        assert_eq!(
+            mountpoint_url("https://caster.example.com:443/OLD", "SF"),
+            "https://caster.example.com:443/SF"
+        );
+        assert_eq!(mountpoint_name("https://caster.example.com:443/SF"), "SF");
+        assert_eq!(mountpoint_name("caster.example.com:2101"), "");
+    }
+}
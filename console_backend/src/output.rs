@@ -20,14 +20,24 @@
 use anyhow::anyhow;
 use log::error;
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local, SecondsFormat};
 use sbp::json::to_vec;
 use sbp::{Frame, Sbp};
 use serde::Serialize;
+use serde_json::{json, Map, Value};
 
 use crate::common_constants as cc;
+use crate::constants::{
+    ANNOTATIONS_TIME_STR_FILEPATH, BASELINE_TIME_STR_FILEPATH, DOPS_TIME_STR_FILEPATH,
+    IMU_TIME_STR_FILEPATH, INS_TIME_STR_FILEPATH, MAG_TIME_STR_FILEPATH,
+    OBSERVATION_TIME_STR_FILEPATH, POS_LLH_TIME_STR_FILEPATH, PROTECTION_LEVEL_TIME_STR_FILEPATH,
+    TRACKING_CN0_TIME_STR_FILEPATH, VEL_TIME_STR_FILEPATH,
+};
 use crate::types::Result;
 use crate::utils::formatters::*;
 use crate::utils::OkOrLog;
@@ -47,13 +57,158 @@ impl CsvLogging {
     }
 }
 
+pub type CsvLogger = cc::CsvLogger;
+impl CsvLogger {
+    /// All CSV loggers in the order they are shown in the logging bar.
+    pub fn all() -> Vec<CsvLogger> {
+        use strum::VariantNames;
+        CsvLogger::VARIANTS
+            .iter()
+            .filter_map(|name| CsvLogger::from_str(name).ok())
+            .collect()
+    }
+
+    /// The strftime pattern used to name this logger's file.
+    pub fn filename_format(&self) -> &'static str {
+        match self {
+            CsvLogger::POSITION => POS_LLH_TIME_STR_FILEPATH,
+            CsvLogger::VELOCITY => VEL_TIME_STR_FILEPATH,
+            CsvLogger::BASELINE => BASELINE_TIME_STR_FILEPATH,
+            CsvLogger::DOPS => DOPS_TIME_STR_FILEPATH,
+            CsvLogger::TRACKING_CN0 => TRACKING_CN0_TIME_STR_FILEPATH,
+            CsvLogger::OBSERVATIONS => OBSERVATION_TIME_STR_FILEPATH,
+            CsvLogger::IMU => IMU_TIME_STR_FILEPATH,
+            CsvLogger::MAG => MAG_TIME_STR_FILEPATH,
+            CsvLogger::INS => INS_TIME_STR_FILEPATH,
+            CsvLogger::PROTECTION_LEVEL => PROTECTION_LEVEL_TIME_STR_FILEPATH,
+            CsvLogger::ANNOTATIONS => ANNOTATIONS_TIME_STR_FILEPATH,
+        }
+    }
+}
+
 pub type SbpLogging = cc::SbpLogging;
 
+pub type SbpJsonOption = cc::SbpJsonOption;
+impl SbpJsonOption {
+    /// The options giving the layout written by `sbp::json::to_vec`.
+    pub fn defaults() -> Vec<SbpJsonOption> {
+        vec![SbpJsonOption::PAYLOAD]
+    }
+}
+
+/// Encodes SBP messages as SBP-JSON lines.
+///
+/// - `COMPACT`: Only `msg_type`, `sender` and the message fields, instead of the full frame.
+/// - `PAYLOAD`: Include the raw payload, base64 encoded.
+/// - `HOST_TIME`: Include the host time the message was logged as `host_time`.
+/// - `PIKSI_TOOLS`: Wrap each message as `{"time", "delta", "timestamp", "data"}` the way
+///   piksi_tools' JSON logger does, `delta` being milliseconds since the log started.
+#[derive(Debug, Clone)]
+pub struct SbpJsonEncoder {
+    compact: bool,
+    payload: bool,
+    host_time: bool,
+    piksi_tools: bool,
+    start: DateTime<Local>,
+}
+
+impl SbpJsonEncoder {
+    pub fn new(options: &[SbpJsonOption]) -> Self {
+        SbpJsonEncoder {
+            compact: options.contains(&SbpJsonOption::COMPACT),
+            payload: options.contains(&SbpJsonOption::PAYLOAD),
+            host_time: options.contains(&SbpJsonOption::HOST_TIME),
+            piksi_tools: options.contains(&SbpJsonOption::PIKSI_TOOLS),
+            start: Local::now(),
+        }
+    }
+
+    pub fn encode(&self, msg: &Sbp) -> Result<Vec<u8>> {
+        let bytes = to_vec(msg).map_err(|e| anyhow!("error serializing SBP to JSON, {e}"))?;
+        if !self.compact && self.payload && !self.host_time && !self.piksi_tools {
+            return Ok(bytes);
+        }
+        let mut fields: Map<String, Value> = serde_json::from_slice(&bytes)?;
+        if self.compact {
+            for key in ["preamble", "length", "crc"] {
+                fields.remove(key);
+            }
+        }
+        if !self.payload {
+            fields.remove("payload");
+        }
+        let now = Local::now();
+        let time = now.to_rfc3339_opts(SecondsFormat::Millis, false);
+        let line = if self.piksi_tools {
+            json!({
+                "time": time,
+                "delta": (now - self.start).num_milliseconds(),
+                "timestamp": now.timestamp_millis() as f64 / 1000.0,
+                "data": fields,
+            })
+        } else {
+            if self.host_time {
+                fields.insert("host_time".into(), time.into());
+            }
+            Value::Object(fields)
+        };
+        let mut bytes = serde_json::to_vec(&line)?;
+        bytes.push(b'\n');
+        Ok(bytes)
+    }
+}
+
+impl Default for SbpJsonEncoder {
+    fn default() -> Self {
+        SbpJsonEncoder::new(&SbpJsonOption::defaults())
+    }
+}
+
+/// Flushes a log and syncs it to disk at most once per interval, so a crash or
+/// power loss costs at most one interval of data.
+#[derive(Debug)]
+pub struct LogSync {
+    interval: Option<Duration>,
+    last_sync: Instant,
+}
+
+impl LogSync {
+    /// # Parameters:
+    /// - `interval`: Time between syncs; `None` leaves syncing to the OS until the log closes.
+    pub fn new(interval: Option<Duration>) -> Self {
+        LogSync {
+            interval,
+            last_sync: Instant::now(),
+        }
+    }
+
+    /// Run `sync` if the interval has passed since the last sync.
+    pub fn maybe_sync(&mut self, sync: impl FnOnce() -> io::Result<()>) {
+        let due = self
+            .interval
+            .is_some_and(|interval| self.last_sync.elapsed() >= interval);
+        if due {
+            if let Err(e) = sync() {
+                error!("issue syncing log to disk, {e}");
+            }
+            self.last_sync = Instant::now();
+        }
+    }
+}
+
+impl Default for LogSync {
+    fn default() -> Self {
+        LogSync::new(None)
+    }
+}
+
 #[derive(Debug)]
 pub struct SbpFileLogger {
     logger: SbpLogging,
     path: PathBuf,
     write: File,
+    sync: LogSync,
+    json: SbpJsonEncoder,
 }
 
 impl SbpFileLogger {
@@ -65,9 +220,21 @@ impl SbpFileLogger {
             logger,
             path,
             write,
+            sync: LogSync::default(),
+            json: SbpJsonEncoder::default(),
         })
     }
 
+    pub fn with_sync_interval(mut self, interval: Option<Duration>) -> Self {
+        self.sync = LogSync::new(interval);
+        self
+    }
+
+    pub fn with_json_options(mut self, options: &[SbpJsonOption]) -> Self {
+        self.json = SbpJsonEncoder::new(options);
+        self
+    }
+
     /// Log data into respective outputs, SBP or JSON format
     ///
     /// # Parameters:
@@ -82,8 +249,8 @@ impl SbpFileLogger {
         }
         let bytes = match &self.logger {
             SbpLogging::SBP_JSON => msg
-                .map(to_vec)
-                .and_then(|ret| ret.ok_or_log(|_| error!("error serializing SBP to JSON"))),
+                .map(|msg| self.json.encode(msg))
+                .and_then(|ret| ret.ok_or_log(|e| error!("{e}"))),
             SbpLogging::SBP => Some(frame.as_bytes().to_owned()),
         };
 
@@ -95,13 +262,16 @@ impl SbpFileLogger {
             ));
         }
 
-        Ok(bytes
+        let size = bytes
             .map(|b| self.write.write_all(b.as_slice()).map(|_| b))
             .transpose()
             .ok_or_log(|e| error!("{e}"))
             .flatten()
             .map(|b| b.len() as u16)
-            .unwrap_or(0))
+            .unwrap_or(0);
+        let write = &self.write;
+        self.sync.maybe_sync(|| write.sync_data());
+        Ok(size)
     }
 }
 
@@ -116,12 +286,21 @@ impl SbpLogging {
 #[derive(Debug)]
 pub struct CsvSerializer {
     writer: csv::Writer<File>,
+    sync: LogSync,
 }
 
 impl CsvSerializer {
     pub fn new(filepath: impl AsRef<Path>) -> Result<CsvSerializer> {
         let writer = csv::Writer::from_path(filepath)?;
-        Ok(CsvSerializer { writer })
+        Ok(CsvSerializer {
+            writer,
+            sync: LogSync::default(),
+        })
+    }
+
+    pub fn with_sync_interval(mut self, interval: Option<Duration>) -> Self {
+        self.sync = LogSync::new(interval);
+        self
     }
 
     pub fn new_option(filepath: impl AsRef<Path> + Copy) -> Option<CsvSerializer> {
@@ -133,11 +312,17 @@ impl CsvSerializer {
 
     pub fn serialize(&mut self, ds: &impl Serialize) -> Result<()> {
         self.writer.serialize(ds)?;
+        let writer = &mut self.writer;
+        self.sync.maybe_sync(|| {
+            writer.flush()?;
+            writer.get_ref().sync_data()
+        });
         Ok(())
     }
 
     pub fn flush(&mut self) -> Result<()> {
         self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
         Ok(())
     }
 }
@@ -203,6 +388,121 @@ pub struct VelLog {
     pub num_signals: u8,
 }
 
+#[derive(Serialize)]
+pub struct DopsLog {
+    pub pc_time: String,
+    pub gps_time: Option<String>,
+    #[serde(rename = "tow(sec)", with = "float_formatter_3")]
+    pub tow_s: Option<f64>,
+    #[serde(with = "float_formatter_2")]
+    pub pdop: Option<f64>,
+    #[serde(with = "float_formatter_2")]
+    pub gdop: Option<f64>,
+    #[serde(with = "float_formatter_2")]
+    pub tdop: Option<f64>,
+    #[serde(with = "float_formatter_2")]
+    pub hdop: Option<f64>,
+    #[serde(with = "float_formatter_2")]
+    pub vdop: Option<f64>,
+    pub flags: u8,
+}
+
+#[derive(Serialize)]
+pub struct TrackingCn0Log {
+    pub pc_time: String,
+    pub signal: String,
+    pub sat: i16,
+    #[serde(rename = "cn0(dB-Hz)", with = "float_formatter_2")]
+    pub cn0_dbhz: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct ObservationLog {
+    pub pc_time: String,
+    pub gps_time: Option<String>,
+    #[serde(rename = "tow(sec)", with = "float_formatter_3")]
+    pub tow_s: Option<f64>,
+    pub sender_id: Option<u16>,
+    pub signal: String,
+    pub sat: i16,
+    #[serde(rename = "pseudorange(meters)", with = "float_formatter_4")]
+    pub pseudo_range_m: Option<f64>,
+    #[serde(rename = "carrier_phase(cycles)", with = "float_formatter_4")]
+    pub carrier_phase_cycles: Option<f64>,
+    #[serde(rename = "cn0(dB-Hz)", with = "float_formatter_2")]
+    pub cn0_dbhz: Option<f64>,
+    #[serde(rename = "measured_doppler(Hz)", with = "float_formatter_4")]
+    pub measured_doppler_hz: Option<f64>,
+    pub lock: u16,
+    pub flags: u8,
+}
+
+#[derive(Serialize)]
+pub struct ImuRawLog {
+    pub pc_time: String,
+    #[serde(rename = "tow(sec)", with = "float_formatter_6")]
+    pub tow_s: Option<f64>,
+    pub acc_x: i16,
+    pub acc_y: i16,
+    pub acc_z: i16,
+    pub gyr_x: i16,
+    pub gyr_y: i16,
+    pub gyr_z: i16,
+    #[serde(rename = "temperature(degC)", with = "float_formatter_2")]
+    pub temperature_c: Option<f64>,
+    pub imu_conf: u8,
+}
+
+#[derive(Serialize)]
+pub struct MagRawLog {
+    pub pc_time: String,
+    #[serde(rename = "tow(sec)", with = "float_formatter_6")]
+    pub tow_s: Option<f64>,
+    pub mag_x: i16,
+    pub mag_y: i16,
+    pub mag_z: i16,
+}
+
+#[derive(Serialize)]
+pub struct InsLog {
+    pub pc_time: String,
+    pub msg: &'static str,
+    #[serde(rename = "tow(sec)", with = "float_formatter_3")]
+    pub tow_s: Option<f64>,
+    pub status_flags: Option<u32>,
+    pub gnsspos: Option<u8>,
+    pub gnssvel: Option<u8>,
+    pub wheelticks: Option<u8>,
+    pub speed: Option<u8>,
+    pub nhc: Option<u8>,
+    pub zerovel: Option<u8>,
+}
+
+#[derive(Serialize)]
+pub struct ProtectionLevelLog {
+    pub pc_time: String,
+    pub gps_time: Option<String>,
+    #[serde(rename = "tow(sec)", with = "float_formatter_3")]
+    pub tow_s: Option<f64>,
+    #[serde(rename = "latitude(degrees)", with = "float_formatter_10")]
+    pub latitude_d: Option<f64>,
+    #[serde(rename = "longitude(degrees)", with = "float_formatter_10")]
+    pub longitude_d: Option<f64>,
+    #[serde(rename = "hpl(meters)", with = "float_formatter_2")]
+    pub hpl_m: Option<f64>,
+    #[serde(rename = "vpl(meters)", with = "float_formatter_2")]
+    pub vpl_m: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct AnnotationLog {
+    pub pc_time: String,
+    pub gps_time: Option<String>,
+    #[serde(rename = "tow(sec)", with = "float_formatter_3")]
+    pub tow_s: Option<f64>,
+    pub text: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -318,4 +618,31 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn sbp_json_encoder_test() {
+        let msg = Sbp::MsgAgeCorrections(MsgAgeCorrections {
+            sender_id: Some(1337),
+            age: 10,
+            tow: 1000,
+        });
+        let full: Value =
+            serde_json::from_slice(&SbpJsonEncoder::default().encode(&msg).unwrap()).unwrap();
+        assert!(full.get("crc").is_some());
+        assert!(full.get("payload").is_some());
+
+        let encoder = SbpJsonEncoder::new(&[SbpJsonOption::COMPACT, SbpJsonOption::HOST_TIME]);
+        let compact: Value = serde_json::from_slice(&encoder.encode(&msg).unwrap()).unwrap();
+        assert!(compact.get("crc").is_none());
+        assert!(compact.get("payload").is_none());
+        assert!(compact.get("host_time").is_some());
+        assert_eq!(compact["age"], 10);
+
+        let encoder = SbpJsonEncoder::new(&[SbpJsonOption::PIKSI_TOOLS]);
+        let wrapped: Value = serde_json::from_slice(&encoder.encode(&msg).unwrap()).unwrap();
+        assert!(wrapped.get("time").is_some());
+        assert!(wrapped.get("delta").is_some());
+        assert_eq!(wrapped["data"]["msg_type"], 0x0210);
+        assert!(wrapped["data"].get("payload").is_none());
+    }
 }
@@ -65,7 +65,7 @@ pub fn process_messages(
             msg_sender.clone(),
         )
     } else {
-        Tabs::new(
+        Tabs::with_read_only_settings(
             shared_state.clone(),
             client_sender.clone(),
             msg_sender.clone(),
@@ -100,9 +100,15 @@ pub fn process_messages(
             });
         }
         scope.spawn(|_| process_shared_state_events(event_rx, &tabs));
-        for (frame, _) in &mut messages {
+        shared_state.set_gps_time(None);
+        let ntrip_server = shared_state.ntrip_server_uplink();
+        for (frame, time) in &mut messages {
+            if let Some(Ok(time)) = time {
+                shared_state.set_gps_time(Some(time.into()));
+            }
             tabs.status_bar.lock().unwrap().add_bytes(frame.len());
             tabs.advanced_networking.lock().unwrap().update(&frame);
+            ntrip_server.handle_frame(&frame);
             let msg = match frame.to_sbp() {
                 Ok(msg) => Some(msg),
                 Err(e) => {
@@ -302,12 +308,7 @@ fn register_events(link: sbp::link::Link<Tabs>) {
             .lock()
             .unwrap()
             .handle_pos_llh(msg.clone());
-        tabs.status_bar.lock().unwrap().handle_pos_llh(msg.clone());
-
-        // ntrip tab dynamic position
-        let mut guard = tabs.shared_state.lock();
-        let ntrip = &mut guard.ntrip_tab;
-        ntrip.set_last_data(msg);
+        tabs.status_bar.lock().unwrap().handle_pos_llh(msg);
     });
     link.register(|tabs: &Tabs, msg: MsgPosLlhCov| {
         tabs.solution_position
@@ -0,0 +1,1086 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! RINEX 3.04 observation and navigation writers.
+//!
+//! Observations are taken from `MsgObs` and ephemerides from the GPS, QZSS,
+//! Galileo, BeiDou and GLONASS ephemeris messages. Both writers stream to disk so
+//! they can be used as live loggers as well as by the offline converter.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration as StdDuration;
+
+use chrono::{Datelike, Duration, TimeZone, Timelike, Utc};
+use log::error;
+use sbp::messages::observation::{
+    MsgEphemerisBds, MsgEphemerisGal, MsgEphemerisGlo, MsgEphemerisGps, MsgEphemerisQzss,
+};
+use sbp::{Sbp, SbpIterExt};
+
+use crate::constants::{GLO_FCN_OFFSET, GLO_SLOT_SAT_MAX, GPS_UTC_LEAP_SECONDS};
+use crate::output::LogSync;
+use crate::session_metadata::DeviceInfo;
+use crate::types::{ObservationMsg, Result, SignalCodes, UtcDateTime};
+use crate::utils::OkOrLog;
+
+const RINEX_VERSION: f64 = 3.04;
+const RINEX_PROGRAM: &str = "Swift Console";
+const RINEX_AGENCY: &str = "Swift Navigation";
+const SECONDS_PER_WEEK: i64 = 604800;
+/// BeiDou time started at GPS week 1356, 14 seconds behind GPS time.
+const BDT_GPS_WEEK_OFFSET: i64 = 1356;
+const BDT_GPS_SECONDS_OFFSET: i64 = 14;
+const QZSS_PRN_OFFSET: i16 = 192;
+const SBAS_PRN_OFFSET: i16 = 100;
+const OBS_TYPES_PER_LINE: usize = 13;
+const OBS_KINDS: [char; 4] = ['C', 'L', 'D', 'S'];
+
+/// Satellite systems in the order they are listed in RINEX headers.
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RinexSystem {
+    Gps,
+    Glonass,
+    Galileo,
+    Beidou,
+    Qzss,
+    Sbas,
+}
+
+impl RinexSystem {
+    pub const ALL: [RinexSystem; 6] = [
+        RinexSystem::Gps,
+        RinexSystem::Glonass,
+        RinexSystem::Galileo,
+        RinexSystem::Beidou,
+        RinexSystem::Qzss,
+        RinexSystem::Sbas,
+    ];
+
+    pub fn letter(&self) -> char {
+        match self {
+            RinexSystem::Gps => 'G',
+            RinexSystem::Glonass => 'R',
+            RinexSystem::Galileo => 'E',
+            RinexSystem::Beidou => 'C',
+            RinexSystem::Qzss => 'J',
+            RinexSystem::Sbas => 'S',
+        }
+    }
+
+    /// Convert an SBP satellite id into the two digit RINEX satellite number.
+    pub fn sat_number(&self, sat: i16) -> Option<u8> {
+        let sat = match self {
+            RinexSystem::Qzss => sat - QZSS_PRN_OFFSET,
+            RinexSystem::Sbas => sat - SBAS_PRN_OFFSET,
+            // GLONASS satellites without a known slot can't be represented
+            RinexSystem::Glonass if sat > GLO_SLOT_SAT_MAX as i16 => return None,
+            _ => sat,
+        };
+        (1..=99).contains(&sat).then_some(sat as u8)
+    }
+}
+
+/// SBP signal codes and their RINEX system and band/attribute observation code.
+const RINEX_SIGNALS: [(SignalCodes, RinexSystem, &str); 59] = [
+    (SignalCodes::CodeGpsL1Ca, RinexSystem::Gps, "1C"),
+    (SignalCodes::CodeGpsL1P, RinexSystem::Gps, "1W"),
+    (SignalCodes::CodeGpsL1Ci, RinexSystem::Gps, "1S"),
+    (SignalCodes::CodeGpsL1Cq, RinexSystem::Gps, "1L"),
+    (SignalCodes::CodeGpsL1Cx, RinexSystem::Gps, "1X"),
+    (SignalCodes::CodeGpsL2Cm, RinexSystem::Gps, "2S"),
+    (SignalCodes::CodeGpsL2Cl, RinexSystem::Gps, "2L"),
+    (SignalCodes::CodeGpsL2Cx, RinexSystem::Gps, "2X"),
+    (SignalCodes::CodeGpsL2P, RinexSystem::Gps, "2W"),
+    (SignalCodes::CodeGpsL5I, RinexSystem::Gps, "5I"),
+    (SignalCodes::CodeGpsL5Q, RinexSystem::Gps, "5Q"),
+    (SignalCodes::CodeGpsL5X, RinexSystem::Gps, "5X"),
+    (SignalCodes::CodeGloL1Of, RinexSystem::Glonass, "1C"),
+    (SignalCodes::CodeGloL1P, RinexSystem::Glonass, "1P"),
+    (SignalCodes::CodeGloL2Of, RinexSystem::Glonass, "2C"),
+    (SignalCodes::CodeGloL2P, RinexSystem::Glonass, "2P"),
+    (SignalCodes::CodeGalE1B, RinexSystem::Galileo, "1B"),
+    (SignalCodes::CodeGalE1C, RinexSystem::Galileo, "1C"),
+    (SignalCodes::CodeGalE1X, RinexSystem::Galileo, "1X"),
+    (SignalCodes::CodeGalE5I, RinexSystem::Galileo, "5I"),
+    (SignalCodes::CodeGalE5Q, RinexSystem::Galileo, "5Q"),
+    (SignalCodes::CodeGalE5X, RinexSystem::Galileo, "5X"),
+    (SignalCodes::CodeGalE6B, RinexSystem::Galileo, "6B"),
+    (SignalCodes::CodeGalE6C, RinexSystem::Galileo, "6C"),
+    (SignalCodes::CodeGalE6X, RinexSystem::Galileo, "6X"),
+    (SignalCodes::CodeGalE7I, RinexSystem::Galileo, "7I"),
+    (SignalCodes::CodeGalE7Q, RinexSystem::Galileo, "7Q"),
+    (SignalCodes::CodeGalE7X, RinexSystem::Galileo, "7X"),
+    (SignalCodes::CodeGalE8I, RinexSystem::Galileo, "8I"),
+    (SignalCodes::CodeGalE8Q, RinexSystem::Galileo, "8Q"),
+    (SignalCodes::CodeGalE8X, RinexSystem::Galileo, "8X"),
+    (SignalCodes::CodeBds2B1, RinexSystem::Beidou, "2I"),
+    (SignalCodes::CodeBds3B1Ci, RinexSystem::Beidou, "1D"),
+    (SignalCodes::CodeBds3B1Cq, RinexSystem::Beidou, "1P"),
+    (SignalCodes::CodeBds3B1Cx, RinexSystem::Beidou, "1X"),
+    (SignalCodes::CodeBds3B5I, RinexSystem::Beidou, "5D"),
+    (SignalCodes::CodeBds3B5Q, RinexSystem::Beidou, "5P"),
+    (SignalCodes::CodeBds3B5X, RinexSystem::Beidou, "5X"),
+    (SignalCodes::CodeBds3B3I, RinexSystem::Beidou, "6I"),
+    (SignalCodes::CodeBds3B3Q, RinexSystem::Beidou, "6Q"),
+    (SignalCodes::CodeBds3B3X, RinexSystem::Beidou, "6X"),
+    (SignalCodes::CodeBds2B2, RinexSystem::Beidou, "7I"),
+    (SignalCodes::CodeBds3B7I, RinexSystem::Beidou, "7D"),
+    (SignalCodes::CodeBds3B7Q, RinexSystem::Beidou, "7P"),
+    (SignalCodes::CodeBds3B7X, RinexSystem::Beidou, "7Z"),
+    (SignalCodes::CodeQzsL1Ca, RinexSystem::Qzss, "1C"),
+    (SignalCodes::CodeQzsL1Ci, RinexSystem::Qzss, "1S"),
+    (SignalCodes::CodeQzsL1Cq, RinexSystem::Qzss, "1L"),
+    (SignalCodes::CodeQzsL1Cx, RinexSystem::Qzss, "1X"),
+    (SignalCodes::CodeQzsL2Cm, RinexSystem::Qzss, "2S"),
+    (SignalCodes::CodeQzsL2Cl, RinexSystem::Qzss, "2L"),
+    (SignalCodes::CodeQzsL2Cx, RinexSystem::Qzss, "2X"),
+    (SignalCodes::CodeQzsL5I, RinexSystem::Qzss, "5I"),
+    (SignalCodes::CodeQzsL5Q, RinexSystem::Qzss, "5Q"),
+    (SignalCodes::CodeQzsL5X, RinexSystem::Qzss, "5X"),
+    (SignalCodes::CodeSbasL1Ca, RinexSystem::Sbas, "1C"),
+    (SignalCodes::CodeSbasL5I, RinexSystem::Sbas, "5I"),
+    (SignalCodes::CodeSbasL5Q, RinexSystem::Sbas, "5Q"),
+    (SignalCodes::CodeSbasL5X, RinexSystem::Sbas, "5X"),
+];
+
+/// The RINEX system and observation code (e.g. `1C`) of an SBP signal.
+pub fn rinex_signal(code: SignalCodes) -> Option<(RinexSystem, &'static str)> {
+    RINEX_SIGNALS
+        .iter()
+        .find(|(signal, _, _)| *signal == code)
+        .map(|(_, system, obs_code)| (*system, *obs_code))
+}
+
+/// All observation types (e.g. `C1C`) written for a system, in header order.
+fn obs_types(system: RinexSystem) -> Vec<String> {
+    RINEX_SIGNALS
+        .iter()
+        .filter(|(_, sys, _)| *sys == system)
+        .flat_map(|(_, _, code)| OBS_KINDS.iter().map(move |kind| format!("{kind}{code}")))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SignalObs {
+    pseudorange: Option<f64>,
+    carrier_phase: Option<f64>,
+    doppler: Option<f64>,
+    cn0: Option<f64>,
+    /// Loss of lock indicator, only applies to the carrier phase.
+    lli: u8,
+    /// Signal strength indicator, 1 (weakest) to 9.
+    ssi: Option<u8>,
+}
+
+impl SignalObs {
+    fn value(&self, kind: char) -> Option<f64> {
+        match kind {
+            'C' => self.pseudorange,
+            'L' => self.carrier_phase,
+            'D' => self.doppler,
+            'S' => self.cn0,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ObsEpoch {
+    time: UtcDateTime,
+    sats: BTreeMap<(RinexSystem, u8), HashMap<&'static str, SignalObs>>,
+}
+
+/// Streams `MsgObs` observations to a RINEX 3.04 observation file.
+///
+/// The header is written once the first epoch is complete, so the time of first
+/// observation and approximate position can be filled in.
+#[derive(Debug)]
+pub struct RinexObsWriter {
+    writer: BufWriter<File>,
+    receiver: DeviceInfo,
+    approx_position: [f64; 3],
+    header_written: bool,
+    epoch: Option<ObsEpoch>,
+    locks: HashMap<(SignalCodes, i16), u16>,
+    epochs: usize,
+}
+
+impl RinexObsWriter {
+    pub fn new(filepath: impl AsRef<Path>, receiver: DeviceInfo) -> Result<RinexObsWriter> {
+        Ok(RinexObsWriter {
+            writer: BufWriter::new(File::create(filepath)?),
+            receiver,
+            approx_position: [0.0; 3],
+            header_written: false,
+            epoch: None,
+            locks: HashMap::new(),
+            epochs: 0,
+        })
+    }
+
+    /// Set the ECEF position written to the header, if it hasn't been written yet.
+    pub fn set_approx_position(&mut self, position: [f64; 3]) {
+        if !self.header_written {
+            self.approx_position = position;
+        }
+    }
+
+    pub fn write_obs(&mut self, msg: &ObservationMsg) -> Result<()> {
+        let fields = msg.fields();
+        // sender id 0 are the base station observations forwarded to the rover
+        if fields.sender_id == Some(0) {
+            return Ok(());
+        }
+        let time = gps_epoch()
+            + Duration::weeks(fields.wn as i64)
+            + Duration::milliseconds((fields.tow * 1e3).round() as i64)
+            + Duration::nanoseconds(fields.ns_residual as i64);
+        if matches!(&self.epoch, Some(epoch) if epoch.time != time) {
+            self.write_epoch()?;
+        }
+        let epoch = self.epoch.get_or_insert_with(|| ObsEpoch {
+            time,
+            sats: BTreeMap::new(),
+        });
+        for state in fields.states.iter() {
+            let obs = state.fields();
+            let (system, code) = match rinex_signal(obs.code) {
+                Some(signal) => signal,
+                None => continue,
+            };
+            let sat = match system.sat_number(obs.sat) {
+                Some(sat) => sat,
+                None => continue,
+            };
+            // Bit 0 pseudorange valid, bit 1 carrier phase valid,
+            // bit 2 half cycle ambiguity resolved and bit 3 doppler valid.
+            let carrier_valid = obs.flags & 0x02 != 0;
+            let cn0 = obs.cn0 / 4.0;
+            let mut lli = 0;
+            let last_lock = self.locks.insert((obs.code, obs.sat), obs.lock);
+            if matches!(last_lock, Some(lock) if obs.lock < lock) {
+                lli |= 0x1;
+            }
+            if carrier_valid && obs.flags & 0x04 == 0 {
+                lli |= 0x2;
+            }
+            let signal = SignalObs {
+                pseudorange: (obs.flags & 0x01 != 0).then_some(obs.pseudo_range),
+                carrier_phase: carrier_valid.then_some(obs.carrier_phase),
+                doppler: (obs.flags & 0x08 != 0).then_some(obs.measured_doppler),
+                cn0: (cn0 > 0.0).then_some(cn0),
+                lli,
+                ssi: (cn0 > 0.0).then(|| ((cn0 / 6.0) as u8).clamp(1, 9)),
+            };
+            epoch
+                .sats
+                .entry((system, sat))
+                .or_default()
+                .insert(code, signal);
+        }
+        let total = fields.n_obs >> 4;
+        let count = fields.n_obs & 0x0F;
+        if count + 1 >= total {
+            self.write_epoch()?;
+        }
+        Ok(())
+    }
+
+    /// Write out any pending epoch and flush the file.
+    ///
+    /// # Returns
+    /// - The number of epochs written.
+    pub fn finish(mut self) -> Result<usize> {
+        self.write_epoch()?;
+        self.writer.flush()?;
+        Ok(self.epochs)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()
+    }
+
+    fn write_epoch(&mut self) -> Result<()> {
+        let epoch = match self.epoch.take() {
+            Some(epoch) if !epoch.sats.is_empty() => epoch,
+            _ => return Ok(()),
+        };
+        if !self.header_written {
+            self.write_header(epoch.time)?;
+            self.header_written = true;
+        }
+        let t = epoch.time;
+        writeln!(
+            self.writer,
+            "> {:04} {:02} {:02} {:02} {:02}{:11.7}  0{:3}",
+            t.year(),
+            t.month(),
+            t.day(),
+            t.hour(),
+            t.minute(),
+            seconds(&t),
+            epoch.sats.len()
+        )?;
+        for ((system, sat), signals) in epoch.sats.iter() {
+            let mut line = format!("{}{:02}", system.letter(), sat);
+            for obs_type in obs_types(*system) {
+                let (kind, code) = obs_type.split_at(1);
+                let kind = kind.chars().next().unwrap_or_default();
+                let signal = signals.get(code);
+                match signal.and_then(|signal| signal.value(kind)) {
+                    Some(value) => {
+                        let signal = signal.copied().unwrap_or_default();
+                        let lli = match signal.lli {
+                            0 => ' ',
+                            lli if kind == 'L' => char::from(b'0' + lli),
+                            _ => ' ',
+                        };
+                        let ssi = match signal.ssi {
+                            Some(ssi) if kind != 'S' => char::from(b'0' + ssi),
+                            _ => ' ',
+                        };
+                        line.push_str(&format!("{value:14.3}{lli}{ssi}"));
+                    }
+                    None => line.push_str(&" ".repeat(16)),
+                }
+            }
+            writeln!(self.writer, "{}", line.trim_end())?;
+        }
+        self.epochs += 1;
+        Ok(())
+    }
+
+    fn write_header(&mut self, first_obs: UtcDateTime) -> Result<()> {
+        let receiver = &self.receiver;
+        let [x, y, z] = self.approx_position;
+        let mut lines = vec![
+            header_line(
+                format!(
+                    "{:9.2}{:11}{:<20}{:<20}",
+                    RINEX_VERSION, "", "OBSERVATION DATA", "M: Mixed"
+                ),
+                "RINEX VERSION / TYPE",
+            ),
+            program_line(),
+            header_line(
+                receiver.serial_number.as_deref().unwrap_or("UNKNOWN"),
+                "MARKER NAME",
+            ),
+            header_line(
+                format!("{:<20}{:<40}", "", RINEX_AGENCY),
+                "OBSERVER / AGENCY",
+            ),
+            header_line(
+                format!(
+                    "{:<20.20}{:<20.20}{:<20.20}",
+                    receiver.serial_number.as_deref().unwrap_or_default(),
+                    receiver.product_id.as_deref().unwrap_or_default(),
+                    receiver.firmware_version.as_deref().unwrap_or_default()
+                ),
+                "REC # / TYPE / VERS",
+            ),
+            header_line("", "ANT # / TYPE"),
+            header_line(format!("{x:14.4}{y:14.4}{z:14.4}"), "APPROX POSITION XYZ"),
+            header_line(
+                format!("{:14.4}{:14.4}{:14.4}", 0.0, 0.0, 0.0),
+                "ANTENNA: DELTA H/E/N",
+            ),
+        ];
+        for system in RinexSystem::ALL {
+            let types = obs_types(system);
+            for (i, chunk) in types.chunks(OBS_TYPES_PER_LINE).enumerate() {
+                let prefix = if i == 0 {
+                    format!("{}  {:3}", system.letter(), types.len())
+                } else {
+                    " ".repeat(6)
+                };
+                let types: String = chunk.iter().map(|t| format!(" {t}")).collect();
+                lines.push(header_line(prefix + &types, "SYS / # / OBS TYPES"));
+            }
+        }
+        lines.push(header_line("DBHZ", "SIGNAL STRENGTH UNIT"));
+        lines.push(header_line(
+            format!(
+                "{:6}{:6}{:6}{:6}{:6}{:13.7}{:5}{:<3}",
+                first_obs.year(),
+                first_obs.month(),
+                first_obs.day(),
+                first_obs.hour(),
+                first_obs.minute(),
+                seconds(&first_obs),
+                "",
+                "GPS"
+            ),
+            "TIME OF FIRST OBS",
+        ));
+        for system in RinexSystem::ALL {
+            lines.push(header_line(
+                system.letter().to_string(),
+                "SYS / PHASE SHIFT",
+            ));
+        }
+        lines.push(header_line(format!("{:3}", 0), "GLONASS SLOT / FRQ #"));
+        lines.push(header_line(
+            " C1C    0.000 C1P    0.000 C2C    0.000 C2P    0.000",
+            "GLONASS COD/PHS/BIS",
+        ));
+        lines.push(header_line("", "END OF HEADER"));
+        for line in lines {
+            writeln!(self.writer, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Streams broadcast ephemerides to a RINEX 3.04 navigation file.
+///
+/// Ephemerides are rebroadcast regularly, each one is only written once.
+#[derive(Debug)]
+pub struct RinexNavWriter {
+    writer: BufWriter<File>,
+    written: HashSet<(RinexSystem, u8, u16, u32)>,
+}
+
+impl RinexNavWriter {
+    pub fn new(filepath: impl AsRef<Path>) -> Result<RinexNavWriter> {
+        let mut nav = RinexNavWriter {
+            writer: BufWriter::new(File::create(filepath)?),
+            written: HashSet::new(),
+        };
+        let lines = [
+            header_line(
+                format!(
+                    "{:9.2}{:11}{:<20}{:<20}",
+                    RINEX_VERSION, "", "N: GNSS NAV DATA", "M: Mixed"
+                ),
+                "RINEX VERSION / TYPE",
+            ),
+            program_line(),
+            header_line(format!("{GPS_UTC_LEAP_SECONDS:6}"), "LEAP SECONDS"),
+            header_line("", "END OF HEADER"),
+        ];
+        for line in lines {
+            writeln!(nav.writer, "{line}")?;
+        }
+        Ok(nav)
+    }
+
+    /// Write the ephemeris in `msg`, ignoring any other message.
+    pub fn write_ephemeris(&mut self, msg: &Sbp) -> Result<()> {
+        match msg {
+            Sbp::MsgEphemerisGps(msg) => self.write_gps(msg),
+            Sbp::MsgEphemerisQzss(msg) => self.write_qzss(msg),
+            Sbp::MsgEphemerisGal(msg) => self.write_gal(msg),
+            Sbp::MsgEphemerisBds(msg) => self.write_bds(msg),
+            Sbp::MsgEphemerisGlo(msg) => self.write_glo(msg),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flush the file.
+    ///
+    /// # Returns
+    /// - The number of ephemerides written.
+    pub fn finish(mut self) -> Result<usize> {
+        self.writer.flush()?;
+        Ok(self.written.len())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()
+    }
+
+    fn write_gps(&mut self, msg: &MsgEphemerisGps) -> Result<()> {
+        let c = &msg.common;
+        let toe = c.toe.tow as f64;
+        self.write_record(
+            RinexSystem::Gps,
+            c.sid.sat,
+            (c.toe.wn, c.toe.tow, c.valid),
+            gps_time(msg.toc.wn, msg.toc.tow as i64),
+            [msg.af0 as f64, msg.af1 as f64, msg.af2 as f64],
+            &[
+                [
+                    Some(msg.iode as f64),
+                    Some(msg.c_rs as f64),
+                    Some(msg.dn),
+                    Some(msg.m0),
+                ],
+                [
+                    Some(msg.c_uc as f64),
+                    Some(msg.ecc),
+                    Some(msg.c_us as f64),
+                    Some(msg.sqrta),
+                ],
+                [
+                    Some(toe),
+                    Some(msg.c_ic as f64),
+                    Some(msg.omega0),
+                    Some(msg.c_is as f64),
+                ],
+                [
+                    Some(msg.inc),
+                    Some(msg.c_rc as f64),
+                    Some(msg.w),
+                    Some(msg.omegadot),
+                ],
+                [
+                    Some(msg.inc_dot),
+                    Some(0.0),
+                    Some(c.toe.wn as f64),
+                    Some(0.0),
+                ],
+                [
+                    Some(c.ura as f64),
+                    Some(c.health_bits as f64),
+                    Some(msg.tgd as f64),
+                    Some(msg.iodc as f64),
+                ],
+                [Some(toe), Some(c.fit_interval as f64 / 3600.0), None, None],
+            ],
+        )
+    }
+
+    fn write_qzss(&mut self, msg: &MsgEphemerisQzss) -> Result<()> {
+        let c = &msg.common;
+        let toe = c.toe.tow as f64;
+        let fit_interval_flag = if c.fit_interval > 7200 { 1.0 } else { 0.0 };
+        self.write_record(
+            RinexSystem::Qzss,
+            c.sid.sat,
+            (c.toe.wn, c.toe.tow, c.valid),
+            gps_time(msg.toc.wn, msg.toc.tow as i64),
+            [msg.af0 as f64, msg.af1 as f64, msg.af2 as f64],
+            &[
+                [
+                    Some(msg.iode as f64),
+                    Some(msg.c_rs as f64),
+                    Some(msg.dn),
+                    Some(msg.m0),
+                ],
+                [
+                    Some(msg.c_uc as f64),
+                    Some(msg.ecc),
+                    Some(msg.c_us as f64),
+                    Some(msg.sqrta),
+                ],
+                [
+                    Some(toe),
+                    Some(msg.c_ic as f64),
+                    Some(msg.omega0),
+                    Some(msg.c_is as f64),
+                ],
+                [
+                    Some(msg.inc),
+                    Some(msg.c_rc as f64),
+                    Some(msg.w),
+                    Some(msg.omegadot),
+                ],
+                [
+                    Some(msg.inc_dot),
+                    Some(0.0),
+                    Some(c.toe.wn as f64),
+                    Some(0.0),
+                ],
+                [
+                    Some(c.ura as f64),
+                    Some(c.health_bits as f64),
+                    Some(msg.tgd as f64),
+                    Some(msg.iodc as f64),
+                ],
+                [Some(toe), Some(fit_interval_flag), None, None],
+            ],
+        )
+    }
+
+    fn write_gal(&mut self, msg: &MsgEphemerisGal) -> Result<()> {
+        let c = &msg.common;
+        let toe = c.toe.tow as f64;
+        // I/NAV is E1-B and E5b-I with clock for E5b/E1, F/NAV is E5a-I with clock for E5a/E1
+        let data_sources = if msg.source == 0 { 517.0 } else { 258.0 };
+        self.write_record(
+            RinexSystem::Galileo,
+            c.sid.sat,
+            (c.toe.wn, c.toe.tow, c.valid),
+            gps_time(msg.toc.wn, msg.toc.tow as i64),
+            [msg.af0, msg.af1, msg.af2 as f64],
+            &[
+                [
+                    Some(msg.iode as f64),
+                    Some(msg.c_rs as f64),
+                    Some(msg.dn),
+                    Some(msg.m0),
+                ],
+                [
+                    Some(msg.c_uc as f64),
+                    Some(msg.ecc),
+                    Some(msg.c_us as f64),
+                    Some(msg.sqrta),
+                ],
+                [
+                    Some(toe),
+                    Some(msg.c_ic as f64),
+                    Some(msg.omega0),
+                    Some(msg.c_is as f64),
+                ],
+                [
+                    Some(msg.inc),
+                    Some(msg.c_rc as f64),
+                    Some(msg.w),
+                    Some(msg.omegadot),
+                ],
+                [
+                    Some(msg.inc_dot),
+                    Some(data_sources),
+                    Some(c.toe.wn as f64),
+                    None,
+                ],
+                [
+                    Some(c.ura as f64),
+                    Some(c.health_bits as f64),
+                    Some(msg.bgd_e1e5a as f64),
+                    Some(msg.bgd_e1e5b as f64),
+                ],
+                [Some(toe), None, None, None],
+            ],
+        )
+    }
+
+    fn write_bds(&mut self, msg: &MsgEphemerisBds) -> Result<()> {
+        let c = &msg.common;
+        let (bdt_week, toe) = bdt_week_seconds(c.toe.wn, c.toe.tow);
+        let toc =
+            gps_time(msg.toc.wn, msg.toc.tow as i64) - Duration::seconds(BDT_GPS_SECONDS_OFFSET);
+        self.write_record(
+            RinexSystem::Beidou,
+            c.sid.sat,
+            (c.toe.wn, c.toe.tow, c.valid),
+            toc,
+            [msg.af0, msg.af1 as f64, msg.af2 as f64],
+            &[
+                [
+                    Some(msg.iode as f64),
+                    Some(msg.c_rs as f64),
+                    Some(msg.dn),
+                    Some(msg.m0),
+                ],
+                [
+                    Some(msg.c_uc as f64),
+                    Some(msg.ecc),
+                    Some(msg.c_us as f64),
+                    Some(msg.sqrta),
+                ],
+                [
+                    Some(toe),
+                    Some(msg.c_ic as f64),
+                    Some(msg.omega0),
+                    Some(msg.c_is as f64),
+                ],
+                [
+                    Some(msg.inc),
+                    Some(msg.c_rc as f64),
+                    Some(msg.w),
+                    Some(msg.omegadot),
+                ],
+                [Some(msg.inc_dot), None, Some(bdt_week), None],
+                [
+                    Some(c.ura as f64),
+                    Some(c.health_bits as f64),
+                    Some(msg.tgd1 as f64),
+                    Some(msg.tgd2 as f64),
+                ],
+                [Some(toe), Some(msg.iodc as f64), None, None],
+            ],
+        )
+    }
+
+    fn write_glo(&mut self, msg: &MsgEphemerisGlo) -> Result<()> {
+        let c = &msg.common;
+        let toe = gps_time(c.toe.wn, c.toe.tow as i64) - Duration::seconds(GPS_UTC_LEAP_SECONDS);
+        let frame_time = (c.toe.wn as i64 * SECONDS_PER_WEEK + c.toe.tow as i64
+            - GPS_UTC_LEAP_SECONDS)
+            .rem_euclid(SECONDS_PER_WEEK);
+        let km = |m: f64| m / 1e3;
+        self.write_record(
+            RinexSystem::Glonass,
+            c.sid.sat,
+            (c.toe.wn, c.toe.tow, c.valid),
+            toe,
+            [-msg.tau as f64, msg.gamma as f64, frame_time as f64],
+            &[
+                [
+                    Some(km(msg.pos[0])),
+                    Some(km(msg.vel[0])),
+                    Some(km(msg.acc[0] as f64)),
+                    Some(c.health_bits as f64),
+                ],
+                [
+                    Some(km(msg.pos[1])),
+                    Some(km(msg.vel[1])),
+                    Some(km(msg.acc[1] as f64)),
+                    Some((msg.fcn as i16 - GLO_FCN_OFFSET) as f64),
+                ],
+                [
+                    Some(km(msg.pos[2])),
+                    Some(km(msg.vel[2])),
+                    Some(km(msg.acc[2] as f64)),
+                    Some(0.0),
+                ],
+            ],
+        )
+    }
+
+    fn write_record(
+        &mut self,
+        system: RinexSystem,
+        sat: u8,
+        (toe_wn, toe_tow, valid): (u16, u32, u8),
+        epoch: UtcDateTime,
+        clock: [f64; 3],
+        orbits: &[[Option<f64>; 4]],
+    ) -> Result<()> {
+        let sat = match system.sat_number(sat as i16) {
+            Some(sat) if valid != 0 => sat,
+            _ => return Ok(()),
+        };
+        if !self.written.insert((system, sat, toe_wn, toe_tow)) {
+            return Ok(());
+        }
+        writeln!(
+            self.writer,
+            "{}{:02} {:04} {:02} {:02} {:02} {:02} {:02}{}{}{}",
+            system.letter(),
+            sat,
+            epoch.year(),
+            epoch.month(),
+            epoch.day(),
+            epoch.hour(),
+            epoch.minute(),
+            epoch.second(),
+            rinex_float(clock[0]),
+            rinex_float(clock[1]),
+            rinex_float(clock[2])
+        )?;
+        for orbit in orbits {
+            let line: String = orbit
+                .iter()
+                .map(|value| value.map_or_else(|| " ".repeat(19), rinex_float))
+                .collect();
+            writeln!(self.writer, "    {}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes RINEX observation and navigation files side by side from live SBP messages.
+#[derive(Debug)]
+pub struct RinexLogger {
+    obs: RinexObsWriter,
+    nav: RinexNavWriter,
+    sync: LogSync,
+}
+
+impl RinexLogger {
+    pub fn new(
+        obs_filepath: impl AsRef<Path>,
+        nav_filepath: impl AsRef<Path>,
+        receiver: DeviceInfo,
+    ) -> Result<RinexLogger> {
+        Ok(RinexLogger {
+            obs: RinexObsWriter::new(obs_filepath, receiver)?,
+            nav: RinexNavWriter::new(nav_filepath)?,
+            sync: LogSync::default(),
+        })
+    }
+
+    pub fn with_sync_interval(mut self, interval: Option<StdDuration>) -> Self {
+        self.sync = LogSync::new(interval);
+        self
+    }
+
+    pub fn new_option(
+        obs_filepath: impl AsRef<Path> + Copy,
+        nav_filepath: impl AsRef<Path> + Copy,
+        receiver: DeviceInfo,
+    ) -> Option<RinexLogger> {
+        RinexLogger::new(obs_filepath, nav_filepath, receiver).ok_or_log(|e| {
+            let fname = obs_filepath.as_ref().display();
+            error!("issue creating file, {fname:?}, error, {e}")
+        })
+    }
+
+    pub fn handle_msg(&mut self, msg: &Sbp) -> Result<()> {
+        match msg {
+            Sbp::MsgObs(obs) => self.obs.write_obs(&ObservationMsg::MsgObs(obs.clone()))?,
+            Sbp::MsgPosEcef(pos) if pos.flags & 0x7 != 0 => {
+                self.obs.set_approx_position([pos.x, pos.y, pos.z]);
+            }
+            _ => self.nav.write_ephemeris(msg)?,
+        }
+        let (obs, nav) = (&mut self.obs, &mut self.nav);
+        self.sync.maybe_sync(|| {
+            obs.sync()?;
+            nav.sync()
+        });
+        Ok(())
+    }
+
+    /// Flush both files.
+    ///
+    /// # Returns
+    /// - The number of observation epochs and ephemerides written.
+    pub fn finish(self) -> Result<(usize, usize)> {
+        Ok((self.obs.finish()?, self.nav.finish()?))
+    }
+}
+
+/// Convert the observations and ephemerides in an SBP file into RINEX.
+///
+/// # Parameters
+/// - `input`: The binary SBP stream to read.
+/// - `obs_filepath`: The RINEX observation file to create.
+/// - `nav_filepath`: The RINEX navigation file to create.
+///
+/// # Returns
+/// - The number of observation epochs and ephemerides written.
+pub fn convert_sbp<R: Read>(
+    input: R,
+    obs_filepath: &Path,
+    nav_filepath: &Path,
+) -> Result<(usize, usize)> {
+    let mut logger = RinexLogger::new(obs_filepath, nav_filepath, DeviceInfo::default())?;
+    for msg in sbp::iter_messages(input).log_errors(log::Level::Debug) {
+        logger.handle_msg(&msg)?;
+    }
+    logger.finish()
+}
+
+fn header_line(content: impl AsRef<str>, label: &str) -> String {
+    format!("{:<60.60}{}", content.as_ref(), label)
+}
+
+fn program_line() -> String {
+    let date = Utc::now().format("%Y%m%d %H%M%S UTC").to_string();
+    header_line(
+        format!("{RINEX_PROGRAM:<20}{:<20}{date:<20}", ""),
+        "PGM / RUN BY / DATE",
+    )
+}
+
+fn gps_epoch() -> UtcDateTime {
+    Utc.with_ymd_and_hms(1980, 1, 6, 0, 0, 0).unwrap()
+}
+
+/// GPS time as a calendar date, without any leap second correction.
+fn gps_time(wn: u16, tow_s: i64) -> UtcDateTime {
+    gps_epoch() + Duration::weeks(wn as i64) + Duration::seconds(tow_s)
+}
+
+/// Convert a GPS week and time of week into a BeiDou week and seconds of week.
+fn bdt_week_seconds(wn: u16, tow: u32) -> (f64, f64) {
+    let seconds =
+        (wn as i64 - BDT_GPS_WEEK_OFFSET) * SECONDS_PER_WEEK + tow as i64 - BDT_GPS_SECONDS_OFFSET;
+    (
+        seconds.div_euclid(SECONDS_PER_WEEK) as f64,
+        seconds.rem_euclid(SECONDS_PER_WEEK) as f64,
+    )
+}
+
+fn seconds(t: &UtcDateTime) -> f64 {
+    t.second() as f64 + t.nanosecond() as f64 / 1e9
+}
+
+/// Format a value as a RINEX `D19.12` field, e.g. ` 1.234567890123E-04`.
+fn rinex_float(value: f64) -> String {
+    let formatted = format!("{value:.12E}");
+    let (mantissa, exponent) = formatted.split_once('E').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or_default();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{mantissa:>15}E{sign}{:02}", exponent.abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sbp::messages::{
+        gnss::{CarrierPhase, GnssSignal, GpsTime, GpsTimeSec},
+        observation::{
+            Doppler, EphemerisCommonContent, MsgObs, ObservationHeader, PackedObsContent,
+        },
+    };
+    use tempfile::TempDir;
+
+    fn obs(code: u8, sat: u8, lock: u8) -> PackedObsContent {
+        PackedObsContent {
+            p: 1_000_000_000,
+            l: CarrierPhase {
+                i: 105_000_000,
+                f: 128,
+            },
+            d: Doppler { i: -1200, f: 64 },
+            cn0: 180,
+            lock,
+            flags: 0x0F,
+            sid: GnssSignal { code, sat },
+        }
+    }
+
+    fn obs_msg(tow: u32, n_obs: u8, obs: Vec<PackedObsContent>) -> ObservationMsg {
+        ObservationMsg::MsgObs(MsgObs {
+            sender_id: Some(1337),
+            header: ObservationHeader {
+                t: GpsTime {
+                    tow,
+                    ns_residual: 0,
+                    wn: 2190,
+                },
+                n_obs,
+            },
+            obs,
+        })
+    }
+
+    #[test]
+    fn rinex_float_test() {
+        assert_eq!(rinex_float(1.234567890123e-4), " 1.234567890123E-04");
+        assert_eq!(rinex_float(-26_559_419.0), "-2.655941900000E+07");
+        assert_eq!(rinex_float(0.0), " 0.000000000000E+00");
+        assert_eq!(rinex_float(0.0).len(), 19);
+    }
+
+    #[test]
+    fn rinex_signal_test() {
+        assert_eq!(
+            rinex_signal(SignalCodes::CodeGpsL1Ca),
+            Some((RinexSystem::Gps, "1C"))
+        );
+        assert_eq!(
+            rinex_signal(SignalCodes::CodeGalE5Q),
+            Some((RinexSystem::Galileo, "5Q"))
+        );
+        assert_eq!(rinex_signal(SignalCodes::CodeAuxGps), None);
+        assert_eq!(RinexSystem::Qzss.sat_number(193), Some(1));
+        assert_eq!(RinexSystem::Sbas.sat_number(131), Some(31));
+        assert_eq!(RinexSystem::Glonass.sat_number(105), None);
+        assert_eq!(obs_types(RinexSystem::Glonass).len(), 16);
+    }
+
+    #[test]
+    fn obs_writer_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("test.obs");
+        let mut writer = RinexObsWriter::new(&path, DeviceInfo::default()).unwrap();
+        // one epoch split over two messages, then a second epoch with a reset lock counter
+        writer
+            .write_obs(&obs_msg(1000, 0x20, vec![obs(0, 1, 5)]))
+            .unwrap();
+        writer
+            .write_obs(&obs_msg(1000, 0x21, vec![obs(0, 2, 5), obs(6, 2, 5)]))
+            .unwrap();
+        writer
+            .write_obs(&obs_msg(2000, 0x10, vec![obs(0, 1, 1)]))
+            .unwrap();
+        assert_eq!(writer.finish().unwrap(), 2);
+
+        let rinex = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = rinex.lines().collect();
+        assert!(lines[0].ends_with("RINEX VERSION / TYPE"));
+        assert!(lines.iter().any(|l| l.ends_with("END OF HEADER")));
+        let epochs: Vec<&&str> = lines.iter().filter(|l| l.starts_with('>')).collect();
+        assert_eq!(epochs.len(), 2);
+        assert_eq!(*epochs[0], "> 2021 12 26 00 00  1.0000000  0  2");
+        let g01: Vec<&&str> = lines.iter().filter(|l| l.starts_with("G01")).collect();
+        // C1C, then L1C with no loss of lock, then again with the lock counter reset
+        assert_eq!(&g01[0][3..19], "  20000000.000 7");
+        assert_eq!(&g01[0][19..35], " 105000000.500 7");
+        assert_eq!(&g01[1][19..35], " 105000000.50017");
+        let g02 = lines.iter().find(|l| l.starts_with("G02")).unwrap();
+        assert!(
+            g02.len() > 3 + 16 * 4,
+            "L2W observations follow the L1 ones"
+        );
+    }
+
+    #[test]
+    fn nav_writer_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("test.nav");
+        let mut writer = RinexNavWriter::new(&path).unwrap();
+        let common = EphemerisCommonContent {
+            sid: GnssSignal { sat: 12, code: 0 },
+            toe: GpsTimeSec {
+                tow: 7200,
+                wn: 2190,
+            },
+            ura: 2.0,
+            fit_interval: 14400,
+            valid: 1,
+            health_bits: 0,
+        };
+        let msg = Sbp::MsgEphemerisGps(MsgEphemerisGps {
+            sender_id: Some(0),
+            common,
+            tgd: -1e-8,
+            c_rs: 1.0,
+            c_rc: 2.0,
+            c_uc: 3.0,
+            c_us: 4.0,
+            c_ic: 5.0,
+            c_is: 6.0,
+            dn: 7.0,
+            m0: 8.0,
+            ecc: 0.01,
+            sqrta: 5153.6,
+            omega0: 1.0,
+            omegadot: -8e-9,
+            w: 2.0,
+            inc: 0.97,
+            inc_dot: 1e-10,
+            af0: 0.5,
+            af1: 1e-12,
+            af2: 0.0,
+            toc: GpsTimeSec {
+                tow: 7200,
+                wn: 2190,
+            },
+            iode: 42,
+            iodc: 42,
+        });
+        writer.write_ephemeris(&msg).unwrap();
+        // rebroadcasts of the same ephemeris are skipped
+        writer.write_ephemeris(&msg).unwrap();
+        assert_eq!(writer.finish().unwrap(), 1);
+
+        let rinex = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = rinex.lines().collect();
+        let record = lines
+            .iter()
+            .position(|l| l.ends_with("END OF HEADER"))
+            .unwrap()
+            + 1;
+        assert_eq!(lines.len() - record, 8);
+        assert!(lines[record].starts_with("G12 2021 12 26 02 00 00 5.000000000000E-01"));
+        assert!(lines[record + 1].starts_with("     4.200000000000E+01"));
+    }
+}
@@ -0,0 +1,1313 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//! RTCM 3 framing and conversion to SBP.
+//!
+//! Frames are pulled out of the correction stream and their CRC-24Q checked.
+//! MSM4-7 observations are converted to `MsgObs`, station positions (1005/1006)
+//! to `MsgBasePosEcef` and GLONASS code-phase biases (1230) to `MsgGloBiases`.
+//! Receiver descriptors (1033) are used to supply zero GLONASS biases for Swift
+//! base stations that don't send 1230.
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use chrono::{TimeZone, Utc};
+use log::{info, warn};
+use sbp::messages::gnss::{CarrierPhase, GnssSignal, GpsTime};
+use sbp::messages::observation::{
+    Doppler, MsgBasePosEcef, MsgGloBiases, MsgObs, ObservationHeader, PackedObsContent,
+};
+use sbp::Sbp;
+
+use crate::constants::GPS_UTC_LEAP_SECONDS;
+use crate::types::{Result, SignalCodes};
+
+pub const RTCM3_PREAMBLE: u8 = 0xD3;
+const RTCM3_HEADER_LEN: usize = 3;
+const RTCM3_CRC_LEN: usize = 3;
+const CRC24Q_POLY: u32 = 0x186_4CFB;
+
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+const MS_PER_DAY: i64 = 86_400_000;
+const MS_PER_WEEK: i64 = 7 * MS_PER_DAY;
+/// GLONASS time is UTC + 3h.
+const GLO_UTC_OFFSET_MS: i64 = 3 * 3_600_000;
+/// BeiDou time is 14 seconds behind GPS time.
+const BDS_GPS_OFFSET_MS: i64 = 14_000;
+/// GLONASS day of week when it isn't known.
+const GLO_UNKNOWN_DAY: u32 = 7;
+const GLO_FCN_OFFSET: i8 = 7;
+const GLO_FCN_MAX: u8 = 13;
+
+/// Converted messages come from sender 0, the id of a legacy base station, so
+/// they are treated as base station data by the receiver and the console.
+const BASE_SENDER_ID: u16 = 0;
+/// Observations that fit in one `MsgObs`.
+const MAX_OBS_PER_MSG: usize = 14;
+/// `MsgObs` sequences are limited to 15 messages by the 4 bit count in the header.
+const MAX_OBS_MSGS: usize = 15;
+
+const OBS_FLAG_PSEUDORANGE: u8 = 1 << 0;
+const OBS_FLAG_CARRIER_PHASE: u8 = 1 << 1;
+const OBS_FLAG_HALF_CYCLE_RESOLVED: u8 = 1 << 2;
+const OBS_FLAG_DOPPLER: u8 = 1 << 3;
+/// L1 C/A, L1 P, L2 C/A and L2 P biases present.
+const GLO_BIAS_MASK_ALL: u8 = 0x0F;
+/// Receiver type prefixes in a 1033 that identify a Swift base station.
+const SWIFT_RECEIVERS: [&str; 2] = ["PIKSI", "SWIFT"];
+
+const L1_HZ: f64 = 1575.42e6;
+const L2_HZ: f64 = 1227.60e6;
+const L5_HZ: f64 = 1176.45e6;
+const E6_HZ: f64 = 1278.75e6;
+const E5B_HZ: f64 = 1207.14e6;
+const E5AB_HZ: f64 = 1191.795e6;
+const B1_HZ: f64 = 1561.098e6;
+const B3_HZ: f64 = 1268.52e6;
+const GLO_L1_HZ: f64 = 1602.0e6;
+const GLO_L1_STEP_HZ: f64 = 0.5625e6;
+const GLO_L2_HZ: f64 = 1246.0e6;
+const GLO_L2_STEP_HZ: f64 = 0.4375e6;
+
+/// A complete RTCM 3 frame with a valid CRC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtcmFrame(Vec<u8>);
+
+impl RtcmFrame {
+    /// The frame as received, header and CRC included.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.0[RTCM3_HEADER_LEN..self.0.len() - RTCM3_CRC_LEN]
+    }
+
+    pub fn message_type(&self) -> u16 {
+        let payload = self.payload();
+        (payload[0] as u16) << 4 | (payload[1] as u16) >> 4
+    }
+
+    /// Reference station ID of observation, station, descriptor, bias and MSM messages.
+    pub fn station_id(&self) -> Option<u16> {
+        let message_type = self.message_type();
+        let has_station = matches!(message_type, 1001..=1013 | 1033 | 1230)
+            || Constellation::from_msm_type(message_type).is_some();
+        if !has_station {
+            return None;
+        }
+        let mut bits = BitReader::new(self.payload());
+        bits.u(12).ok()?;
+        bits.u(12).ok().map(|id| id as u16)
+    }
+}
+
+/// Splits a byte stream into RTCM 3 frames, dropping anything between frames.
+#[derive(Debug, Default)]
+pub struct RtcmFramer {
+    buf: Vec<u8>,
+    crc_errors: u64,
+}
+
+impl RtcmFramer {
+    /// Add received data and return the frames completed by it.
+    pub fn push(&mut self, data: &[u8]) -> Vec<RtcmFrame> {
+        self.buf.extend_from_slice(data);
+        let mut frames = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.buf[start..].iter().position(|&b| b == RTCM3_PREAMBLE) {
+            start += offset;
+            let rest = &self.buf[start..];
+            if rest.len() < RTCM3_HEADER_LEN {
+                break;
+            }
+            // the six bits after the preamble are reserved and always zero
+            if rest[1] & 0xFC != 0 {
+                start += 1;
+                continue;
+            }
+            let len = ((rest[1] & 0x03) as usize) << 8 | rest[2] as usize;
+            let frame_len = RTCM3_HEADER_LEN + len + RTCM3_CRC_LEN;
+            if rest.len() < frame_len {
+                break;
+            }
+            let (body, crc) = rest[..frame_len].split_at(frame_len - RTCM3_CRC_LEN);
+            if crc24q(body) != u32::from_be_bytes([0, crc[0], crc[1], crc[2]]) {
+                self.crc_errors += 1;
+                start += 1;
+                continue;
+            }
+            // a message type needs at least 12 bits
+            if len >= 2 {
+                frames.push(RtcmFrame(rest[..frame_len].to_vec()));
+            }
+            start += frame_len;
+        }
+        if !self.buf[start..].contains(&RTCM3_PREAMBLE) {
+            start = self.buf.len();
+        }
+        self.buf.drain(..start);
+        frames
+    }
+
+    /// Number of candidate frames dropped because of a bad CRC.
+    pub fn crc_errors(&self) -> u64 {
+        self.crc_errors
+    }
+}
+
+/// Qualcomm CRC-24Q used by RTCM 3.
+pub fn crc24q(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= CRC24Q_POLY;
+            }
+        }
+    }
+    crc & 0xFF_FFFF
+}
+
+/// Reads big-endian bit fields.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    fn u(&mut self, bits: usize) -> Result<u64> {
+        if self.pos + bits > self.data.len() * 8 {
+            bail!("RTCM message truncated");
+        }
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let bit = self.data[self.pos / 8] >> (7 - self.pos % 8) & 1;
+            value = value << 1 | bit as u64;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn i(&mut self, bits: usize) -> Result<i64> {
+        let value = self.u(bits)?;
+        if value >> (bits - 1) & 1 == 1 {
+            Ok(value as i64 - (1i64 << bits))
+        } else {
+            Ok(value as i64)
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u(8)?;
+        (0..len).map(|_| Ok(self.u(8)? as u8 as char)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Constellation {
+    Gps,
+    Glo,
+    Gal,
+    Sbas,
+    Qzs,
+    Bds,
+}
+
+impl Constellation {
+    fn name(&self) -> &'static str {
+        match self {
+            Constellation::Gps => "GPS",
+            Constellation::Glo => "GLONASS",
+            Constellation::Gal => "Galileo",
+            Constellation::Sbas => "SBAS",
+            Constellation::Qzs => "QZSS",
+            Constellation::Bds => "BeiDou",
+        }
+    }
+
+    /// Constellation and MSM number (1-7) of an MSM message type.
+    fn from_msm_type(message_type: u16) -> Option<(Constellation, u8)> {
+        let constellation = match message_type / 10 {
+            107 => Constellation::Gps,
+            108 => Constellation::Glo,
+            109 => Constellation::Gal,
+            110 => Constellation::Sbas,
+            111 => Constellation::Qzs,
+            112 => Constellation::Bds,
+            _ => return None,
+        };
+        let msm = (message_type % 10) as u8;
+        (1..=7).contains(&msm).then_some((constellation, msm))
+    }
+
+    /// SBP satellite number of an MSM satellite id.
+    fn sat(&self, id: u8) -> u8 {
+        match self {
+            Constellation::Sbas => id + 119,
+            Constellation::Qzs => id + 192,
+            _ => id,
+        }
+    }
+
+    /// SBP signal code of an MSM signal id.
+    fn signal_code(&self, id: u8) -> Option<SignalCodes> {
+        use SignalCodes::*;
+        let code = match (self, id) {
+            (Constellation::Gps, 2) => CodeGpsL1Ca,
+            (Constellation::Gps, 3 | 4) => CodeGpsL1P,
+            (Constellation::Gps, 9 | 10) => CodeGpsL2P,
+            (Constellation::Gps, 15) => CodeGpsL2Cm,
+            (Constellation::Gps, 16) => CodeGpsL2Cl,
+            (Constellation::Gps, 17) => CodeGpsL2Cx,
+            (Constellation::Gps, 22) => CodeGpsL5I,
+            (Constellation::Gps, 23) => CodeGpsL5Q,
+            (Constellation::Gps, 24) => CodeGpsL5X,
+            (Constellation::Gps, 30) => CodeGpsL1Ci,
+            (Constellation::Gps, 31) => CodeGpsL1Cq,
+            (Constellation::Gps, 32) => CodeGpsL1Cx,
+            (Constellation::Glo, 2) => CodeGloL1Of,
+            (Constellation::Glo, 3) => CodeGloL1P,
+            (Constellation::Glo, 8) => CodeGloL2Of,
+            (Constellation::Glo, 9) => CodeGloL2P,
+            (Constellation::Gal, 2) => CodeGalE1C,
+            (Constellation::Gal, 4) => CodeGalE1B,
+            (Constellation::Gal, 5) => CodeGalE1X,
+            (Constellation::Gal, 8) => CodeGalE6C,
+            (Constellation::Gal, 10) => CodeGalE6B,
+            (Constellation::Gal, 11) => CodeGalE6X,
+            (Constellation::Gal, 14) => CodeGalE7I,
+            (Constellation::Gal, 15) => CodeGalE7Q,
+            (Constellation::Gal, 16) => CodeGalE7X,
+            (Constellation::Gal, 18) => CodeGalE8I,
+            (Constellation::Gal, 19) => CodeGalE8Q,
+            (Constellation::Gal, 20) => CodeGalE8X,
+            (Constellation::Gal, 22) => CodeGalE5I,
+            (Constellation::Gal, 23) => CodeGalE5Q,
+            (Constellation::Gal, 24) => CodeGalE5X,
+            (Constellation::Sbas, 2) => CodeSbasL1Ca,
+            (Constellation::Sbas, 22) => CodeSbasL5I,
+            (Constellation::Sbas, 23) => CodeSbasL5Q,
+            (Constellation::Sbas, 24) => CodeSbasL5X,
+            (Constellation::Qzs, 2) => CodeQzsL1Ca,
+            (Constellation::Qzs, 15) => CodeQzsL2Cm,
+            (Constellation::Qzs, 16) => CodeQzsL2Cl,
+            (Constellation::Qzs, 17) => CodeQzsL2Cx,
+            (Constellation::Qzs, 22) => CodeQzsL5I,
+            (Constellation::Qzs, 23) => CodeQzsL5Q,
+            (Constellation::Qzs, 24) => CodeQzsL5X,
+            (Constellation::Qzs, 30) => CodeQzsL1Ci,
+            (Constellation::Qzs, 31) => CodeQzsL1Cq,
+            (Constellation::Qzs, 32) => CodeQzsL1Cx,
+            (Constellation::Bds, 2) => CodeBds2B1,
+            (Constellation::Bds, 8) => CodeBds3B3I,
+            (Constellation::Bds, 9) => CodeBds3B3Q,
+            (Constellation::Bds, 10) => CodeBds3B3X,
+            (Constellation::Bds, 14) => CodeBds2B2,
+            (Constellation::Bds, 15) => CodeBds3B7Q,
+            (Constellation::Bds, 16) => CodeBds3B7X,
+            (Constellation::Bds, 22) => CodeBds3B5I,
+            (Constellation::Bds, 23) => CodeBds3B5Q,
+            (Constellation::Bds, 24) => CodeBds3B5X,
+            (Constellation::Bds, 30) => CodeBds3B1Ci,
+            (Constellation::Bds, 31) => CodeBds3B1Cq,
+            (Constellation::Bds, 32) => CodeBds3B1Cx,
+            _ => return None,
+        };
+        Some(code)
+    }
+}
+
+/// Carrier frequency of a signal, GLONASS signals need the satellite's frequency channel.
+fn carrier_frequency(code: SignalCodes, glo_fcn: Option<i8>) -> Option<f64> {
+    use SignalCodes::*;
+    let freq = match code {
+        CodeGpsL1Ca | CodeGpsL1P | CodeGpsL1Ci | CodeGpsL1Cq | CodeGpsL1Cx | CodeQzsL1Ca
+        | CodeQzsL1Ci | CodeQzsL1Cq | CodeQzsL1Cx | CodeSbasL1Ca | CodeGalE1B | CodeGalE1C
+        | CodeGalE1X | CodeBds3B1Ci | CodeBds3B1Cq | CodeBds3B1Cx => L1_HZ,
+        CodeGpsL2Cm | CodeGpsL2Cl | CodeGpsL2Cx | CodeGpsL2P | CodeQzsL2Cm | CodeQzsL2Cl
+        | CodeQzsL2Cx => L2_HZ,
+        CodeGpsL5I | CodeGpsL5Q | CodeGpsL5X | CodeQzsL5I | CodeQzsL5Q | CodeQzsL5X
+        | CodeSbasL5I | CodeSbasL5Q | CodeSbasL5X | CodeGalE5I | CodeGalE5Q | CodeGalE5X
+        | CodeBds3B5I | CodeBds3B5Q | CodeBds3B5X => L5_HZ,
+        CodeGalE6B | CodeGalE6C | CodeGalE6X => E6_HZ,
+        CodeGalE7I | CodeGalE7Q | CodeGalE7X | CodeBds2B2 | CodeBds3B7I | CodeBds3B7Q
+        | CodeBds3B7X => E5B_HZ,
+        CodeGalE8I | CodeGalE8Q | CodeGalE8X => E5AB_HZ,
+        CodeBds2B1 => B1_HZ,
+        CodeBds3B3I | CodeBds3B3Q | CodeBds3B3X => B3_HZ,
+        CodeGloL1Of | CodeGloL1P => GLO_L1_HZ + glo_fcn? as f64 * GLO_L1_STEP_HZ,
+        CodeGloL2Of | CodeGloL2P => GLO_L2_HZ + glo_fcn? as f64 * GLO_L2_STEP_HZ,
+        _ => return None,
+    };
+    Some(freq)
+}
+
+/// Satellite data of an MSM message, ranges in milliseconds.
+#[derive(Debug, Clone, Copy)]
+struct MsmSat {
+    id: u8,
+    rough_range: Option<f64>,
+    extended_info: Option<u8>,
+    rough_range_rate: Option<f64>,
+}
+
+/// Signal data of an MSM message, ranges in milliseconds and range rates in m/s.
+#[derive(Debug, Clone, Copy)]
+struct MsmCell {
+    sat: usize,
+    signal: u8,
+    fine_pseudorange: Option<f64>,
+    fine_phase_range: Option<f64>,
+    lock_time_ms: u32,
+    half_cycle_ambiguity: bool,
+    cn0: f64,
+    fine_range_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct Msm {
+    constellation: Constellation,
+    epoch: u32,
+    multiple_message: bool,
+    sats: Vec<MsmSat>,
+    cells: Vec<MsmCell>,
+}
+
+/// Header of an MSM message, enough to monitor a correction stream without decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MsmHeader {
+    pub constellation: &'static str,
+    /// MSM number, 1 to 7.
+    pub msm: u8,
+    pub station_id: u16,
+    /// GPS time of week of the epoch in milliseconds.
+    pub tow_ms: i64,
+    pub multiple_message: bool,
+    pub satellites: u32,
+    pub signals: u32,
+}
+
+impl MsmHeader {
+    /// Decode the header of an MSM frame, with GLONASS epochs placed around the
+    /// GPS week and time of week `reference`.
+    pub fn decode(frame: &RtcmFrame, reference: (u16, i64)) -> Option<MsmHeader> {
+        let (constellation, msm) = Constellation::from_msm_type(frame.message_type())?;
+        decode_msm_header(frame.payload(), constellation, msm, reference).ok()
+    }
+}
+
+fn decode_msm_header(
+    payload: &[u8],
+    constellation: Constellation,
+    msm: u8,
+    reference: (u16, i64),
+) -> Result<MsmHeader> {
+    let mut bits = BitReader::new(payload);
+    bits.u(12)?;
+    let station_id = bits.u(12)? as u16;
+    let epoch = bits.u(30)? as u32;
+    let multiple_message = bits.u(1)? == 1;
+    // IODS, reserved, clock steering, external clock, smoothing and smoothing interval
+    bits.u(3 + 7 + 2 + 2 + 1 + 3)?;
+    let satellites = bits.u(64)?.count_ones();
+    let signals = bits.u(32)?.count_ones();
+    Ok(MsmHeader {
+        constellation: constellation.name(),
+        msm,
+        station_id,
+        tow_ms: gps_tow_ms(constellation, epoch, reference),
+        multiple_message,
+        satellites,
+        signals,
+    })
+}
+
+/// Station ID and ECEF antenna reference point of a 1005 or 1006 message.
+pub fn station_position(frame: &RtcmFrame) -> Option<(u16, [f64; 3])> {
+    matches!(frame.message_type(), 1005 | 1006)
+        .then(|| decode_station_position(frame.payload()).ok())
+        .flatten()
+}
+
+fn decode_station_position(payload: &[u8]) -> Result<(u16, [f64; 3])> {
+    let mut bits = BitReader::new(payload);
+    bits.u(12)?;
+    let station_id = bits.u(12)? as u16;
+    // ITRF year, GPS/GLONASS/Galileo and reference station indicators
+    bits.u(6 + 4)?;
+    let x = bits.i(38)? as f64 * 0.0001;
+    // single receiver oscillator and reserved
+    bits.u(2)?;
+    let y = bits.i(38)? as f64 * 0.0001;
+    // quarter cycle indicator
+    bits.u(2)?;
+    let z = bits.i(38)? as f64 * 0.0001;
+    Ok((station_id, [x, y, z]))
+}
+
+/// Decode an MSM4, MSM5, MSM6 or MSM7 message.
+fn decode_msm(payload: &[u8], constellation: Constellation, msm: u8) -> Result<Msm> {
+    let mut bits = BitReader::new(payload);
+    let _message_type = bits.u(12)?;
+    let _station_id = bits.u(12)?;
+    let epoch = bits.u(30)? as u32;
+    let multiple_message = bits.u(1)? == 1;
+    // IODS, reserved, clock steering, external clock, smoothing and smoothing interval
+    bits.u(3 + 7 + 2 + 2 + 1 + 3)?;
+    let sat_mask = bits.u(64)?;
+    let sig_mask = bits.u(32)?;
+    let sat_ids: Vec<u8> = (0..64u32)
+        .filter(|i| sat_mask >> (63 - i) & 1 == 1)
+        .map(|i| i as u8 + 1)
+        .collect();
+    let sig_ids: Vec<u8> = (0..32u32)
+        .filter(|i| sig_mask >> (31 - i) & 1 == 1)
+        .map(|i| i as u8 + 1)
+        .collect();
+    if sat_ids.len() * sig_ids.len() > 64 {
+        bail!("MSM cell mask too large");
+    }
+    let mut cell_ids = Vec::new();
+    for sat in 0..sat_ids.len() {
+        for &signal in &sig_ids {
+            if bits.u(1)? == 1 {
+                cell_ids.push((sat, signal));
+            }
+        }
+    }
+
+    let extended = msm == 5 || msm == 7;
+    let high_res = msm == 6 || msm == 7;
+    let n_sats = sat_ids.len();
+    let rough_int = read_n(&mut bits, n_sats, |b| b.u(8))?;
+    let extended_info = if extended {
+        read_n(&mut bits, n_sats, |b| b.u(4))?
+    } else {
+        Vec::new()
+    };
+    let rough_mod = read_n(&mut bits, n_sats, |b| b.u(10))?;
+    let rough_rate = if extended {
+        read_n(&mut bits, n_sats, |b| b.i(14))?
+    } else {
+        Vec::new()
+    };
+    let sats = sat_ids
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| MsmSat {
+            id,
+            rough_range: (rough_int[i] != 0xFF)
+                .then(|| rough_int[i] as f64 + rough_mod[i] as f64 / 1024.0),
+            extended_info: extended_info.get(i).map(|&info| info as u8),
+            rough_range_rate: rough_rate
+                .get(i)
+                .filter(|&&rate| rate != -8192)
+                .map(|&rate| rate as f64),
+        })
+        .collect();
+
+    let n_cells = cell_ids.len();
+    let (pr_bits, pr_scale, cp_bits, cp_scale) = if high_res {
+        (20, 2f64.powi(-29), 24, 2f64.powi(-31))
+    } else {
+        (15, 2f64.powi(-24), 22, 2f64.powi(-29))
+    };
+    let fine_pr = read_n(&mut bits, n_cells, |b| b.i(pr_bits))?;
+    let fine_cp = read_n(&mut bits, n_cells, |b| b.i(cp_bits))?;
+    let lock = read_n(&mut bits, n_cells, |b| b.u(if high_res { 10 } else { 4 }))?;
+    let half_cycle = read_n(&mut bits, n_cells, |b| b.u(1))?;
+    let cn0 = read_n(&mut bits, n_cells, |b| b.u(if high_res { 10 } else { 6 }))?;
+    let fine_rate = if extended {
+        read_n(&mut bits, n_cells, |b| b.i(15))?
+    } else {
+        Vec::new()
+    };
+    let invalid = |value: i64, bits: usize| value == -(1i64 << (bits - 1));
+    let cells = cell_ids
+        .iter()
+        .enumerate()
+        .map(|(i, &(sat, signal))| MsmCell {
+            sat,
+            signal,
+            fine_pseudorange: (!invalid(fine_pr[i], pr_bits)).then(|| fine_pr[i] as f64 * pr_scale),
+            fine_phase_range: (!invalid(fine_cp[i], cp_bits)).then(|| fine_cp[i] as f64 * cp_scale),
+            lock_time_ms: if high_res {
+                extended_lock_time_ms(lock[i] as u32)
+            } else {
+                lock_time_ms(lock[i] as u32)
+            },
+            half_cycle_ambiguity: half_cycle[i] == 1,
+            cn0: if high_res {
+                cn0[i] as f64 / 16.0
+            } else {
+                cn0[i] as f64
+            },
+            fine_range_rate: fine_rate
+                .get(i)
+                .filter(|&&rate| !invalid(rate, 15))
+                .map(|&rate| rate as f64 * 0.0001),
+        })
+        .collect();
+
+    Ok(Msm {
+        constellation,
+        epoch,
+        multiple_message,
+        sats,
+        cells,
+    })
+}
+
+fn read_n<T>(
+    bits: &mut BitReader,
+    n: usize,
+    mut read: impl FnMut(&mut BitReader) -> Result<T>,
+) -> Result<Vec<T>> {
+    (0..n).map(|_| read(bits)).collect()
+}
+
+/// Minimum lock time of an MSM4/5 lock time indicator (DF402).
+fn lock_time_ms(indicator: u32) -> u32 {
+    match indicator {
+        0 => 0,
+        i => 32 << (i - 1),
+    }
+}
+
+/// Minimum lock time of an MSM6/7 extended lock time indicator (DF407).
+fn extended_lock_time_ms(indicator: u32) -> u32 {
+    match indicator {
+        0..=63 => indicator,
+        64..=703 => {
+            let n = indicator / 32 - 1;
+            (indicator - 32 * n) << n
+        }
+        _ => 67_108_864,
+    }
+}
+
+/// SBP lock indicator, which uses the same steps as DF402.
+fn sbp_lock(lock_time_ms: u32) -> u8 {
+    if lock_time_ms < 32 {
+        0
+    } else {
+        (lock_time_ms / 32).ilog2().min(14) as u8 + 1
+    }
+}
+
+/// Time of week in milliseconds rounded to the week nearest `reference`.
+fn nearest_week(tow_ms: i64, reference: (u16, i64)) -> GpsTime {
+    let (wn, ref_tow) = reference;
+    let wn = match tow_ms - ref_tow {
+        diff if diff > MS_PER_WEEK / 2 => wn.wrapping_sub(1),
+        diff if diff < -MS_PER_WEEK / 2 => wn.wrapping_add(1),
+        _ => wn,
+    };
+    GpsTime {
+        tow: tow_ms as u32,
+        ns_residual: 0,
+        wn,
+    }
+}
+
+/// Distance between two times of week, accounting for the week rollover.
+fn tow_distance(a: i64, b: i64) -> i64 {
+    ((a - b + MS_PER_WEEK / 2).rem_euclid(MS_PER_WEEK) - MS_PER_WEEK / 2).abs()
+}
+
+/// GPS week and time of week in milliseconds from the system clock.
+pub fn gps_time_now() -> (u16, i64) {
+    let gps_epoch = Utc.with_ymd_and_hms(1980, 1, 6, 0, 0, 0).unwrap();
+    let ms = (Utc::now() - gps_epoch).num_milliseconds() + GPS_UTC_LEAP_SECONDS * 1000;
+    ((ms / MS_PER_WEEK) as u16, ms % MS_PER_WEEK)
+}
+
+/// Encode a value as an integer and 1/256 fraction.
+fn fixed_point(value: f64) -> (i64, u8) {
+    let scaled = (value * 256.0).round() as i64;
+    (scaled.div_euclid(256), scaled.rem_euclid(256) as u8)
+}
+
+/// Converts RTCM 3 frames into SBP messages.
+#[derive(Debug, Default)]
+pub struct RtcmConverter {
+    /// GPS week and time of week used to resolve the week of observations,
+    /// the system clock when `None`.
+    reference_time: Option<(u16, i64)>,
+    epoch: Option<GpsTime>,
+    obs: Vec<PackedObsContent>,
+    glo_fcn: HashMap<u8, i8>,
+    glo_biases_received: bool,
+}
+
+impl RtcmConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve the week of observations against this GPS time instead of the system clock.
+    pub fn with_reference_time(mut self, wn: u16, tow_ms: u32) -> Self {
+        self.reference_time = Some((wn, tow_ms as i64));
+        self
+    }
+
+    /// Convert a frame into SBP messages.
+    pub fn convert(&mut self, frame: &RtcmFrame) -> Vec<Sbp> {
+        let message_type = frame.message_type();
+        let converted = match message_type {
+            1005 | 1006 => self.base_position(frame.payload()),
+            1033 => self.receiver_descriptor(frame.payload()),
+            1230 => self.glo_biases(frame.payload()),
+            _ => match Constellation::from_msm_type(message_type) {
+                Some((constellation, msm)) if msm >= 4 => {
+                    decode_msm(frame.payload(), constellation, msm)
+                        .map(|msm| self.observations(msm))
+                }
+                _ => Ok(Vec::new()),
+            },
+        };
+        converted.unwrap_or_else(|e| {
+            warn!("Unable to convert RTCM {message_type}, {e}");
+            Vec::new()
+        })
+    }
+
+    /// Emit the observations of the current epoch.
+    pub fn flush(&mut self) -> Vec<Sbp> {
+        let Some(t) = self.epoch.take() else {
+            return Vec::new();
+        };
+        let obs = std::mem::take(&mut self.obs);
+        let chunks: Vec<_> = obs.chunks(MAX_OBS_PER_MSG).take(MAX_OBS_MSGS).collect();
+        let total = chunks.len() as u8;
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, obs)| {
+                Sbp::MsgObs(MsgObs {
+                    sender_id: Some(BASE_SENDER_ID),
+                    header: ObservationHeader {
+                        t: t.clone(),
+                        n_obs: total << 4 | i as u8,
+                    },
+                    obs: obs.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    fn base_position(&mut self, payload: &[u8]) -> Result<Vec<Sbp>> {
+        let (_station_id, [x, y, z]) = decode_station_position(payload)?;
+        Ok(vec![Sbp::MsgBasePosEcef(MsgBasePosEcef {
+            sender_id: Some(BASE_SENDER_ID),
+            x,
+            y,
+            z,
+        })])
+    }
+
+    fn receiver_descriptor(&mut self, payload: &[u8]) -> Result<Vec<Sbp>> {
+        let mut bits = BitReader::new(payload);
+        bits.u(12 + 12)?;
+        let antenna = bits.string()?;
+        let _antenna_setup = bits.u(8)?;
+        let _antenna_serial = bits.string()?;
+        let receiver = bits.string()?;
+        let firmware = bits.string()?;
+        info!(
+            "Base station receiver {} {}, antenna {}",
+            receiver.trim(),
+            firmware.trim(),
+            antenna.trim()
+        );
+        let swift_receiver = SWIFT_RECEIVERS
+            .iter()
+            .any(|prefix| receiver.trim().to_uppercase().starts_with(prefix));
+        if self.glo_biases_received || !swift_receiver {
+            return Ok(Vec::new());
+        }
+        Ok(vec![Sbp::MsgGloBiases(MsgGloBiases {
+            sender_id: Some(BASE_SENDER_ID),
+            mask: GLO_BIAS_MASK_ALL,
+            l1ca_bias: 0,
+            l1p_bias: 0,
+            l2ca_bias: 0,
+            l2p_bias: 0,
+        })])
+    }
+
+    fn glo_biases(&mut self, payload: &[u8]) -> Result<Vec<Sbp>> {
+        let mut bits = BitReader::new(payload);
+        // message type, station id, bias indicator and reserved
+        bits.u(12 + 12 + 1 + 3)?;
+        let mask = bits.u(4)? as u8;
+        let mut biases = [0i16; 4];
+        for (i, bias) in biases.iter_mut().enumerate() {
+            if mask >> (3 - i) & 1 == 1 {
+                // both RTCM and SBP use 0.02 m units
+                *bias = bits.i(16)? as i16;
+            }
+        }
+        self.glo_biases_received = true;
+        Ok(vec![Sbp::MsgGloBiases(MsgGloBiases {
+            sender_id: Some(BASE_SENDER_ID),
+            mask,
+            l1ca_bias: biases[0],
+            l1p_bias: biases[1],
+            l2ca_bias: biases[2],
+            l2p_bias: biases[3],
+        })])
+    }
+
+    fn observations(&mut self, msm: Msm) -> Vec<Sbp> {
+        let t = self.epoch_time(msm.constellation, msm.epoch);
+        let mut msgs = Vec::new();
+        if self
+            .epoch
+            .as_ref()
+            .is_some_and(|epoch| epoch.tow != t.tow || epoch.wn != t.wn)
+        {
+            msgs.extend(self.flush());
+        }
+        self.epoch = Some(t);
+
+        if msm.constellation == Constellation::Glo {
+            for sat in &msm.sats {
+                if let Some(info) = sat.extended_info.filter(|&info| info <= GLO_FCN_MAX) {
+                    self.glo_fcn.insert(sat.id, info as i8 - GLO_FCN_OFFSET);
+                }
+            }
+        }
+        for cell in &msm.cells {
+            let sat = msm.sats[cell.sat];
+            if let Some(obs) = self.packed_obs(msm.constellation, sat, cell) {
+                self.obs.push(obs);
+            }
+        }
+
+        if !msm.multiple_message {
+            msgs.extend(self.flush());
+        }
+        msgs
+    }
+
+    fn packed_obs(
+        &self,
+        constellation: Constellation,
+        sat: MsmSat,
+        cell: &MsmCell,
+    ) -> Option<PackedObsContent> {
+        let code = constellation.signal_code(cell.signal)?;
+        let rough_range = sat.rough_range?;
+        let glo_fcn = self.glo_fcn.get(&sat.id).copied();
+        let wavelength = carrier_frequency(code, glo_fcn).map(|freq| SPEED_OF_LIGHT / freq);
+        let ms_to_m = SPEED_OF_LIGHT / 1000.0;
+
+        let mut flags = 0;
+        let mut p = 0;
+        if let Some(fine) = cell.fine_pseudorange {
+            p = ((rough_range + fine) * ms_to_m * 50.0).round() as u32;
+            flags |= OBS_FLAG_PSEUDORANGE;
+        }
+        let mut l = CarrierPhase { i: 0, f: 0 };
+        if let (Some(fine), Some(wavelength)) = (cell.fine_phase_range, wavelength) {
+            let (i, f) = fixed_point((rough_range + fine) * ms_to_m / wavelength);
+            l = CarrierPhase { i: i as i32, f };
+            flags |= OBS_FLAG_CARRIER_PHASE;
+            if !cell.half_cycle_ambiguity {
+                flags |= OBS_FLAG_HALF_CYCLE_RESOLVED;
+            }
+        }
+        let mut d = Doppler { i: 0, f: 0 };
+        if let (Some(rough), Some(fine), Some(wavelength)) =
+            (sat.rough_range_rate, cell.fine_range_rate, wavelength)
+        {
+            // Doppler is positive for an approaching satellite, the range rate negative
+            let (i, f) = fixed_point(-(rough + fine) / wavelength);
+            d = Doppler { i: i as i16, f };
+            flags |= OBS_FLAG_DOPPLER;
+        }
+        if flags == 0 {
+            return None;
+        }
+        Some(PackedObsContent {
+            p,
+            l,
+            d,
+            cn0: (cell.cn0 * 4.0).round().min(u8::MAX as f64) as u8,
+            lock: sbp_lock(cell.lock_time_ms),
+            flags,
+            sid: GnssSignal {
+                sat: constellation.sat(sat.id),
+                code: code as u8,
+            },
+        })
+    }
+
+    /// GPS time of an MSM epoch.
+    fn epoch_time(&self, constellation: Constellation, epoch: u32) -> GpsTime {
+        let reference = self.reference_time.unwrap_or_else(gps_time_now);
+        nearest_week(gps_tow_ms(constellation, epoch, reference), reference)
+    }
+}
+
+/// GPS time of week in milliseconds of an MSM epoch, GLONASS epochs without a day
+/// are placed on the day nearest `reference`.
+fn gps_tow_ms(constellation: Constellation, epoch: u32, reference: (u16, i64)) -> i64 {
+    match constellation {
+        Constellation::Glo => {
+            let day = epoch >> 27;
+            let tod = (epoch & 0x7FF_FFFF) as i64 - GLO_UTC_OFFSET_MS + GPS_UTC_LEAP_SECONDS * 1000;
+            if day == GLO_UNKNOWN_DAY {
+                let ref_day = reference.1 / MS_PER_DAY;
+                (ref_day - 1..=ref_day + 1)
+                    .map(|day| (day * MS_PER_DAY + tod).rem_euclid(MS_PER_WEEK))
+                    .min_by_key(|&tow| tow_distance(tow, reference.1))
+                    .unwrap_or_default()
+            } else {
+                (day as i64 * MS_PER_DAY + tod).rem_euclid(MS_PER_WEEK)
+            }
+        }
+        Constellation::Bds => (epoch as i64 + BDS_GPS_OFFSET_MS).rem_euclid(MS_PER_WEEK),
+        _ => epoch as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Station 2003 position message from the RTCM 10403.3 standard.
+    const MSG_1005: [u8; 25] = [
+        0xd3, 0x00, 0x13, 0x3e, 0xd7, 0xd3, 0x02, 0x02, 0x98, 0x0e, 0xde, 0xef, 0x34, 0xb4, 0xbd,
+        0x62, 0xac, 0x09, 0x41, 0x98, 0x6f, 0x33, 0x36, 0x0b, 0x98,
+    ];
+
+    /// One epoch from station 1234, a GPS MSM4 (1074) for G03 on L1 C/A and L2C and G17
+    /// on L1 C/A, then a Galileo MSM7 (1097) for E11 on E1C and E5b. The frames were
+    /// assembled field by field from the RTCM 10403.3 tables, independently of
+    /// `BitWriter`, for the pseudoranges, phase ranges and range rates in `msm_fixture_test`.
+    const MSM_EPOCH: &[u8] = include_bytes!("../tests/data/msm4_msm7.rtcm");
+
+    #[derive(Default)]
+    struct BitWriter {
+        data: Vec<u8>,
+        len: usize,
+    }
+
+    impl BitWriter {
+        fn put(&mut self, bits: usize, value: i64) -> &mut Self {
+            for i in (0..bits).rev() {
+                if self.len % 8 == 0 {
+                    self.data.push(0);
+                }
+                let bit = (value >> i & 1) as u8;
+                *self.data.last_mut().unwrap() |= bit << (7 - self.len % 8);
+                self.len += 1;
+            }
+            self
+        }
+
+        fn frame(&self) -> RtcmFrame {
+            let len = self.data.len();
+            let mut frame = vec![RTCM3_PREAMBLE, (len >> 8) as u8, len as u8];
+            frame.extend_from_slice(&self.data);
+            let crc = crc24q(&frame);
+            frame.extend_from_slice(&crc.to_be_bytes()[1..]);
+            RtcmFrame(frame)
+        }
+    }
+
+    struct TestCell {
+        signal: u8,
+        fine_pseudorange: i64,
+        fine_phase_range: i64,
+        lock: i64,
+        cn0: i64,
+        fine_range_rate: i64,
+    }
+
+    /// A single satellite MSM4-7 message.
+    fn msm_frame(
+        message_type: u16,
+        epoch: u32,
+        multiple_message: bool,
+        sat: u8,
+        rough_range: (i64, i64),
+        rough_range_rate: i64,
+        cells: &[TestCell],
+    ) -> RtcmFrame {
+        let msm = message_type % 10;
+        let extended = msm == 5 || msm == 7;
+        let high_res = msm == 6 || msm == 7;
+        let mut w = BitWriter::default();
+        w.put(12, message_type as i64)
+            .put(12, 0)
+            .put(30, epoch as i64)
+            .put(1, multiple_message as i64)
+            .put(18, 0)
+            .put(64, (1u64 << (64 - sat)) as i64);
+        let sig_mask = cells
+            .iter()
+            .fold(0u32, |mask, cell| mask | 1 << (32 - cell.signal));
+        w.put(32, sig_mask as i64).put(cells.len(), -1);
+        w.put(8, rough_range.0);
+        if extended {
+            w.put(4, 0);
+        }
+        w.put(10, rough_range.1);
+        if extended {
+            w.put(14, rough_range_rate);
+        }
+        let (pr_bits, cp_bits, lock_bits, cn0_bits) = if high_res {
+            (20, 24, 10, 10)
+        } else {
+            (15, 22, 4, 6)
+        };
+        for cell in cells {
+            w.put(pr_bits, cell.fine_pseudorange);
+        }
+        for cell in cells {
+            w.put(cp_bits, cell.fine_phase_range);
+        }
+        for cell in cells {
+            w.put(lock_bits, cell.lock);
+        }
+        for _ in cells {
+            w.put(1, 0);
+        }
+        for cell in cells {
+            w.put(cn0_bits, cell.cn0);
+        }
+        if extended {
+            for cell in cells {
+                w.put(15, cell.fine_range_rate);
+            }
+        }
+        w.frame()
+    }
+
+    fn gps_msm7(multiple_message: bool) -> RtcmFrame {
+        let cells = [
+            TestCell {
+                signal: 2,
+                fine_pseudorange: 53687,
+                fine_phase_range: 236223,
+                lock: 100,
+                cn0: 728,
+                fine_range_rate: 5000,
+            },
+            TestCell {
+                signal: 15,
+                fine_pseudorange: -(1 << 19),
+                fine_phase_range: 0,
+                lock: 0,
+                cn0: 640,
+                fine_range_rate: 0,
+            },
+        ];
+        msm_frame(
+            1077,
+            345_600_000,
+            multiple_message,
+            5,
+            (70, 512),
+            -300,
+            &cells,
+        )
+    }
+
+    fn obs_msgs(msgs: &[Sbp]) -> Vec<&MsgObs> {
+        msgs.iter()
+            .map(|msg| match msg {
+                Sbp::MsgObs(obs) => obs,
+                msg => panic!("unexpected message {msg:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn framer_test() {
+        let frame = MSG_1005;
+        let mut framer = RtcmFramer::default();
+        assert!(framer.push(&[0x00, 0x42]).is_empty());
+        assert!(framer.push(&frame[..10]).is_empty());
+        let frames = framer.push(&frame[10..]);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].message_type(), 1005);
+        assert_eq!(frames[0].as_bytes(), &frame[..]);
+        assert_eq!(framer.crc_errors(), 0);
+
+        let mut corrupted = frame;
+        corrupted[8] ^= 0x01;
+        let mut framer = RtcmFramer::default();
+        assert!(framer.push(&corrupted).is_empty());
+        assert_eq!(framer.crc_errors(), 1);
+    }
+
+    #[test]
+    fn base_position_test() {
+        let frame = RtcmFramer::default().push(&MSG_1005);
+        let msgs = RtcmConverter::new().convert(&frame[0]);
+        let Sbp::MsgBasePosEcef(pos) = &msgs[0] else {
+            panic!("expected MsgBasePosEcef, got {msgs:?}");
+        };
+        assert!((pos.x - 1_114_104.5999).abs() < 1e-6);
+        assert!((pos.y + 4_850_729.7108).abs() < 1e-6);
+        assert!((pos.z - 3_975_521.4643).abs() < 1e-6);
+    }
+
+    #[test]
+    fn header_test() {
+        let frames = RtcmFramer::default().push(&MSG_1005);
+        assert_eq!(frames[0].station_id(), Some(2003));
+        let (station_id, [x, _, _]) = station_position(&frames[0]).unwrap();
+        assert_eq!(station_id, 2003);
+        assert!((x - 1_114_104.5999).abs() < 1e-6);
+        assert!(MsmHeader::decode(&frames[0], (2200, 345_000_000)).is_none());
+
+        let header = MsmHeader::decode(&gps_msm7(true), (2200, 345_000_000)).unwrap();
+        assert_eq!(
+            header,
+            MsmHeader {
+                constellation: "GPS",
+                msm: 7,
+                station_id: 0,
+                tow_ms: 345_600_000,
+                multiple_message: true,
+                satellites: 1,
+                signals: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn msm7_test() {
+        let mut converter = RtcmConverter::new().with_reference_time(2200, 345_000_000);
+        let msgs = converter.convert(&gps_msm7(false));
+        let obs = obs_msgs(&msgs);
+        assert_eq!(obs.len(), 1);
+        assert_eq!(obs[0].sender_id, Some(BASE_SENDER_ID));
+        assert_eq!(obs[0].header.t.wn, 2200);
+        assert_eq!(obs[0].header.t.tow, 345_600_000);
+        assert_eq!(obs[0].header.n_obs, 0x10);
+        assert_eq!(obs[0].obs.len(), 2);
+
+        let l1 = &obs[0].obs[0];
+        assert_eq!(l1.sid.sat, 5);
+        assert_eq!(l1.sid.code, SignalCodes::CodeGpsL1Ca as u8);
+        assert_eq!(l1.p, 1_056_769_913);
+        assert_eq!((l1.l.i, l1.l.f), (111_067_283, 76));
+        assert_eq!((l1.d.i, l1.d.f), (1573, 226));
+        assert_eq!(l1.cn0, 182);
+        assert_eq!(l1.lock, 3);
+        assert_eq!(
+            l1.flags,
+            OBS_FLAG_PSEUDORANGE
+                | OBS_FLAG_CARRIER_PHASE
+                | OBS_FLAG_HALF_CYCLE_RESOLVED
+                | OBS_FLAG_DOPPLER
+        );
+
+        let l2 = &obs[0].obs[1];
+        assert_eq!(l2.sid.code, SignalCodes::CodeGpsL2Cm as u8);
+        assert_eq!(l2.flags & OBS_FLAG_PSEUDORANGE, 0);
+        assert_ne!(l2.flags & OBS_FLAG_CARRIER_PHASE, 0);
+        assert_eq!(l2.cn0, 160);
+    }
+
+    #[test]
+    fn msm_fixture_test() {
+        let frames = RtcmFramer::default().push(MSM_EPOCH);
+        let types: Vec<_> = frames.iter().map(RtcmFrame::message_type).collect();
+        assert_eq!(types, [1074, 1097]);
+        assert!(frames.iter().all(|frame| frame.station_id() == Some(1234)));
+
+        let mut converter = RtcmConverter::new().with_reference_time(2200, 345_000_000);
+        assert!(converter.convert(&frames[0]).is_empty());
+        let msgs = converter.convert(&frames[1]);
+        let obs = obs_msgs(&msgs);
+        assert_eq!(obs.len(), 1);
+        assert_eq!(obs[0].header.t.tow, 345_600_000);
+
+        use SignalCodes::*;
+        // satellite, signal, pseudorange (m), phase range (m), range rate (m/s), C/N0, lock
+        let expected = [
+            (
+                3,
+                CodeGpsL1Ca,
+                20_345_678.901,
+                20_345_679.512,
+                None,
+                188,
+                10,
+            ),
+            (3, CodeGpsL2Cm, 20_345_680.117, 20_345_680.950, None, 164, 8),
+            (
+                17,
+                CodeGpsL1Ca,
+                23_456_789.012,
+                23_456_788.250,
+                None,
+                156,
+                5,
+            ),
+            (
+                11,
+                CodeGalE1C,
+                25_123_456.789,
+                25_123_457.333,
+                Some(-456.789),
+                178,
+                15,
+            ),
+            (
+                11,
+                CodeGalE7Q,
+                25_123_458.222,
+                25_123_459.876,
+                Some(-456.790),
+                169,
+                15,
+            ),
+        ];
+        assert_eq!(obs[0].obs.len(), expected.len());
+        for (obs, &(sat, code, pseudorange, phase_range, range_rate, cn0, lock)) in
+            obs[0].obs.iter().zip(expected.iter())
+        {
+            assert_eq!((obs.sid.sat, obs.sid.code), (sat, code as u8));
+            let wavelength = SPEED_OF_LIGHT / carrier_frequency(code, None).unwrap();
+            assert!((obs.p as f64 / 50.0 - pseudorange).abs() < 0.03, "{obs:?}");
+            let cycles = obs.l.i as f64 + obs.l.f as f64 / 256.0;
+            assert!((cycles - phase_range / wavelength).abs() < 0.01, "{obs:?}");
+            if let Some(range_rate) = range_rate {
+                let doppler = obs.d.i as f64 + obs.d.f as f64 / 256.0;
+                assert!((doppler + range_rate / wavelength).abs() < 0.01, "{obs:?}");
+            } else {
+                assert_eq!(obs.flags & OBS_FLAG_DOPPLER, 0);
+            }
+            assert_eq!(obs.cn0, cn0);
+            assert_eq!(obs.lock, lock);
+        }
+    }
+
+    #[test]
+    fn multiple_message_epoch() {
+        let mut converter = RtcmConverter::new().with_reference_time(2200, 345_000_000);
+        assert!(converter.convert(&gps_msm7(true)).is_empty());
+
+        // BeiDou time is 14 seconds behind GPS time
+        let bds = msm_frame(
+            1124,
+            345_600_000 - 14_000,
+            false,
+            12,
+            (75, 256),
+            0,
+            &[TestCell {
+                signal: 2,
+                fine_pseudorange: 1000,
+                fine_phase_range: 2000,
+                lock: 15,
+                cn0: 40,
+                fine_range_rate: 0,
+            }],
+        );
+        let msgs = converter.convert(&bds);
+        let obs = obs_msgs(&msgs);
+        assert_eq!(obs.len(), 1);
+        assert_eq!(obs[0].header.t.tow, 345_600_000);
+        assert_eq!(obs[0].obs.len(), 3);
+        let b1 = &obs[0].obs[2];
+        assert_eq!(b1.sid.sat, 12);
+        assert_eq!(b1.sid.code, SignalCodes::CodeBds2B1 as u8);
+        assert_eq!(b1.p, 1_127_970_017);
+        assert_eq!((b1.l.i, b1.l.f), (117_472_630, 81));
+        assert_eq!(b1.flags & OBS_FLAG_DOPPLER, 0);
+        assert_eq!(b1.lock, 15);
+        assert_eq!(b1.cn0, 160);
+    }
+
+    #[test]
+    fn glonass_without_frequency_channel() {
+        let mut converter = RtcmConverter::new().with_reference_time(2200, 345_000_000);
+        // MSM4 doesn't carry the frequency channel, so only the pseudorange can be used
+        let glo = msm_frame(
+            1084,
+            (4 << 27) + 10_800_000 - 18_000,
+            false,
+            3,
+            (70, 0),
+            0,
+            &[TestCell {
+                signal: 2,
+                fine_pseudorange: 0,
+                fine_phase_range: 0,
+                lock: 1,
+                cn0: 40,
+                fine_range_rate: 0,
+            }],
+        );
+        let msgs = converter.convert(&glo);
+        let obs = obs_msgs(&msgs);
+        assert_eq!(obs[0].header.t.tow, 345_600_000);
+        assert_eq!(obs[0].obs[0].sid.code, SignalCodes::CodeGloL1Of as u8);
+        assert_eq!(obs[0].obs[0].flags, OBS_FLAG_PSEUDORANGE);
+    }
+
+    #[test]
+    fn glo_biases_test() {
+        let mut w = BitWriter::default();
+        w.put(12, 1230)
+            .put(12, 0)
+            .put(1, 1)
+            .put(3, 0)
+            .put(4, 0b1010)
+            .put(16, 100)
+            .put(16, -50);
+        let msgs = RtcmConverter::new().convert(&w.frame());
+        let Sbp::MsgGloBiases(biases) = &msgs[0] else {
+            panic!("expected MsgGloBiases, got {msgs:?}");
+        };
+        assert_eq!(biases.mask, 0b1010);
+        assert_eq!(biases.l1ca_bias, 100);
+        assert_eq!(biases.l1p_bias, 0);
+        assert_eq!(biases.l2ca_bias, -50);
+        assert_eq!(biases.l2p_bias, 0);
+    }
+
+    #[test]
+    fn lock_time_test() {
+        assert_eq!(lock_time_ms(15), 524_288);
+        assert_eq!(extended_lock_time_ms(100), 144);
+        assert_eq!(extended_lock_time_ms(704), 67_108_864);
+        assert_eq!(sbp_lock(31), 0);
+        assert_eq!(sbp_lock(32), 1);
+        assert_eq!(sbp_lock(144), 3);
+        assert_eq!(sbp_lock(lock_time_ms(15)), 15);
+        assert_eq!(sbp_lock(extended_lock_time_ms(704)), 15);
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! SBP framing for streams and logs that can't be read with the sbp crate's
+//! iterators, such as corrections arriving in arbitrary chunks or logs cut short.
+//!
+//! A frame is the preamble, a six byte header including it, the payload and a
+//! CRC-16 over everything but the preamble.
+
+const SBP_PREAMBLE: u8 = 0x55;
+const SBP_HEADER_LEN: usize = 6;
+const SBP_CRC_LEN: usize = 2;
+
+/// What starts at a position in an SBP stream.
+enum FrameAt {
+    /// A frame with a valid CRC, ending at the given position.
+    Frame(usize),
+    /// The start of a frame that isn't all there yet.
+    Incomplete,
+    /// Anything else, including frames with a bad CRC.
+    Invalid,
+}
+
+fn frame_at(data: &[u8], pos: usize) -> FrameAt {
+    if data[pos] != SBP_PREAMBLE {
+        return FrameAt::Invalid;
+    }
+    if pos + SBP_HEADER_LEN > data.len() {
+        return FrameAt::Incomplete;
+    }
+    let end = pos + SBP_HEADER_LEN + data[pos + 5] as usize + SBP_CRC_LEN;
+    if end > data.len() {
+        return FrameAt::Incomplete;
+    }
+    let crc = u16::from_le_bytes([data[end - 2], data[end - 1]]);
+    if crc16(&data[pos + 1..end - SBP_CRC_LEN]) == crc {
+        FrameAt::Frame(end)
+    } else {
+        FrameAt::Invalid
+    }
+}
+
+/// Splits an SBP stream received in arbitrary chunks into frames.
+#[derive(Debug, Default)]
+pub struct SbpFramer {
+    buf: Vec<u8>,
+}
+
+impl SbpFramer {
+    /// Add received data and return the message types of the frames completed by it.
+    pub fn push(&mut self, data: &[u8]) -> Vec<u16> {
+        self.buf.extend_from_slice(data);
+        let mut msg_types = Vec::new();
+        let mut pos = 0;
+        while pos < self.buf.len() {
+            match frame_at(&self.buf, pos) {
+                FrameAt::Frame(end) => {
+                    msg_types.push(u16::from_le_bytes([self.buf[pos + 1], self.buf[pos + 2]]));
+                    pos = end;
+                }
+                FrameAt::Incomplete => break,
+                FrameAt::Invalid => pos += 1,
+            }
+        }
+        self.buf.drain(..pos);
+        msg_types
+    }
+}
+
+/// Length of `data` up to the end of its last frame with a valid CRC.
+pub fn complete_len(data: &[u8]) -> Option<usize> {
+    let mut complete = None;
+    let mut pos = 0;
+    while pos < data.len() {
+        match frame_at(data, pos) {
+            FrameAt::Frame(end) => {
+                complete = Some(end);
+                pos = end;
+            }
+            // a partial frame may just be a payload byte that looks like a preamble
+            FrameAt::Incomplete | FrameAt::Invalid => pos += 1,
+        }
+    }
+    complete
+}
+
+/// CRC-16/XMODEM as used by SBP.
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        let mut crc = crc ^ ((byte as u16) << 8);
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sbp::messages::observation::MsgBasePosEcef;
+    use sbp::messages::ConcreteMessage;
+
+    fn base_pos() -> Vec<u8> {
+        sbp::to_vec(&MsgBasePosEcef {
+            sender_id: Some(42),
+            x: -2_700_000.0,
+            y: -4_300_000.0,
+            z: 3_850_000.0,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn frame_sbp_stream() {
+        let frame = base_pos();
+        let mut stream = vec![0x00, SBP_PREAMBLE];
+        stream.extend_from_slice(&frame);
+        stream.extend_from_slice(&frame);
+
+        let mut framer = SbpFramer::default();
+        let (first, rest) = stream.split_at(frame.len());
+        assert!(framer.push(first).is_empty());
+        assert_eq!(
+            framer.push(rest),
+            [MsgBasePosEcef::MESSAGE_TYPE, MsgBasePosEcef::MESSAGE_TYPE]
+        );
+        assert!(framer.buf.is_empty());
+    }
+
+    #[test]
+    fn complete_len_test() {
+        let frame = base_pos();
+        let mut data = frame.clone();
+        data.extend_from_slice(&frame[..frame.len() - 1]);
+        assert_eq!(complete_len(&data), Some(frame.len()));
+        assert_eq!(complete_len(&frame[..frame.len() - 1]), None);
+        data[frame.len() - 1] ^= 0xFF;
+        assert_eq!(complete_len(&data), None);
+    }
+}
@@ -31,6 +31,8 @@ use crate::connection::ConnectionManager;
 
 use crate::client_sender::{BoxedClientSender, ChannelSender};
 use crate::log_panel::setup_logging;
+use crate::log_recovery::recover_logs;
+use crate::log_schedule::start_log_schedule;
 use crate::server_recv_thread::server_recv_thread;
 use crate::shared_state::SharedState;
 use crate::utils::{refresh_connection_frontend, refresh_loggingbar};
@@ -134,6 +136,7 @@ impl Server {
 
     #[pyo3(text_signature = "($self, /)")]
     pub fn start(&mut self) -> PyResult<ServerEndpoint> {
+        let started = chrono::Local::now();
         attach_console();
         let (client_send, client_recv) = channel::unbounded();
         let (server_send, server_recv) = channel::unbounded();
@@ -149,8 +152,10 @@ impl Server {
         // Handle CLI Opts.
         handle_cli(opt, &conn_manager, shared_state.clone());
         setup_logging(client_send.clone(), shared_state.clone());
+        recover_logs(&shared_state, started);
         refresh_connection_frontend(&client_send, &shared_state);
         refresh_loggingbar(&client_send, &shared_state);
+        start_log_schedule(&client_send, &shared_state);
         server_recv_thread(conn_manager, client_send, server_recv, shared_state);
         Ok(server_endpoint)
     }
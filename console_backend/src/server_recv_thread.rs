@@ -23,25 +23,33 @@ use capnp::serialize;
 use chrono::{DateTime, Utc};
 
 use crossbeam::channel;
-use log::{debug, error, warn};
+use log::{debug, error, info, warn};
 
 use crate::client_sender::BoxedClientSender;
 use crate::common_constants::SbpLogging;
 use crate::connection::ConnectionManager;
 use crate::console_backend_capnp as m;
+use crate::credentials::{Credential, CredentialKey, CredentialStore, Secret};
 use crate::errors::{
     CAP_N_PROTO_DESERIALIZATION_FAILURE, CONVERT_TO_STR_FAILURE,
     SOLUTION_POSITION_UNIT_SELECTION_NOT_AVAILABLE,
 };
 use crate::log_panel::LogLevel;
-use crate::output::CsvLogging;
+use crate::ntrip_sourcetable::{send_sourcetable, Sourcetable};
+use crate::output::{CsvLogger, CsvLogging, SbpJsonOption};
 use crate::shared_state::{AdvancedNetworkingState, ConnectionState, SharedState};
-use crate::tabs::advanced_tab::ntrip_tab::NtripOptions;
+use crate::tabs::advanced_tab::ntrip_server_tab::NtripServerOptions;
+use crate::tabs::advanced_tab::ntrip_tab::{
+    ConnectionState as NtripConnectionState, ConnectionStatus, NtripOptions,
+};
 use crate::tabs::{
     settings_tab::SaveRequest, solution_tab::LatLonUnits, update_tab::UpdateTabUpdate,
 };
+use crate::track_export::TrackFormat;
 use crate::types::{FlowControl, RealtimeDelay};
-use crate::utils::refresh_connection_frontend;
+use crate::utils::{
+    refresh_connection_frontend, refresh_loggingbar, send_credentials_status, OkOrLog,
+};
 
 pub type Error = anyhow::Error;
 pub type Result<T> = anyhow::Result<T>;
@@ -130,6 +138,44 @@ pub fn server_recv_thread(
                         SbpLogging::from_str(sbp_logging_format).expect(CONVERT_TO_STR_FAILURE),
                     );
                 }
+                m::message::LoggingBarCsvLoggersFront(Ok(cv_in)) => {
+                    let csv_loggers = cv_in
+                        .get_csv_loggers()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let csv_loggers: Vec<CsvLogger> = csv_loggers
+                        .iter()
+                        .filter_map(|x| CsvLogger::from_str(x.unwrap()).ok())
+                        .collect();
+                    shared_state.set_csv_loggers(csv_loggers);
+                }
+                m::message::LoggingBarTrackFormatsFront(Ok(cv_in)) => {
+                    let track_formats = cv_in
+                        .get_track_formats()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let track_formats: Vec<TrackFormat> = track_formats
+                        .iter()
+                        .filter_map(|x| TrackFormat::from_str(x.unwrap()).ok())
+                        .collect();
+                    shared_state.set_track_formats(track_formats);
+                }
+                m::message::LoggingBarSbpJsonOptionsFront(Ok(cv_in)) => {
+                    let options = cv_in
+                        .get_sbp_json_options()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let options: Vec<SbpJsonOption> = options
+                        .iter()
+                        .filter_map(|x| SbpJsonOption::from_str(x.unwrap()).ok())
+                        .collect();
+                    shared_state.set_sbp_json_options(options);
+                    refresh_loggingbar(&client_sender, &shared_state);
+                }
+                m::message::LoggingBarRinexFront(Ok(cv_in)) => {
+                    shared_state.set_rinex_logging(cv_in.get_rinex_logging());
+                }
+                m::message::LoggingBarAnnotationFront(Ok(cv_in)) => {
+                    let text = cv_in.get_text().expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    shared_state.add_session_annotation(text.to_string());
+                }
                 m::message::LogLevelFront(Ok(cv_in)) => {
                     let shared_state = shared_state.clone();
                     let log_level = cv_in
@@ -363,6 +409,23 @@ pub fn server_recv_thread(
                         .get_output_type()
                         .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
                         .to_string();
+                    let auto_mountpoint = cv_in.get_auto_mountpoint();
+                    let version = cv_in
+                        .get_ntrip_version()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let credential = cv_in
+                        .get_credential()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let (usr, pwd) = match shared_state.resolve_credential(credential, usr, pwd) {
+                        Ok(credentials) => credentials,
+                        Err(e) => {
+                            prompt_unlock(&shared_state, &client_sender, &e.to_string());
+                            ConnectionStatus::new("NTRIP", client_sender.clone())
+                                .set(NtripConnectionState::Failed, &e.to_string());
+                            continue;
+                        }
+                    };
                     let position: Option<(f64, f64, f64)> = match cv_in.get_position().which() {
                         Ok(m::ntrip_connect::position::Pos(Ok(pos))) => {
                             Some((pos.get_lat(), pos.get_lon(), pos.get_alt()))
@@ -384,8 +447,15 @@ pub fn server_recv_thread(
                                 position,
                                 gga_period,
                                 &output_type,
+                                auto_mountpoint,
+                            )
+                            .with_version(&version);
+                            guard.ntrip_tab.connect(
+                                msg_sender,
+                                heartbeat,
+                                options,
+                                client_sender.clone(),
                             );
-                            guard.ntrip_tab.connect(msg_sender, heartbeat, options);
                         }
                         _ => error!("ntrip unable to find connected device"),
                     }
@@ -393,6 +463,158 @@ pub fn server_recv_thread(
                 m::message::NtripDisconnect(Ok(_)) => {
                     shared_state.lock().ntrip_tab.disconnect();
                 }
+                m::message::NtripServerConnect(Ok(cv_in)) => {
+                    let url = cv_in
+                        .get_url()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let usr = cv_in
+                        .get_username()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let pwd = cv_in
+                        .get_password()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let version = cv_in
+                        .get_ntrip_version()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let credential = cv_in
+                        .get_credential()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let Some((usr, pwd)) = shared_state
+                        .resolve_credential(credential, usr, pwd)
+                        .ok_or_log(|e| {
+                            error!("{e}");
+                            prompt_unlock(&shared_state, &client_sender, &e.to_string());
+                        })
+                    else {
+                        continue;
+                    };
+                    let options = NtripServerOptions::new(url, usr, pwd).with_version(&version);
+                    shared_state
+                        .lock()
+                        .ntrip_server_tab
+                        .connect(options, client_sender.clone());
+                }
+                m::message::NtripServerDisconnect(Ok(_)) => {
+                    shared_state.lock().ntrip_server_tab.disconnect();
+                }
+                m::message::NtripSourcetableRequest(Ok(cv_in)) => {
+                    let url = cv_in
+                        .get_url()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let username = cv_in
+                        .get_username()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let password = cv_in
+                        .get_password()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let credential = cv_in
+                        .get_credential()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let (username, password) =
+                        match shared_state.resolve_credential(credential, username, password) {
+                            Ok(credentials) => credentials,
+                            Err(e) => {
+                                error!("{e}");
+                                prompt_unlock(&shared_state, &client_sender, &e.to_string());
+                                send_sourcetable(
+                                    &client_sender,
+                                    &Sourcetable::default(),
+                                    None,
+                                    None,
+                                    Some(&e.to_string()),
+                                );
+                                continue;
+                            }
+                        };
+                    let username = Some(username).filter(|s| !s.is_empty());
+                    let password = Some(password).filter(|s| !s.is_empty());
+                    let position = shared_state.lock().ntrip_tab.position();
+                    let client_sender = client_sender.clone();
+                    thread::spawn(move || {
+                        match Sourcetable::fetch(&url, username.as_deref(), password.as_deref()) {
+                            Ok(table) => {
+                                send_sourcetable(&client_sender, &table, position, None, None)
+                            }
+                            Err(e) => {
+                                error!("{e}");
+                                send_sourcetable(
+                                    &client_sender,
+                                    &Sourcetable::default(),
+                                    None,
+                                    None,
+                                    Some(&e.to_string()),
+                                );
+                            }
+                        }
+                    });
+                }
+                m::message::SaveCredential(Ok(cv_in)) => {
+                    let name = cv_in
+                        .get_name()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let username = cv_in
+                        .get_username()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE)
+                        .to_string();
+                    let password = cv_in
+                        .get_password()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let credential = Credential {
+                        username,
+                        password: Secret::new(password),
+                    };
+                    match shared_state.save_credential(&name, credential) {
+                        Ok(()) => {
+                            info!("Saved credential \"{name}\"");
+                            send_credentials_status(
+                                &client_sender,
+                                true,
+                                &format!("Saved credential \"{name}\""),
+                            );
+                        }
+                        Err(e) => {
+                            error!("{e}");
+                            send_credentials_status(
+                                &client_sender,
+                                shared_state.credentials_unlocked(),
+                                &e.to_string(),
+                            );
+                        }
+                    }
+                }
+                m::message::UnlockCredentials(Ok(cv_in)) => {
+                    let passphrase = cv_in
+                        .get_passphrase()
+                        .expect(CAP_N_PROTO_DESERIALIZATION_FAILURE);
+                    let key = CredentialKey::Passphrase(Secret::new(passphrase));
+                    match CredentialStore::open(&key) {
+                        Ok(credentials) => {
+                            shared_state.set_credentials(credentials);
+                            info!("Unlocked the credentials store");
+                            send_credentials_status(
+                                &client_sender,
+                                true,
+                                "Unlocked the credentials store",
+                            );
+                        }
+                        Err(e) => {
+                            error!("could not unlock the credentials store, {e}");
+                            send_credentials_status(
+                                &client_sender,
+                                shared_state.credentials_unlocked(),
+                                &format!("could not unlock the credentials store, {e}"),
+                            );
+                        }
+                    }
+                }
                 _ => {
                     error!("unknown message from front-end");
                 }
@@ -402,3 +624,11 @@ pub fn server_recv_thread(
         client_sender.set_connected(false);
     });
 }
+
+/// Ask the frontend for the passphrase when a credential couldn't be used because
+/// the store is still locked.
+fn prompt_unlock(shared_state: &SharedState, client_sender: &BoxedClientSender, error: &str) {
+    if !shared_state.credentials_unlocked() {
+        send_credentials_status(client_sender, false, error);
+    }
+}
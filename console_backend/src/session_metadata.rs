@@ -0,0 +1,321 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Session metadata sidecar written next to every log.
+//!
+//! The sidecar is written when logging starts and rewritten when logging ends so
+//! a log can always be traced back to the device and console that produced it.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use indexmap::IndexMap;
+use sbp::messages::logging::MsgLog;
+use sbp::{Frame, Sbp, SbpMessage};
+use serde::Serialize;
+
+use crate::constants::{ANNOTATION_SENDER_ID, ANNOTATION_TAG, SESSION_METADATA_EXTENSION};
+use crate::output::AnnotationLog;
+use crate::shared_state::{ConnectionState, SharedState};
+use crate::types::Result;
+use crate::utils::date_conv::{datetime_to_string_and_seconds, gps_time_logging_string};
+
+// MsgLog level used for annotation markers, INFO.
+const ANNOTATION_LOG_LEVEL: u8 = 6;
+// Longest MsgLog text that fits in a single SBP payload.
+const ANNOTATION_MAX_LEN: usize = 254;
+
+/// Device details collected from the settings table.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviceInfo {
+    pub firmware_version: Option<String>,
+    pub product_id: Option<String>,
+    pub serial_number: Option<String>,
+    /// Full settings table, keyed by group then setting name.
+    pub settings: IndexMap<String, IndexMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+impl From<&ConnectionState> for ConnectionInfo {
+    fn from(conn: &ConnectionState) -> Self {
+        let kind = match conn {
+            ConnectionState::Connected { conn, .. } => Some(conn.connection_type().to_string()),
+            _ => None,
+        };
+        ConnectionInfo {
+            name: conn.name(),
+            kind,
+        }
+    }
+}
+
+/// GPS week number and time of week in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WeekTime {
+    pub wn: i16,
+    pub tow: f64,
+}
+
+impl From<sbp::time::GpsTime> for WeekTime {
+    fn from(time: sbp::time::GpsTime) -> Self {
+        WeekTime {
+            wn: time.wn(),
+            tow: time.tow(),
+        }
+    }
+}
+
+/// An operator's note about a moment in a log, e.g. "going under the bridge now".
+#[derive(Debug, Clone, Serialize)]
+pub struct Annotation {
+    pub pc_time: DateTime<Local>,
+    pub gps_time: Option<WeekTime>,
+    pub text: String,
+}
+
+impl Annotation {
+    pub fn new(text: String) -> Self {
+        Annotation {
+            pc_time: Local::now(),
+            gps_time: None,
+            text,
+        }
+    }
+
+    pub fn with_gps_time(mut self, gps_time: Option<WeekTime>) -> Self {
+        self.gps_time = gps_time;
+        self
+    }
+
+    /// The marker written into SBP logs, a `MsgLog` tagged with `[annotation]`
+    /// followed by the GPS time, the PC time and the text.
+    pub fn to_msg_log(&self) -> MsgLog {
+        let gps_time = match self.gps_time {
+            Some(WeekTime { wn, tow }) => format!("{wn}:{tow:.3}"),
+            None => String::from("-"),
+        };
+        let pc_time = self.pc_time.to_rfc3339_opts(SecondsFormat::Millis, false);
+        let mut text = format!("{ANNOTATION_TAG} gps={gps_time} pc={pc_time} {}", self.text);
+        while text.len() > ANNOTATION_MAX_LEN {
+            text.pop();
+        }
+        MsgLog {
+            sender_id: Some(ANNOTATION_SENDER_ID),
+            level: ANNOTATION_LOG_LEVEL,
+            text: text.into(),
+        }
+    }
+
+    /// Read back a marker written by [`Annotation::to_msg_log`].
+    pub fn from_msg_log(msg: &MsgLog) -> Option<Self> {
+        let text = msg.text.to_string();
+        let rest = text.strip_prefix(ANNOTATION_TAG)?.trim_start();
+        let (gps_time, rest) = rest.strip_prefix("gps=")?.split_once(' ')?;
+        let rest = rest.strip_prefix("pc=")?;
+        let (pc_time, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        let pc_time = DateTime::parse_from_rfc3339(pc_time)
+            .ok()?
+            .with_timezone(&Local);
+        let gps_time = gps_time.split_once(':').and_then(|(wn, tow)| {
+            Some(WeekTime {
+                wn: wn.parse().ok()?,
+                tow: tow.parse().ok()?,
+            })
+        });
+        Some(Annotation {
+            pc_time,
+            gps_time,
+            text: text.to_string(),
+        })
+    }
+
+    /// Row for the annotations CSV log.
+    pub fn to_log_row(&self) -> AnnotationLog {
+        let (pc_date, pc_secs) = datetime_to_string_and_seconds(self.pc_time);
+        let gps_time = self
+            .gps_time
+            .and_then(|t| gps_time_logging_string(Some(t.wn as u16), t.tow));
+        AnnotationLog {
+            pc_time: format!("{pc_date}:{pc_secs:0>6.06}"),
+            gps_time,
+            tow_s: self.gps_time.map(|t| t.tow),
+            text: self.text.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMetadata {
+    #[serde(skip)]
+    path: PathBuf,
+    pub logs: Vec<PathBuf>,
+    pub console_version: String,
+    pub connection: ConnectionInfo,
+    pub device: DeviceInfo,
+    pub start_time: DateTime<Local>,
+    pub stop_time: Option<DateTime<Local>>,
+    pub message_count: u64,
+    pub message_counts: BTreeMap<String, u64>,
+    pub annotations: Vec<Annotation>,
+}
+
+impl SessionMetadata {
+    /// Create the metadata for a new logging session and write the initial sidecar.
+    ///
+    /// # Parameters:
+    /// - `path`: The path of the sidecar file.
+    /// - `logs`: The log files this sidecar describes.
+    /// - `shared_state`: The shared state to pull connection and device details from.
+    pub fn start(path: PathBuf, logs: Vec<PathBuf>, shared_state: &SharedState) -> Result<Self> {
+        let metadata = SessionMetadata {
+            path,
+            logs,
+            console_version: shared_state.console_version(),
+            connection: ConnectionInfo::from(&shared_state.connection()),
+            device: shared_state.device_info(),
+            start_time: Local::now(),
+            stop_time: None,
+            message_count: 0,
+            message_counts: BTreeMap::new(),
+            annotations: shared_state.session_annotations(),
+        };
+        metadata.write()?;
+        Ok(metadata)
+    }
+
+    /// Count a message written to the log.
+    pub fn record(&mut self, frame: &Frame, msg: Option<&Sbp>) {
+        let name = match (msg, frame.msg_type()) {
+            (Some(msg), _) => msg.message_name().to_string(),
+            (None, Some(msg_type)) => format!("0x{msg_type:04X}"),
+            (None, None) => "unknown".to_string(),
+        };
+        *self.message_counts.entry(name).or_default() += 1;
+        self.message_count += 1;
+    }
+
+    pub fn annotate(&mut self, annotation: Annotation) {
+        self.annotations.push(annotation);
+    }
+
+    /// Stamp the stop time, refresh the device details and rewrite the sidecar.
+    pub fn finish(&mut self, device: DeviceInfo) -> Result<()> {
+        self.stop_time = Some(Local::now());
+        self.device = device;
+        self.write()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write(&self) -> Result<()> {
+        serde_json::to_writer_pretty(File::create(&self.path)?, self)?;
+        Ok(())
+    }
+}
+
+/// The sidecar path for a log file, e.g. `swift-gnss-20220101-000000.sbp.metadata.json`.
+pub fn sidecar_path(log: &Path) -> PathBuf {
+    let mut name = log.as_os_str().to_owned();
+    name.push(SESSION_METADATA_EXTENSION);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_common::msg_to_frame;
+    use sbp::messages::navigation::MsgAgeCorrections;
+    use tempfile::TempDir;
+
+    #[test]
+    fn sidecar_path_test() {
+        let path = sidecar_path(Path::new("/tmp/swift-gnss-20220101-000000.sbp"));
+        assert_eq!(
+            path,
+            PathBuf::from("/tmp/swift-gnss-20220101-000000.sbp.metadata.json")
+        );
+    }
+
+    #[test]
+    fn session_metadata_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let log = tmp_dir.path().join("test.sbp");
+        let path = sidecar_path(&log);
+        let shared_state = SharedState::new();
+        let mut metadata =
+            SessionMetadata::start(path.clone(), vec![log.clone()], &shared_state).unwrap();
+        assert!(path.exists());
+
+        let msg = MsgAgeCorrections {
+            sender_id: Some(1337),
+            age: 0xFFFF,
+            tow: 0,
+        };
+        let frame = msg_to_frame(msg.clone());
+        metadata.record(&frame, Some(&Sbp::from(msg)));
+        metadata.record(&frame, None);
+        metadata.annotate(Annotation::new("antenna moved".into()));
+        metadata.finish(DeviceInfo::default()).unwrap();
+
+        let value: serde_json::Value = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(value["message_count"], 2);
+        assert_eq!(value["message_counts"]["MSG_AGE_CORRECTIONS"], 1);
+        assert_eq!(value["annotations"][0]["text"], "antenna moved");
+        assert_eq!(value["logs"][0], log.display().to_string());
+        assert!(!value["stop_time"].is_null());
+    }
+
+    #[test]
+    fn annotation_marker_test() {
+        let annotation =
+            Annotation::new("going under the bridge now".into()).with_gps_time(Some(WeekTime {
+                wn: 2200,
+                tow: 3600.25,
+            }));
+        let msg = annotation.to_msg_log();
+        assert_eq!(msg.sender_id, Some(ANNOTATION_SENDER_ID));
+        let parsed = Annotation::from_msg_log(&msg).unwrap();
+        assert_eq!(parsed.text, annotation.text);
+        assert_eq!(parsed.gps_time, annotation.gps_time);
+        assert_eq!(
+            parsed.pc_time.timestamp_millis(),
+            annotation.pc_time.timestamp_millis()
+        );
+
+        let parsed = Annotation::from_msg_log(&Annotation::new(String::new()).to_msg_log());
+        assert_eq!(parsed.unwrap().gps_time, None);
+        let msg = MsgLog {
+            sender_id: Some(0),
+            level: ANNOTATION_LOG_LEVEL,
+            text: String::from("not an annotation").into(),
+        };
+        assert!(Annotation::from_msg_log(&msg).is_none());
+    }
+}
@@ -20,24 +20,25 @@
 use std::str::FromStr;
 use std::{
     cmp::{Eq, PartialEq},
+    collections::HashMap,
     fmt::Debug,
     fs,
     hash::Hash,
     ops::Deref,
     path::{Path, PathBuf},
     sync::Arc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use strum_macros::EnumString;
 
-use anyhow::{Context, Result as AHResult};
+use anyhow::{bail, Context, Result as AHResult};
 use chrono::{DateTime, Utc};
 use crossbeam::channel::{unbounded, Receiver, SendError, Sender};
 use directories::{ProjectDirs, UserDirs};
 use indexmap::set::IndexSet;
 use indexmap::IndexMap;
 use lazy_static::lazy_static;
-use log::error;
+use log::{error, info};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use serialport::FlowControl;
@@ -45,15 +46,25 @@ use serialport::FlowControl;
 use crate::client_sender::BoxedClientSender;
 use crate::constants::{
     APPLICATION_NAME, APPLICATION_ORGANIZATION, APPLICATION_QUALIFIER, CONNECTION_HISTORY_FILENAME,
-    DEFAULT_IP_ADDRESS, DEFAULT_LOG_DIRECTORY, DEFAULT_PORT, MAX_CONNECTION_HISTORY, MPS,
+    DEFAULT_IP_ADDRESS, DEFAULT_LOG_DIRECTORY, DEFAULT_PORT, LOG_SYNC_INTERVAL,
+    MAX_CONNECTION_HISTORY, MPS,
 };
+use crate::credentials::{Credential, CredentialStore, UNLOCK_HELP};
 use crate::errors::CONVERT_TO_STR_FAILURE;
 use crate::log_panel::LogLevel;
-use crate::output::{CsvLogging, CsvSerializer};
+use crate::log_path::LogPathTemplate;
+use crate::log_schedule::LogSchedule;
+use crate::nmea::{NmeaOutput, NmeaSentence, NmeaTarget};
+use crate::output::{CsvLogger, CsvLogging, CsvSerializer, SbpJsonOption};
 use crate::process_messages::StopToken;
+use crate::rinex::RinexLogger;
+use crate::session_metadata::{Annotation, DeviceInfo, SessionMetadata, WeekTime};
 use crate::shared_state::EventType::Refresh;
-use crate::tabs::advanced_tab::ntrip_tab::NtripState;
+use crate::sqlite_log::SqliteLogger;
+use crate::tabs::advanced_tab::ntrip_server_tab::{NtripServerState, NtripServerUplink};
+use crate::tabs::advanced_tab::ntrip_tab::{NtripSolution, NtripState};
 use crate::tabs::{settings_tab, solution_tab::LatLonUnits, update_tab::UpdateTabUpdate};
+use crate::track_export::{TrackFormat, TrackPoint, TrackWriter};
 use crate::utils::{send_conn_state, OkOrLog};
 use crate::watch::{WatchReceiver, Watched};
 use crate::{common_constants::ConnectionType, connection::Connection, MsgSender};
@@ -146,6 +157,12 @@ impl SharedState {
     pub fn set_sbp_logging_format(&self, logging: SbpLogging) {
         self.lock().logging_bar.sbp_logging_format = logging;
     }
+    pub fn sbp_json_options(&self) -> Vec<SbpJsonOption> {
+        self.lock().logging_bar.sbp_json_options.clone()
+    }
+    pub fn set_sbp_json_options(&self, options: Vec<SbpJsonOption>) {
+        self.lock().logging_bar.sbp_json_options = options;
+    }
     pub fn sbp_logging_filename(&self) -> Option<PathBuf> {
         self.lock().logging_bar.sbp_logging_filename.clone()
     }
@@ -206,8 +223,251 @@ impl SharedState {
             .connection_history
             .record_serial(device, baud, flow);
     }
+    pub fn csv_loggers(&self) -> Vec<CsvLogger> {
+        self.lock().logging_bar.csv_loggers.clone()
+    }
+    pub fn set_csv_loggers(&self, csv_loggers: Vec<CsvLogger>) {
+        self.lock().logging_bar.csv_loggers = csv_loggers;
+    }
+    /// Whether any CSV log file is currently open.
+    pub fn csv_logs_open(&self) -> bool {
+        let guard = self.lock();
+        guard.solution_tab.position_tab.log_file.is_some()
+            || guard.solution_tab.velocity_tab.log_file.is_some()
+            || guard.baseline_tab.log_file.is_some()
+            || !guard.csv_logs.is_empty()
+            || !guard.track_logs.is_empty()
+            || guard.rinex_log.is_some()
+    }
+    pub fn csv_log_open(&self, logger: &CsvLogger) -> bool {
+        self.lock().csv_logs.contains_key(logger)
+    }
+    /// Time between flushing and syncing open logs to disk.
+    pub fn log_sync_interval(&self) -> Option<Duration> {
+        self.lock().logging_bar.log_sync_interval
+    }
+    pub fn set_log_sync_interval(&self, interval: Option<Duration>) {
+        self.lock().logging_bar.log_sync_interval = interval;
+    }
+    pub fn record_corrections(&self) -> bool {
+        self.lock().logging_bar.record_corrections
+    }
+    pub fn set_record_corrections(&self, record: bool) {
+        self.lock().logging_bar.record_corrections = record;
+    }
+    pub fn log_schedule(&self) -> Option<LogSchedule> {
+        self.lock().logging_bar.log_schedule.clone()
+    }
+    pub fn set_log_schedule(&self, schedule: Option<LogSchedule>) {
+        self.lock().logging_bar.log_schedule = schedule;
+    }
+    pub fn log_schedule_status(&self) -> Option<String> {
+        self.lock().logging_bar.log_schedule_status.clone()
+    }
+    pub fn set_log_schedule_status(&self, status: Option<String>) {
+        self.lock().logging_bar.log_schedule_status = status;
+    }
+    pub fn log_path_template(&self) -> Option<LogPathTemplate> {
+        self.lock().logging_bar.log_path_template.clone()
+    }
+    pub fn set_log_path_template(&self, template: Option<LogPathTemplate>) {
+        self.lock().logging_bar.log_path_template = template;
+    }
+    fn new_csv_serializer(&self, path: &Path) -> Option<CsvSerializer> {
+        let interval = self.log_sync_interval();
+        CsvSerializer::new_option(path).map(|log| log.with_sync_interval(interval))
+    }
+    pub fn start_csv_log(&self, logger: CsvLogger, path: &Path) {
+        if let Some(log) = self.new_csv_serializer(path) {
+            self.lock().csv_logs.insert(logger, log);
+        }
+    }
+    pub fn write_csv_log<T: Serialize>(&self, logger: CsvLogger, rows: &[T]) {
+        if let Some(log) = self.lock().csv_logs.get_mut(&logger) {
+            for row in rows {
+                if let Err(err) = log.serialize(row) {
+                    error!("Unable to write to {logger} log, error {err}.");
+                    break;
+                }
+            }
+        }
+    }
+    pub fn end_csv_logs(&self) -> Result<()> {
+        let logs: Vec<CsvSerializer> = self.lock().csv_logs.drain().map(|(_, log)| log).collect();
+        for mut log in logs {
+            log.flush()?;
+        }
+        Ok(())
+    }
+    pub fn track_formats(&self) -> Vec<TrackFormat> {
+        self.lock().logging_bar.track_formats.clone()
+    }
+    pub fn set_track_formats(&self, track_formats: Vec<TrackFormat>) {
+        self.lock().logging_bar.track_formats = track_formats;
+    }
+    pub fn start_track_log(&self, format: TrackFormat, path: &Path) {
+        if let Some(log) = TrackWriter::new_option(path, format.clone()) {
+            let log = log.with_sync_interval(self.log_sync_interval());
+            self.lock().track_logs.insert(format, log);
+        }
+    }
+    pub fn write_track_point(&self, point: TrackPoint) {
+        for (format, log) in self.lock().track_logs.iter_mut() {
+            if let Err(err) = log.write_point(point.clone()) {
+                error!("Unable to write to {format} track, error {err}.");
+            }
+        }
+    }
+    pub fn end_track_logs(&self) -> Result<()> {
+        let logs: Vec<TrackWriter> = self.lock().track_logs.drain().map(|(_, log)| log).collect();
+        for log in logs {
+            log.finish()?;
+        }
+        Ok(())
+    }
+    pub fn rinex_logging(&self) -> bool {
+        self.lock().logging_bar.rinex_logging
+    }
+    pub fn set_rinex_logging(&self, rinex_logging: bool) {
+        self.lock().logging_bar.rinex_logging = rinex_logging;
+    }
+    pub fn start_rinex_log(&self, obs_path: &Path, nav_path: &Path) {
+        let device_info = self.device_info();
+        let interval = self.log_sync_interval();
+        let log = RinexLogger::new_option(obs_path, nav_path, device_info)
+            .map(|log| log.with_sync_interval(interval));
+        self.lock().rinex_log = log;
+    }
+    pub fn write_rinex(&self, msg: &sbp::Sbp) {
+        let mut guard = self.lock();
+        if let Some(log) = guard.rinex_log.as_mut() {
+            if let Err(err) = log.handle_msg(msg) {
+                error!("Unable to write to RINEX log, error {err}.");
+                guard.rinex_log = None;
+            }
+        }
+    }
+    pub fn end_rinex_log(&self) -> Result<()> {
+        if let Some(log) = self.lock().rinex_log.take() {
+            log.finish()?;
+        }
+        Ok(())
+    }
+    /// Start writing NMEA sentences to `targets`, replacing any current NMEA output.
+    pub fn start_nmea_output(&self, targets: &[NmeaTarget], sentences: Vec<NmeaSentence>) {
+        let output = NmeaOutput::new(targets, sentences);
+        self.lock().nmea_output = (!output.is_empty()).then_some(output);
+    }
+    pub fn write_nmea(&self, msg: &sbp::Sbp) {
+        if let Some(output) = self.lock().nmea_output.as_mut() {
+            output.handle_msg(msg);
+        }
+    }
+    /// Write every NMEA sentence to `path` without dropping epochs, for converting logs.
+    pub fn start_nmea_file(&self, path: &Path) {
+        let target = NmeaTarget::File(path.to_path_buf());
+        let output = NmeaOutput::new(&[target], NmeaSentence::all()).lossless();
+        self.lock().nmea_output = (!output.is_empty()).then_some(output);
+    }
+    pub fn end_nmea_output(&self) {
+        self.lock().nmea_output = None;
+    }
+    /// Close the NMEA output once the sentences queued have been written.
+    pub fn finish_nmea_output(&self) {
+        let output = self.lock().nmea_output.take();
+        if let Some(output) = output {
+            output.finish();
+        }
+    }
+    /// Handle for updating the solution reported to NTRIP casters in dynamic mode.
+    pub fn ntrip_solution(&self) -> NtripSolution {
+        self.lock().ntrip_tab.solution()
+    }
+    /// Start the NTRIP client configured on the command line on a newly connected device.
+    pub fn start_headless_ntrip(&self, msg_sender: MsgSender, client_sender: &BoxedClientSender) {
+        let mut guard = self.lock();
+        let heartbeat = guard.heartbeat_data.clone();
+        guard
+            .ntrip_tab
+            .device_connected(msg_sender, heartbeat, client_sender.clone());
+    }
+    pub fn stop_headless_ntrip(&self) {
+        self.lock().ntrip_tab.device_disconnected();
+    }
+    /// Record the corrections received while the SBP log `log` is open.
+    pub fn start_corrections_log(&self, log: &Path) {
+        self.lock().ntrip_tab.recorder.start(log);
+    }
+    pub fn stop_corrections_log(&self) {
+        self.lock().ntrip_tab.recorder.stop();
+    }
+    /// Use the unlocked `credentials` for credentials referenced by name.
+    pub fn set_credentials(&self, credentials: CredentialStore) {
+        self.lock().credentials = Some(credentials);
+    }
+    /// Whether credentials referenced by name can be used.
+    pub fn credentials_unlocked(&self) -> bool {
+        self.lock().credentials.is_some()
+    }
+    /// The username and password to use, from the saved credential `name` if it isn't empty.
+    pub fn resolve_credential(
+        &self,
+        name: &str,
+        username: String,
+        password: String,
+    ) -> Result<(String, String)> {
+        if name.is_empty() {
+            return Ok((username, password));
+        }
+        let guard = self.lock();
+        let Some(credentials) = guard.credentials.as_ref() else {
+            bail!("can't use credential \"{name}\", {UNLOCK_HELP}");
+        };
+        let credential = credentials.get(name)?;
+        Ok((
+            credential.username.clone(),
+            credential.password.expose().to_string(),
+        ))
+    }
+    /// Save a username and password under `name` in the unlocked credentials store.
+    pub fn save_credential(&self, name: &str, credential: Credential) -> Result<()> {
+        match self.lock().credentials.as_mut() {
+            Some(credentials) => credentials.insert(name, credential),
+            None => bail!("can't save credential \"{name}\", {UNLOCK_HELP}"),
+        }
+    }
+    /// Handle for queueing the device's base station messages for the NTRIP server.
+    pub fn ntrip_server_uplink(&self) -> NtripServerUplink {
+        self.lock().ntrip_server_tab.uplink()
+    }
+    /// Start writing the solution to the SQLite database at `path`, keeping rows
+    /// for `retention` if set.
+    pub fn start_sqlite_log(&self, path: &Path, retention: Option<Duration>) {
+        let interval = self.log_sync_interval();
+        let log = SqliteLogger::new(path)
+            .map(|log| log.with_retention(retention).with_sync_interval(interval))
+            .ok_or_log(|e| error!("Unable to open SQLite log {}, error {e}.", path.display()));
+        self.lock().sqlite_log = log;
+    }
+    pub fn write_sqlite(&self, msg: &sbp::Sbp) {
+        let mut guard = self.lock();
+        let gps_time = guard.gps_time;
+        if let Some(log) = guard.sqlite_log.as_mut() {
+            if let Err(err) = log.handle_msg(msg, gps_time) {
+                error!("Unable to write to SQLite log, error {err}.");
+                guard.sqlite_log = None;
+            }
+        }
+    }
+    pub fn end_sqlite_log(&self) -> Result<()> {
+        if let Some(log) = self.lock().sqlite_log.take() {
+            log.finish()?;
+        }
+        Ok(())
+    }
     pub fn start_vel_log(&self, path: &Path) {
-        self.lock().solution_tab.velocity_tab.log_file = CsvSerializer::new_option(path);
+        let log = self.new_csv_serializer(path);
+        self.lock().solution_tab.velocity_tab.log_file = log;
     }
     pub fn end_vel_log(&self) -> Result<()> {
         if let Some(ref mut log) = self.lock().solution_tab.velocity_tab.log_file {
@@ -217,7 +477,8 @@ impl SharedState {
         Ok(())
     }
     pub fn start_pos_log(&self, path: &Path) {
-        self.lock().solution_tab.position_tab.log_file = CsvSerializer::new_option(path);
+        let log = self.new_csv_serializer(path);
+        self.lock().solution_tab.position_tab.log_file = log;
     }
     pub fn end_pos_log(&self) -> Result<()> {
         if let Some(ref mut log) = self.lock().solution_tab.position_tab.log_file {
@@ -227,7 +488,8 @@ impl SharedState {
         Ok(())
     }
     pub fn start_baseline_log(&self, path: &Path) {
-        self.lock().baseline_tab.log_file = CsvSerializer::new_option(path);
+        let log = self.new_csv_serializer(path);
+        self.lock().baseline_tab.log_file = log;
     }
     pub fn end_baseline_log(&self) -> Result<()> {
         if let Some(ref mut log) = self.lock().baseline_tab.log_file {
@@ -236,6 +498,88 @@ impl SharedState {
         self.lock().baseline_tab.log_file = None;
         Ok(())
     }
+    pub fn start_sbp_session(&self, session: Option<SessionMetadata>) {
+        self.lock().logging_bar.sbp_session = session;
+    }
+    pub fn end_sbp_session(&self) -> Result<()> {
+        let session = self.lock().logging_bar.sbp_session.take();
+        if let Some(mut session) = session {
+            session.finish(self.device_info())?;
+        }
+        Ok(())
+    }
+    /// Close every log started alongside CSV logging and finish their session sidecar.
+    pub fn close_csv_logs(&self) -> Result<()> {
+        self.end_vel_log()?;
+        self.end_pos_log()?;
+        self.end_baseline_log()?;
+        self.end_csv_logs()?;
+        self.end_track_logs()?;
+        self.end_rinex_log()?;
+        self.end_csv_session()?;
+        Ok(())
+    }
+    pub fn start_csv_session(&self, session: Option<SessionMetadata>) {
+        self.lock().logging_bar.csv_session = session;
+    }
+    pub fn end_csv_session(&self) -> Result<()> {
+        let session = self.lock().logging_bar.csv_session.take();
+        if let Some(mut session) = session {
+            session.finish(self.device_info())?;
+        }
+        Ok(())
+    }
+    pub fn record_session_message(&self, frame: &sbp::Frame, msg: Option<&sbp::Sbp>) {
+        let mut guard = self.lock();
+        let logging_bar = &mut guard.logging_bar;
+        for session in logging_bar
+            .sbp_session
+            .iter_mut()
+            .chain(logging_bar.csv_session.iter_mut())
+        {
+            session.record(frame, msg);
+        }
+    }
+    /// Annotations added while logging are queued as markers for the active logs,
+    /// otherwise they are kept and attached to every session started afterwards.
+    pub fn add_session_annotation(&self, text: String) {
+        let mut guard = self.lock();
+        let annotation = Annotation::new(text).with_gps_time(guard.gps_time);
+        info!("Annotation: {}", annotation.text);
+        let logging_bar = &mut guard.logging_bar;
+        if logging_bar.sbp_session.is_some() || logging_bar.csv_session.is_some() {
+            logging_bar.annotation_markers.push(annotation);
+        } else {
+            logging_bar.annotations.push(annotation);
+        }
+    }
+    /// Annotations waiting to be written into the active logs.
+    pub fn take_annotation_markers(&self) -> Vec<Annotation> {
+        std::mem::take(&mut self.lock().logging_bar.annotation_markers)
+    }
+    /// Add an annotation to the active sessions and the annotations CSV log.
+    pub fn record_annotation(&self, annotation: Annotation) {
+        self.write_csv_log(CsvLogger::ANNOTATIONS, &[annotation.to_log_row()]);
+        let mut guard = self.lock();
+        let logging_bar = &mut guard.logging_bar;
+        for session in logging_bar
+            .sbp_session
+            .iter_mut()
+            .chain(logging_bar.csv_session.iter_mut())
+        {
+            session.annotate(annotation.clone());
+        }
+    }
+    /// The latest rover GPS time seen on the current connection.
+    pub fn gps_time(&self) -> Option<WeekTime> {
+        self.lock().gps_time
+    }
+    pub fn set_gps_time(&self, gps_time: Option<WeekTime>) {
+        self.lock().gps_time = gps_time;
+    }
+    pub fn session_annotations(&self) -> Vec<Annotation> {
+        self.lock().logging_bar.annotations.clone()
+    }
     pub fn update_tab_sender(&self) -> Option<Sender<Option<UpdateTabUpdate>>> {
         self.lock().update_tab_sender.clone()
     }
@@ -313,6 +657,12 @@ impl SharedState {
     pub fn firmware_version(&self) -> Option<String> {
         self.lock().firmware_version.take()
     }
+    pub fn set_device_info(&self, device_info: DeviceInfo) {
+        self.lock().device_info = device_info;
+    }
+    pub fn device_info(&self) -> DeviceInfo {
+        self.lock().device_info.clone()
+    }
     pub fn set_dgnss_enabled(&self, dgnss_solution_mode: String) {
         self.lock()
             .heartbeat_data
@@ -394,6 +744,9 @@ pub struct SharedStateInner {
     pub(crate) logging_bar: LoggingBarState,
     pub(crate) log_panel: LogPanelState,
     pub(crate) ntrip_tab: NtripState,
+    pub(crate) ntrip_server_tab: NtripServerState,
+    /// Saved caster credentials, `None` until unlocked.
+    pub(crate) credentials: Option<CredentialStore>,
     pub(crate) tracking_tab: TrackingTabState,
     pub(crate) connection_history: ConnectionHistory,
     pub(crate) conn: Watched<ConnectionState>,
@@ -401,11 +754,24 @@ pub struct SharedStateInner {
     pub(crate) debug: bool,
     pub(crate) solution_tab: SolutionTabState,
     pub(crate) baseline_tab: BaselineTabState,
+    /// CSV logs which are not owned by a specific tab state.
+    pub(crate) csv_logs: HashMap<CsvLogger, CsvSerializer>,
+    /// Track exports written alongside the CSV logs.
+    pub(crate) track_logs: HashMap<TrackFormat, TrackWriter>,
+    /// RINEX observation and navigation files written alongside the CSV logs.
+    pub(crate) rinex_log: Option<RinexLogger>,
+    /// NMEA sentences generated from the solution, independent of logging.
+    pub(crate) nmea_output: Option<NmeaOutput>,
+    /// Rolling SQLite database of the solution, independent of logging sessions.
+    pub(crate) sqlite_log: Option<SqliteLogger>,
+    /// Latest rover GPS time, used to stamp annotations.
+    pub(crate) gps_time: Option<WeekTime>,
     pub(crate) advanced_spectrum_analyzer_tab: AdvancedSpectrumAnalyzerTabState,
     pub(crate) update_tab_sender: Option<Sender<Option<UpdateTabUpdate>>>,
     pub(crate) settings_tab: Watched<SettingsTabState>,
     pub(crate) console_version: String,
     pub(crate) firmware_version: Option<String>,
+    pub(crate) device_info: DeviceInfo,
     pub(crate) advanced_networking_update: Option<AdvancedNetworkingState>,
     pub(crate) auto_survey_data: AutoSurveyData,
     pub(crate) heartbeat_data: Heartbeat,
@@ -424,6 +790,8 @@ impl SharedStateInner {
             logging_bar: LoggingBarState::new(log_directory),
             log_panel: LogPanelState::new(),
             ntrip_tab: NtripState::default(),
+            ntrip_server_tab: NtripServerState::default(),
+            credentials: None,
             tracking_tab: TrackingTabState::new(),
             debug: false,
             connection_history,
@@ -431,11 +799,18 @@ impl SharedStateInner {
             connection_dialog_visible: true,
             solution_tab: SolutionTabState::new(),
             baseline_tab: BaselineTabState::new(),
+            csv_logs: HashMap::new(),
+            track_logs: HashMap::new(),
+            rinex_log: None,
+            nmea_output: None,
+            sqlite_log: None,
+            gps_time: None,
             advanced_spectrum_analyzer_tab: AdvancedSpectrumAnalyzerTabState::new(),
             update_tab_sender: None,
             settings_tab: Watched::new(SettingsTabState::new()),
             console_version,
             firmware_version: None,
+            device_info: DeviceInfo::default(),
             advanced_networking_update: None,
             auto_survey_data: AutoSurveyData::new(),
             heartbeat_data,
@@ -485,12 +860,38 @@ pub struct AdvancedNetworkingState {
 pub struct LoggingBarState {
     pub sbp_logging: bool,
     pub sbp_logging_format: SbpLogging,
+    /// Layout of SBP-JSON logs
+    pub sbp_json_options: Vec<SbpJsonOption>,
     /// User-supplied SBP log filename to use instead of the autogenerated one
     pub sbp_logging_filename: Option<PathBuf>,
     /// Full path to the current SBP log
     pub sbp_logging_filepath: Option<PathBuf>,
     pub csv_logging: CsvLogging,
+    /// CSV loggers selected in the logging bar
+    pub csv_loggers: Vec<CsvLogger>,
+    /// Track formats exported alongside the CSV logs
+    pub track_formats: Vec<TrackFormat>,
+    /// Whether RINEX OBS/NAV files are written alongside the CSV logs
+    pub rinex_logging: bool,
     pub logging_directory: PathBuf,
+    /// Per-session log path layout, `None` to log flat into the logging directory
+    pub log_path_template: Option<LogPathTemplate>,
+    /// Metadata sidecar for the current SBP log
+    pub sbp_session: Option<SessionMetadata>,
+    /// Metadata sidecar for the current set of CSV logs
+    pub csv_session: Option<SessionMetadata>,
+    /// Annotations waiting for the next logging session
+    pub annotations: Vec<Annotation>,
+    /// Annotations waiting to be written into the active logs
+    pub annotation_markers: Vec<Annotation>,
+    /// Time between flushing and syncing open logs to disk, `None` to only sync on close
+    pub log_sync_interval: Option<Duration>,
+    /// Schedule that starts and stops SBP logging
+    pub log_schedule: Option<LogSchedule>,
+    /// Current state of the logging schedule shown in the logging bar
+    pub log_schedule_status: Option<String>,
+    /// Save the received corrections next to each SBP log
+    pub record_corrections: bool,
 }
 
 impl LoggingBarState {
@@ -509,10 +910,23 @@ impl LoggingBarState {
         LoggingBarState {
             sbp_logging: false,
             sbp_logging_format: SbpLogging::SBP_JSON,
+            sbp_json_options: SbpJsonOption::defaults(),
             sbp_logging_filename: None,
             sbp_logging_filepath: None,
             csv_logging: CsvLogging::OFF,
+            csv_loggers: CsvLogger::all(),
+            track_formats: Vec::new(),
+            rinex_logging: false,
             logging_directory,
+            log_path_template: None,
+            sbp_session: None,
+            csv_session: None,
+            annotations: Vec::new(),
+            annotation_markers: Vec::new(),
+            log_sync_interval: Some(LOG_SYNC_INTERVAL),
+            log_schedule: None,
+            log_schedule_status: None,
+            record_corrections: false,
         }
     }
 }
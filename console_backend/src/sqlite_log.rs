@@ -0,0 +1,555 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Rolling SQLite database of the solution, for queries across long sessions.
+//!
+//! Every row is keyed by `gps_time`, seconds since the GPS epoch, taken from the
+//! rover time of the message. Messages that arrive before the GPS week is known
+//! are skipped. Solution modes and tracked signals are stored once in the `modes`
+//! and `signals` tables and referenced by id, e.g.
+//!
+//! ```sql
+//! SELECT p.gps_time, p.h_accuracy FROM position p JOIN modes m ON p.mode = m.id
+//! WHERE p.h_accuracy > 0.5 AND m.name = 'Float RTK';
+//! ```
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use log::error;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OpenFlags};
+use sbp::Sbp;
+
+use crate::constants::{SQLITE_COMMIT_INTERVAL, SQLITE_COMMIT_MESSAGES, SQLITE_PRUNE_INTERVAL};
+use crate::piksi_tools_constants::mode_dict;
+use crate::session_metadata::WeekTime;
+use crate::types::{BaselineNED, Dops, PosLLH, Result, SignalCodes, VelNED};
+use crate::utils::{decisec_to_sec, mm_to_m};
+
+const SECONDS_PER_WEEK: f64 = 604800.0;
+
+/// Tables that are trimmed to the retention period.
+const DATA_TABLES: [&str; 6] = [
+    "position",
+    "velocity",
+    "baseline",
+    "dops",
+    "tracking_cn0",
+    "status",
+];
+
+const SCHEMA: &str = "
+PRAGMA journal_mode = WAL;
+CREATE TABLE IF NOT EXISTS modes (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS signals (
+    id INTEGER PRIMARY KEY,
+    code INTEGER NOT NULL,
+    sat INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    UNIQUE (code, sat)
+);
+CREATE TABLE IF NOT EXISTS position (
+    gps_time REAL NOT NULL,
+    lat REAL,
+    lon REAL,
+    height REAL,
+    h_accuracy REAL,
+    v_accuracy REAL,
+    n_sats INTEGER,
+    mode INTEGER REFERENCES modes (id),
+    flags INTEGER
+);
+CREATE INDEX IF NOT EXISTS position_gps_time ON position (gps_time);
+CREATE TABLE IF NOT EXISTS velocity (
+    gps_time REAL NOT NULL,
+    north REAL,
+    east REAL,
+    down REAL,
+    speed REAL,
+    n_sats INTEGER,
+    flags INTEGER
+);
+CREATE INDEX IF NOT EXISTS velocity_gps_time ON velocity (gps_time);
+CREATE TABLE IF NOT EXISTS baseline (
+    gps_time REAL NOT NULL,
+    north REAL,
+    east REAL,
+    down REAL,
+    distance REAL,
+    h_accuracy REAL,
+    v_accuracy REAL,
+    n_sats INTEGER,
+    mode INTEGER REFERENCES modes (id),
+    flags INTEGER
+);
+CREATE INDEX IF NOT EXISTS baseline_gps_time ON baseline (gps_time);
+CREATE TABLE IF NOT EXISTS dops (
+    gps_time REAL NOT NULL,
+    pdop REAL,
+    gdop REAL,
+    tdop REAL,
+    hdop REAL,
+    vdop REAL,
+    flags INTEGER
+);
+CREATE INDEX IF NOT EXISTS dops_gps_time ON dops (gps_time);
+CREATE TABLE IF NOT EXISTS tracking_cn0 (
+    gps_time REAL NOT NULL,
+    signal INTEGER NOT NULL REFERENCES signals (id),
+    cn0 REAL
+);
+CREATE INDEX IF NOT EXISTS tracking_cn0_gps_time ON tracking_cn0 (gps_time);
+CREATE TABLE IF NOT EXISTS status (
+    gps_time REAL NOT NULL,
+    pos_mode INTEGER REFERENCES modes (id),
+    num_sats INTEGER,
+    age_of_corrections REAL,
+    ins_status INTEGER
+);
+CREATE INDEX IF NOT EXISTS status_gps_time ON status (gps_time);
+";
+
+/// Writes the solution into a SQLite database, dropping rows older than the
+/// retention period as new ones arrive.
+///
+/// Rows are written in batches, each committed in a single transaction. The
+/// open batch is committed when the logger is finished or dropped.
+#[derive(Debug)]
+pub struct SqliteLogger {
+    conn: Connection,
+    signals: HashMap<(u8, u8), i64>,
+    age_of_corrections: Option<f64>,
+    ins_status: Option<u32>,
+    retention: Option<Duration>,
+    last_prune: Instant,
+    commit_interval: Duration,
+    last_commit: Instant,
+    uncommitted: usize,
+}
+
+impl SqliteLogger {
+    /// Open the database at `path`, creating it and its tables if needed.
+    pub fn new(path: impl AsRef<Path>) -> Result<SqliteLogger> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        for (id, name) in mode_dict.iter() {
+            conn.execute(
+                "INSERT OR REPLACE INTO modes (id, name) VALUES (?1, ?2)",
+                params![id, name],
+            )?;
+        }
+        let signals = {
+            let mut stmt = conn.prepare("SELECT code, sat, id FROM signals")?;
+            let rows = stmt.query_map([], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        conn.execute_batch("BEGIN")?;
+        Ok(SqliteLogger {
+            conn,
+            signals,
+            age_of_corrections: None,
+            ins_status: None,
+            retention: None,
+            last_prune: Instant::now(),
+            commit_interval: SQLITE_COMMIT_INTERVAL,
+            last_commit: Instant::now(),
+            uncommitted: 0,
+        })
+    }
+
+    /// Keep rows for this long, measured back from the newest GPS time written.
+    pub fn with_retention(mut self, retention: Option<Duration>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Commit the rows written so far at least once per `interval`, rather than
+    /// every few seconds.
+    pub fn with_sync_interval(mut self, interval: Option<Duration>) -> Self {
+        self.commit_interval = interval.unwrap_or(SQLITE_COMMIT_INTERVAL);
+        self
+    }
+
+    /// Write the rows derived from `msg`.
+    ///
+    /// # Parameters
+    /// - `msg`: The message to write.
+    /// - `gps_time`: Rover GPS time of the message, nothing is written without it.
+    pub fn handle_msg(&mut self, msg: &Sbp, gps_time: Option<WeekTime>) -> Result<()> {
+        match msg {
+            Sbp::MsgAgeCorrections(msg) => {
+                self.age_of_corrections =
+                    (msg.age != 0xFFFF).then(|| decisec_to_sec(msg.age as f64));
+                return Ok(());
+            }
+            Sbp::MsgInsStatus(msg) => {
+                self.ins_status = Some(msg.flags);
+                return Ok(());
+            }
+            _ => {}
+        }
+        let Some(WeekTime { wn, tow }) = gps_time else {
+            return Ok(());
+        };
+        let gps_time = wn as f64 * SECONDS_PER_WEEK + tow;
+        match msg {
+            Sbp::MsgPosLlh(msg) => self.write_pos_llh(gps_time, PosLLH::MsgPosLlh(msg.clone()))?,
+            Sbp::MsgPosLlhDepA(msg) => {
+                self.write_pos_llh(gps_time, PosLLH::MsgPosLlhDepA(msg.clone()))?
+            }
+            Sbp::MsgVelNed(msg) => self.write_vel_ned(gps_time, VelNED::MsgVelNed(msg.clone()))?,
+            Sbp::MsgVelNedDepA(msg) => {
+                self.write_vel_ned(gps_time, VelNED::MsgVelNedDepA(msg.clone()))?
+            }
+            Sbp::MsgBaselineNed(msg) => {
+                self.write_baseline_ned(gps_time, BaselineNED::MsgBaselineNed(msg.clone()))?
+            }
+            Sbp::MsgBaselineNedDepA(msg) => {
+                self.write_baseline_ned(gps_time, BaselineNED::MsgBaselineNedDepA(msg.clone()))?
+            }
+            Sbp::MsgDops(msg) => self.write_dops(gps_time, Dops::MsgDops(msg.clone()))?,
+            Sbp::MsgDopsDepA(msg) => self.write_dops(gps_time, Dops::MsgDopsDepA(msg.clone()))?,
+            Sbp::MsgMeasurementState(msg) => {
+                let states = msg.states.iter();
+                self.write_cn0(gps_time, states.map(|s| (s.mesid.code, s.mesid.sat, s.cn0)))?
+            }
+            Sbp::MsgTrackingState(msg) => {
+                let states = msg.states.iter();
+                self.write_cn0(gps_time, states.map(|s| (s.sid.code, s.sid.sat, s.cn0)))?
+            }
+            _ => return Ok(()),
+        }
+        if self.last_prune.elapsed() >= SQLITE_PRUNE_INTERVAL {
+            self.prune(gps_time)?;
+            self.last_prune = Instant::now();
+        }
+        self.uncommitted += 1;
+        if self.uncommitted >= SQLITE_COMMIT_MESSAGES
+            || self.last_commit.elapsed() >= self.commit_interval
+        {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Commit the open batch and start the next one.
+    fn commit(&mut self) -> Result<()> {
+        self.conn.execute_batch("COMMIT; BEGIN")?;
+        self.uncommitted = 0;
+        self.last_commit = Instant::now();
+        Ok(())
+    }
+
+    fn write_pos_llh(&mut self, gps_time: f64, msg: PosLLH) -> Result<()> {
+        let mode = msg.mode();
+        let fields = msg.fields();
+        self.conn
+            .prepare_cached(
+                "INSERT INTO position (gps_time, lat, lon, height, h_accuracy, v_accuracy, \
+                 n_sats, mode, flags) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?
+            .execute(params![
+                gps_time,
+                fields.lat,
+                fields.lon,
+                fields.height,
+                fields.h_accuracy,
+                fields.v_accuracy,
+                fields.n_sats,
+                mode,
+                fields.flags,
+            ])?;
+        self.conn
+            .prepare_cached(
+                "INSERT INTO status (gps_time, pos_mode, num_sats, age_of_corrections, \
+                 ins_status) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![
+                gps_time,
+                mode,
+                fields.n_sats,
+                self.age_of_corrections,
+                self.ins_status,
+            ])?;
+        Ok(())
+    }
+
+    fn write_vel_ned(&mut self, gps_time: f64, msg: VelNED) -> Result<()> {
+        let fields = msg.fields();
+        let (n, e, d) = (
+            mm_to_m(fields.n as f64),
+            mm_to_m(fields.e as f64),
+            mm_to_m(fields.d as f64),
+        );
+        self.conn
+            .prepare_cached(
+                "INSERT INTO velocity (gps_time, north, east, down, speed, n_sats, flags) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?
+            .execute(params![
+                gps_time,
+                n,
+                e,
+                d,
+                (n * n + e * e + d * d).sqrt(),
+                fields.n_sats,
+                fields.flags,
+            ])?;
+        Ok(())
+    }
+
+    fn write_baseline_ned(&mut self, gps_time: f64, msg: BaselineNED) -> Result<()> {
+        let mode = msg.mode();
+        let fields = msg.fields();
+        let (n, e, d) = (
+            mm_to_m(fields.n as f64),
+            mm_to_m(fields.e as f64),
+            mm_to_m(fields.d as f64),
+        );
+        self.conn
+            .prepare_cached(
+                "INSERT INTO baseline (gps_time, north, east, down, distance, h_accuracy, \
+                 v_accuracy, n_sats, mode, flags) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?
+            .execute(params![
+                gps_time,
+                n,
+                e,
+                d,
+                (n * n + e * e + d * d).sqrt(),
+                mm_to_m(fields.h_accuracy as f64),
+                mm_to_m(fields.v_accuracy as f64),
+                fields.n_sats,
+                mode,
+                fields.flags,
+            ])?;
+        Ok(())
+    }
+
+    fn write_dops(&mut self, gps_time: f64, msg: Dops) -> Result<()> {
+        let fields = msg.fields();
+        self.conn
+            .prepare_cached(
+                "INSERT INTO dops (gps_time, pdop, gdop, tdop, hdop, vdop, flags) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?
+            .execute(params![
+                gps_time,
+                fields.pdop as f64 * 0.01,
+                fields.gdop as f64 * 0.01,
+                fields.tdop as f64 * 0.01,
+                fields.hdop as f64 * 0.01,
+                fields.vdop as f64 * 0.01,
+                fields.flags,
+            ])?;
+        Ok(())
+    }
+
+    /// Write the C/N0 of each tracked signal, given as (code, sat, cn0 * 4).
+    fn write_cn0(
+        &mut self,
+        gps_time: f64,
+        states: impl Iterator<Item = (u8, u8, u8)>,
+    ) -> Result<()> {
+        for (code, sat, cn0) in states.filter(|&(_, _, cn0)| cn0 != 0) {
+            let signal = self.signal_id(code, sat)?;
+            self.conn
+                .prepare_cached(
+                    "INSERT INTO tracking_cn0 (gps_time, signal, cn0) VALUES (?1, ?2, ?3)",
+                )?
+                .execute(params![gps_time, signal, cn0 as f64 / 4.0])?;
+        }
+        Ok(())
+    }
+
+    fn signal_id(&mut self, code: u8, sat: u8) -> Result<i64> {
+        if let Some(id) = self.signals.get(&(code, sat)) {
+            return Ok(*id);
+        }
+        self.conn.execute(
+            "INSERT INTO signals (code, sat, name) VALUES (?1, ?2, ?3)",
+            params![code, sat, SignalCodes::from(code).to_string()],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.signals.insert((code, sat), id);
+        Ok(id)
+    }
+
+    /// Drop rows older than the retention period.
+    fn prune(&mut self, gps_time: f64) -> Result<()> {
+        if let Some(retention) = self.retention {
+            let cutoff = gps_time - retention.as_secs_f64();
+            for table in DATA_TABLES {
+                self.conn.execute(
+                    &format!("DELETE FROM {table} WHERE gps_time < ?1"),
+                    [cutoff],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Commit the rows written so far.
+    pub fn finish(self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+}
+
+impl Drop for SqliteLogger {
+    fn drop(&mut self) {
+        // a failed write may have rolled the batch back already
+        if !self.conn.is_autocommit() {
+            if let Err(e) = self.conn.execute_batch("COMMIT") {
+                error!("Unable to commit SQLite log, error {e}.");
+            }
+        }
+    }
+}
+
+/// Run `query` against the database at `path` and write the result as CSV.
+///
+/// # Returns
+/// The number of rows written.
+pub fn export_query(path: impl AsRef<Path>, query: &str, writer: impl Write) -> Result<usize> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(query)?;
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(stmt.column_names())?;
+    let columns = stmt.column_count();
+    let mut rows = stmt.query([])?;
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let mut record = Vec::with_capacity(columns);
+        for i in 0..columns {
+            record.push(match row.get_ref(i)? {
+                ValueRef::Null => String::new(),
+                ValueRef::Integer(value) => value.to_string(),
+                ValueRef::Real(value) => value.to_string(),
+                ValueRef::Text(value) | ValueRef::Blob(value) => {
+                    String::from_utf8_lossy(value).into_owned()
+                }
+            });
+        }
+        csv.write_record(&record)?;
+        count += 1;
+    }
+    csv.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    use sbp::messages::navigation::MsgPosLlh;
+    use tempfile::TempDir;
+
+    fn pos_llh(h_accuracy: u16, flags: u8) -> Sbp {
+        Sbp::MsgPosLlh(MsgPosLlh {
+            sender_id: Some(1337),
+            tow: 0,
+            lat: 37.77,
+            lon: -122.39,
+            height: 10.0,
+            h_accuracy,
+            v_accuracy: 0,
+            n_sats: 12,
+            flags,
+        })
+    }
+
+    #[test]
+    fn query_and_retention_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("solution.sqlite");
+        let mut logger = SqliteLogger::new(&path)
+            .unwrap()
+            .with_retention(Some(Duration::from_secs(60)));
+        let time = |tow| Some(WeekTime { wn: 2200, tow });
+        logger.handle_msg(&pos_llh(300, 4), time(0.0)).unwrap();
+        logger.handle_msg(&pos_llh(800, 3), time(1.0)).unwrap();
+        logger.handle_msg(&pos_llh(900, 3), None).unwrap();
+        logger.handle_msg(&pos_llh(700, 3), time(120.0)).unwrap();
+        logger.prune(2200.0 * SECONDS_PER_WEEK + 120.0).unwrap();
+        logger.finish().unwrap();
+
+        let mut out = Vec::new();
+        let rows = export_query(
+            &path,
+            "SELECT p.h_accuracy, m.name FROM position p JOIN modes m ON p.mode = m.id \
+             WHERE p.h_accuracy > 0.5 AND m.name = 'Float RTK' ORDER BY p.gps_time",
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(rows, 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "h_accuracy,name\n0.7,Float RTK\n"
+        );
+    }
+
+    #[test]
+    fn commit_on_drop_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("solution.sqlite");
+        let mut logger = SqliteLogger::new(&path).unwrap();
+        let time = |tow| Some(WeekTime { wn: 2200, tow });
+        for tow in 0..3 {
+            logger
+                .handle_msg(&pos_llh(300, 4), time(tow as f64))
+                .unwrap();
+        }
+        drop(logger);
+
+        let rows = export_query(&path, "SELECT * FROM position", io::sink()).unwrap();
+        assert_eq!(rows, 3);
+        // reopening continues the same database
+        let mut logger = SqliteLogger::new(&path).unwrap();
+        logger.handle_msg(&pos_llh(300, 4), time(3.0)).unwrap();
+        drop(logger);
+        let rows = export_query(&path, "SELECT * FROM position", io::sink()).unwrap();
+        assert_eq!(rows, 4);
+    }
+
+    #[test]
+    fn commit_in_batches_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("solution.sqlite");
+        let mut logger = SqliteLogger::new(&path).unwrap();
+        let time = |tow| Some(WeekTime { wn: 2200, tow });
+        for tow in 0..SQLITE_COMMIT_MESSAGES + 1 {
+            logger
+                .handle_msg(&pos_llh(300, 4), time(tow as f64))
+                .unwrap();
+        }
+        // a reader only sees committed rows while the logger is still open
+        let rows = export_query(&path, "SELECT * FROM position", io::sink()).unwrap();
+        assert_eq!(rows, SQLITE_COMMIT_MESSAGES);
+        logger.finish().unwrap();
+    }
+}
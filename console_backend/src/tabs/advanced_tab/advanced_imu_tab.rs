@@ -26,9 +26,13 @@ use sbp::messages::imu::msg_imu_aux::ImuType;
 use crate::client_sender::BoxedClientSender;
 use crate::constants::*;
 use crate::fusion_status_flags::FusionStatusFlags;
+use crate::output::{CsvLogger, ImuRawLog};
 use crate::shared_state::{SharedState, TabName};
 use crate::types::RingBuffer;
-use crate::utils::{euclidean_distance, serialize_capnproto_builder};
+use crate::utils::{
+    date_conv::local_time_logging_string, euclidean_distance, ms_to_sec,
+    serialize_capnproto_builder,
+};
 
 #[derive(Debug)]
 pub struct AdvancedImuTab {
@@ -128,6 +132,25 @@ impl AdvancedImuTab {
     /// # Parameters
     /// - `msg`: MsgImuRaw to extract data from.
     pub fn handle_imu_raw(&mut self, msg: MsgImuRaw) {
+        if self.shared_state.csv_log_open(&CsvLogger::IMU) {
+            // The top two bits of tow are time status flags, tow_f is in units of 1/256 ms.
+            let tow = (msg.tow & 0x3FFF_FFFF) as f64 + msg.tow_f as f64 / 256.0;
+            self.shared_state.write_csv_log(
+                CsvLogger::IMU,
+                &[ImuRawLog {
+                    pc_time: local_time_logging_string(),
+                    tow_s: Some(ms_to_sec(tow)),
+                    acc_x: msg.acc_x,
+                    acc_y: msg.acc_y,
+                    acc_z: msg.acc_z,
+                    gyr_x: msg.gyr_x,
+                    gyr_y: msg.gyr_y,
+                    gyr_z: msg.gyr_z,
+                    temperature_c: Some(self.imu_temp),
+                    imu_conf: self.imu_conf,
+                }],
+            );
+        }
         self.acc_x.push(msg.acc_x as f64);
         self.acc_y.push(msg.acc_y as f64);
         self.acc_z.push(msg.acc_z as f64);
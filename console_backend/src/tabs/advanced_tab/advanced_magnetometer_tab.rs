@@ -23,9 +23,10 @@ use capnp::message::Builder;
 
 use crate::client_sender::BoxedClientSender;
 use crate::constants::{MAGNETOMETER_Y_AXIS_PADDING_MULTIPLIER, NUM_POINTS};
+use crate::output::{CsvLogger, MagRawLog};
 use crate::shared_state::{SharedState, TabName};
 use crate::types::RingBuffer;
-use crate::utils::serialize_capnproto_builder;
+use crate::utils::{date_conv::local_time_logging_string, ms_to_sec, serialize_capnproto_builder};
 use crate::zip;
 
 #[derive(Debug)]
@@ -77,6 +78,20 @@ impl AdvancedMagnetometerTab {
     /// # Parameters
     /// - `msg`: MsgMagRaw to extract data from.
     pub fn handle_mag_raw(&mut self, msg: MsgMagRaw) {
+        if self.shared_state.csv_log_open(&CsvLogger::MAG) {
+            // The top two bits of tow are time status flags, tow_f is in units of 1/256 ms.
+            let tow = (msg.tow & 0x3FFF_FFFF) as f64 + msg.tow_f as f64 / 256.0;
+            self.shared_state.write_csv_log(
+                CsvLogger::MAG,
+                &[MagRawLog {
+                    pc_time: local_time_logging_string(),
+                    tow_s: Some(ms_to_sec(tow)),
+                    mag_x: msg.mag_x,
+                    mag_y: msg.mag_y,
+                    mag_z: msg.mag_z,
+                }],
+            );
+        }
         self.mag_x.push(msg.mag_x as f64);
         self.mag_y.push(msg.mag_y as f64);
         self.mag_z.push(msg.mag_z as f64);
@@ -22,4 +22,5 @@ pub mod advanced_magnetometer_tab;
 pub mod advanced_networking_tab;
 pub mod advanced_spectrum_analyzer_tab;
 pub mod advanced_system_monitor_tab;
+pub mod ntrip_server_tab;
 pub mod ntrip_tab;
@@ -0,0 +1,728 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! NTRIP server uplink, publishing the connected receiver to a caster as a base station.
+//!
+//! The device's observations and base position are forwarded as SBP to a caster
+//! mountpoint with an NTRIP v1 `SOURCE` or v2 `POST` request, reconnecting with a
+//! backoff like the client.
+//!
+//! Only SBP is published. [`crate::rtcm`] converts RTCM to SBP but has no encoder
+//! for the other direction, so rovers that need RTCM from the mountpoint still
+//! need the base station to be configured to output RTCM itself.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use capnp::message::Builder;
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender};
+use log::{info, warn};
+use sbp::messages::{
+    observation::{
+        MsgBasePosEcef, MsgBasePosLlh, MsgGloBiases, MsgObs, MsgObsDepA, MsgObsDepB, MsgObsDepC,
+    },
+    ConcreteMessage,
+};
+use sbp::Frame;
+
+use crate::client_sender::BoxedClientSender;
+use crate::credentials::{redact_url, Secret};
+use crate::tabs::advanced_tab::ntrip_tab::{
+    log_connection_state, parse_status_line, sleep_while_running, Backoff, ConnectionState,
+    NtripVersion,
+};
+use crate::types::ArcBool;
+use crate::utils::serialize_capnproto_builder;
+
+/// Messages a base station sends to rovers, the observations and base position
+/// forwarded by the UDP relay plus the GLONASS biases.
+const BASE_STATION_MSGS: &[u16] = &[
+    MsgObs::MESSAGE_TYPE,
+    MsgObsDepA::MESSAGE_TYPE,
+    MsgObsDepB::MESSAGE_TYPE,
+    MsgObsDepC::MESSAGE_TYPE,
+    MsgBasePosLlh::MESSAGE_TYPE,
+    MsgBasePosEcef::MESSAGE_TYPE,
+    MsgGloBiases::MESSAGE_TYPE,
+];
+
+const NTRIP_DEFAULT_PORT: u16 = 2101;
+const SERVER_AGENT: &str = "NTRIP ntrip-server/1.0";
+/// Frames queued for the caster, further frames are dropped while it can't keep up.
+const UPLOAD_QUEUE_LEN: usize = 256;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REPLY_LEN: usize = 1024;
+/// How often the upload checks for a disconnect while the device is quiet.
+const UPLOAD_POLL_PERIOD: Duration = Duration::from_millis(100);
+/// How long without base station messages before the upload is reported as stalled.
+const STALLED_AFTER: Duration = Duration::from_secs(5);
+/// How often upload stats are sent to the frontend.
+const SERVER_STATUS_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default, Clone)]
+pub struct NtripServerOptions {
+    pub(crate) url: String,
+    pub(crate) username: Option<String>,
+    pub(crate) password: Option<Secret>,
+    pub(crate) version: NtripVersion,
+}
+
+impl NtripServerOptions {
+    pub fn new(url: String, username: String, password: String) -> Self {
+        NtripServerOptions {
+            url,
+            username: Some(username).filter(|s| !s.is_empty()),
+            password: Some(password).filter(|s| !s.is_empty()).map(Secret::new),
+            version: NtripVersion::Auto,
+        }
+    }
+
+    /// Use a protocol version, one of "auto", "v1" or "v2".
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = NtripVersion::from_str(version).unwrap_or_else(|_| {
+            warn!("unknown ntrip version \"{version}\", using auto");
+            NtripVersion::Auto
+        });
+        self
+    }
+}
+
+/// Caster address and mountpoint, parsed from `[http://]host[:port]/mountpoint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    mountpoint: String,
+}
+
+impl FromStr for Endpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(url: &str) -> anyhow::Result<Self> {
+        if url.starts_with("https://") {
+            bail!("the ntrip server doesn't support TLS casters, use an http:// url");
+        }
+        let url = url.strip_prefix("http://").unwrap_or(url);
+        let (address, mountpoint) = url
+            .split_once('/')
+            .filter(|(_, mountpoint)| !mountpoint.is_empty())
+            .ok_or_else(|| anyhow!("missing mountpoint in ntrip server url {url}"))?;
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .with_context(|| format!("invalid ntrip caster port {port}"))?,
+            ),
+            None => (address, NTRIP_DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            bail!("missing caster address in ntrip server url {url}");
+        }
+        Ok(Endpoint {
+            host: host.to_string(),
+            port,
+            mountpoint: mountpoint.to_string(),
+        })
+    }
+}
+
+/// Why a caster refused an upload.
+#[derive(Debug)]
+pub enum UploadError {
+    /// Wrong username or password.
+    Unauthorized,
+    /// The caster has no such mountpoint.
+    NotFound(String),
+    /// Another server is already publishing to the mountpoint.
+    MountpointTaken(String),
+    /// Any other reply, or none at all.
+    Rejected(String),
+}
+
+impl UploadError {
+    /// Errors which won't go away by reconnecting.
+    fn is_fatal(&self) -> bool {
+        matches!(self, UploadError::Unauthorized | UploadError::NotFound(_))
+    }
+
+    /// Replies from a caster which doesn't understand the request's NTRIP version.
+    fn is_version_mismatch(&self) -> bool {
+        match self {
+            UploadError::Rejected(reply) => {
+                matches!(parse_status_line(reply), None | Some(400 | 501 | 505))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::Unauthorized => write!(f, "ntrip caster rejected the credentials"),
+            UploadError::NotFound(mountpoint) => {
+                write!(f, "ntrip caster has no mountpoint {mountpoint}")
+            }
+            UploadError::MountpointTaken(mountpoint) => {
+                write!(f, "ntrip mountpoint {mountpoint} is already in use")
+            }
+            UploadError::Rejected(reply) if reply.is_empty() => {
+                write!(f, "ntrip caster closed the connection without a reply")
+            }
+            UploadError::Rejected(reply) => write!(f, "ntrip caster refused the upload: {reply}"),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// Check the first line the caster replies with.
+fn check_reply(reply: &str, mountpoint: &str) -> Result<(), UploadError> {
+    if reply.starts_with("ERROR") {
+        // v1 casters reply with e.g. `ERROR - Bad Password`
+        return Err(if reply.contains("Bad Password") {
+            UploadError::Unauthorized
+        } else if reply.contains("Mount Point Taken") {
+            UploadError::MountpointTaken(mountpoint.to_string())
+        } else {
+            UploadError::Rejected(reply.to_string())
+        });
+    }
+    if reply == "OK" {
+        return Ok(());
+    }
+    match parse_status_line(reply) {
+        Some(200) => Ok(()),
+        Some(401) => Err(UploadError::Unauthorized),
+        Some(404) => Err(UploadError::NotFound(mountpoint.to_string())),
+        Some(409) => Err(UploadError::MountpointTaken(mountpoint.to_string())),
+        _ => Err(UploadError::Rejected(reply.to_string())),
+    }
+}
+
+/// The request opening an upload, `version` is either v1 or v2.
+fn request(endpoint: &Endpoint, opts: &NtripServerOptions, version: NtripVersion) -> String {
+    let password = opts.password.as_ref().map_or("", Secret::expose);
+    if version == NtripVersion::V1 {
+        // v1 casters only authenticate sources with a password
+        return format!(
+            "SOURCE {password} /{}\r\nSource-Agent: {SERVER_AGENT}\r\n\r\n",
+            endpoint.mountpoint
+        );
+    }
+    let mut request = format!(
+        "POST /{} HTTP/1.1\r\nHost: {}:{}\r\n",
+        endpoint.mountpoint, endpoint.host, endpoint.port
+    );
+    request += &format!("Ntrip-Version: Ntrip/2.0\r\nUser-Agent: {SERVER_AGENT}\r\n");
+    if opts.username.is_some() || opts.password.is_some() {
+        let credentials = format!(
+            "{}:{password}",
+            opts.username.as_deref().unwrap_or_default()
+        );
+        request += &format!("Authorization: Basic {}\r\n", base64::encode(credentials));
+    }
+    request + "Connection: close\r\nTransfer-Encoding: chunked\r\n\r\n"
+}
+
+/// Read the caster's status line.
+fn read_reply(stream: &mut impl Read) -> io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0];
+    while line.len() < MAX_REPLY_LEN && stream.read(&mut byte)? == 1 && byte[0] != b'\n' {
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+/// Connect to the caster and open the upload.
+fn open(
+    endpoint: &Endpoint,
+    opts: &NtripServerOptions,
+    version: NtripVersion,
+) -> anyhow::Result<TcpStream> {
+    let address = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("unable to resolve ntrip caster {}", endpoint.host))?;
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .with_context(|| format!("unable to connect to {}:{}", endpoint.host, endpoint.port))?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    stream.write_all(request(endpoint, opts, version).as_bytes())?;
+    let reply = read_reply(&mut stream).context("no reply from the ntrip caster")?;
+    check_reply(&reply, &endpoint.mountpoint)?;
+    Ok(stream)
+}
+
+/// Upload stats reported to the frontend.
+#[derive(Debug, Default)]
+struct UploadStats {
+    state: ConnectionState,
+    detail: String,
+    bytes: u64,
+    messages: u64,
+    reconnects: u64,
+    /// When the current upload was accepted.
+    connected_at: Option<Instant>,
+    /// Bytes sent and time of the previous report, for the upload rate.
+    last_report: Option<(u64, Instant)>,
+}
+
+/// Reports the upload state and stats to the frontend and the log panel.
+#[derive(Debug, Clone)]
+struct ServerStatus {
+    client_sender: BoxedClientSender,
+    stats: Arc<Mutex<UploadStats>>,
+    /// Frames dropped because the caster couldn't keep up, counted by the uplink.
+    dropped: Arc<AtomicU64>,
+}
+
+impl ServerStatus {
+    fn new(client_sender: BoxedClientSender, dropped: Arc<AtomicU64>) -> Self {
+        ServerStatus {
+            client_sender,
+            stats: Arc::new(Mutex::new(UploadStats::default())),
+            dropped,
+        }
+    }
+
+    fn get(&self) -> ConnectionState {
+        self.stats.lock().unwrap().state
+    }
+
+    fn set(&self, state: ConnectionState, detail: &str) {
+        {
+            let mut stats = self.stats.lock().unwrap();
+            if stats.state == state && stats.detail == detail {
+                return;
+            }
+            stats.state = state;
+            stats.detail = detail.to_string();
+            if state == ConnectionState::Streaming && stats.connected_at.is_none() {
+                stats.connected_at = Some(Instant::now());
+            } else if !matches!(state, ConnectionState::Streaming | ConnectionState::Stalled) {
+                stats.connected_at = None;
+            }
+        }
+        log_connection_state("NTRIP server", state, detail);
+        self.send();
+    }
+
+    fn sent(&self, len: usize) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.bytes += len as u64;
+        stats.messages += 1;
+    }
+
+    fn reconnecting(&self) {
+        self.stats.lock().unwrap().reconnects += 1;
+    }
+
+    fn send(&self) {
+        let mut builder = Builder::new_default();
+        let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
+        let mut status = msg.init_ntrip_server_status();
+        {
+            let mut stats = self.stats.lock().unwrap();
+            let now = Instant::now();
+            let rate = match stats.last_report {
+                Some((bytes, at)) if now > at => {
+                    (stats.bytes - bytes) as f64 / (now - at).as_secs_f64()
+                }
+                _ => 0.0,
+            };
+            stats.last_report = Some((stats.bytes, now));
+            status.set_state(&stats.state.to_string());
+            status.set_detail(&stats.detail);
+            status.set_bytes_sent(stats.bytes);
+            status.set_messages_sent(stats.messages);
+            status.set_dropped(self.dropped.load(Ordering::Relaxed));
+            status.set_rate(rate);
+            status.set_reconnects(stats.reconnects);
+            status.set_uptime(
+                stats
+                    .connected_at
+                    .map_or(0.0, |at| at.elapsed().as_secs_f64()),
+            );
+        }
+        self.client_sender
+            .send_data(serialize_capnproto_builder(builder));
+    }
+}
+
+/// Send `data` as one chunk of a chunked v2 upload.
+fn write_chunk(stream: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let mut chunk = format!("{:X}\r\n", data.len()).into_bytes();
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(b"\r\n");
+    stream.write_all(&chunk)
+}
+
+/// Upload the base station messages from `rx` until stopped or the connection fails.
+fn upload(
+    endpoint: &Endpoint,
+    opts: &NtripServerOptions,
+    version: NtripVersion,
+    rx: &Receiver<Vec<u8>>,
+    is_running: &ArcBool,
+    status: &ServerStatus,
+) -> anyhow::Result<()> {
+    let protocol = if version == NtripVersion::V1 {
+        "v1"
+    } else {
+        "v2"
+    };
+    status.set(
+        ConnectionState::Connecting,
+        &format!("{} (NTRIP {protocol})", redact_url(&opts.url)),
+    );
+    let mut stream = open(endpoint, opts, version)?;
+    // messages queued while disconnected are stale
+    rx.try_iter().for_each(drop);
+    status.set(ConnectionState::Streaming, &endpoint.mountpoint);
+    let mut last_data = Instant::now();
+    while is_running.get() {
+        match rx.recv_timeout(UPLOAD_POLL_PERIOD) {
+            Ok(frame) => {
+                if version == NtripVersion::V1 {
+                    stream.write_all(&frame)
+                } else {
+                    write_chunk(&mut stream, &frame)
+                }
+                .context("ntrip caster closed the upload")?;
+                status.sent(frame.len());
+                last_data = Instant::now();
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_data.elapsed() >= STALLED_AFTER {
+            status.set(
+                ConnectionState::Stalled,
+                "no base station messages from the device",
+            );
+        } else if status.get() == ConnectionState::Stalled {
+            status.set(ConnectionState::Streaming, &endpoint.mountpoint);
+        }
+    }
+    if version != NtripVersion::V1 {
+        // end the chunked body
+        let _ = stream.write_all(b"0\r\n\r\n");
+    }
+    Ok(())
+}
+
+/// Upload with the configured version, auto tries v2 and falls back to v1.
+fn publish(
+    endpoint: &Endpoint,
+    opts: &NtripServerOptions,
+    rx: &Receiver<Vec<u8>>,
+    is_running: &ArcBool,
+    status: &ServerStatus,
+) -> anyhow::Result<()> {
+    if opts.version != NtripVersion::Auto {
+        return upload(endpoint, opts, opts.version, rx, is_running, status);
+    }
+    match upload(endpoint, opts, NtripVersion::V2, rx, is_running, status) {
+        Err(e)
+            if e.downcast_ref::<UploadError>()
+                .is_some_and(UploadError::is_version_mismatch) =>
+        {
+            info!("ntrip caster refused a v2 upload ({e}), retrying with v1");
+            upload(endpoint, opts, NtripVersion::V1, rx, is_running, status)
+        }
+        result => result,
+    }
+}
+
+/// Keep publishing, reconnecting with a backoff until stopped or rejected.
+fn run(
+    opts: NtripServerOptions,
+    rx: Receiver<Vec<u8>>,
+    is_running: ArcBool,
+    status: &ServerStatus,
+) -> anyhow::Result<()> {
+    let endpoint: Endpoint = opts.url.parse()?;
+    let mut backoff = Backoff::default();
+    loop {
+        let result = publish(&endpoint, &opts, &rx, &is_running, status);
+        if !is_running.get() {
+            return Ok(());
+        }
+        let e = result
+            .err()
+            .unwrap_or_else(|| anyhow!("device stream ended"));
+        if e.downcast_ref::<UploadError>()
+            .is_some_and(UploadError::is_fatal)
+        {
+            status.set(ConnectionState::Failed, &e.to_string());
+            return Ok(());
+        }
+        if matches!(
+            status.get(),
+            ConnectionState::Streaming | ConnectionState::Stalled
+        ) {
+            backoff.reset();
+        }
+        let delay = backoff.next_delay();
+        status.set(
+            ConnectionState::Backoff,
+            &format!("{e}, reconnecting in {} s", delay.as_secs()),
+        );
+        if !sleep_while_running(&is_running, delay) {
+            return Ok(());
+        }
+        status.reconnecting();
+    }
+}
+
+/// Hands the device's base station messages to the upload, without taking the
+/// shared state lock for every frame.
+#[derive(Debug, Clone)]
+pub struct NtripServerUplink {
+    tx: Sender<Vec<u8>>,
+    is_running: ArcBool,
+    dropped: Arc<AtomicU64>,
+}
+
+impl NtripServerUplink {
+    /// Queue `frame` for the caster if it is a base station message.
+    pub fn handle_frame(&self, frame: &Frame) {
+        if !self.is_running.get()
+            || !frame
+                .msg_type()
+                .is_some_and(|msg_type| BASE_STATION_MSGS.contains(&msg_type))
+        {
+            return;
+        }
+        if self.tx.try_send(frame.as_bytes().to_vec()).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NtripServerState {
+    connected_thd: Option<JoinHandle<()>>,
+    is_running: ArcBool,
+    uplink: NtripServerUplink,
+    rx: Receiver<Vec<u8>>,
+}
+
+impl Default for NtripServerState {
+    fn default() -> Self {
+        let (tx, rx) = channel::bounded(UPLOAD_QUEUE_LEN);
+        let is_running = ArcBool::new();
+        NtripServerState {
+            connected_thd: None,
+            is_running: is_running.clone(),
+            uplink: NtripServerUplink {
+                tx,
+                is_running,
+                dropped: Arc::new(AtomicU64::new(0)),
+            },
+            rx,
+        }
+    }
+}
+
+impl NtripServerState {
+    pub fn connect(&mut self, options: NtripServerOptions, client_sender: BoxedClientSender) {
+        if self.is_running.get() {
+            // is already publishing
+            return;
+        }
+        // collect a previous upload which failed on its own
+        self.disconnect();
+        self.uplink.dropped.store(0, Ordering::Relaxed);
+        let rx = self.rx.clone();
+        let status = ServerStatus::new(client_sender, self.uplink.dropped.clone());
+        self.is_running.set(true);
+        let thd = thread::spawn({
+            let running = self.is_running.clone();
+            move || {
+                let status_thd = thread::spawn({
+                    let running = running.clone();
+                    let status = status.clone();
+                    move || {
+                        while sleep_while_running(&running, SERVER_STATUS_PERIOD) {
+                            status.send();
+                        }
+                    }
+                });
+                if let Err(e) = run(options, rx, running.clone(), &status) {
+                    status.set(ConnectionState::Failed, &e.to_string());
+                } else if status.get() != ConnectionState::Failed {
+                    status.set(ConnectionState::Disconnected, "");
+                }
+                running.set(false);
+                let _ = status_thd.join();
+            }
+        });
+        self.connected_thd = Some(thd);
+    }
+
+    pub fn disconnect(&mut self) {
+        self.is_running.set(false);
+        if let Some(thd) = self.connected_thd.take() {
+            let _ = thd.join();
+        }
+    }
+
+    /// Handle for queueing the device's frames, see [`NtripServerUplink::handle_frame`].
+    pub fn uplink(&self) -> NtripServerUplink {
+        self.uplink.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_sender::TestSender;
+    use std::net::TcpListener;
+
+    #[test]
+    fn parse_endpoints() {
+        assert_eq!(
+            Endpoint::from_str("http://caster.example.com:2102/BASE1").unwrap(),
+            Endpoint {
+                host: "caster.example.com".to_string(),
+                port: 2102,
+                mountpoint: "BASE1".to_string(),
+            }
+        );
+        assert_eq!(
+            Endpoint::from_str("caster.example.com/BASE1").unwrap().port,
+            NTRIP_DEFAULT_PORT
+        );
+        assert!(Endpoint::from_str("caster.example.com:2101").is_err());
+        assert!(Endpoint::from_str("caster.example.com:2101/").is_err());
+        assert!(Endpoint::from_str("caster.example.com:port/BASE1").is_err());
+        assert!(Endpoint::from_str("https://caster.example.com/BASE1").is_err());
+    }
+
+    #[test]
+    fn check_replies() {
+        assert!(check_reply("ICY 200 OK", "BASE1").is_ok());
+        assert!(check_reply("OK", "BASE1").is_ok());
+        assert!(check_reply("HTTP/1.1 200 OK", "BASE1").is_ok());
+        let bad_password = check_reply("ERROR - Bad Password", "BASE1").unwrap_err();
+        assert!(bad_password.is_fatal());
+        assert!(matches!(
+            check_reply("ERROR - Mount Point Taken or Invalid", "BASE1"),
+            Err(UploadError::MountpointTaken(_))
+        ));
+        assert!(matches!(
+            check_reply("HTTP/1.1 409 Conflict", "BASE1"),
+            Err(e) if !e.is_fatal()
+        ));
+        assert!(check_reply("HTTP/1.1 404 Not Found", "BASE1")
+            .unwrap_err()
+            .is_fatal());
+        assert!(check_reply("", "BASE1").unwrap_err().is_version_mismatch());
+        assert!(
+            check_reply("HTTP/1.1 505 HTTP Version Not Supported", "BASE1")
+                .unwrap_err()
+                .is_version_mismatch()
+        );
+        assert!(!check_reply("HTTP/1.1 503 Service Unavailable", "BASE1")
+            .unwrap_err()
+            .is_version_mismatch());
+    }
+
+    #[test]
+    fn requests() {
+        let endpoint = Endpoint::from_str("caster.example.com/BASE1").unwrap();
+        let opts = NtripServerOptions::new(
+            "caster.example.com/BASE1".to_string(),
+            "user".to_string(),
+            "secret".to_string(),
+        );
+        assert_eq!(
+            request(&endpoint, &opts, NtripVersion::V1),
+            "SOURCE secret /BASE1\r\nSource-Agent: NTRIP ntrip-server/1.0\r\n\r\n"
+        );
+        let v2 = request(&endpoint, &opts, NtripVersion::V2);
+        assert!(v2.starts_with("POST /BASE1 HTTP/1.1\r\nHost: caster.example.com:2101\r\n"));
+        assert!(v2.contains("Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+        assert!(v2.ends_with("Transfer-Encoding: chunked\r\n\r\n"));
+    }
+
+    #[test]
+    fn upload_to_caster() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/BASE1", listener.local_addr().unwrap());
+        let caster = thread::spawn(move || {
+            let (mut client, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut byte = [0];
+                client.read_exact(&mut byte).unwrap();
+                request.push(byte[0]);
+            }
+            client.write_all(b"ICY 200 OK\r\n").unwrap();
+            let mut data = [0; 4];
+            client.read_exact(&mut data).unwrap();
+            (String::from_utf8(request).unwrap(), data)
+        });
+
+        let opts =
+            NtripServerOptions::new(url, String::new(), "secret".to_string()).with_version("v1");
+        let endpoint = Endpoint::from_str(&opts.url).unwrap();
+        let (tx, rx) = channel::bounded(UPLOAD_QUEUE_LEN);
+        let is_running = ArcBool::new_with(true);
+        let status = ServerStatus::new(TestSender::boxed(), Default::default());
+        let uploader = thread::spawn({
+            let is_running = is_running.clone();
+            let status = status.clone();
+            move || {
+                upload(
+                    &endpoint,
+                    &opts,
+                    NtripVersion::V1,
+                    &rx,
+                    &is_running,
+                    &status,
+                )
+            }
+        });
+        while status.get() != ConnectionState::Streaming {
+            thread::sleep(UPLOAD_POLL_PERIOD);
+        }
+        tx.send(vec![0x55, 0x4a, 0x00, 0x01]).unwrap();
+        let (request, data) = caster.join().unwrap();
+        assert!(request.starts_with("SOURCE secret /BASE1\r\n"));
+        assert_eq!(data, [0x55, 0x4a, 0x00, 0x01]);
+
+        is_running.set(false);
+        uploader.join().unwrap().unwrap();
+        let stats = status.stats.lock().unwrap();
+        assert_eq!((stats.bytes, stats.messages), (4, 1));
+    }
+}
@@ -1,22 +1,178 @@
+use crate::client_sender::BoxedClientSender;
+use crate::correction_recorder::CorrectionRecorder;
+use crate::correction_source::{CorrectionSource, Corrections};
+use crate::correction_stats::CorrectionStats;
+use crate::credentials::{redact_url, Secret};
+use crate::nmea::{Gga, NmeaGenerator};
 use crate::ntrip_output::MessageConverter;
+use crate::ntrip_sourcetable::{
+    distance_km, mountpoint_name, mountpoint_url, send_sourcetable, Sourcetable,
+};
 use crate::status_bar::Heartbeat;
-use crate::types::{ArcBool, MsgSender, PosLLH};
-use anyhow::Context;
-use chrono::{DateTime, Utc};
+use crate::types::{ArcBool, GnssModes, MsgSender};
+use crate::utils::serialize_capnproto_builder;
+use anyhow::{anyhow, bail, Context};
+use capnp::message::Builder;
+use chrono::Utc;
 use crossbeam::channel;
 use crossbeam::channel::Sender;
 use crossbeam::channel::TryRecvError;
 use curl::easy::{Easy, HttpVersion, List, ReadError};
-use log::error;
+use log::{error, info, warn};
+use sbp::Sbp;
 use std::cell::RefCell;
 use std::io::Write;
 use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::thread;
 use std::thread::JoinHandle;
-use std::time::{Duration, SystemTime};
-use std::{iter, thread};
-use strum_macros::EnumString;
+use std::time::{Duration, Instant};
+use strum_macros::{Display, EnumString};
+
+/// How far the rover moves before the nearest mountpoint is picked again in auto mode.
+const AUTO_MOUNTPOINT_SWITCH_DISTANCE_KM: f64 = 10.0;
+/// How often the rover position is checked against the auto mode mountpoint.
+const AUTO_MOUNTPOINT_CHECK_PERIOD: Duration = Duration::from_secs(10);
+/// How often the auto mode waits for a position or checks for a disconnect.
+const AUTO_MOUNTPOINT_POLL_PERIOD: Duration = Duration::from_secs(1);
+
+/// How long without corrections before the stream is reported as stalled.
+pub(crate) const STALLED_AFTER: Duration = Duration::from_secs(5);
+/// How long without corrections before the connection is dropped and retried.
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// How often a reconnect backoff checks for a disconnect.
+const RECONNECT_POLL_PERIOD: Duration = Duration::from_millis(100);
+
+/// How often the GGA is rebuilt while waiting for the first fix.
+const GGA_RETRY_PERIOD: Duration = Duration::from_secs(1);
+
+/// How often correction stream analytics are sent to the frontend.
+const CORRECTIONS_STATUS_PERIOD: Duration = Duration::from_secs(1);
+
+/// Mountpoint replies that carry no corrections, checked before the body is streamed.
+const SOURCETABLE_PREFIX: &[u8] = b"SOURCETABLE";
+const SOURCETABLE_CONTENT_TYPE: &str = "gnss/sourcetable";
+
+/// State of an NTRIP client or server connection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Streaming,
+    /// Connected but no corrections received for `STALLED_AFTER`.
+    Stalled,
+    /// Waiting to reconnect after the connection dropped.
+    Backoff,
+    /// Gave up, the caster rejected the request.
+    Failed,
+}
+
+/// Reports connection state changes of a correction source to the frontend and the
+/// log panel.
+#[derive(Clone)]
+pub(crate) struct ConnectionStatus {
+    name: &'static str,
+    client_sender: BoxedClientSender,
+    state: Arc<Mutex<(ConnectionState, String)>>,
+}
+
+impl ConnectionStatus {
+    pub(crate) fn new(name: &'static str, client_sender: BoxedClientSender) -> Self {
+        ConnectionStatus {
+            name,
+            client_sender,
+            state: Arc::new(Mutex::new(Default::default())),
+        }
+    }
+
+    pub(crate) fn get(&self) -> ConnectionState {
+        self.state.lock().unwrap().0
+    }
+
+    pub(crate) fn set(&self, state: ConnectionState, detail: &str) {
+        {
+            let mut current = self.state.lock().unwrap();
+            if current.0 == state && current.1 == detail {
+                return;
+            }
+            *current = (state, detail.to_string());
+        }
+        log_connection_state(self.name, state, detail);
+        let mut builder = Builder::new_default();
+        let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
+        let mut status = msg.init_ntrip_connection_state();
+        status.set_state(&state.to_string());
+        status.set_detail(detail);
+        self.client_sender
+            .send_data(serialize_capnproto_builder(builder));
+    }
+}
+
+/// Log a connection state change of `name` to the log panel.
+pub(crate) fn log_connection_state(name: &str, state: ConnectionState, detail: &str) {
+    let line = if detail.is_empty() {
+        format!("{name} {state}")
+    } else {
+        format!("{name} {state}: {detail}")
+    };
+    match state {
+        ConnectionState::Failed => error!("{line}"),
+        ConnectionState::Stalled | ConnectionState::Backoff => warn!("{line}"),
+        _ => info!("{line}"),
+    }
+}
+
+/// Exponential delay between reconnect attempts.
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    next: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            next: RECONNECT_BACKOFF_MIN,
+        }
+    }
+}
+
+impl Backoff {
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(RECONNECT_BACKOFF_MAX);
+        delay
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = Backoff::default();
+    }
+}
+
+/// Sleep for `duration`, returning early with false if the connection is stopped.
+pub(crate) fn sleep_while_running(is_running: &ArcBool, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while is_running.get() {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep(RECONNECT_POLL_PERIOD.min(deadline - now));
+    }
+    false
+}
+
+/// Stops a helper thread when the transfer it watches ends.
+struct StopOnDrop(ArcBool);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct NtripState {
@@ -24,13 +180,78 @@ pub struct NtripState {
     pub(crate) options: NtripOptions,
     pub(crate) is_running: ArcBool,
     last_data: Arc<Mutex<LastData>>,
+    /// Corrections given on the command line, started on every device connection.
+    headless: Option<Corrections>,
+    /// Saves the corrections next to the SBP log.
+    pub(crate) recorder: CorrectionRecorder,
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+/// The rover solution, reported to the caster in GGA sentences.
+#[derive(Debug)]
 struct LastData {
-    lat: f64,
-    lon: f64,
-    alt: f64,
+    solution: NmeaGenerator,
+    /// Latest solution with a fix.
+    last_fix: Option<Gga>,
+}
+
+impl Default for LastData {
+    fn default() -> Self {
+        LastData {
+            solution: NmeaGenerator::new(Vec::new()),
+            last_fix: None,
+        }
+    }
+}
+
+impl LastData {
+    fn handle_msg(&mut self, msg: &Sbp) {
+        self.solution.handle_msg(msg);
+        if let Some(gga) = self.solution.last_gga() {
+            if gga.mode != GnssModes::NoFix {
+                self.last_fix = Some(gga);
+            }
+        }
+    }
+
+    /// Latitude and longitude, once the receiver has had a fix.
+    fn position(&self) -> Option<(f64, f64)> {
+        self.last_fix.map(|gga| (gga.lat, gga.lon))
+    }
+
+    /// GGA sentence for the caster in dynamic mode, `None` before the first fix.
+    ///
+    /// While the receiver has no fix the last fixed position is repeated as a
+    /// standalone fix so VRS casters keep generating corrections for it.
+    fn dynamic_gga(&self) -> Option<Gga> {
+        let latest = self.solution.last_gga()?;
+        if latest.mode != GnssModes::NoFix {
+            return Some(latest);
+        }
+        self.last_fix.map(|fix| Gga {
+            time: latest.time,
+            mode: GnssModes::Spp,
+            n_sats: latest.n_sats,
+            hdop: None,
+            age_corrections: None,
+            ..fix
+        })
+    }
+
+    /// GGA sentence for a static position, reported as a standalone fix at the
+    /// receiver's UTC time, or the system clock when no receiver time is known.
+    fn static_gga(&self, lat: f64, lon: f64, alt: f64) -> Gga {
+        Gga {
+            time: self.solution.utc_time().unwrap_or_else(Utc::now),
+            mode: GnssModes::Spp,
+            lat,
+            lon,
+            height: alt,
+            orthometric_height: None,
+            n_sats: self.solution.last_gga().map_or(0, |gga| gga.n_sats),
+            hdop: None,
+            age_corrections: None,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -50,15 +271,161 @@ pub enum OutputType {
     SBP,
 }
 
+/// NTRIP protocol version used to request a mountpoint.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum NtripVersion {
+    /// Request with v2 and retry with v1 if the caster doesn't understand it.
+    #[default]
+    Auto,
+    /// HTTP/1.0 request, GGA sent in the body, `ICY 200 OK` responses.
+    V1,
+    /// HTTP/1.1 request with the `Ntrip-Version` and `Ntrip-GGA` headers.
+    V2,
+}
+
+/// Why a caster didn't stream a mountpoint.
+#[derive(Debug)]
+enum CasterError {
+    Unauthorized,
+    NotFound(String),
+    Sourcetable(String),
+    Status(String),
+    NoResponse(curl::Error),
+}
+
+impl CasterError {
+    /// Whether retrying can't help until the options are changed.
+    fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            CasterError::Unauthorized | CasterError::NotFound(_) | CasterError::Sourcetable(_)
+        )
+    }
+
+    /// Whether a v1 request might succeed where this v2 request failed.
+    fn is_version_mismatch(&self) -> bool {
+        match self {
+            CasterError::Status(_) => true,
+            CasterError::NoResponse(e) => {
+                !(e.is_couldnt_resolve_host()
+                    || e.is_couldnt_connect()
+                    || e.is_operation_timedout()
+                    || e.is_ssl_connect_error()
+                    || e.is_aborted_by_callback())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for CasterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CasterError::Unauthorized => write!(
+                f,
+                "ntrip caster rejected the username or password (401 Unauthorized)"
+            ),
+            CasterError::NotFound(mountpoint) => {
+                write!(
+                    f,
+                    "ntrip mountpoint \"{mountpoint}\" not found (404 Not Found)"
+                )
+            }
+            CasterError::Sourcetable(mountpoint) => write!(
+                f,
+                "ntrip caster sent its sourcetable instead of mountpoint \"{mountpoint}\", \
+                 check the mountpoint name or browse the sourcetable"
+            ),
+            CasterError::Status(status) => write!(f, "ntrip caster refused the request: {status}"),
+            CasterError::NoResponse(e) => write!(f, "ntrip caster did not respond: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for CasterError {}
+
+/// The caster's reply, recorded until corrections start flowing.
+#[derive(Debug, Default)]
+struct Response {
+    code: Option<u32>,
+    status: String,
+    sourcetable: bool,
+    streaming: bool,
+    error: Option<CasterError>,
+}
+
+impl Response {
+    fn header(&mut self, line: &str) {
+        if let Some(code) = parse_status_line(line) {
+            // a new status line (e.g. after 100 Continue) starts a new reply
+            *self = Response {
+                code: Some(code),
+                status: line.to_string(),
+                ..Default::default()
+            };
+        } else if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-type") {
+                self.sourcetable = value.trim().starts_with(SOURCETABLE_CONTENT_TYPE);
+            }
+        }
+    }
+
+    /// Strip a status line that reached the body, e.g. an `ICY 200 OK` sent without headers.
+    fn strip_status_line<'a>(&mut self, body: &'a [u8]) -> &'a [u8] {
+        if self.code.is_some() {
+            return body;
+        }
+        let Some(end) = body.windows(2).position(|w| w == b"\r\n") else {
+            return body;
+        };
+        let line = String::from_utf8_lossy(&body[..end]);
+        if parse_status_line(&line).is_none() {
+            return body;
+        }
+        self.header(&line);
+        let rest = &body[end + 2..];
+        // a bare status line may still be followed by the blank line ending the headers
+        rest.strip_prefix(b"\r\n").unwrap_or(rest)
+    }
+
+    /// Check the reply against the first bytes of the body.
+    fn check(&self, body: &[u8], mountpoint: &str) -> Result<(), CasterError> {
+        match self.code {
+            Some(401) => return Err(CasterError::Unauthorized),
+            Some(404) => return Err(CasterError::NotFound(mountpoint.to_string())),
+            Some(code) if code >= 300 => return Err(CasterError::Status(self.status.clone())),
+            _ => {}
+        }
+        if self.sourcetable || body.starts_with(SOURCETABLE_PREFIX) {
+            return Err(CasterError::Sourcetable(mountpoint.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Status code of an `HTTP/1.x 200 OK` or NTRIP v1 `ICY 200 OK` status line.
+pub(crate) fn parse_status_line(line: &str) -> Option<u32> {
+    let mut parts = line.split_whitespace();
+    let protocol = parts.next()?;
+    if !(protocol.starts_with("HTTP/") || protocol == "ICY") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct NtripOptions {
     pub(crate) url: String,
     pub(crate) username: Option<String>,
-    pub(crate) password: Option<String>,
+    pub(crate) password: Option<Secret>,
     pub(crate) nmea_period: u64,
     pub(crate) pos_mode: PositionMode,
     pub(crate) client_id: String,
     pub(crate) output_type: Option<OutputType>,
+    /// Connect to the nearest mountpoint from the caster's sourcetable instead of `url`.
+    pub(crate) auto_mountpoint: bool,
+    pub(crate) version: NtripVersion,
 }
 
 impl NtripOptions {
@@ -69,13 +436,14 @@ impl NtripOptions {
         pos_mode: Option<(f64, f64, f64)>,
         nmea_period: u64,
         output_type: &str,
+        auto_mountpoint: bool,
     ) -> Self {
         let pos_mode = pos_mode
             .map(|(lat, lon, alt)| PositionMode::Static { lat, lon, alt })
             .unwrap_or(PositionMode::Dynamic);
 
         let username = Some(username).filter(|s| !s.is_empty());
-        let password = Some(password).filter(|s| !s.is_empty());
+        let password = Some(password).filter(|s| !s.is_empty()).map(Secret::new);
         NtripOptions {
             url,
             username,
@@ -84,119 +452,90 @@ impl NtripOptions {
             nmea_period,
             client_id: "00000000-0000-0000-0000-000000000000".to_string(),
             output_type: OutputType::from_str(output_type).ok(),
+            auto_mountpoint,
+            version: NtripVersion::Auto,
         }
     }
-}
-
-#[derive(Debug, Clone, Copy, serde::Deserialize)]
-#[serde(rename_all = "lowercase")]
-enum Message {
-    Gga { lat: f64, lon: f64, height: f64 },
-}
 
-fn build_gga(opts: &NtripOptions, last_data: &Arc<Mutex<LastData>>) -> Command {
-    let (lat, lon, height) = match opts.pos_mode {
-        PositionMode::Dynamic => {
-            let guard = last_data.lock().unwrap();
-            (guard.lat, guard.lon, guard.alt)
-        }
-        PositionMode::Static { lat, lon, alt } => (lat, lon, alt),
-    };
-    Command {
-        epoch: None,
-        after: 0,
-        crc: None,
-        message: Message::Gga { lat, lon, height },
-    }
-}
-
-#[derive(Debug, Clone, Copy, serde::Deserialize)]
-struct Command {
-    #[serde(default = "default_after")]
-    after: u64,
-    epoch: Option<u64>,
-    crc: Option<u8>,
-    #[serde(flatten)]
-    message: Message,
-}
-
-const fn default_after() -> u64 {
-    10
-}
-
-impl Command {
-    fn to_bytes(self) -> Vec<u8> {
-        self.to_string().into_bytes()
-    }
-}
-
-impl std::fmt::Display for Command {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let now = self.epoch.map_or_else(SystemTime::now, |e| {
-            SystemTime::UNIX_EPOCH + Duration::from_secs(e)
+    /// Use a protocol version, one of "auto", "v1" or "v2".
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = NtripVersion::from_str(version).unwrap_or_else(|_| {
+            warn!("unknown ntrip version \"{version}\", using auto");
+            NtripVersion::Auto
         });
-        let message = self.message.format(now.into());
-        let checksum = self.crc.unwrap_or_else(|| checksum(message.as_bytes()));
-        write!(f, "{message}*{checksum:X}")
+        self
     }
-}
 
-fn checksum(buf: &[u8]) -> u8 {
-    let mut sum = 0;
-    for c in &buf[1..] {
-        sum ^= c;
+    /// Position used to select a mountpoint, `None` until a dynamic position is known.
+    fn position(&self, last_data: &Arc<Mutex<LastData>>) -> Option<(f64, f64)> {
+        match self.pos_mode {
+            PositionMode::Dynamic => last_data.lock().unwrap().position(),
+            PositionMode::Static { lat, lon, .. } => Some((lat, lon)),
+        }
     }
-    sum
 }
 
-impl Message {
-    fn format(&self, time: DateTime<Utc>) -> String {
-        match *self {
-            Message::Gga { lat, lon, height } => {
-                let time = time.format("%H%M%S.00");
-
-                let latn = ((lat * 1e8).round() / 1e8).abs();
-                let lonn = ((lon * 1e8).round() / 1e8).abs();
-
-                let lat_deg = latn as u16;
-                let lon_deg = lonn as u16;
-
-                let lat_min = (latn - (lat_deg as f64)) * 60.0;
-                let lon_min = (lonn - (lon_deg as f64)) * 60.0;
-
-                let lat_dir = if lat < 0.0 { 'S' } else { 'N' };
-                let lon_dir = if lon < 0.0 { 'W' } else { 'E' };
-
-                format!(
-                    "$GPGGA,{},{:02}{:010.7},{},{:03}{:010.7},{},4,12,1.3,{:.2},M,0.0,M,1.7,0078",
-                    time, lat_deg, lat_min, lat_dir, lon_deg, lon_min, lon_dir, height
-                )
-            }
-        }
-    }
+/// GGA sentence reporting the rover position to the caster, `None` until it is known.
+fn build_gga(opts: &NtripOptions, last_data: &Arc<Mutex<LastData>>) -> Option<String> {
+    let last_data = last_data.lock().unwrap();
+    let gga = match opts.pos_mode {
+        PositionMode::Dynamic => last_data.dynamic_gga()?,
+        PositionMode::Static { lat, lon, alt } => last_data.static_gga(lat, lon, alt),
+    };
+    Some(gga.sentence())
 }
 
-fn get_commands(
+/// Send the GGA every `nmea_period` seconds while `active`. With no period v1 casters
+/// still get it once, as they only read the position from the request body.
+fn send_gga(
     opt: NtripOptions,
+    version: NtripVersion,
     last_data: Arc<Mutex<LastData>>,
-) -> anyhow::Result<Box<dyn Iterator<Item = Command> + Send>> {
-    if opt.nmea_period == 0 {
-        return Ok(Box::new(iter::empty()));
-    }
-    let first = build_gga(&opt, &last_data);
-    let rest = iter::repeat(Command {
-        after: opt.nmea_period,
-        ..first
-    });
-    Ok(Box::new(iter::once(first).chain(rest)))
+    active: ArcBool,
+    tx: Sender<Vec<u8>>,
+) {
+    if opt.nmea_period == 0 && version != NtripVersion::V1 {
+        return;
+    }
+    while active.get() {
+        let wait = match build_gga(&opt, &last_data) {
+            Some(gga) => {
+                if tx.send(gga.into_bytes()).is_err() || opt.nmea_period == 0 {
+                    return;
+                }
+                Duration::from_secs(opt.nmea_period)
+            }
+            // nothing to report before the first fix
+            None => GGA_RETRY_PERIOD,
+        };
+        thread::park_timeout(wait);
+    }
 }
 
-#[derive(Default)]
 struct Progress {
     ul_tot: f64,
     dl_tot: f64,
     ul_tot_old: f64,
     dl_tot_old: f64,
+    /// When corrections last arrived, or the transfer started.
+    last_data: Instant,
+    streaming: bool,
+    /// The transfer was dropped after `STALL_TIMEOUT` without corrections.
+    timed_out: bool,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            ul_tot: 0.0,
+            dl_tot: 0.0,
+            ul_tot_old: 0.0,
+            dl_tot_old: 0.0,
+            last_data: Instant::now(),
+            streaming: false,
+            timed_out: false,
+        }
+    }
 }
 
 impl Progress {
@@ -215,21 +554,84 @@ impl Progress {
     }
 }
 
+/// Stream a mountpoint with the configured protocol version, falling back to v1 in auto mode.
+fn stream(
+    heartbeat: Heartbeat,
+    opt: NtripOptions,
+    last_data: Arc<Mutex<LastData>>,
+    is_running: ArcBool,
+    rtcm_tx: Sender<Vec<u8>>,
+    status: &ConnectionStatus,
+) -> anyhow::Result<()> {
+    if opt.version != NtripVersion::Auto {
+        let version = opt.version;
+        return main(
+            heartbeat, opt, version, last_data, is_running, rtcm_tx, status,
+        );
+    }
+    let result = main(
+        heartbeat.clone(),
+        opt.clone(),
+        NtripVersion::V2,
+        last_data.clone(),
+        is_running.clone(),
+        rtcm_tx.clone(),
+        status,
+    );
+    match result {
+        Err(e)
+            if is_running.get()
+                && e.downcast_ref::<CasterError>()
+                    .is_some_and(CasterError::is_version_mismatch) =>
+        {
+            info!("{e}, retrying with NTRIP v1");
+            main(
+                heartbeat,
+                opt,
+                NtripVersion::V1,
+                last_data,
+                is_running,
+                rtcm_tx,
+                status,
+            )
+        }
+        result => result,
+    }
+}
+
 fn main(
     mut heartbeat: Heartbeat,
     opt: NtripOptions,
+    version: NtripVersion,
     last_data: Arc<Mutex<LastData>>,
     is_running: ArcBool,
     rtcm_tx: Sender<Vec<u8>>,
+    status: &ConnectionStatus,
 ) -> anyhow::Result<()> {
+    let protocol = if version == NtripVersion::V1 {
+        "v1"
+    } else {
+        "v2"
+    };
+    status.set(
+        ConnectionState::Connecting,
+        &format!("{} (NTRIP {protocol})", redact_url(&opt.url)),
+    );
     let mut curl = Easy::new();
     let mut headers = List::new();
     headers.append("Transfer-Encoding:")?;
-    headers.append("Ntrip-Version: Ntrip/2.0")?;
     headers.append(&format!("X-SwiftNav-Client-Id: {}", opt.client_id))?;
-
-    let gga = build_gga(&opt, &last_data);
-    headers.append(&format!("Ntrip-GGA: {gga}"))?;
+    if version == NtripVersion::V1 {
+        // v1 casters expect a plain HTTP/1.0 GET with the GGA following the request
+        curl.http_version(HttpVersion::V10)?;
+    } else {
+        headers.append("Ntrip-Version: Ntrip/2.0")?;
+        if let Some(gga) = build_gga(&opt, &last_data) {
+            headers.append(&format!("Ntrip-GGA: {gga}"))?;
+        }
+        // keep HTTP/1.1 so TLS casters don't negotiate HTTP/2 through ALPN
+        curl.http_version(HttpVersion::V11)?;
+    }
 
     curl.http_headers(headers)?;
     curl.useragent("NTRIP ntrip-client/1.0")?;
@@ -237,33 +639,49 @@ fn main(
     curl.progress(true)?;
     curl.put(true)?;
     curl.custom_request("GET")?;
-    curl.http_version(HttpVersion::Any)?;
     curl.http_09_allowed(true)?;
+    // NTRIP v1 casters reply with `ICY 200 OK`, have curl parse it as a status line
+    let mut aliases = List::new();
+    aliases.append("ICY 200 OK")?;
+    curl.http_200_aliases(aliases)?;
 
     if let Some(username) = &opt.username {
         curl.username(username)?;
     }
 
     if let Some(password) = &opt.password {
-        curl.password(password)?;
+        curl.password(password.expose())?;
     }
+    let mountpoint = mountpoint_name(&opt.url);
+    let response = RefCell::new(Response::default());
     let (tx, rx) = channel::bounded::<Vec<u8>>(1);
     let transfer = Rc::new(RefCell::new(curl.transfer()));
 
     let progress = Arc::new(Mutex::new(Progress::default()));
-    let progress_clone = progress.clone();
-    let progress_thd = thread::spawn({
+    let active = ArcBool::new_with(true);
+    let _stop_progress = StopOnDrop(active.clone());
+    thread::spawn({
         let running = is_running.clone();
+        let active = active.clone();
+        let progress = progress.clone();
+        let status = status.clone();
         move || loop {
             {
-                if !running.get() {
-                    return false;
+                if !running.get() || !active.get() {
+                    return;
                 }
             }
             {
-                let mut progress = progress_clone.lock().unwrap();
+                let mut progress = progress.lock().unwrap();
                 heartbeat.set_ntrip_ul(progress.tick_ul());
                 heartbeat.set_ntrip_dl(progress.tick_dl());
+                if progress.streaming {
+                    if progress.last_data.elapsed() >= STALLED_AFTER {
+                        status.set(ConnectionState::Stalled, "no corrections received");
+                    } else if status.get() == ConnectionState::Stalled {
+                        status.set(ConnectionState::Streaming, "");
+                    }
+                }
             }
             thread::park_timeout(Duration::from_secs(1))
         }
@@ -272,6 +690,7 @@ fn main(
     transfer.borrow_mut().progress_function({
         let rx = &rx;
         let transfer = Rc::clone(&transfer);
+        let progress = progress.clone();
         move |_dlnow, dltot, _ulnow, ultot| {
             {
                 if !is_running.get() {
@@ -282,6 +701,10 @@ fn main(
                 let mut progress = progress.lock().unwrap();
                 progress.ul_tot = ultot;
                 progress.dl_tot = dltot;
+                if progress.last_data.elapsed() >= STALL_TIMEOUT {
+                    progress.timed_out = true;
+                    return false;
+                }
             }
             if !rx.is_empty() {
                 if let Err(e) = transfer.borrow().unpause_read() {
@@ -293,8 +716,33 @@ fn main(
         }
     })?;
 
-    transfer.borrow_mut().write_function(move |data| {
-        if let Err(e) = rtcm_tx.send(data.to_owned()) {
+    transfer.borrow_mut().header_function(|header| {
+        response
+            .borrow_mut()
+            .header(String::from_utf8_lossy(header).trim());
+        true
+    })?;
+    transfer.borrow_mut().write_function(|data| {
+        let mut response = response.borrow_mut();
+        let body = if response.streaming {
+            data
+        } else {
+            response.strip_status_line(data)
+        };
+        if body.is_empty() {
+            return Ok(data.len());
+        }
+        if !response.streaming {
+            if let Err(e) = response.check(body, mountpoint) {
+                response.error = Some(e);
+                return Ok(0);
+            }
+            response.streaming = true;
+            progress.lock().unwrap().streaming = true;
+            status.set(ConnectionState::Streaming, mountpoint);
+        }
+        progress.lock().unwrap().last_data = Instant::now();
+        if let Err(e) = rtcm_tx.send(body.to_owned()) {
             error!("ntrip write error: {e}");
             return Ok(0);
         }
@@ -304,7 +752,8 @@ fn main(
         let mut bytes = match rx.try_recv() {
             Ok(bytes) => bytes,
             Err(TryRecvError::Empty) => return Err(ReadError::Pause),
-            Err(TryRecvError::Disconnected) => return Err(ReadError::Abort),
+            // no more GGA to send, keep receiving corrections
+            Err(TryRecvError::Disconnected) => return Err(ReadError::Pause),
         };
         bytes.extend_from_slice(b"\r\n");
         if let Err(e) = data.write_all(&bytes) {
@@ -314,65 +763,270 @@ fn main(
         Ok(bytes.len())
     })?;
 
-    let commands = get_commands(opt.clone(), last_data)?;
-    let handle = thread::spawn(move || {
-        for cmd in commands {
-            if cmd.after > 0 {
-                // need to unpark thread (?)
-                thread::park_timeout(Duration::from_secs(cmd.after));
-            }
-            if tx.send(cmd.to_bytes()).is_err() {
-                break;
-            }
-        }
-        Ok(())
+    thread::spawn({
+        let opt = opt.clone();
+        let active = active.clone();
+        move || send_gga(opt, version, last_data, active, tx)
     });
 
-    transfer
-        .borrow()
-        .perform()
-        .context("ntrip curl perform errored")?;
-    if !handle.is_finished() {
-        Ok(())
-    } else {
-        // an error stopped the thread early
-        progress_thd.join().expect("could not join progress thread");
-        handle.join().expect("could not join on handle thread")
+    let performed = transfer.borrow().perform();
+    let mut response = response.borrow_mut();
+    if let Some(e) = response.error.take() {
+        return Err(e.into());
+    }
+    if progress.lock().unwrap().timed_out {
+        bail!(
+            "no ntrip corrections received for {} s",
+            STALL_TIMEOUT.as_secs()
+        );
+    }
+    match performed {
+        Err(e) if response.streaming => Err(e).context("ntrip stream interrupted"),
+        Err(e) => Err(CasterError::NoResponse(e).into()),
+        Ok(()) if !response.streaming => {
+            response.check(&[], mountpoint)?;
+            bail!("ntrip caster closed the connection without sending corrections")
+        }
+        Ok(()) => bail!("ntrip caster closed the stream"),
+    }
+}
+
+/// Keep the client connected, reconnecting with a backoff until stopped or rejected.
+fn run(
+    heartbeat: Heartbeat,
+    opt: NtripOptions,
+    last_data: Arc<Mutex<LastData>>,
+    is_running: ArcBool,
+    rtcm_tx: Sender<Vec<u8>>,
+    client_sender: BoxedClientSender,
+    status: &ConnectionStatus,
+) -> anyhow::Result<()> {
+    let mut backoff = Backoff::default();
+    loop {
+        let result = if opt.auto_mountpoint {
+            auto_main(
+                heartbeat.clone(),
+                opt.clone(),
+                last_data.clone(),
+                is_running.clone(),
+                rtcm_tx.clone(),
+                client_sender.clone(),
+                status,
+            )
+        } else {
+            stream(
+                heartbeat.clone(),
+                opt.clone(),
+                last_data.clone(),
+                is_running.clone(),
+                rtcm_tx.clone(),
+                status,
+            )
+        };
+        if !is_running.get() {
+            return Ok(());
+        }
+        let e = result
+            .err()
+            .unwrap_or_else(|| anyhow!("ntrip stream ended"));
+        if e.downcast_ref::<CasterError>()
+            .is_some_and(CasterError::is_fatal)
+        {
+            status.set(ConnectionState::Failed, &e.to_string());
+            return Ok(());
+        }
+        if matches!(
+            status.get(),
+            ConnectionState::Streaming | ConnectionState::Stalled
+        ) {
+            backoff.reset();
+        }
+        let delay = backoff.next_delay();
+        status.set(
+            ConnectionState::Backoff,
+            &format!("{e}, reconnecting in {} s", delay.as_secs()),
+        );
+        if !sleep_while_running(&is_running, delay) {
+            return Ok(());
+        }
     }
 }
 
+/// Stream from the nearest mountpoint, switching mountpoints as the rover moves.
+fn auto_main(
+    heartbeat: Heartbeat,
+    opt: NtripOptions,
+    last_data: Arc<Mutex<LastData>>,
+    is_running: ArcBool,
+    rtcm_tx: Sender<Vec<u8>>,
+    client_sender: BoxedClientSender,
+    status: &ConnectionStatus,
+) -> anyhow::Result<()> {
+    let password = opt.password.as_ref().map(Secret::expose);
+    let table = Sourcetable::fetch(&opt.url, opt.username.as_deref(), password).map_err(|e| {
+        send_sourcetable(
+            &client_sender,
+            &Sourcetable::default(),
+            None,
+            None,
+            Some(&e.to_string()),
+        );
+        e
+    })?;
+    while is_running.get() {
+        let Some((lat, lon)) = opt.position(&last_data) else {
+            thread::sleep(AUTO_MOUNTPOINT_POLL_PERIOD);
+            continue;
+        };
+        let mountpoint = table
+            .nearest(lat, lon)
+            .context("no RTCM 3 mountpoint with a location in the sourcetable")?
+            .clone();
+        info!(
+            "Using NTRIP mountpoint {}, {:.1} km away",
+            mountpoint.name,
+            mountpoint.distance_km(lat, lon)
+        );
+        send_sourcetable(
+            &client_sender,
+            &table,
+            Some((lat, lon)),
+            Some(&mountpoint.name),
+            None,
+        );
+
+        let session = ArcBool::new_with(true);
+        let watcher = thread::spawn({
+            let (opt, table, last_data) = (opt.clone(), table.clone(), last_data.clone());
+            let (is_running, session) = (is_running.clone(), session.clone());
+            let name = mountpoint.name.clone();
+            move || {
+                let mut since_check = Duration::ZERO;
+                while session.get() {
+                    thread::sleep(AUTO_MOUNTPOINT_POLL_PERIOD);
+                    if !is_running.get() {
+                        session.set(false);
+                        return false;
+                    }
+                    since_check += AUTO_MOUNTPOINT_POLL_PERIOD;
+                    if since_check < AUTO_MOUNTPOINT_CHECK_PERIOD {
+                        continue;
+                    }
+                    since_check = Duration::ZERO;
+                    let Some(pos) = opt.position(&last_data) else {
+                        continue;
+                    };
+                    let moved = distance_km(pos, (lat, lon)) > AUTO_MOUNTPOINT_SWITCH_DISTANCE_KM;
+                    let nearest = table.nearest(pos.0, pos.1).map(|m| m.name.as_str());
+                    if moved && nearest.is_some_and(|nearest| nearest != name) {
+                        session.set(false);
+                        return true;
+                    }
+                }
+                false
+            }
+        });
+        let session_opt = NtripOptions {
+            url: mountpoint_url(&opt.url, &mountpoint.name),
+            ..opt.clone()
+        };
+        let result = stream(
+            heartbeat.clone(),
+            session_opt,
+            last_data.clone(),
+            session.clone(),
+            rtcm_tx.clone(),
+            status,
+        );
+        session.set(false);
+        let switched = watcher.join().unwrap_or(false);
+        if !switched {
+            return result;
+        }
+        info!("Rover moved, switching NTRIP mountpoint");
+    }
+    Ok(())
+}
+
 impl NtripState {
     pub fn connect(
         &mut self,
         msg_sender: MsgSender,
-        mut heartbeat: Heartbeat,
+        heartbeat: Heartbeat,
         options: NtripOptions,
+        client_sender: BoxedClientSender,
+    ) {
+        self.connect_source(
+            msg_sender,
+            heartbeat,
+            Corrections::ntrip(options),
+            client_sender,
+        );
+    }
+
+    /// Write corrections from any source to the device.
+    pub fn connect_source(
+        &mut self,
+        msg_sender: MsgSender,
+        mut heartbeat: Heartbeat,
+        corrections: Corrections,
+        client_sender: BoxedClientSender,
     ) {
         if self.connected_thd.is_some() && heartbeat.get_ntrip_connected() {
             // is already connected
             return;
         }
 
-        self.options = options.clone();
+        if let CorrectionSource::Ntrip(options) = &corrections.source {
+            self.options = options.clone();
+        }
         let last_data = self.last_data.clone();
+        let recorder = self.recorder.clone();
         self.is_running.set(true);
         heartbeat.set_ntrip_connected(true);
         let thd = thread::spawn({
             let running = self.is_running.clone();
             move || {
                 let (conv_tx, conv_rx) = channel::unbounded::<Vec<u8>>();
-                let output_type = options.output_type.clone().unwrap_or(OutputType::RTCM);
-                let mut output_converter = MessageConverter::new(conv_rx, output_type);
-                if let Err(e) = output_converter.start(msg_sender).and(main(
-                    heartbeat.clone(),
-                    options,
-                    last_data,
-                    running.clone(),
-                    conv_tx,
-                )) {
-                    error!("{e}");
+                let stats = Arc::new(Mutex::new(CorrectionStats::default()));
+                let mut output_converter = MessageConverter::new(conv_rx, corrections.output)
+                    .with_input(corrections.input)
+                    .with_stats(stats.clone())
+                    .with_recorder(recorder);
+                let stats_thd = thread::spawn({
+                    let running = running.clone();
+                    let client_sender = client_sender.clone();
+                    move || {
+                        while sleep_while_running(&running, CORRECTIONS_STATUS_PERIOD) {
+                            stats.lock().unwrap().send(&client_sender);
+                        }
+                    }
+                });
+                let status =
+                    ConnectionStatus::new(corrections.source.name(), client_sender.clone());
+                let input = corrections.input;
+                let started = output_converter.start(msg_sender);
+                let result = started.and_then(|_| match corrections.source {
+                    CorrectionSource::Ntrip(options) => run(
+                        heartbeat.clone(),
+                        options,
+                        last_data,
+                        running.clone(),
+                        conv_tx,
+                        client_sender,
+                        &status,
+                    ),
+                    CorrectionSource::Local(source) => {
+                        source.run(input, &running, conv_tx, &status)
+                    }
+                });
+                if let Err(e) = result {
+                    status.set(ConnectionState::Failed, &e.to_string());
+                } else if status.get() != ConnectionState::Failed {
+                    status.set(ConnectionState::Disconnected, "");
                 }
                 running.set(false);
+                let _ = stats_thd.join();
                 heartbeat.set_ntrip_connected(false);
             }
         });
@@ -387,13 +1041,280 @@ impl NtripState {
         }
     }
 
-    /// Update data required for dynamic mode.
-    /// Currently used for position data, potentially epoch in the future.
-    pub fn set_last_data(&mut self, val: PosLLH) {
-        let fields = val.fields();
-        let mut guard = self.last_data.lock().unwrap();
-        guard.lat = fields.lat;
-        guard.lon = fields.lon;
-        guard.alt = fields.height;
+    /// Start `corrections` each time a device connects, without the frontend.
+    pub fn set_headless(&mut self, corrections: Option<Corrections>) {
+        self.headless = corrections;
+    }
+
+    /// Restart the headless client, writing corrections to the newly connected device.
+    pub fn device_connected(
+        &mut self,
+        msg_sender: MsgSender,
+        heartbeat: Heartbeat,
+        client_sender: BoxedClientSender,
+    ) {
+        let Some(corrections) = self.headless.clone() else {
+            return;
+        };
+        self.disconnect();
+        self.connect_source(msg_sender, heartbeat, corrections, client_sender);
+    }
+
+    /// Stop the headless client until a device connects again.
+    pub fn device_disconnected(&mut self) {
+        if self.headless.is_some() {
+            self.disconnect();
+        }
+    }
+
+    /// Latest rover position, used to sort sourcetable mountpoints by distance.
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.last_data.lock().unwrap().position()
+    }
+
+    /// Handle for updating the solution, see [`NtripSolution::handle_msg`].
+    pub fn solution(&self) -> NtripSolution {
+        NtripSolution(self.last_data.clone())
+    }
+}
+
+/// Hands the rover solution to the GGA sentences, without taking the shared
+/// state lock for every message.
+#[derive(Debug, Clone)]
+pub struct NtripSolution(Arc<Mutex<LastData>>);
+
+impl NtripSolution {
+    /// Update the solution reported to the caster in dynamic mode.
+    pub fn handle_msg(&self, msg: &Sbp) {
+        self.0.lock().unwrap().handle_msg(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_sender::TestSender;
+    use sbp::messages::navigation::{MsgPosLlh, MsgUtcTime};
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(NtripVersion::from_str("auto").unwrap(), NtripVersion::Auto);
+        assert_eq!(NtripVersion::from_str("v1").unwrap(), NtripVersion::V1);
+        assert_eq!(NtripVersion::from_str("V2").unwrap(), NtripVersion::V2);
+        assert!(NtripVersion::from_str("v3").is_err());
+    }
+
+    #[test]
+    fn reconnect_backoff() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<u64> = std::iter::repeat_with(|| backoff.next_delay().as_secs())
+            .take(8)
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), RECONNECT_BACKOFF_MIN);
+    }
+
+    #[test]
+    fn fatal_caster_errors() {
+        assert!(CasterError::Unauthorized.is_fatal());
+        assert!(CasterError::NotFound("MP".to_string()).is_fatal());
+        assert!(!CasterError::Status("HTTP/1.1 503 Service Unavailable".to_string()).is_fatal());
+    }
+
+    #[test]
+    fn parse_status_lines() {
+        assert_eq!(parse_status_line("HTTP/1.1 200 OK"), Some(200));
+        assert_eq!(parse_status_line("HTTP/1.0 401 Unauthorized"), Some(401));
+        assert_eq!(parse_status_line("ICY 200 OK"), Some(200));
+        assert_eq!(parse_status_line("Content-Type: gnss/data"), None);
+        assert_eq!(parse_status_line("SOURCETABLE 200 OK"), None);
+    }
+
+    fn response(headers: &[&str]) -> Response {
+        let mut response = Response::default();
+        for header in headers {
+            response.header(header);
+        }
+        response
+    }
+
+    #[test]
+    fn check_responses() {
+        let ok = response(&["HTTP/1.1 200 OK", "Content-Type: gnss/data"]);
+        assert!(ok.check(b"\xd3\x00\x13", "MP").is_ok());
+        assert!(response(&["ICY 200 OK"]).check(b"\xd3", "MP").is_ok());
+        // v1 casters reply to an unknown mountpoint with a bare sourcetable
+        assert!(matches!(
+            Response::default().check(b"SOURCETABLE 200 OK\r\n", "MP"),
+            Err(CasterError::Sourcetable(mp)) if mp == "MP"
+        ));
+        assert!(matches!(
+            response(&["HTTP/1.1 200 OK", "Content-Type: gnss/sourcetable"]).check(b"STR;", "MP"),
+            Err(CasterError::Sourcetable(_))
+        ));
+        assert!(matches!(
+            response(&["HTTP/1.1 100 Continue", "HTTP/1.1 401 Unauthorized"]).check(&[], "MP"),
+            Err(CasterError::Unauthorized)
+        ));
+        assert!(matches!(
+            response(&["HTTP/1.1 404 Not Found"]).check(&[], "MP"),
+            Err(CasterError::NotFound(mp)) if mp == "MP"
+        ));
+        let refused = response(&["HTTP/1.1 505 HTTP Version Not Supported"]).check(&[], "MP");
+        assert!(matches!(&refused, Err(e) if e.is_version_mismatch()));
+        assert!(!CasterError::Unauthorized.is_version_mismatch());
+    }
+
+    #[test]
+    fn strip_status_lines() {
+        let mut response = Response::default();
+        assert_eq!(
+            response.strip_status_line(b"ICY 200 OK\r\n\xd3\x00"),
+            b"\xd3\x00"
+        );
+        assert_eq!(response.code, Some(200));
+        // once the status is known the body is passed through untouched
+        assert_eq!(
+            response.strip_status_line(b"ICY 200 OK\r\n"),
+            b"ICY 200 OK\r\n"
+        );
+        let mut response = Response::default();
+        assert_eq!(response.strip_status_line(b"ICY 200 OK\r\n\r\n"), b"");
+        let mut response = Response::default();
+        assert_eq!(response.strip_status_line(b"\xd3\x00\r\n"), b"\xd3\x00\r\n");
+        assert_eq!(response.code, None);
+    }
+
+    const CORRECTIONS: &[u8] = b"\xd3\x00\x13\x3e\xd7\xd3\x02\x02\x98\x0e\xde\xef\x34\xb4\xbd";
+
+    /// Serve one NTRIP v1 client, answering its request with `status` and `CORRECTIONS`.
+    fn fake_v1_caster(status: &'static [u8]) -> (String, JoinHandle<String>) {
+        use std::io::Read;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/MP", listener.local_addr().unwrap());
+        let caster = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                assert!(n > 0, "client closed before sending its request");
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(status).unwrap();
+            stream.write_all(CORRECTIONS).unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (url, caster)
+    }
+
+    /// Corrections forwarded from a fake v1 caster replying with `status`.
+    fn receive_from_v1_caster(status: &'static [u8]) -> Vec<u8> {
+        let (url, caster) = fake_v1_caster(status);
+        let (rtcm_tx, rtcm_rx) = channel::unbounded();
+        let opt = NtripOptions {
+            url,
+            ..Default::default()
+        };
+        let result = main(
+            Heartbeat::new(),
+            opt,
+            NtripVersion::V1,
+            Arc::new(Mutex::new(LastData::default())),
+            ArcBool::new_with(true),
+            rtcm_tx,
+            &ConnectionStatus::new("NTRIP", TestSender::boxed()),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "ntrip caster closed the stream"
+        );
+        assert!(caster.join().unwrap().starts_with("GET /MP HTTP/1.0\r\n"));
+        rtcm_rx.try_iter().flatten().collect()
+    }
+
+    #[test]
+    fn v1_caster_status_line() {
+        assert_eq!(receive_from_v1_caster(b"ICY 200 OK\r\n\r\n"), CORRECTIONS);
+        assert_eq!(
+            receive_from_v1_caster(b"ICY 200 OK\r\nServer: NTRIP Caster\r\n\r\n"),
+            CORRECTIONS
+        );
+    }
+
+    fn solution(lat: f64, lon: f64, n_sats: u8, flags: u8) -> [Sbp; 2] {
+        [
+            Sbp::MsgUtcTime(MsgUtcTime {
+                sender_id: Some(1337),
+                flags: 1,
+                tow: 1000,
+                year: 2022,
+                month: 3,
+                day: 4,
+                hours: 12,
+                minutes: 34,
+                seconds: 56,
+                ns: 500_000_000,
+            }),
+            Sbp::MsgPosLlh(MsgPosLlh {
+                sender_id: Some(1337),
+                tow: 1000,
+                lat,
+                lon,
+                height: 10.0,
+                h_accuracy: 0,
+                v_accuracy: 0,
+                n_sats,
+                flags,
+            }),
+        ]
+    }
+
+    #[test]
+    fn uplink_gga() {
+        let dynamic = NtripOptions::default();
+        let fixed = NtripOptions {
+            pos_mode: PositionMode::Static {
+                lat: 37.5,
+                lon: -122.25,
+                alt: 10.0,
+            },
+            ..Default::default()
+        };
+        let last_data = Arc::new(Mutex::new(LastData::default()));
+        assert!(build_gga(&dynamic, &last_data).is_none());
+        let gga = build_gga(&fixed, &last_data).unwrap();
+        assert!(gga.contains(",3730.0000000,N,12215.0000000,W,1,00,,10.000,M,0.000,M,,*"));
+
+        for msg in solution(37.5, -122.25, 14, 4) {
+            last_data.lock().unwrap().handle_msg(&msg);
+        }
+        assert!(build_gga(&dynamic, &last_data)
+            .unwrap()
+            .starts_with("$GNGGA,123456.50,3730.0000000,N,12215.0000000,W,4,14,"));
+        assert!(build_gga(&fixed, &last_data)
+            .unwrap()
+            .starts_with("$GNGGA,123456.50,3730.0000000,N,12215.0000000,W,1,14,"));
+
+        // losing the fix repeats the last fixed position
+        for msg in solution(0.0, 0.0, 3, 0) {
+            last_data.lock().unwrap().handle_msg(&msg);
+        }
+        assert!(build_gga(&dynamic, &last_data).unwrap().starts_with(
+            "$GNGGA,123456.50,3730.0000000,N,12215.0000000,W,1,03,,10.000,M,0.000,M,,*"
+        ));
+        assert_eq!(last_data.lock().unwrap().position(), Some((37.5, -122.25)));
+    }
+
+    #[test]
+    fn solution_handle_test() {
+        let state = NtripState::default();
+        let handle = state.solution();
+        assert_eq!(state.position(), None);
+        for msg in solution(37.5, -122.25, 14, 4) {
+            handle.handle_msg(&msg);
+        }
+        assert_eq!(state.position(), Some((37.5, -122.25)));
     }
 }
@@ -26,19 +26,29 @@ use sbp::{Frame, Sbp};
 use crate::client_sender::BoxedClientSender;
 use crate::common_constants::SbpLogging;
 use crate::constants::{
-    BASELINE_TIME_STR_FILEPATH, POS_LLH_TIME_STR_FILEPATH, SBP_FILEPATH, SBP_JSON_FILEPATH,
-    VEL_TIME_STR_FILEPATH,
+    CSV_METADATA_TIME_STR_FILEPATH, RINEX_NAV_TIME_STR_FILEPATH, RINEX_OBS_TIME_STR_FILEPATH,
+    SBP_FILEPATH, SBP_JSON_FILEPATH,
 };
-use crate::output::{CsvLogging, SbpFileLogger};
+use crate::log_path::LogPathContext;
+use crate::output::{CsvLogger, CsvLogging, SbpFileLogger};
+use crate::session_metadata::{sidecar_path, Annotation, SessionMetadata};
 use crate::shared_state::{create_directory, SharedState};
-use crate::utils::{refresh_log_recording_size, refresh_loggingbar, start_recording};
+use crate::tabs::advanced_tab::ntrip_tab::NtripSolution;
+use crate::tabs::settings_tab::settings_snapshot;
+use crate::track_export::TrackFormat;
+use crate::utils::{refresh_log_recording_size, refresh_loggingbar, start_recording, OkOrLog};
 
 pub struct MainTab {
     logging_directory: PathBuf,
     last_csv_logging: CsvLogging,
+    last_csv_loggers: Vec<CsvLogger>,
+    last_track_formats: Vec<TrackFormat>,
+    last_rinex_logging: bool,
     last_sbp_logging: bool,
     last_sbp_logging_format: SbpLogging,
     sbp_logger: Option<SbpFileLogger>,
+    settings_snapshot_pending: bool,
+    ntrip_solution: NtripSolution,
     client_sender: BoxedClientSender,
     shared_state: SharedState,
 }
@@ -49,14 +59,14 @@ impl MainTab {
         // reopen an existing log if we disconnected
         let sbp_logger = shared_state
             .sbp_logging_filepath()
-            .and_then(|path| sbp_logging_format.new_logger(path).ok());
+            .and_then(|path| sbp_logging_format.new_logger(path).ok())
+            .map(|logger| {
+                logger
+                    .with_sync_interval(shared_state.log_sync_interval())
+                    .with_json_options(&shared_state.sbp_json_options())
+            });
         let last_sbp_logging = sbp_logger.is_some() && shared_state.sbp_logging();
-        let csv_logging_live = shared_state
-            .lock()
-            .solution_tab
-            .velocity_tab
-            .log_file
-            .is_some();
+        let csv_logging_live = shared_state.csv_logs_open();
 
         let last_csv_logging =
             if !csv_logging_live && matches!(shared_state.csv_logging(), CsvLogging::ON) {
@@ -68,53 +78,84 @@ impl MainTab {
         MainTab {
             logging_directory: shared_state.logging_directory(),
             last_csv_logging,
+            last_csv_loggers: shared_state.csv_loggers(),
+            last_track_formats: shared_state.track_formats(),
+            last_rinex_logging: shared_state.rinex_logging(),
             last_sbp_logging,
             last_sbp_logging_format: sbp_logging_format,
             sbp_logger,
+            settings_snapshot_pending: false,
+            ntrip_solution: shared_state.ntrip_solution(),
             client_sender,
             shared_state,
         }
     }
 
-    /// Initialize Baseline and Solution Position and Velocity Loggers.
+    /// Initialize the CSV Loggers selected in the logging bar.
     ///
     /// # Generates:
     /// - `Solution Position Log`
     /// - `Solution Velocity Log`
     /// - `Baseline Log` // TODO(john-michaelburke@) [CPP-1337] Implement Baseline log.
-    ///
-    /// # Parameters:
-    /// - `logging`: The type of sbp logging to use; otherwise, None.
+    /// - `DOPs`, `Tracking C/N0`, `Observation`, `IMU`, `Magnetometer`, `INS` and
+    ///   `Protection Level` Logs
+    /// - `Annotations` Log of the markers added while logging
+    /// - `GPX`, `KML` and `GeoJSON` tracks of the position solution, when selected
+    /// - `RINEX` observation and navigation files, when selected
+    /// - `Session Metadata` sidecar describing the above logs.
     pub fn init_csv_logging(&mut self) {
         let local_t = Local::now();
+        let log_dir = match self.shared_state.log_path_template() {
+            Some(template) => template.log_dir(&self.log_path_context()),
+            None => self.logging_directory.clone(),
+        };
 
-        if let Err(e) = create_directory(self.logging_directory.clone()) {
+        if let Err(e) = create_directory(log_dir.clone()) {
             error!("Issue creating directory {}.", e);
         }
-        let vel_log_file = local_t.format(VEL_TIME_STR_FILEPATH).to_string();
-        let vel_log_file = self.logging_directory.join(vel_log_file);
-        self.shared_state.start_vel_log(&vel_log_file);
-
-        let pos_log_file = local_t.format(POS_LLH_TIME_STR_FILEPATH).to_string();
-        let pos_log_file = self.logging_directory.join(pos_log_file);
-        self.shared_state.start_pos_log(&pos_log_file);
+        let mut log_files = vec![];
+        for logger in self.shared_state.csv_loggers() {
+            let log_file = local_t.format(logger.filename_format()).to_string();
+            let log_file = log_dir.join(log_file);
+            match logger {
+                CsvLogger::POSITION => self.shared_state.start_pos_log(&log_file),
+                CsvLogger::VELOCITY => self.shared_state.start_vel_log(&log_file),
+                CsvLogger::BASELINE => self.shared_state.start_baseline_log(&log_file),
+                _ => self.shared_state.start_csv_log(logger, &log_file),
+            }
+            log_files.push(log_file);
+        }
+        for format in self.shared_state.track_formats() {
+            let log_file = local_t.format(format.filename_format()).to_string();
+            let log_file = log_dir.join(log_file);
+            self.shared_state.start_track_log(format, &log_file);
+            log_files.push(log_file);
+        }
+        if self.shared_state.rinex_logging() {
+            let obs_file = local_t.format(RINEX_OBS_TIME_STR_FILEPATH).to_string();
+            let obs_file = log_dir.join(obs_file);
+            let nav_file = local_t.format(RINEX_NAV_TIME_STR_FILEPATH).to_string();
+            let nav_file = log_dir.join(nav_file);
+            self.shared_state.start_rinex_log(&obs_file, &nav_file);
+            log_files.push(obs_file);
+            log_files.push(nav_file);
+        }
 
-        let baseline_log_file = local_t.format(BASELINE_TIME_STR_FILEPATH).to_string();
-        let baseline_log_file = self.logging_directory.join(baseline_log_file);
-        self.shared_state.start_baseline_log(&baseline_log_file);
+        let metadata_file = local_t.format(CSV_METADATA_TIME_STR_FILEPATH).to_string();
+        let metadata_file = log_dir.join(metadata_file);
+        let session = SessionMetadata::start(metadata_file, log_files, &self.shared_state)
+            .ok_or_log(|e| error!("Issue writing session metadata, {}", e));
+        self.shared_state.start_csv_session(session);
 
         self.shared_state.set_csv_logging(CsvLogging::ON);
     }
 
     pub fn end_csv_logging(&mut self) -> crate::types::Result<()> {
         self.shared_state.set_csv_logging(CsvLogging::OFF);
-        self.shared_state.end_vel_log()?;
-        self.shared_state.end_pos_log()?;
-        self.shared_state.end_baseline_log()?;
-        Ok(())
+        self.shared_state.close_csv_logs()
     }
 
-    /// Initialize SBP Logger.
+    /// Initialize SBP Logger and its session metadata sidecar.
     ///
     /// # Parameters:
     /// - `logging`: The type of sbp logging to use; otherwise, None.
@@ -126,13 +167,28 @@ impl MainTab {
             }
         }
 
-        self.sbp_logger = logging.new_logger(filepath.clone()).ok();
+        self.sbp_logger = logging.new_logger(filepath.clone()).ok().map(|logger| {
+            logger
+                .with_sync_interval(self.shared_state.log_sync_interval())
+                .with_json_options(&self.shared_state.sbp_json_options())
+        });
 
         if self.sbp_logger.is_some() {
             self.shared_state.set_sbp_logging(true);
             self.shared_state
                 .set_sbp_logging_filepath(Some(filepath.clone()));
             self.shared_state.set_settings_refresh(true);
+            self.write_settings_snapshot();
+            let session = SessionMetadata::start(
+                sidecar_path(&filepath),
+                vec![filepath.clone()],
+                &self.shared_state,
+            )
+            .ok_or_log(|e| error!("Issue writing session metadata, {}", e));
+            self.shared_state.start_sbp_session(session);
+            if self.shared_state.record_corrections() {
+                self.shared_state.start_corrections_log(&filepath);
+            }
         }
         self.shared_state.set_sbp_logging_format(logging);
         start_recording(&self.client_sender, filepath.display().to_string());
@@ -144,16 +200,25 @@ impl MainTab {
     /// - `frame`: The raw incoming data frame
     /// - `msg`: Parsed message if present
     pub fn serialize(&mut self, frame: &Frame, msg: Option<&Sbp>) {
+        for annotation in self.shared_state.take_annotation_markers() {
+            self.write_annotation_marker(annotation);
+        }
         let csv_logging;
+        let loggers_changed;
         let sbp_logging;
         let sbp_logging_format;
         let directory;
+        let settings_read;
         {
             let shared_data = self.shared_state.lock();
             csv_logging = shared_data.logging_bar.csv_logging.clone();
+            loggers_changed = shared_data.logging_bar.csv_loggers != self.last_csv_loggers
+                || shared_data.logging_bar.track_formats != self.last_track_formats
+                || shared_data.logging_bar.rinex_logging != self.last_rinex_logging;
             sbp_logging = shared_data.logging_bar.sbp_logging;
             sbp_logging_format = shared_data.logging_bar.sbp_logging_format.clone();
             directory = shared_data.logging_bar.logging_directory.clone();
+            settings_read = !shared_data.device_info.settings.is_empty();
         }
         self.logging_directory = self.shared_state.clone().logging_directory();
 
@@ -173,11 +238,26 @@ impl MainTab {
             if let Err(e) = self.end_csv_logging() {
                 error!("Issue closing csv file, {}", e);
             }
+            self.last_csv_loggers = self.shared_state.csv_loggers();
+            self.last_track_formats = self.shared_state.track_formats();
+            self.last_rinex_logging = self.shared_state.rinex_logging();
             if let CsvLogging::ON = &csv_logging {
                 self.init_csv_logging();
             }
             self.last_csv_logging = csv_logging;
             refresh_loggingbar(&self.client_sender, &self.shared_state);
+        } else if loggers_changed {
+            // restart so the new selection of loggers start with fresh files
+            if let CsvLogging::ON = &self.last_csv_logging {
+                if let Err(e) = self.end_csv_logging() {
+                    error!("Issue closing csv file, {}", e);
+                }
+                self.init_csv_logging();
+            }
+            self.last_csv_loggers = self.shared_state.csv_loggers();
+            self.last_track_formats = self.shared_state.track_formats();
+            self.last_rinex_logging = self.shared_state.rinex_logging();
+            refresh_loggingbar(&self.client_sender, &self.shared_state);
         }
         if self.last_sbp_logging != sbp_logging
             || self.last_sbp_logging_format != sbp_logging_format
@@ -190,6 +270,9 @@ impl MainTab {
             self.last_sbp_logging_format = sbp_logging_format;
             refresh_loggingbar(&self.client_sender, &self.shared_state);
         }
+        if self.settings_snapshot_pending && settings_read {
+            self.write_settings_snapshot();
+        }
 
         let size = self
             .sbp_logger
@@ -201,24 +284,86 @@ impl MainTab {
             None if sbp_logging => self.close_sbp(),
             _ => {}
         };
+        if let Some(msg) = msg {
+            if let Sbp::MsgLog(log) = msg {
+                // markers from a log being played back or converted
+                if let Some(annotation) = Annotation::from_msg_log(log) {
+                    self.shared_state.record_annotation(annotation);
+                }
+            }
+            self.shared_state.write_rinex(msg);
+            self.shared_state.write_nmea(msg);
+            self.ntrip_solution.handle_msg(msg);
+            self.shared_state.write_sqlite(msg);
+        }
+        self.shared_state.record_session_message(frame, msg);
+    }
+
+    /// Write an annotation into the SBP log as a tagged `MsgLog` and record it in
+    /// the sidecars and the annotations CSV log.
+    fn write_annotation_marker(&mut self, annotation: Annotation) {
+        self.write_to_sbp_log(&Sbp::MsgLog(annotation.to_msg_log()));
+        self.shared_state.record_annotation(annotation);
+    }
+
+    /// Write the last settings table read from the device into the SBP log, so
+    /// the configuration can be seen when the log is played back. If the settings
+    /// haven't been read yet the snapshot is written once the read completes.
+    fn write_settings_snapshot(&mut self) {
+        let settings = self.shared_state.device_info().settings;
+        self.settings_snapshot_pending = settings.is_empty();
+        for msg in settings_snapshot(&settings) {
+            self.write_to_sbp_log(&msg);
+        }
+    }
+
+    /// Write a message generated by the console into the SBP log.
+    fn write_to_sbp_log(&mut self, msg: &Sbp) {
+        let Some(sbp_logger) = self.sbp_logger.as_mut() else {
+            return;
+        };
+        let frame = sbp::to_vec(msg)
+            .ok()
+            .and_then(|bytes| sbp::iter_frames(bytes.as_slice()).next()?.ok());
+        match frame {
+            Some(frame) => {
+                if let Ok(size) = sbp_logger.serialize(&frame, Some(msg)) {
+                    refresh_log_recording_size(&self.client_sender, size);
+                }
+            }
+            None => error!("Unable to encode message for the SBP log"),
+        }
     }
 
     pub fn close_sbp(&mut self) {
+        if let Err(e) = self.shared_state.end_sbp_session() {
+            error!("Issue writing session metadata, {}", e);
+        }
         self.sbp_logger = None;
+        self.settings_snapshot_pending = false;
+        self.shared_state.stop_corrections_log();
         self.shared_state.set_sbp_logging(false);
         self.shared_state.set_sbp_logging_filepath(None);
         refresh_loggingbar(&self.client_sender, &self.shared_state);
     }
 
     fn sbp_logging_filepath(&self, logging: SbpLogging) -> PathBuf {
-        let name = self.shared_state.sbp_logging_filename().unwrap_or_else(|| {
-            let fmt = match logging {
-                SbpLogging::SBP => SBP_FILEPATH,
-                SbpLogging::SBP_JSON => SBP_JSON_FILEPATH,
-            };
-            Local::now().format(fmt).to_string().into()
-        });
-        self.logging_directory.join(name)
+        if let Some(name) = self.shared_state.sbp_logging_filename() {
+            return self.logging_directory.join(name);
+        }
+        if let Some(template) = self.shared_state.log_path_template() {
+            return template.sbp_path(&self.log_path_context(), logging);
+        }
+        let fmt = match logging {
+            SbpLogging::SBP => SBP_FILEPATH,
+            SbpLogging::SBP_JSON => SBP_JSON_FILEPATH,
+        };
+        self.logging_directory
+            .join(Local::now().format(fmt).to_string())
+    }
+
+    fn log_path_context(&self) -> LogPathContext {
+        LogPathContext::new(&self.shared_state, self.logging_directory.clone())
     }
 }
 
@@ -226,13 +371,18 @@ impl MainTab {
 mod tests {
     use super::*;
     use crate::client_sender::TestSender;
+    use crate::session_metadata::DeviceInfo;
     use crate::tabs::baseline_tab::BaselineTab;
+    use crate::tabs::settings_tab::SettingsTab;
     use crate::tabs::solution_tab::solution_position_tab::SolutionPositionTab;
     use crate::test_common::msg_to_frame;
-    use crate::types::{BaselineNED, MsgSender, PosLLH, VelNED};
+    use crate::types::{BaselineNED, Dops, MsgSender, PosLLH, VelNED};
     use crate::utils::{mm_to_m, ms_to_sec};
     use glob::glob;
-    use sbp::messages::navigation::{MsgBaselineNed, MsgPosLlh, MsgVelNed};
+    use indexmap::IndexMap;
+    use sbp::messages::navigation::{MsgBaselineNed, MsgDops, MsgPosLlh, MsgVelNed};
+    use sbp::messages::settings::MsgSettingsReadByIndexResp;
+    use sbp::messages::{ConcreteMessage, SbpMessage};
     use sbp::Sbp;
     use std::{
         fs::File,
@@ -374,6 +524,49 @@ mod tests {
         assert!(d_ - mm_to_m(d_m3 as f64) <= f64::EPSILON);
     }
 
+    #[test]
+    fn csv_loggers_selection_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let tmp_dir = tmp_dir.path().to_path_buf();
+        let shared_state = SharedState::new();
+        let client_send = TestSender::boxed();
+        let mut main = MainTab::new(shared_state.clone(), client_send.clone());
+        let mut solution_tab = SolutionPositionTab::new(shared_state, client_send);
+        main.shared_state.set_csv_loggers(vec![CsvLogger::DOPS]);
+        main.shared_state.set_csv_logging(CsvLogging::ON);
+        main.shared_state.set_logging_directory(tmp_dir.clone());
+
+        let msg = MsgDops {
+            sender_id: Some(1337),
+            tow: 1337,
+            gdop: 150,
+            pdop: 125,
+            tdop: 100,
+            hdop: 75,
+            vdop: 50,
+            flags: 0x01,
+        };
+        main.serialize(&msg_to_frame(msg.clone()), None);
+        solution_tab.handle_dops(Dops::MsgDops(msg));
+        main.end_csv_logging().unwrap();
+
+        let pattern = tmp_dir.join("position_log_*");
+        assert!(glob(&pattern.to_string_lossy()).unwrap().next().is_none());
+
+        let pattern = tmp_dir.join("dops_log_*");
+        let path = glob(&pattern.to_string_lossy())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let mut reader = csv::Reader::from_reader(File::open(path).unwrap());
+        let mut records = reader.records();
+        let record = records.next().unwrap().unwrap();
+        let pdop: &f64 = &record[3].parse().unwrap();
+        assert!(pdop - 1.25 <= f64::EPSILON);
+        assert!(records.next().is_none());
+    }
+
     #[test]
     fn sbp_logging_test() {
         let tmp_dir = TempDir::new().unwrap();
@@ -463,6 +656,16 @@ mod tests {
             }
             _ => panic!("second message does not match"),
         }
+
+        let pattern = tmp_dir.join("swift-gnss-*.sbp.metadata.json");
+        let path = glob(&pattern.to_string_lossy())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        assert_eq!(value["message_count"], 2);
+        assert!(!value["stop_time"].is_null());
     }
 
     #[test]
@@ -549,4 +752,175 @@ mod tests {
         let n_ = value.get("n").unwrap();
         assert_eq!(*n_, n);
     }
+
+    #[test]
+    fn annotation_marker_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let tmp_dir = tmp_dir.path().to_path_buf();
+        let shared_state = SharedState::new();
+        let client_send = TestSender::boxed();
+        let mut main = MainTab::new(shared_state, client_send);
+        main.shared_state.set_logging_directory(tmp_dir.clone());
+        main.shared_state.set_sbp_logging_format(SbpLogging::SBP);
+        main.shared_state.set_sbp_logging(true);
+        main.shared_state
+            .set_csv_loggers(vec![CsvLogger::ANNOTATIONS]);
+        main.shared_state.set_csv_logging(CsvLogging::ON);
+
+        let msg = MsgDops {
+            sender_id: Some(1337),
+            tow: 1000,
+            gdop: 1,
+            pdop: 1,
+            tdop: 1,
+            hdop: 1,
+            vdop: 1,
+            flags: 1,
+        };
+        let frame = msg_to_frame(msg.clone());
+        let msg = Sbp::from(msg);
+        main.serialize(&frame, Some(&msg));
+        main.shared_state
+            .add_session_annotation("under the bridge".into());
+        main.serialize(&frame, Some(&msg));
+        main.close_sbp();
+        main.end_csv_logging().unwrap();
+
+        let pattern = tmp_dir.join("swift-gnss-*.sbp");
+        let path = glob(&pattern.to_string_lossy())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let markers: Vec<Annotation> = sbp::iter_messages(File::open(path).unwrap())
+            .filter_map(|msg| match msg {
+                Ok(Sbp::MsgLog(log)) => Annotation::from_msg_log(&log),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].text, "under the bridge");
+
+        let pattern = tmp_dir.join("annotations_log_*.csv");
+        let path = glob(&pattern.to_string_lossy())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let lines: Vec<String> = BufReader::new(File::open(path).unwrap())
+            .lines()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with("under the bridge"));
+    }
+
+    #[test]
+    fn settings_snapshot_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let tmp_dir = tmp_dir.path().to_path_buf();
+        let shared_state = SharedState::new();
+        let client_send = TestSender::boxed();
+        let mut settings = IndexMap::new();
+        settings.insert(
+            "solution".to_string(),
+            IndexMap::from([("elevation_mask".to_string(), "15".to_string())]),
+        );
+        settings.insert(
+            "system_info".to_string(),
+            IndexMap::from([("firmware_version".to_string(), "v4.11.0".to_string())]),
+        );
+        shared_state.set_device_info(DeviceInfo {
+            settings,
+            ..Default::default()
+        });
+        let mut main = MainTab::new(shared_state.clone(), client_send.clone());
+        main.shared_state.set_logging_directory(tmp_dir.clone());
+        main.init_sbp_logging(SbpLogging::SBP);
+        main.close_sbp();
+
+        let pattern = tmp_dir.join("swift-gnss-*.sbp");
+        let path = glob(&pattern.to_string_lossy())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let snapshot: Vec<Sbp> = sbp::iter_messages(File::open(path).unwrap())
+            .map(|msg| msg.unwrap())
+            .collect();
+        assert_eq!(snapshot.len(), 2);
+
+        // play the snapshot back into a fresh settings table
+        let playback_state = SharedState::new();
+        let tab = SettingsTab::new(playback_state.clone(), client_send, MsgSender::new(sink()))
+            .read_only();
+        for msg in snapshot {
+            tab.handle_msg(msg);
+        }
+        tab.stop();
+        let entry = tab.get("solution", "elevation_mask").unwrap();
+        assert_eq!(entry.value.unwrap().to_string(), "15");
+        assert_eq!(
+            playback_state.device_info().firmware_version.as_deref(),
+            Some("v4.11.0")
+        );
+    }
+
+    #[test]
+    fn settings_snapshot_after_read_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let tmp_dir = tmp_dir.path().to_path_buf();
+        let shared_state = SharedState::new();
+        let client_send = TestSender::boxed();
+        let mut main = MainTab::new(shared_state, client_send);
+        main.shared_state.set_logging_directory(tmp_dir.clone());
+        main.shared_state.set_sbp_logging_format(SbpLogging::SBP);
+        main.shared_state.set_sbp_logging(true);
+
+        let msg = MsgDops {
+            sender_id: Some(1337),
+            tow: 1000,
+            gdop: 1,
+            pdop: 1,
+            tdop: 1,
+            hdop: 1,
+            vdop: 1,
+            flags: 1,
+        };
+        let frame = msg_to_frame(msg.clone());
+        let msg = Sbp::from(msg);
+        // logging starts before the settings read completes
+        main.serialize(&frame, Some(&msg));
+        let mut settings = IndexMap::new();
+        settings.insert(
+            "solution".to_string(),
+            IndexMap::from([("elevation_mask".to_string(), "15".to_string())]),
+        );
+        main.shared_state.set_device_info(DeviceInfo {
+            settings,
+            ..Default::default()
+        });
+        main.serialize(&frame, Some(&msg));
+        main.serialize(&frame, Some(&msg));
+        main.close_sbp();
+
+        let pattern = tmp_dir.join("swift-gnss-*.sbp");
+        let path = glob(&pattern.to_string_lossy())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let msg_types: Vec<u16> = sbp::iter_messages(File::open(path).unwrap())
+            .map(|msg| msg.unwrap().message_type().unwrap())
+            .collect();
+        assert_eq!(
+            msg_types,
+            [
+                MsgDops::MESSAGE_TYPE,
+                MsgSettingsReadByIndexResp::MESSAGE_TYPE,
+                MsgDops::MESSAGE_TYPE,
+                MsgDops::MESSAGE_TYPE
+            ]
+        );
+    }
 }
@@ -23,8 +23,10 @@ use log::warn;
 use std::collections::{BTreeMap, HashMap};
 
 use crate::client_sender::BoxedClientSender;
+use crate::output::{CsvLogger, ObservationLog};
 use crate::shared_state::{SharedState, TabName};
-use crate::types::{ObservationMsg, SignalCodes};
+use crate::types::{ObservationMsg, ObservationMsgFields, SignalCodes};
+use crate::utils::date_conv::{gps_time_logging_string, local_time_logging_string};
 use crate::utils::{compute_doppler, sec_to_ns, serialize_capnproto_builder};
 
 #[derive(Clone, Debug)]
@@ -185,6 +187,7 @@ impl ObservationTab {
         {
             return;
         }
+        self.log_obs(&msg_fields);
 
         let old_carrier_phase = match is_remote {
             true => &self.remote.old_carrier_phase,
@@ -265,6 +268,42 @@ impl ObservationTab {
         }
     }
 
+    /// Write every observation in a message to the observations CSV log, if open.
+    ///
+    /// # Parameters:
+    ///
+    /// - `msg_fields`: The fields of the observation message being handled.
+    fn log_obs(&self, msg_fields: &ObservationMsgFields) {
+        if !self.shared_state.csv_log_open(&CsvLogger::OBSERVATIONS) {
+            return;
+        }
+        let pc_time = local_time_logging_string();
+        let gps_time = gps_time_logging_string(Some(msg_fields.wn), msg_fields.tow);
+        let rows: Vec<ObservationLog> = msg_fields
+            .states
+            .iter()
+            .map(|state| {
+                let obs_fields = state.fields();
+                ObservationLog {
+                    pc_time: pc_time.clone(),
+                    gps_time: gps_time.clone(),
+                    tow_s: Some(msg_fields.tow),
+                    sender_id: msg_fields.sender_id,
+                    signal: obs_fields.code.to_string(),
+                    sat: obs_fields.sat,
+                    pseudo_range_m: Some(obs_fields.pseudo_range),
+                    carrier_phase_cycles: Some(obs_fields.carrier_phase),
+                    cn0_dbhz: Some(obs_fields.cn0 / 4.0),
+                    measured_doppler_hz: Some(obs_fields.measured_doppler),
+                    lock: obs_fields.lock,
+                    flags: obs_fields.flags,
+                }
+            })
+            .collect();
+        self.shared_state
+            .write_csv_log(CsvLogger::OBSERVATIONS, &rows);
+    }
+
     /// Update remote or local table using the observation data accumulated by handle_obs.
     ///
     /// # Parameters:
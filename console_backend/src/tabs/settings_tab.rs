@@ -21,6 +21,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
@@ -33,17 +34,21 @@ use log::{error, warn};
 use parking_lot::Mutex;
 use sbp::link::LinkSource;
 use sbp::messages::piksi::MsgReset;
-use sbp::messages::settings::MsgSettingsSave;
+use sbp::messages::settings::{MsgSettingsReadByIndexResp, MsgSettingsSave};
 use sbp::Sbp;
 use sbp_settings::{Client, Context, Setting, SettingKind, SettingValue};
 
 use crate::client_sender::BoxedClientSender;
+use crate::constants::SETTINGS_SNAPSHOT_SENDER_ID;
+use crate::credentials::{is_secret_setting, REDACTED};
+use crate::session_metadata::DeviceInfo;
 use crate::shared_state::{SettingsTabState, SharedState};
 use crate::types::{Error, MsgSender, Result};
 use crate::utils::*;
 
 const FIRMWARE_VERSION_SETTING_KEY: &str = "firmware_version";
 const PRODUCT_ID_SETTING_KEY: &str = "product_id";
+const SERIAL_NUMBER_SETTING_KEY: &str = "serial_number";
 const ALLOWED_PRODUCT_ID_SUBSTRINGS: [&str; 2] = ["Piksi Multi", "Duro"];
 const DGNSS_SOLUTION_MODE_SETTING_KEY: &str = "dgnss_solution_mode";
 
@@ -55,6 +60,9 @@ const NTRIP_ENABLE_SETTING_KEY: &str = "enable";
 
 const SETTINGS_READ_WRITE_TIMEOUT: Duration = Duration::from_millis(1000);
 
+// Payload of a MsgSettingsReadByIndexResp left after the index
+const SETTINGS_SNAPSHOT_MAX_LEN: usize = 253;
+
 // Timeout used to read all settings
 const GLOBAL_TIMEOUT: Duration = Duration::from_secs(15);
 
@@ -133,6 +141,10 @@ pub struct SettingsTab {
     settings: Mutex<Settings>,
     sbp_client: Mutex<Client<'static>>,
     send: Sender<Sbp>,
+    /// Settings come from the snapshot in a log being played back rather than the device
+    read_only: bool,
+    /// Snapshot entries have been read but not sent to the frontend yet
+    snapshot_pending: AtomicBool,
 }
 
 impl Drop for SettingsTab {
@@ -167,19 +179,65 @@ impl SettingsTab {
                 msg_sender.send(msg).map_err(Into::into)
             })),
             send,
+            read_only: false,
+            snapshot_pending: AtomicBool::new(false),
         }
     }
 
+    /// Show the settings snapshot of a log being played back instead of talking to a device.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
     pub fn handle_msg(&self, msg: Sbp) {
+        if self.read_only {
+            self.handle_snapshot_msg(&msg);
+            return;
+        }
         if self.send.send(msg).is_err() {
             warn!("could not forward message to the settings tab");
         };
     }
 
     pub fn stop(&self) {
+        if self.snapshot_pending.swap(false, Ordering::Relaxed) {
+            self.snapshot_loaded();
+        }
         self.shared_state.reset_settings_state();
     }
 
+    /// Rebuild the settings table from the `MsgSettingsReadByIndexResp` frames
+    /// of a log, the table is sent once the run of snapshot frames ends.
+    fn handle_snapshot_msg(&self, msg: &Sbp) {
+        if let Sbp::MsgSettingsReadByIndexResp(msg) = msg {
+            if let Some(entry) = snapshot_entry(msg) {
+                self.settings.lock().insert_entry(entry);
+                self.snapshot_pending.store(true, Ordering::Relaxed);
+            }
+        } else if self.snapshot_pending.swap(false, Ordering::Relaxed) {
+            self.snapshot_loaded();
+        }
+    }
+
+    fn snapshot_loaded(&self) {
+        let find = |key: &str| {
+            self.settings
+                .lock()
+                .groups()
+                .into_iter()
+                .flatten()
+                .find(|(setting, _)| setting.name == key)
+                .map(|(_, value)| value.to_string())
+        };
+        self.update_device_info(
+            find(FIRMWARE_VERSION_SETTING_KEY),
+            find(PRODUCT_ID_SETTING_KEY),
+            find(SERIAL_NUMBER_SETTING_KEY),
+        );
+        self.send_table_data();
+    }
+
     pub fn get(&self, group: &str, name: &str) -> Result<SettingsEntry> {
         self.settings.lock().get(group, name).cloned()
     }
@@ -471,11 +529,13 @@ impl SettingsTab {
         }
         let mut firmware_version = None;
         let mut product_id = None;
+        let mut serial_number = None;
         for entry in settings {
             if let Some(ref value) = entry.value {
                 match entry.setting.name.as_str() {
                     FIRMWARE_VERSION_SETTING_KEY => firmware_version = Some(value.to_string()),
                     PRODUCT_ID_SETTING_KEY => product_id = Some(value.to_string()),
+                    SERIAL_NUMBER_SETTING_KEY => serial_number = Some(value.to_string()),
                     DGNSS_SOLUTION_MODE_SETTING_KEY => {
                         self.shared_state.set_dgnss_enabled(value.to_string())
                     }
@@ -484,6 +544,7 @@ impl SettingsTab {
             }
             self.settings.lock().insert(entry);
         }
+        self.update_device_info(firmware_version.clone(), product_id.clone(), serial_number);
         if let Some(product_id) = product_id {
             for substring in ALLOWED_PRODUCT_ID_SUBSTRINGS {
                 if product_id.contains(substring) {
@@ -496,6 +557,36 @@ impl SettingsTab {
         }
     }
 
+    /// Snapshot the settings table into the shared state for log metadata, with secrets
+    /// such as the device's NTRIP password redacted.
+    fn update_device_info(
+        &self,
+        firmware_version: Option<String>,
+        product_id: Option<String>,
+        serial_number: Option<String>,
+    ) {
+        let mut settings: IndexMap<String, IndexMap<String, String>> = IndexMap::new();
+        for group in self.settings.lock().groups() {
+            for (setting, value) in group {
+                let value = if is_secret_setting(&setting.name) {
+                    REDACTED.to_string()
+                } else {
+                    value.to_string()
+                };
+                settings
+                    .entry(setting.group.clone())
+                    .or_default()
+                    .insert(setting.name.clone(), value);
+            }
+        }
+        self.shared_state.set_device_info(DeviceInfo {
+            firmware_version,
+            product_id,
+            serial_number,
+            settings,
+        });
+    }
+
     /// Package settings table data into a message buffer and send to frontend.
     fn send_table_data(&self) {
         let settings = self.settings.lock();
@@ -519,7 +610,7 @@ impl SettingsTab {
                 entry.set_group(&setting.group);
                 entry.set_type(setting.kind.to_str());
                 entry.set_expert(setting.expert);
-                entry.set_readonly(setting.readonly);
+                entry.set_readonly(setting.readonly || self.read_only);
                 {
                     let entry = entry.reborrow();
                     if let Some(ref description) = setting.description {
@@ -774,19 +865,55 @@ impl Settings {
     }
 
     fn insert(&mut self, entry: sbp_settings::Entry) {
+        self.insert_entry(SettingsEntry {
+            setting: entry.setting,
+            value: entry.value,
+        });
+    }
+
+    fn insert_entry(&mut self, entry: SettingsEntry) {
         self.inner
             .entry(entry.setting.group.clone())
             .or_default()
-            .insert(
-                entry.setting.name.clone(),
-                SettingsEntry {
-                    setting: entry.setting,
-                    value: entry.value,
-                },
-            );
+            .insert(entry.setting.name.clone(), entry);
     }
 }
 
+/// Encode a settings table as `MsgSettingsReadByIndexResp` messages, as if the
+/// device had answered a read of every setting, so it can be embedded in a log.
+/// Settings too long to fit in a single message are left out.
+pub fn settings_snapshot(settings: &IndexMap<String, IndexMap<String, String>>) -> Vec<Sbp> {
+    settings
+        .iter()
+        .flat_map(|(group, names)| names.iter().map(move |(name, value)| (group, name, value)))
+        .map(|(group, name, value)| format!("{group}\0{name}\0{value}\0"))
+        .filter(|setting| setting.len() <= SETTINGS_SNAPSHOT_MAX_LEN)
+        .enumerate()
+        .map(|(index, setting)| {
+            Sbp::MsgSettingsReadByIndexResp(MsgSettingsReadByIndexResp {
+                sender_id: Some(SETTINGS_SNAPSHOT_SENDER_ID),
+                index: index as u16,
+                setting: setting.into(),
+            })
+        })
+        .collect()
+}
+
+/// Look up the setting in a `MsgSettingsReadByIndexResp`, settings unknown to
+/// libsettings are skipped since their type and description can't be shown.
+fn snapshot_entry(msg: &MsgSettingsReadByIndexResp) -> Option<SettingsEntry> {
+    let setting = msg.setting.to_string();
+    let mut fields = setting.split('\0');
+    let (group, name, value) = (fields.next()?, fields.next()?, fields.next()?);
+    let setting = Setting::all()
+        .iter()
+        .find(|setting| setting.group == group && setting.name == name)?;
+    Some(SettingsEntry {
+        setting: Cow::Borrowed(setting),
+        value: Some(SettingValue::String(value.to_string())),
+    })
+}
+
 /// A reference to a particular setting and its value if it has been fetched
 #[derive(Debug, Clone)]
 pub struct SettingsEntry {
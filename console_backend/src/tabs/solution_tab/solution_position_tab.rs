@@ -28,10 +28,11 @@ use std::{collections::HashMap, time::Instant};
 
 use crate::client_sender::BoxedClientSender;
 use crate::constants::*;
-use crate::output::{PosLLHLog, VelLog};
+use crate::output::{CsvLogger, DopsLog, InsLog, PosLLHLog, ProtectionLevelLog, VelLog};
 use crate::piksi_tools_constants::EMPTY_STR;
 use crate::shared_state::SharedState;
 use crate::tabs::solution_tab::LatLonUnits;
+use crate::track_export::{solution_time, TrackPoint};
 use crate::types::{
     Dops, GnssModes, GpsTime, PosLLH, ProtectionLevel, RingBuffer, UtcDateTime, VelNED,
 };
@@ -95,8 +96,12 @@ pub struct SolutionPositionTab {
     utc_source: Option<String>,
     /// The stored monotonic Utc time.
     utc_time: Option<UtcDateTime>,
+    /// The tow of the stored Utc time.
+    utc_tow: Option<u32>,
     /// The stored week value from GPS Time messages.
     week: Option<u16>,
+    /// The tow of the stored week value.
+    week_tow: Option<u32>,
 }
 
 impl SolutionPositionTab {
@@ -168,7 +173,9 @@ impl SolutionPositionTab {
             unit,
             utc_source: None,
             utc_time: None,
+            utc_tow: None,
             week: None,
+            week_tow: None,
             h_acc: 0.0,
         }
     }
@@ -180,9 +187,11 @@ impl SolutionPositionTab {
     pub fn handle_utc_time(&mut self, msg: MsgUtcTime) {
         if msg.flags & 0x7 == 0 {
             self.utc_time = None;
+            self.utc_tow = None;
             self.utc_source = None;
         } else {
             self.utc_time = Some(utc_time_from_msg(&msg));
+            self.utc_tow = Some(msg.tow);
             self.utc_source = Some(utc_source(msg.flags));
         }
     }
@@ -195,6 +204,7 @@ impl SolutionPositionTab {
         let gps_time_fields = msg.fields();
         if gps_time_fields.flags != 0 {
             self.week = Some(gps_time_fields.wn);
+            self.week_tow = Some(gps_time_fields.tow);
             self.nsec = Some(gps_time_fields.ns_residual);
         }
     }
@@ -366,6 +376,21 @@ impl SolutionPositionTab {
     /// # Parameters
     /// - `msg`: The MsgInsUpdates to extract data from.
     pub fn handle_ins_updates(&mut self, msg: MsgInsUpdates) {
+        self.shared_state.write_csv_log(
+            CsvLogger::INS,
+            &[InsLog {
+                pc_time: local_time_logging_string(),
+                msg: "INS_UPDATES",
+                tow_s: Some(ms_to_sec(msg.tow as f64)),
+                status_flags: None,
+                gnsspos: Some(msg.gnsspos),
+                gnssvel: Some(msg.gnssvel),
+                wheelticks: Some(msg.wheelticks),
+                speed: Some(msg.speed),
+                nhc: Some(msg.nhc),
+                zerovel: Some(msg.zerovel),
+            }],
+        );
         let tic = msg.wheelticks;
         if ((tic & 0xF0) >> 4) > (tic & 0x0F) {
             self.last_odo_update_time = Instant::now();
@@ -382,6 +407,21 @@ impl SolutionPositionTab {
     /// # Parameters
     /// - `msg`: The MsgInsStatus to extract data from.
     pub fn handle_ins_status(&mut self, msg: MsgInsStatus) {
+        self.shared_state.write_csv_log(
+            CsvLogger::INS,
+            &[InsLog {
+                pc_time: local_time_logging_string(),
+                msg: "INS_STATUS",
+                tow_s: None,
+                status_flags: Some(msg.flags),
+                gnsspos: None,
+                gnssvel: None,
+                wheelticks: None,
+                speed: None,
+                nhc: None,
+                zerovel: None,
+            }],
+        );
         self.ins_status_flags = msg.flags;
         self.table
             .insert(INS_STATUS, format!("0x{:<01x}", self.ins_status_flags));
@@ -400,6 +440,21 @@ impl SolutionPositionTab {
     /// - `msg`: Dops wrapper around a MsgDops or MsgDopsDepA.
     pub fn handle_dops(&mut self, msg: Dops) {
         let dops_fields = msg.fields();
+        let tow = ms_to_sec(dops_fields.tow as f64);
+        self.shared_state.write_csv_log(
+            CsvLogger::DOPS,
+            &[DopsLog {
+                pc_time: local_time_logging_string(),
+                gps_time: gps_time_logging_string(self.week, tow),
+                tow_s: Some(tow),
+                pdop: Some(dops_fields.pdop as f64 * DILUTION_OF_PRECISION_UNITS),
+                gdop: Some(dops_fields.gdop as f64 * DILUTION_OF_PRECISION_UNITS),
+                tdop: Some(dops_fields.tdop as f64 * DILUTION_OF_PRECISION_UNITS),
+                hdop: Some(dops_fields.hdop as f64 * DILUTION_OF_PRECISION_UNITS),
+                vdop: Some(dops_fields.vdop as f64 * DILUTION_OF_PRECISION_UNITS),
+                flags: dops_fields.flags,
+            }],
+        );
         self.table
             .insert(DOPS_FLAGS, format!("0x{:<03x}", dops_fields.flags));
         if dops_fields.flags != 0 {
@@ -431,6 +486,19 @@ impl SolutionPositionTab {
 
     pub fn handle_prot_lvl(&mut self, msg: ProtectionLevel) {
         let fields = msg.fields();
+        let tow = ms_to_sec(fields.tow as f64);
+        self.shared_state.write_csv_log(
+            CsvLogger::PROTECTION_LEVEL,
+            &[ProtectionLevelLog {
+                pc_time: local_time_logging_string(),
+                gps_time: gps_time_logging_string(self.week, tow),
+                tow_s: Some(tow),
+                latitude_d: Some(fields.lat),
+                longitude_d: Some(fields.lon),
+                hpl_m: Some(fields.hpl as f64 / 100.0),
+                vpl_m: Some(fields.vpl as f64 / 100.0),
+            }],
+        );
         // Send protection level
         let mut builder = Builder::new_default();
         let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
@@ -504,6 +572,14 @@ impl SolutionPositionTab {
                 }
             }
         }
+        let track_time = solution_time(
+            pos_llh_fields.tow as u32,
+            self.utc_tow.zip(self.utc_time),
+            self.week_tow.zip(self.week),
+        );
+        if let Some(point) = TrackPoint::from_pos_llh(&msg, track_time) {
+            self.shared_state.write_track_point(point);
+        }
 
         if self.last_pos_mode == 0 {
             self.table.insert(GPS_WEEK, String::from(EMPTY_STR));
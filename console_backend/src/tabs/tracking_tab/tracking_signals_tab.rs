@@ -37,9 +37,13 @@ use crate::constants::{
     GPS_L1_FAMILY, GPS_L2_FAMILY, GPS_L5_FAMILY, NUM_POINTS, QZS_L1_FAMILY, QZS_L2_FAMILY,
     QZS_L5_FAMILY, SBAS_L1_FAMILY, SBAS_L5_FAMILY, TRACKING_UPDATE_PERIOD, TRK_RATE,
 };
+use crate::output::{CsvLogger, TrackingCn0Log};
 use crate::shared_state::{SharedState, TabName};
 use crate::types::{Cn0Age, Cn0Dict, ObservationMsg, RingBuffer, SignalCodes};
-use crate::utils::{serialize_capnproto_builder, signal_key_color, signal_key_label};
+use crate::utils::{
+    date_conv::local_time_logging_string, serialize_capnproto_builder, signal_key_color,
+    signal_key_label,
+};
 
 #[derive(Debug)]
 pub struct TrackingSignalsTab {
@@ -238,6 +242,10 @@ impl TrackingSignalsTab {
         let mut codes_that_came: Vec<(SignalCodes, i16)> = Vec::new();
         let t = (Instant::now()).duration_since(self.t_init).as_secs_f64();
         self.time.push(t);
+        let mut cn0_log = self
+            .shared_state
+            .csv_log_open(&CsvLogger::TRACKING_CN0)
+            .then(|| (local_time_logging_string(), Vec::new()));
         for (idx, state) in states.iter().enumerate() {
             let mut sat = state.mesid.sat as i16;
             let signal_code = SignalCodes::from(state.mesid.code);
@@ -264,6 +272,14 @@ impl TrackingSignalsTab {
             if state.cn0 != 0 {
                 self.push_to_cn0_dict(key, t, state.cn0 as f64 / 4.0);
                 self.push_to_cn0_age(key, t);
+                if let Some((pc_time, rows)) = cn0_log.as_mut() {
+                    rows.push(TrackingCn0Log {
+                        pc_time: pc_time.clone(),
+                        signal: signal_code.to_string(),
+                        sat,
+                        cn0_dbhz: Some(state.cn0 as f64 / 4.0),
+                    });
+                }
             }
             if !self.received_codes.contains(&signal_code) {
                 self.received_codes.push(signal_code);
@@ -274,6 +290,10 @@ impl TrackingSignalsTab {
                 cn0_deque.push((t, 0.0));
             }
         }
+        if let Some((_, rows)) = cn0_log {
+            self.shared_state
+                .write_csv_log(CsvLogger::TRACKING_CN0, &rows);
+        }
         self.clean_cn0();
         self.update_plot();
         self.send_data();
@@ -304,6 +324,10 @@ impl TrackingSignalsTab {
         let mut codes_that_came: Vec<(SignalCodes, i16)> = Vec::new();
         let t = (Instant::now()).duration_since(self.t_init).as_secs_f64();
         self.time.push(t);
+        let mut cn0_log = self
+            .shared_state
+            .csv_log_open(&CsvLogger::TRACKING_CN0)
+            .then(|| (local_time_logging_string(), Vec::new()));
         for state in states.iter() {
             let mut sat = state.sid.sat as i16;
             let signal_code = SignalCodes::from(state.sid.code);
@@ -320,8 +344,20 @@ impl TrackingSignalsTab {
             if state.cn0 != 0 {
                 self.push_to_cn0_dict(key, t, state.cn0 as f64 / 4.0);
                 self.push_to_cn0_age(key, t);
+                if let Some((pc_time, rows)) = cn0_log.as_mut() {
+                    rows.push(TrackingCn0Log {
+                        pc_time: pc_time.clone(),
+                        signal: signal_code.to_string(),
+                        sat,
+                        cn0_dbhz: Some(state.cn0 as f64 / 4.0),
+                    });
+                }
             }
         }
+        if let Some((_, rows)) = cn0_log {
+            self.shared_state
+                .write_csv_log(CsvLogger::TRACKING_CN0, &rows);
+        }
         self.clean_cn0();
         self.update_plot();
         self.send_data();
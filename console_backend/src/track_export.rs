@@ -0,0 +1,618 @@
+// Copyright (c) 2022 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Track exporters (GPX, KML and GeoJSON) for the position solution.
+//!
+//! Tracks are split into a new segment every time the fix mode changes so
+//! viewers like Google Earth and QGIS can colour them by [`GnssModes`].
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use log::error;
+use sbp::{Sbp, SbpIterExt};
+use serde_json::json;
+
+use crate::common_constants as cc;
+use crate::constants::{
+    GEOJSON_TIME_STR_FILEPATH, GPS_UTC_LEAP_SECONDS, GPX_TIME_STR_FILEPATH, KML_TIME_STR_FILEPATH,
+};
+use crate::output::LogSync;
+use crate::types::{GnssModes, PosLLH, Result, UtcDateTime};
+use crate::utils::date_conv::utc_time_from_msg;
+use crate::utils::OkOrLog;
+
+const MS_PER_WEEK: u32 = 7 * 24 * 60 * 60 * 1000;
+const TRACK_NAME: &str = "Swift Console Track";
+const KML_SCHEMA_ID: &str = "swift_track";
+const GPX_EXTENSIONS_NAMESPACE: &str = "https://www.swiftnav.com/xmlschemas/track/1";
+const TRACK_MODES: [GnssModes; 6] = [
+    GnssModes::Spp,
+    GnssModes::Dgnss,
+    GnssModes::Float,
+    GnssModes::Fixed,
+    GnssModes::Dr,
+    GnssModes::Sbas,
+];
+
+pub type TrackFormat = cc::TrackFormat;
+impl TrackFormat {
+    /// All track formats in the order they are shown in the logging bar.
+    pub fn all() -> Vec<TrackFormat> {
+        use strum::VariantNames;
+        TrackFormat::VARIANTS
+            .iter()
+            .filter_map(|name| TrackFormat::from_str(name).ok())
+            .collect()
+    }
+
+    /// The strftime pattern used to name a live track log.
+    pub fn filename_format(&self) -> &'static str {
+        match self {
+            TrackFormat::GPX => GPX_TIME_STR_FILEPATH,
+            TrackFormat::KML => KML_TIME_STR_FILEPATH,
+            TrackFormat::GEOJSON => GEOJSON_TIME_STR_FILEPATH,
+        }
+    }
+
+    /// Written by [`TrackWriter::finish`] to close the document.
+    pub(crate) fn trailer(&self) -> &'static str {
+        match self {
+            TrackFormat::GPX => "</gpx>\n",
+            TrackFormat::KML => "</Document>\n</kml>\n",
+            TrackFormat::GEOJSON => "\n]}\n",
+        }
+    }
+
+    /// Length of a track cut short up to the end of its last complete segment, or
+    /// `None` if not even the header was written.
+    pub(crate) fn complete_len(&self, data: &[u8]) -> Option<usize> {
+        let after = |marker: &str| {
+            data.windows(marker.len())
+                .rposition(|w| w == marker.as_bytes())
+                .map(|pos| pos + marker.len())
+        };
+        match self {
+            TrackFormat::GPX => after("</trk>\n").or_else(|| after("</metadata>\n")),
+            TrackFormat::KML => after("</Placemark>\n").or_else(|| after("</Style>\n")),
+            TrackFormat::GEOJSON => {
+                // each feature is written on its own line, followed by a comma if
+                // another one comes after it
+                let mut complete = data
+                    .windows(2)
+                    .position(|w| w == b":[")
+                    .map(|pos| pos + 2)?;
+                for line in data[complete..].split(|&c| c == b'\n').skip(1) {
+                    let feature = line.strip_suffix(b",").unwrap_or(line);
+                    if serde_json::from_slice::<serde_json::Value>(feature).is_err() {
+                        break;
+                    }
+                    complete += 1 + feature.len();
+                    if feature.len() == line.len() {
+                        break;
+                    }
+                }
+                Some(complete)
+            }
+        }
+    }
+
+    /// Guess the track format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<TrackFormat> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "gpx" => Some(TrackFormat::GPX),
+            "kml" => Some(TrackFormat::KML),
+            "geojson" | "json" => Some(TrackFormat::GEOJSON),
+            _ => None,
+        }
+    }
+}
+
+/// A single position solution along a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackPoint {
+    pub time: UtcDateTime,
+    pub lat: f64,
+    pub lon: f64,
+    pub height: f64,
+    pub h_accuracy: f64,
+    pub v_accuracy: f64,
+    pub n_sats: u8,
+    pub mode: GnssModes,
+}
+
+impl TrackPoint {
+    /// Build a track point from a position solution.
+    ///
+    /// # Parameters
+    /// - `msg`: The position solution.
+    /// - `time`: The UTC time of the solution, if known.
+    ///
+    /// # Returns
+    /// - `None` when there is no fix or no UTC time to stamp the point with.
+    pub fn from_pos_llh(msg: &PosLLH, time: Option<UtcDateTime>) -> Option<TrackPoint> {
+        let mode = msg.mode();
+        if mode == 0 || mode > GnssModes::Sbas as u8 {
+            return None;
+        }
+        let fields = msg.fields();
+        Some(TrackPoint {
+            time: time?,
+            lat: fields.lat,
+            lon: fields.lon,
+            height: fields.height,
+            h_accuracy: fields.h_accuracy,
+            v_accuracy: fields.v_accuracy,
+            n_sats: fields.n_sats,
+            mode: GnssModes::from(mode),
+        })
+    }
+
+    fn time_string(&self) -> String {
+        self.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+}
+
+/// Streams a track to disk in one of the [`TrackFormat`]s.
+///
+/// Points are buffered until the fix mode changes, at which point the segment is
+/// written out. [`TrackWriter::finish`] must be called to produce a valid file.
+#[derive(Debug)]
+pub struct TrackWriter {
+    format: TrackFormat,
+    writer: BufWriter<File>,
+    segment: Vec<TrackPoint>,
+    features: usize,
+    sync: LogSync,
+}
+
+impl TrackWriter {
+    pub fn new(filepath: impl AsRef<Path>, format: TrackFormat) -> Result<TrackWriter> {
+        let writer = BufWriter::new(File::create(filepath)?);
+        let mut track = TrackWriter {
+            format,
+            writer,
+            segment: Vec::new(),
+            features: 0,
+            sync: LogSync::default(),
+        };
+        track.write_header()?;
+        Ok(track)
+    }
+
+    pub fn with_sync_interval(mut self, interval: Option<Duration>) -> Self {
+        self.sync = LogSync::new(interval);
+        self
+    }
+
+    pub fn new_option(
+        filepath: impl AsRef<Path> + Copy,
+        format: TrackFormat,
+    ) -> Option<TrackWriter> {
+        TrackWriter::new(filepath, format).ok_or_log(|e| {
+            let fname = filepath.as_ref().display();
+            error!("issue creating file, {fname:?}, error, {e}")
+        })
+    }
+
+    pub fn write_point(&mut self, point: TrackPoint) -> Result<()> {
+        if matches!(self.segment.last(), Some(last) if last.mode != point.mode) {
+            self.write_segment()?;
+        }
+        if let TrackFormat::GEOJSON = self.format {
+            self.write_feature(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [point.lon, point.lat, point.height],
+                },
+                "properties": {
+                    "time": point.time_string(),
+                    "mode": point.mode.to_string(),
+                    "color": point.mode.color(),
+                    "n_sats": point.n_sats,
+                    "h_accuracy": point.h_accuracy,
+                    "v_accuracy": point.v_accuracy,
+                },
+            }))?;
+        }
+        self.segment.push(point);
+        let writer = &mut self.writer;
+        self.sync.maybe_sync(|| {
+            writer.flush()?;
+            writer.get_ref().sync_data()
+        });
+        Ok(())
+    }
+
+    /// Write out the last segment and close the document.
+    pub fn finish(mut self) -> Result<()> {
+        self.write_segment()?;
+        self.writer.write_all(self.format.trailer().as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let w = &mut self.writer;
+        match self.format {
+            TrackFormat::GPX => {
+                writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+                writeln!(
+                    w,
+                    r#"<gpx version="1.1" creator="{TRACK_NAME}" xmlns="http://www.topografix.com/GPX/1/1" xmlns:swift="{GPX_EXTENSIONS_NAMESPACE}">"#
+                )?;
+                writeln!(w, "  <metadata><name>{TRACK_NAME}</name></metadata>")?;
+            }
+            TrackFormat::KML => {
+                writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+                writeln!(
+                    w,
+                    r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">"#
+                )?;
+                writeln!(w, "<Document>")?;
+                writeln!(w, "  <name>{TRACK_NAME}</name>")?;
+                writeln!(w, r#"  <Schema id="{KML_SCHEMA_ID}">"#)?;
+                for (name, kind, display_name) in [
+                    ("n_sats", "int", "Satellites Used"),
+                    ("h_accuracy", "float", "Horizontal Accuracy (m)"),
+                    ("v_accuracy", "float", "Vertical Accuracy (m)"),
+                ] {
+                    writeln!(
+                        w,
+                        r#"    <gx:SimpleArrayField name="{name}" type="{kind}"><displayName>{display_name}</displayName></gx:SimpleArrayField>"#
+                    )?;
+                }
+                writeln!(w, "  </Schema>")?;
+                for mode in TRACK_MODES {
+                    let color = kml_color(&mode.color());
+                    writeln!(
+                        w,
+                        r#"  <Style id="{}"><LineStyle><color>{color}</color><width>3</width></LineStyle><IconStyle><color>{color}</color></IconStyle></Style>"#,
+                        kml_style_id(mode)
+                    )?;
+                }
+            }
+            TrackFormat::GEOJSON => {
+                write!(
+                    w,
+                    r#"{{"type":"FeatureCollection","name":"{TRACK_NAME}","features":["#
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_segment(&mut self) -> Result<()> {
+        let segment = std::mem::take(&mut self.segment);
+        let (first, last) = match (segment.first(), segment.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(()),
+        };
+        let mode = first.mode;
+        match self.format {
+            TrackFormat::GPX => {
+                let w = &mut self.writer;
+                writeln!(w, "  <trk>")?;
+                writeln!(w, "    <name>{mode}</name>")?;
+                writeln!(w, "    <type>{mode}</type>")?;
+                writeln!(w, "    <trkseg>")?;
+                for point in segment.iter() {
+                    writeln!(
+                        w,
+                        r#"      <trkpt lat="{:.9}" lon="{:.9}">"#,
+                        point.lat, point.lon
+                    )?;
+                    writeln!(w, "        <ele>{:.3}</ele>", point.height)?;
+                    writeln!(w, "        <time>{}</time>", point.time_string())?;
+                    writeln!(w, "        <sat>{}</sat>", point.n_sats)?;
+                    writeln!(w, "        <extensions>")?;
+                    writeln!(w, "          <swift:mode>{mode}</swift:mode>")?;
+                    writeln!(
+                        w,
+                        "          <swift:h_accuracy>{:.3}</swift:h_accuracy>",
+                        point.h_accuracy
+                    )?;
+                    writeln!(
+                        w,
+                        "          <swift:v_accuracy>{:.3}</swift:v_accuracy>",
+                        point.v_accuracy
+                    )?;
+                    writeln!(w, "        </extensions>")?;
+                    writeln!(w, "      </trkpt>")?;
+                }
+                writeln!(w, "    </trkseg>")?;
+                writeln!(w, "  </trk>")?;
+            }
+            TrackFormat::KML => {
+                let w = &mut self.writer;
+                writeln!(w, "  <Placemark>")?;
+                writeln!(w, "    <name>{mode}</name>")?;
+                writeln!(w, "    <styleUrl>#{}</styleUrl>", kml_style_id(mode))?;
+                writeln!(w, "    <gx:Track>")?;
+                writeln!(w, "      <altitudeMode>clampToGround</altitudeMode>")?;
+                for point in segment.iter() {
+                    writeln!(w, "      <when>{}</when>", point.time_string())?;
+                }
+                for point in segment.iter() {
+                    writeln!(
+                        w,
+                        "      <gx:coord>{:.9} {:.9} {:.3}</gx:coord>",
+                        point.lon, point.lat, point.height
+                    )?;
+                }
+                writeln!(w, "      <ExtendedData>")?;
+                writeln!(w, r##"        <SchemaData schemaUrl="#{KML_SCHEMA_ID}">"##)?;
+                let arrays: [(&str, fn(&TrackPoint) -> String); 3] = [
+                    ("n_sats", |p| p.n_sats.to_string()),
+                    ("h_accuracy", |p| format!("{:.3}", p.h_accuracy)),
+                    ("v_accuracy", |p| format!("{:.3}", p.v_accuracy)),
+                ];
+                for (name, value) in arrays {
+                    writeln!(w, r#"          <gx:SimpleArrayData name="{name}">"#)?;
+                    for point in segment.iter() {
+                        writeln!(w, "            <gx:value>{}</gx:value>", value(point))?;
+                    }
+                    writeln!(w, "          </gx:SimpleArrayData>")?;
+                }
+                writeln!(w, "        </SchemaData>")?;
+                writeln!(w, "      </ExtendedData>")?;
+                writeln!(w, "    </gx:Track>")?;
+                writeln!(w, "  </Placemark>")?;
+            }
+            TrackFormat::GEOJSON => {
+                // a LineString needs at least two positions, lone points are already written
+                if segment.len() > 1 {
+                    let coordinates: Vec<[f64; 3]> = segment
+                        .iter()
+                        .map(|point| [point.lon, point.lat, point.height])
+                        .collect();
+                    self.write_feature(json!({
+                        "type": "Feature",
+                        "geometry": {
+                            "type": "LineString",
+                            "coordinates": coordinates,
+                        },
+                        "properties": {
+                            "mode": mode.to_string(),
+                            "color": mode.color(),
+                            "start_time": first.time_string(),
+                            "end_time": last.time_string(),
+                            "points": segment.len(),
+                        },
+                    }))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_feature(&mut self, feature: serde_json::Value) -> Result<()> {
+        if self.features > 0 {
+            write!(self.writer, ",")?;
+        }
+        writeln!(self.writer)?;
+        serde_json::to_writer(&mut self.writer, &feature)?;
+        self.features += 1;
+        Ok(())
+    }
+}
+
+/// Convert the position solutions in an SBP file into a track.
+///
+/// # Parameters
+/// - `input`: The binary SBP stream to read.
+/// - `output`: The track file to create.
+/// - `format`: The format of the track file.
+///
+/// # Returns
+/// - The number of points written to the track.
+pub fn export_track<R: Read>(input: R, output: &Path, format: TrackFormat) -> Result<usize> {
+    let mut track = TrackWriter::new(output, format)?;
+    let mut utc_time = None;
+    let mut gps_week = None;
+    let mut points = 0;
+    for msg in sbp::iter_messages(input).log_errors(log::Level::Debug) {
+        let pos_llh = match msg {
+            Sbp::MsgUtcTime(msg) => {
+                utc_time = (msg.flags & 0x7 != 0).then(|| (msg.tow, utc_time_from_msg(&msg)));
+                continue;
+            }
+            Sbp::MsgGpsTime(msg) => {
+                gps_week = (msg.flags & 0x7 != 0).then(|| (msg.tow, msg.wn));
+                continue;
+            }
+            Sbp::MsgPosLlh(msg) => PosLLH::MsgPosLlh(msg),
+            Sbp::MsgPosLlhDepA(msg) => PosLLH::MsgPosLlhDepA(msg),
+            _ => continue,
+        };
+        let time = solution_time(pos_llh.fields().tow as u32, utc_time, gps_week);
+        if let Some(point) = TrackPoint::from_pos_llh(&pos_llh, time) {
+            track.write_point(point)?;
+            points += 1;
+        }
+    }
+    track.finish()?;
+    Ok(points)
+}
+
+/// The time of the solution at `tow`.
+///
+/// The last `MsgUtcTime` is only used when it is from the same epoch, otherwise
+/// the solution is stamped with GPS time converted to UTC so a dropped UTC message
+/// doesn't give it the previous epoch's time.
+pub(crate) fn solution_time(
+    tow: u32,
+    utc_time: Option<(u32, UtcDateTime)>,
+    gps_week: Option<(u32, u16)>,
+) -> Option<UtcDateTime> {
+    match (utc_time, gps_week) {
+        (Some((utc_tow, time)), _) if utc_tow == tow => Some(time),
+        (_, Some((gps_tow, wn))) => {
+            // the solution may be the first of a new week
+            let wn = if tow + MS_PER_WEEK / 2 < gps_tow {
+                wn + 1
+            } else {
+                wn
+            };
+            Some(
+                Utc.with_ymd_and_hms(1980, 1, 6, 0, 0, 0).unwrap()
+                    + chrono::Duration::weeks(wn as i64)
+                    + chrono::Duration::milliseconds(tow as i64)
+                    - chrono::Duration::seconds(GPS_UTC_LEAP_SECONDS),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Convert a `#RRGGBB` colour into KML's `aabbggrr`.
+fn kml_color(color: &str) -> String {
+    let hex = color.trim_start_matches('#');
+    if hex.len() != 6 {
+        return String::from("ffffffff");
+    }
+    format!("ff{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]).to_lowercase()
+}
+
+fn kml_style_id(mode: GnssModes) -> String {
+    format!("mode_{}", mode as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sbp::messages::navigation::MsgPosLlh;
+    use tempfile::TempDir;
+
+    fn point(mode: GnssModes, secs: u32) -> TrackPoint {
+        TrackPoint {
+            time: Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, secs).unwrap(),
+            lat: 37.77,
+            lon: -122.41,
+            height: 10.0,
+            h_accuracy: 0.01,
+            v_accuracy: 0.02,
+            n_sats: 20,
+            mode,
+        }
+    }
+
+    fn write_track(path: &Path, format: TrackFormat) {
+        let mut track = TrackWriter::new(path, format).unwrap();
+        track.write_point(point(GnssModes::Spp, 0)).unwrap();
+        track.write_point(point(GnssModes::Float, 1)).unwrap();
+        track.write_point(point(GnssModes::Fixed, 2)).unwrap();
+        track.write_point(point(GnssModes::Fixed, 3)).unwrap();
+        track.finish().unwrap();
+    }
+
+    #[test]
+    fn track_point_requires_fix_and_time_test() {
+        let msg = MsgPosLlh {
+            sender_id: Some(1337),
+            tow: 1337,
+            lat: 37.77,
+            lon: -122.41,
+            height: 10.0,
+            h_accuracy: 10,
+            v_accuracy: 20,
+            n_sats: 20,
+            flags: 0x4,
+        };
+        let time = Some(Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(
+            TrackPoint::from_pos_llh(&PosLLH::MsgPosLlh(msg.clone()), time),
+            Some(point(GnssModes::Fixed, 0))
+        );
+        assert!(TrackPoint::from_pos_llh(&PosLLH::MsgPosLlh(msg.clone()), None).is_none());
+        let no_fix = MsgPosLlh { flags: 0, ..msg };
+        assert!(TrackPoint::from_pos_llh(&PosLLH::MsgPosLlh(no_fix), time).is_none());
+    }
+
+    #[test]
+    fn solution_time_test() {
+        let utc = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        // 2022-01-01 is saturday of GPS week 2190
+        let tow = 6 * 24 * 60 * 60 * 1000;
+        assert_eq!(
+            solution_time(tow, Some((tow, utc)), Some((tow, 2190))),
+            Some(utc)
+        );
+        // the UTC time is from the previous epoch, use GPS time less the leap seconds
+        let gps = Utc.with_ymd_and_hms(2021, 12, 31, 23, 59, 43).unwrap();
+        assert_eq!(
+            solution_time(tow + 1000, Some((tow, utc)), Some((tow, 2190))),
+            Some(gps)
+        );
+        assert_eq!(solution_time(tow + 1000, Some((tow, utc)), None), None);
+        // rolled over into the next week
+        let next_week = Utc.with_ymd_and_hms(2022, 1, 1, 23, 59, 42).unwrap();
+        assert_eq!(
+            solution_time(0, None, Some((MS_PER_WEEK - 1000, 2190))),
+            Some(next_week)
+        );
+    }
+
+    #[test]
+    fn gpx_segments_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("track.gpx");
+        write_track(&path, TrackFormat::GPX);
+        let gpx = std::fs::read_to_string(path).unwrap();
+        assert_eq!(gpx.matches("<trkseg>").count(), 3);
+        assert_eq!(gpx.matches("<trkpt ").count(), 4);
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+    }
+
+    #[test]
+    fn kml_segments_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("track.kml");
+        write_track(&path, TrackFormat::KML);
+        let kml = std::fs::read_to_string(path).unwrap();
+        assert_eq!(kml.matches("<Placemark>").count(), 3);
+        assert_eq!(kml.matches("<when>").count(), 4);
+        assert!(kml.contains(&format!(
+            "<styleUrl>#{}</styleUrl>",
+            kml_style_id(GnssModes::Fixed)
+        )));
+        assert_eq!(kml_color("#00FF00"), "ff00ff00");
+        assert_eq!(kml_color("#FF0000"), "ff0000ff");
+    }
+
+    #[test]
+    fn geojson_features_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("track.geojson");
+        write_track(&path, TrackFormat::GEOJSON);
+        let value: serde_json::Value = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        let features = value["features"].as_array().unwrap();
+        // four points plus a line for the only segment with more than one point
+        assert_eq!(features.len(), 5);
+        let line = features.last().unwrap();
+        assert_eq!(line["geometry"]["type"], "LineString");
+        assert_eq!(line["properties"]["mode"], GnssModes::Fixed.to_string());
+    }
+}
@@ -1274,14 +1274,22 @@ impl Event for PosLLH {
 /// Struct with shared fields for various Protection Level Message types.
 #[allow(clippy::upper_case_acronyms)]
 pub struct ProtectionLevelFields {
+    pub tow: u32,
     pub lat: f64,
     pub lon: f64,
     pub hpl: u16,
+    pub vpl: u16,
 }
 
 impl ProtectionLevelFields {
-    pub fn new(lat: f64, lon: f64, hpl: u16) -> Self {
-        Self { lat, lon, hpl }
+    pub fn new(tow: u32, lat: f64, lon: f64, hpl: u16, vpl: u16) -> Self {
+        Self {
+            tow,
+            lat,
+            lon,
+            hpl,
+            vpl,
+        }
     }
 }
 
@@ -1297,10 +1305,22 @@ impl ProtectionLevel {
     /// Return protection level fields
     pub fn fields(&self) -> ProtectionLevelFields {
         match self {
-            Self::MsgProtLvl(MsgProtectionLevel { lat, lon, hpl, .. })
-            | Self::MsgProtLvlDepA(MsgProtectionLevelDepA { lat, lon, hpl, .. }) => {
-                ProtectionLevelFields::new(*lat, *lon, *hpl)
-            }
+            Self::MsgProtLvl(MsgProtectionLevel {
+                tow,
+                lat,
+                lon,
+                hpl,
+                vpl,
+                ..
+            })
+            | Self::MsgProtLvlDepA(MsgProtectionLevelDepA {
+                tow,
+                lat,
+                lon,
+                hpl,
+                vpl,
+                ..
+            }) => ProtectionLevelFields::new(*tow, *lat, *lon, *hpl, *vpl),
         }
     }
 }
@@ -1322,6 +1342,7 @@ impl Event for ProtectionLevel {
 
 /// Struct with shared fields for various Dops Message types.
 pub struct DopsFields {
+    pub tow: u32,
     pub pdop: u16,
     pub gdop: u16,
     pub tdop: u16,
@@ -1338,15 +1359,16 @@ pub enum Dops {
 
 impl Dops {
     pub fn fields(self) -> DopsFields {
-        let (pdop, gdop, tdop, hdop, vdop, flags) = match self {
+        let (tow, pdop, gdop, tdop, hdop, vdop, flags) = match self {
             Dops::MsgDops(msg_) => (
-                msg_.pdop, msg_.gdop, msg_.tdop, msg_.hdop, msg_.vdop, msg_.flags,
+                msg_.tow, msg_.pdop, msg_.gdop, msg_.tdop, msg_.hdop, msg_.vdop, msg_.flags,
+            ),
+            Dops::MsgDopsDepA(msg_) => (
+                msg_.tow, msg_.pdop, msg_.gdop, msg_.tdop, msg_.hdop, msg_.vdop, 1_u8,
             ),
-            Dops::MsgDopsDepA(msg_) => {
-                (msg_.pdop, msg_.gdop, msg_.tdop, msg_.hdop, msg_.vdop, 1_u8)
-            }
         };
         DopsFields {
+            tow,
             pdop,
             gdop,
             tdop,
@@ -1372,6 +1394,7 @@ impl Event for Dops {
 /// Struct with shared fields for various GpsTime Message types.
 pub struct GpsTimeFields {
     pub wn: u16,
+    pub tow: u32,
     pub ns_residual: i32,
     pub flags: u8,
 }
@@ -1384,12 +1407,13 @@ pub enum GpsTime {
 
 impl GpsTime {
     pub fn fields(self) -> GpsTimeFields {
-        let (wn, ns_residual, flags) = match self {
-            GpsTime::MsgGpsTime(msg_) => (msg_.wn, msg_.ns_residual, msg_.flags),
-            GpsTime::MsgGpsTimeDepA(msg_) => (msg_.wn, msg_.ns_residual, msg_.flags),
+        let (wn, tow, ns_residual, flags) = match self {
+            GpsTime::MsgGpsTime(msg_) => (msg_.wn, msg_.tow, msg_.ns_residual, msg_.flags),
+            GpsTime::MsgGpsTimeDepA(msg_) => (msg_.wn, msg_.tow, msg_.ns_residual, msg_.flags),
         };
         GpsTimeFields {
             wn,
+            tow,
             ns_residual,
             flags,
         }
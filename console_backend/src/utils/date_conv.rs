@@ -134,6 +134,24 @@ pub fn convert_local_time_to_logging_format() -> (String, f64) {
     (t_local_date, t_local_secs)
 }
 
+/// Returns local time formatted for the pc_time column of CSV logs.
+pub fn local_time_logging_string() -> String {
+    let (tloc, secloc) = convert_local_time_to_logging_format();
+    format!("{tloc}:{secloc:0>6.06}")
+}
+
+/// Returns gps time formatted for the gps_time column of CSV logs.
+///
+/// # Parameters
+/// - `week`: The week number.
+/// - `gnss_tow`: The GPS time of week in seconds.
+pub fn gps_time_logging_string(week: Option<u16>, gnss_tow: f64) -> Option<String> {
+    match convert_gps_time_to_logging_format(week, gnss_tow) {
+        (Some(tgps), Some(secgps)) => Some(format!("{tgps}:{secgps:0>6.06}")),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,39 +46,6 @@ pub fn app_dir() -> crate::types::Result<PathBuf> {
         .map(Path::to_path_buf)
 }
 
-/// Returns directory to packaged python, or workspace when ran locally in dev environment
-/// This is used to locate rtcm3tosbp
-pub fn pythonhome_dir() -> crate::types::Result<PathBuf> {
-    let app_dir = app_dir()?;
-    // If dev environment, hard code check to py311 path "${WORKSPACE}\\py311"
-    // Mac and Linux both share python3 in "${WORKSPACE}/py311/bin"
-    let py311 = if cfg!(target_os = "windows") {
-        Some(app_dir.as_path())
-    } else {
-        app_dir.parent()
-    };
-    if let Some(py311) = py311 {
-        // if we are in the "${WORKSPACE}/py311" directory,
-        // we are in dev environment, move up one folder.
-        if py311.file_name().filter(|&x| x.eq("py311")).is_some() {
-            let workspace = py311
-                .parent()
-                .ok_or(anyhow::format_err!("no workspace found?"));
-            return workspace.map(Path::to_path_buf);
-        }
-        // if compiled on mac, exe should be in "Swift Console.app/MacOS/Swift Console"
-        // app_dir gives "Swift Console.app/MacOS"
-        // returns "Swift Console.app/Resources/lib"
-        if cfg!(target_os = "macos") {
-            let resources = py311.join("Resources/lib");
-            if resources.exists() {
-                return Ok(py311.join("Resources"));
-            }
-        }
-    }
-    Ok(app_dir)
-}
-
 /// Formats DOPS field into string, used in SolutionPositionTab
 pub fn dops_into_string(field: u16) -> String {
     format!("{:.1}", field as f64 * DILUTION_OF_PRECISION_UNITS)
@@ -271,6 +238,31 @@ pub fn refresh_loggingbar(client_sender: &BoxedClientSender, shared_state: &Shar
         prevous_folders.set(i as u32, folder);
     }
 
+    let csv_loggers = shared_state.csv_loggers();
+    let mut selected_loggers = logging_bar_status
+        .reborrow()
+        .init_csv_loggers(csv_loggers.len() as u32);
+    for (i, logger) in csv_loggers.iter().enumerate() {
+        selected_loggers.set(i as u32, &logger.to_string());
+    }
+
+    let track_formats = shared_state.track_formats();
+    let mut selected_formats = logging_bar_status
+        .reborrow()
+        .init_track_formats(track_formats.len() as u32);
+    for (i, format) in track_formats.iter().enumerate() {
+        selected_formats.set(i as u32, &format.to_string());
+    }
+    logging_bar_status.set_rinex_logging(shared_state.rinex_logging());
+    let sbp_json_options = shared_state.sbp_json_options();
+    let mut selected_options = logging_bar_status
+        .reborrow()
+        .init_sbp_json_options(sbp_json_options.len() as u32);
+    for (i, option) in sbp_json_options.iter().enumerate() {
+        selected_options.set(i as u32, &option.to_string());
+    }
+    logging_bar_status.set_log_schedule(&shared_state.log_schedule_status().unwrap_or_default());
+
     client_sender.send_data(serialize_capnproto_builder(builder));
 }
 
@@ -290,6 +282,17 @@ pub fn start_recording(client_sender: &BoxedClientSender, file_name: String) {
     client_sender.send_data(serialize_capnproto_builder(builder));
 }
 
+/// Tell the frontend whether the credentials store is unlocked, prompting for the
+/// passphrase when it isn't.
+pub fn send_credentials_status(client_sender: &BoxedClientSender, unlocked: bool, detail: &str) {
+    let mut builder = Builder::new_default();
+    let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
+    let mut packet = msg.init_credentials_status();
+    packet.set_unlocked(unlocked);
+    packet.set_detail(detail);
+    client_sender.send_data(serialize_capnproto_builder(builder));
+}
+
 pub fn send_settings_import_response(client_sender: &BoxedClientSender, status: &str) {
     let mut builder = Builder::new_default();
     let msg = builder.init_root::<crate::console_backend_capnp::message::Builder>();
@@ -30,7 +30,7 @@ import QtQuick.Layouts
 MainTab {
     id: advancedTab
 
-    subTabNames: Globals.enableNtrip ? ["System Monitor", "IMU", "Magnetometer", "Networking", "Spectrum Analyzer", "INS", "NTRIP"] : ["System Monitor", "IMU", "Magnetometer", "Networking", "Spectrum Analyzer", "INS"]
+    subTabNames: Globals.enableNtrip ? ["System Monitor", "IMU", "Magnetometer", "Networking", "Spectrum Analyzer", "INS", "NTRIP", "NTRIP Server"] : ["System Monitor", "IMU", "Magnetometer", "Networking", "Spectrum Analyzer", "INS"]
     curSubTabIndex: 0
 
     StackLayout {
@@ -59,5 +59,8 @@ MainTab {
 
         AdvancedTabComponents.NtripClientTab {
         }
+
+        AdvancedTabComponents.NtripServerTab {
+        }
     }
 }
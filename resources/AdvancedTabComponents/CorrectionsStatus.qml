@@ -0,0 +1,126 @@
+/****************************************************************************
+ **
+ ** Copyright (c) 2022 Swift Navigation
+ **
+ ** Permission is hereby granted, free of charge, to any person obtaining a copy of
+ ** this software and associated documentation files (the "Software"), to deal in
+ ** the Software without restriction, including without limitation the rights to
+ ** use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ ** the Software, and to permit persons to whom the Software is furnished to do so,
+ ** subject to the following conditions:
+ **
+ ** The above copyright notice and this permission notice shall be included in all
+ ** copies or substantial portions of the Software.
+ **
+ ** THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ ** IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ ** FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ ** COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ ** IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ ** CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ **
+ ****************************************************************************/
+import "../Constants"
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import SwiftConsole
+
+Item {
+    id: correctionsStatus
+
+    property variant rtcmMessages: []
+    property variant constellations: []
+    property int stationId: -1
+    property variant basePosition: []
+    property real latency: -1
+    property real age: -1
+    property int gaps: 0
+    property real longestGap: 0
+    property int crcErrors: 0
+    property real bytes: 0
+
+    function seconds(value) {
+        return value < 0 ? "-" : value.toFixed(1) + " s";
+    }
+
+    function formatBytes(bytes) {
+        if (bytes >= 1048576)
+            return (bytes / 1048576).toFixed(1) + " MB";
+        if (bytes >= 1024)
+            return (bytes / 1024).toFixed(1) + " kB";
+        return bytes + " B";
+    }
+
+    CorrectionsStatusData {
+        id: correctionsStatusData
+
+        function update() {
+            corrections_status_model.fill_console_points(correctionsStatusData);
+            correctionsStatus.rtcmMessages = correctionsStatusData.rtcm_messages;
+            correctionsStatus.constellations = correctionsStatusData.constellations;
+            correctionsStatus.stationId = correctionsStatusData.station_id;
+            correctionsStatus.basePosition = correctionsStatusData.base_position;
+            correctionsStatus.latency = correctionsStatusData.latency;
+            correctionsStatus.age = correctionsStatusData.age;
+            correctionsStatus.gaps = correctionsStatusData.gaps;
+            correctionsStatus.longestGap = correctionsStatusData.longest_gap;
+            correctionsStatus.crcErrors = correctionsStatusData.crc_errors;
+            correctionsStatus.bytes = correctionsStatusData.bytes;
+        }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+
+        Label {
+            text: "Corrections"
+            font.bold: true
+        }
+
+        Label {
+            text: "Base station: " + (stationId < 0 ? "-" : stationId) + (basePosition.length == 3 ? "  " + basePosition[0].toFixed(7) + ", " + basePosition[1].toFixed(7) + ", " + basePosition[2].toFixed(2) + " m" : "  no 1005/1006 received")
+            color: basePosition.length == 3 ? "black" : "orange"
+        }
+
+        Label {
+            text: "Observation latency: " + seconds(latency) + "  Last message: " + seconds(age)
+        }
+
+        Label {
+            text: "Gaps: " + gaps + "  Longest gap: " + longestGap.toFixed(1) + " s  CRC errors: " + crcErrors + "  Received: " + formatBytes(bytes)
+        }
+
+        Label {
+            text: "Constellations"
+            font.bold: true
+        }
+
+        Repeater {
+            model: constellations
+
+            Label {
+                text: modelData[0] + ": MSM" + modelData[1] + ", " + modelData[2] + " satellites, " + modelData[3] + " signals, " + seconds(modelData[4]) + " ago"
+                color: modelData[1] >= 7 ? "black" : "orange"
+            }
+        }
+
+        Label {
+            text: "Messages"
+            font.bold: true
+        }
+
+        Repeater {
+            model: rtcmMessages
+
+            Label {
+                text: modelData[0] + ": " + modelData[1] + " received, " + modelData[2].toFixed(2) + " Hz, " + seconds(modelData[3]) + " ago"
+                font.family: Constants.genericTable.fontFamily
+            }
+        }
+
+        Item {
+            Layout.fillHeight: true
+        }
+    }
+}
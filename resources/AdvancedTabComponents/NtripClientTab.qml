@@ -13,6 +13,14 @@ Item {
     property var floatValidator
     property var intValidator
     property var stringValidator
+    // Request to send again once the passphrase dialog unlocks the credentials store.
+    property var retryAfterUnlock: null
+    property bool unlocking: false
+
+    function sendWithCredentials(credential, request) {
+        retryAfterUnlock = credential ? request : null;
+        request();
+    }
 
     RowLayout {
         anchors.fill: parent
@@ -56,6 +64,47 @@ Item {
                 }
             }
 
+            RowLayout {
+                height: 30
+
+                Label {
+                    text: "Credential: "
+                    Layout.alignment: Qt.AlignVCenter | Qt.AlignLeft
+                }
+
+                TextField {
+                    id: credentialField
+
+                    width: 400
+                    Layout.fillWidth: true
+                    placeholderText: "Saved credential name"
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Use the username and password saved under this name, leave empty to use the fields above"
+                    font.family: Constants.genericTable.fontFamily
+                    font.pixelSize: Constants.largePixelSize
+                    selectByMouse: true
+                    Layout.alignment: Qt.AlignVCenter | Qt.AlignRight
+                    validator: stringValidator
+                    readOnly: connected
+                }
+
+                SwiftButton {
+                    text: "Save"
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Save the username and password above in the encrypted credentials store under this name"
+                    enabled: !connected && credentialField.text.length > 0
+                    onClicked: {
+                        let name = credentialField.text;
+                        let username = generalRepeater.itemAt(1).children[1].text;
+                        let password = generalRepeater.itemAt(2).children[1].text;
+                        sendWithCredentials(name, function() {
+                            backend_request_broker.save_credential(name, username, password);
+                        });
+                        generalRepeater.itemAt(2).children[1].text = "";
+                    }
+                }
+            }
+
             Repeater {
                 id: positionRepeater
 
@@ -114,6 +163,38 @@ Item {
                 enabled: !connected
             }
 
+            CheckBox {
+                id: autoMountpoint
+
+                text: "Auto mountpoint"
+                ToolTip.visible: hovered
+                ToolTip.text: "Select the nearest RTCM3 mountpoint from the caster sourcetable and switch as the rover moves"
+                enabled: !connected
+            }
+
+            ComboBox {
+                id: ntripVersion
+
+                editable: false
+                enabled: !connected
+                ToolTip.visible: hovered
+                ToolTip.text: "NTRIP protocol version, Auto tries v2 and falls back to v1. Use an https:// URL for TLS casters."
+
+                model: ListModel {
+                    ListElement {
+                        text: "Auto"
+                    }
+
+                    ListElement {
+                        text: "v1"
+                    }
+
+                    ListElement {
+                        text: "v2"
+                    }
+                }
+            }
+
             ComboBox {
                 id: outputType
 
@@ -142,6 +223,91 @@ Item {
                 color: "red"
             }
 
+            RowLayout {
+                SwiftButton {
+                    text: "Browse"
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Fetch the mountpoint list from the caster"
+                    enabled: !connected
+                    onClicked: {
+                        let url = generalRepeater.itemAt(0).children[1].text;
+                        if (!url) {
+                            inputErrorLabel.text = "URL is not provided!";
+                            inputErrorLabel.visible = true;
+                            return;
+                        }
+                        let username = generalRepeater.itemAt(1).children[1].text;
+                        let password = generalRepeater.itemAt(2).children[1].text;
+                        sourcetableLabel.text = "Fetching sourcetable...";
+                        let credential = credentialField.text;
+                        sendWithCredentials(credential, function() {
+                            backend_request_broker.ntrip_fetch_sourcetable(url, username, password, credential);
+                        });
+                        inputErrorLabel.visible = false;
+                    }
+                }
+
+                ComboBox {
+                    id: mountpointCombo
+
+                    property var mountpoints: []
+
+                    Layout.preferredWidth: 400
+                    enabled: !connected && mountpoints.length > 0
+                    displayText: currentIndex < 0 ? "Mountpoint" : currentText
+                    model: mountpoints.map(function(mp) {
+                        let desc = mp.name + " (" + mp.format + ", " + mp.navSystems;
+                        if (mp.distance >= 0)
+                            desc += ", " + mp.distance.toFixed(1) + " km";
+                        if (mp.nmea)
+                            desc += ", NMEA";
+                        return desc + ")";
+                    })
+                    onActivated: {
+                        let urlField = generalRepeater.itemAt(0).children[1];
+                        let root = urlField.text.match(/^(\w+:\/\/)?[^\/]+/);
+                        if (root)
+                            urlField.text = root[0] + "/" + mountpoints[currentIndex].name;
+                    }
+                }
+            }
+
+            Label {
+                id: connectionStateLabel
+
+                property string connectionState: "Disconnected"
+                property string detail: ""
+
+                Layout.maximumWidth: 500
+                wrapMode: Text.Wrap
+                text: "State: " + connectionState + (detail ? " (" + detail + ")" : "")
+                font.family: Constants.genericTable.fontFamily
+                font.pixelSize: Constants.largePixelSize
+                color: connectionState == "Failed" ? "red" : (connectionState == "Stalled" || connectionState == "Backoff") ? "orange" : "black"
+            }
+
+            Label {
+                id: credentialsLabel
+
+                property bool unlocked: true
+
+                visible: text.length > 0
+                Layout.maximumWidth: 500
+                wrapMode: Text.Wrap
+                text: ""
+                font.family: Constants.genericTable.fontFamily
+                font.pixelSize: Constants.largePixelSize
+                color: unlocked ? "black" : "red"
+            }
+
+            Label {
+                id: sourcetableLabel
+
+                text: ""
+                font.family: Constants.genericTable.fontFamily
+                font.pixelSize: Constants.largePixelSize
+            }
+
             RowLayout {
                 SwiftButton {
                     invertColor: true
@@ -181,7 +347,12 @@ Item {
                             }
                         }
                         let output_type = outputType.currentText;
-                        backend_request_broker.ntrip_connect(url, username, password, ggaPeriod, lat, lon, alt, output_type);
+                        let autoSelect = autoMountpoint.checked;
+                        let version = ntripVersion.currentText;
+                        let credential = credentialField.text;
+                        sendWithCredentials(credential, function() {
+                            backend_request_broker.ntrip_connect(url, username, password, ggaPeriod, lat, lon, alt, output_type, autoSelect, version, credential);
+                        });
                         connected = true;
                         inputErrorLabel.visible = false;
                     }
@@ -204,19 +375,103 @@ Item {
                 }
             }
         }
+
+        CorrectionsStatus {
+            Layout.fillHeight: true
+            Layout.fillWidth: true
+        }
+    }
+
+    Dialog {
+        id: passphraseDialog
+
+        parent: Overlay.overlay
+        title: "Unlock credentials"
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        anchors.centerIn: parent
+        modal: true
+        closePolicy: Popup.CloseOnEscape
+        focus: true
+        onOpened: passphraseField.forceActiveFocus()
+        onAccepted: {
+            unlocking = true;
+            backend_request_broker.unlock_credentials(passphraseField.text);
+            passphraseField.text = "";
+        }
+        onRejected: {
+            retryAfterUnlock = null;
+            passphraseField.text = "";
+        }
+
+        contentItem: ColumnLayout {
+            Label {
+                Layout.maximumWidth: 400
+                wrapMode: Text.Wrap
+                text: "Enter the passphrase of the credentials store to use saved credentials."
+            }
+
+            TextField {
+                id: passphraseField
+
+                Layout.fillWidth: true
+                placeholderText: "Passphrase"
+                echoMode: TextInput.Password
+                selectByMouse: true
+                onAccepted: passphraseDialog.accept()
+            }
+        }
     }
 
     NtripStatusData {
         id: ntripStatusData
 
         signal ntrip_connected(bool connected)
+        signal ntrip_sourcetable(var mountpoints, string selected, string error)
+        signal ntrip_connection_state(string state, string detail)
+        signal credentials_status(bool unlocked, string detail)
 
         function setConnection(connected) {
             ntripClientTab.connected = connected;
         }
 
+        function setConnectionState(state, detail) {
+            connectionStateLabel.connectionState = state;
+            connectionStateLabel.detail = detail;
+        }
+
+        function setCredentialsStatus(unlocked, detail) {
+            credentialsLabel.unlocked = unlocked;
+            credentialsLabel.text = "Credentials: " + detail;
+            if (!unlocked) {
+                unlocking = false;
+                passphraseDialog.open();
+                return;
+            }
+            let retry = unlocking ? retryAfterUnlock : null;
+            unlocking = false;
+            retryAfterUnlock = null;
+            if (retry)
+                retry();
+        }
+
+        function setSourcetable(mountpoints, selected, error) {
+            if (error) {
+                sourcetableLabel.text = "Sourcetable: " + error;
+                return;
+            }
+            if (mountpoints.length > 0)
+                mountpointCombo.mountpoints = mountpoints;
+            if (selected)
+                sourcetableLabel.text = "Mountpoint: " + selected;
+            else
+                sourcetableLabel.text = mountpoints.length + " mountpoints";
+        }
+
         Component.onCompleted: {
             ntripStatusData.ntrip_connected.connect(setConnection);
+            ntripStatusData.ntrip_sourcetable.connect(setSourcetable);
+            ntripStatusData.ntrip_connection_state.connect(setConnectionState);
+            ntripStatusData.credentials_status.connect(setCredentialsStatus);
         }
     }
 
@@ -0,0 +1,258 @@
+/****************************************************************************
+ **
+ ** Copyright (c) 2022 Swift Navigation
+ **
+ ** Permission is hereby granted, free of charge, to any person obtaining a copy of
+ ** this software and associated documentation files (the "Software"), to deal in
+ ** the Software without restriction, including without limitation the rights to
+ ** use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ ** the Software, and to permit persons to whom the Software is furnished to do so,
+ ** subject to the following conditions:
+ **
+ ** The above copyright notice and this permission notice shall be included in all
+ ** copies or substantial portions of the Software.
+ **
+ ** THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ ** IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ ** FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ ** COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ ** IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ ** CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ **
+ ****************************************************************************/
+import "../BaseComponents"
+import "../Constants"
+import QtQuick 2.15
+import QtQuick.Controls 2.15
+import QtQuick.Layouts 1.15
+import SwiftConsole 1.0
+
+Item {
+    id: ntripServerTab
+
+    property bool connected: false
+    property var stringValidator
+
+    function formatBytes(bytes) {
+        if (bytes >= 1048576)
+            return (bytes / 1048576).toFixed(1) + " MB";
+        if (bytes >= 1024)
+            return (bytes / 1024).toFixed(1) + " kB";
+        return bytes + " B";
+    }
+
+    RowLayout {
+        anchors.fill: parent
+
+        ColumnLayout {
+            Layout.alignment: Qt.AlignTop
+
+            Repeater {
+                id: generalRepeater
+
+                model: ["Url", "Username", "Password"]
+
+                RowLayout {
+                    height: 30
+
+                    Label {
+                        text: modelData + ": "
+                        Layout.alignment: Qt.AlignVCenter | Qt.AlignLeft
+                    }
+
+                    TextField {
+                        width: 400
+                        Layout.fillWidth: true
+                        placeholderText: modelData == "Url" ? "caster:2101/MOUNTPOINT" : modelData
+                        echoMode: modelData == "Password" ? TextInput.Password : TextInput.Normal
+                        font.family: Constants.genericTable.fontFamily
+                        font.pixelSize: Constants.largePixelSize
+                        selectByMouse: true
+                        Layout.alignment: Qt.AlignVCenter | Qt.AlignRight
+                        validator: stringValidator
+                        readOnly: connected
+                    }
+                }
+            }
+
+            RowLayout {
+                height: 30
+
+                Label {
+                    text: "Credential: "
+                    Layout.alignment: Qt.AlignVCenter | Qt.AlignLeft
+                }
+
+                TextField {
+                    id: credentialField
+
+                    width: 400
+                    Layout.fillWidth: true
+                    placeholderText: "Saved credential name"
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Use the username and password saved under this name, leave empty to use the fields above"
+                    font.family: Constants.genericTable.fontFamily
+                    font.pixelSize: Constants.largePixelSize
+                    selectByMouse: true
+                    Layout.alignment: Qt.AlignVCenter | Qt.AlignRight
+                    validator: stringValidator
+                    readOnly: connected
+                }
+            }
+
+            ComboBox {
+                id: ntripVersion
+
+                editable: false
+                enabled: !connected
+                ToolTip.visible: hovered
+                ToolTip.text: "NTRIP protocol version, Auto tries a v2 POST and falls back to a v1 SOURCE request. v1 casters only use the password."
+
+                model: ListModel {
+                    ListElement {
+                        text: "Auto"
+                    }
+
+                    ListElement {
+                        text: "v1"
+                    }
+
+                    ListElement {
+                        text: "v2"
+                    }
+                }
+            }
+
+            Label {
+                id: inputErrorLabel
+
+                visible: false
+                text: ""
+                font.family: Constants.genericTable.fontFamily
+                font.pixelSize: Constants.largePixelSize
+                color: "red"
+            }
+
+            RowLayout {
+                SwiftButton {
+                    invertColor: true
+                    icon.width: 10
+                    icon.height: 10
+                    icon.source: Constants.icons.playPath
+                    icon.color: Constants.materialGrey
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Start publishing the device's observations and base position as SBP, RTCM output isn't supported yet"
+                    enabled: !connected
+                    onClicked: {
+                        let url = generalRepeater.itemAt(0).children[1].text;
+                        if (!url) {
+                            inputErrorLabel.text = "URL is not provided!";
+                            inputErrorLabel.visible = true;
+                            return;
+                        }
+                        let username = generalRepeater.itemAt(1).children[1].text;
+                        let password = generalRepeater.itemAt(2).children[1].text;
+                        backend_request_broker.ntrip_server_connect(url, username, password, ntripVersion.currentText, credentialField.text);
+                        connected = true;
+                        inputErrorLabel.visible = false;
+                    }
+                }
+
+                SwiftButton {
+                    invertColor: true
+                    icon.width: 10
+                    icon.height: 10
+                    icon.source: Constants.icons.pauseButtonUrl
+                    icon.color: Constants.materialGrey
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Stop"
+                    enabled: connected
+                    onClicked: {
+                        backend_request_broker.ntrip_server_disconnect();
+                        connected = false;
+                        inputErrorLabel.visible = false;
+                    }
+                }
+            }
+        }
+
+        ColumnLayout {
+            Layout.alignment: Qt.AlignTop
+            Layout.fillWidth: true
+
+            Label {
+                id: connectionStateLabel
+
+                property string connectionState: "Disconnected"
+                property string detail: ""
+
+                Layout.maximumWidth: 500
+                wrapMode: Text.Wrap
+                text: "State: " + connectionState + (detail ? " (" + detail + ")" : "")
+                font.family: Constants.genericTable.fontFamily
+                font.pixelSize: Constants.largePixelSize
+                color: connectionState == "Failed" ? "red" : (connectionState == "Stalled" || connectionState == "Backoff") ? "orange" : "black"
+            }
+
+            GridLayout {
+                id: statsGrid
+
+                property var status: ({
+                    "bytesSent": 0,
+                    "messagesSent": 0,
+                    "dropped": 0,
+                    "rate": 0,
+                    "reconnects": 0,
+                    "uptime": 0
+                })
+
+                columns: 2
+
+                Repeater {
+                    model: ["Sent", "Messages", "Rate", "Dropped", "Reconnects", "Uptime"]
+
+                    Label {
+                        Layout.column: 0
+                        Layout.row: index
+                        text: modelData + ":"
+                        font.family: Constants.genericTable.fontFamily
+                        font.pixelSize: Constants.largePixelSize
+                    }
+                }
+
+                Repeater {
+                    model: [formatBytes(statsGrid.status.bytesSent), statsGrid.status.messagesSent, formatBytes(Math.round(statsGrid.status.rate)) + "/s", statsGrid.status.dropped, statsGrid.status.reconnects, Math.floor(statsGrid.status.uptime) + " s"]
+
+                    Label {
+                        Layout.column: 1
+                        Layout.row: index
+                        text: modelData
+                        font.family: Constants.genericTable.fontFamily
+                        font.pixelSize: Constants.largePixelSize
+                    }
+                }
+            }
+        }
+    }
+
+    NtripServerStatusData {
+        id: ntripServerStatusData
+
+        signal ntrip_server_status(var status)
+
+        function setStatus(status) {
+            connectionStateLabel.connectionState = status.state;
+            connectionStateLabel.detail = status.detail;
+            statsGrid.status = status;
+            if (status.state == "Disconnected" || status.state == "Failed")
+                ntripServerTab.connected = false;
+        }
+
+        Component.onCompleted: {
+            ntripServerStatusData.ntrip_server_status.connect(setStatus);
+        }
+    }
+
+    stringValidator: RegularExpressionValidator {
+    }
+}
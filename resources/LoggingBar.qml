@@ -96,6 +96,68 @@ Rectangle {
             onClicked: backend_request_broker.logging_bar([csvLoggingButton.checked, sbpLoggingButton.checked, sbpLoggingFormat.currentText], folderPathBar.editText)
         }
 
+        SwiftButton {
+            id: csvLoggersButton
+
+            Layout.preferredWidth: parent.preferredButtonHeight * 0.6
+            Layout.preferredHeight: parent.preferredButtonHeight
+            invertColor: true
+            text: "\u25BE"
+            ToolTip.visible: hovered
+            ToolTip.text: "Select CSV Logs"
+            visible: Globals.showCsvLog
+            onClicked: csvLoggersMenu.popup(csvLoggersButton, 0, csvLoggersButton.height)
+
+            Menu {
+                id: csvLoggersMenu
+
+                Repeater {
+                    model: loggingBarData.csv_logger_labels
+
+                    MenuItem {
+                        text: modelData
+                        checkable: true
+                        checked: loggingBarData.csv_loggers.indexOf(modelData) !== -1
+                        onTriggered: {
+                            let loggers = loggingBarData.csv_loggers.filter(logger => logger !== modelData);
+                            if (checked)
+                                loggers.push(modelData);
+                            backend_request_broker.logging_bar_csv_loggers(loggers);
+                        }
+                    }
+                }
+
+                MenuSeparator {
+                }
+
+                Repeater {
+                    model: loggingBarData.track_format_labels
+
+                    MenuItem {
+                        text: modelData + " Track"
+                        checkable: true
+                        checked: loggingBarData.track_formats.indexOf(modelData) !== -1
+                        onTriggered: {
+                            let formats = loggingBarData.track_formats.filter(format => format !== modelData);
+                            if (checked)
+                                formats.push(modelData);
+                            backend_request_broker.logging_bar_track_formats(formats);
+                        }
+                    }
+                }
+
+                MenuSeparator {
+                }
+
+                MenuItem {
+                    text: "RINEX OBS/NAV"
+                    checkable: true
+                    checked: loggingBarData.rinex_logging
+                    onTriggered: backend_request_broker.logging_bar_rinex(checked)
+                }
+            }
+        }
+
         SwiftButton {
             id: sbpLoggingButton
 
@@ -111,6 +173,57 @@ Rectangle {
             onClicked: backend_request_broker.logging_bar([csvLoggingButton.checked, sbpLoggingButton.checked, sbpLoggingFormat.currentText], folderPathBar.editText)
         }
 
+        SwiftButton {
+            id: annotationButton
+
+            Layout.preferredHeight: parent.preferredButtonHeight
+            invertColor: true
+            text: "Mark"
+            ToolTip.visible: hovered
+            ToolTip.text: "Add an annotation to the logs"
+            onClicked: {
+                annotationPopup.open();
+                annotationText.forceActiveFocus();
+            }
+
+            Popup {
+                id: annotationPopup
+
+                y: -height
+                width: 300
+                focus: true
+
+                TextField {
+                    id: annotationText
+
+                    anchors.fill: parent
+                    placeholderText: "Annotation, Enter to add"
+                    onAccepted: {
+                        backend_request_broker.logging_bar_annotation(text.length > 0 ? text : "marker");
+                        text = "";
+                        annotationPopup.close();
+                    }
+                }
+            }
+        }
+
+        Label {
+            text: loggingBarData.log_schedule
+            visible: text.length > 0
+            Layout.alignment: Qt.AlignVCenter
+            font.pixelSize: Constants.mediumPixelSize
+            font.family: Constants.fontFamily
+            ToolTip.visible: scheduleMouseArea.containsMouse
+            ToolTip.text: "Logging schedule set from the command line or --log-schedule file"
+
+            MouseArea {
+                id: scheduleMouseArea
+
+                anchors.fill: parent
+                hoverEnabled: true
+            }
+        }
+
         RowLayout {
             property int spacerMargin: 5
             property color spacerColor: Constants.spacerColor
@@ -202,6 +315,47 @@ Rectangle {
                     }
                 }
 
+                SwiftButton {
+                    id: sbpJsonOptionsButton
+
+                    property var optionNames: ({
+                            "COMPACT": "Compact",
+                            "PAYLOAD": "Base64 Payload",
+                            "HOST_TIME": "Host Timestamp",
+                            "PIKSI_TOOLS": "piksi_tools Envelope"
+                        })
+
+                    Layout.preferredWidth: loggingBarRowLayout.preferredButtonHeight * 0.6
+                    Layout.preferredHeight: loggingBarRowLayout.preferredButtonHeight
+                    invertColor: true
+                    text: "\u25BE"
+                    enabled: !sbpLoggingButton.checked
+                    visible: sbpLoggingFormat.currentText == "SBP_JSON"
+                    ToolTip.visible: hovered
+                    ToolTip.text: "SBP-JSON Options"
+                    onClicked: sbpJsonOptionsMenu.popup(sbpJsonOptionsButton, 0, sbpJsonOptionsButton.height)
+
+                    Menu {
+                        id: sbpJsonOptionsMenu
+
+                        Repeater {
+                            model: loggingBarData.sbp_json_option_labels
+
+                            MenuItem {
+                                text: sbpJsonOptionsButton.optionNames[modelData] || modelData
+                                checkable: true
+                                checked: loggingBarData.sbp_json_options.indexOf(modelData) !== -1
+                                onTriggered: {
+                                    let options = loggingBarData.sbp_json_options.filter(option => option !== modelData);
+                                    if (checked)
+                                        options.push(modelData);
+                                    backend_request_broker.logging_bar_sbp_json_options(options);
+                                }
+                            }
+                        }
+                    }
+                }
+
                 SwiftComboBox {
                     id: recordingFilenameText
 
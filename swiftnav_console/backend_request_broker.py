@@ -320,6 +320,51 @@ class BackendRequestBroker(QObject):  # pylint: disable=too-many-instance-attrib
         buffer = m.to_bytes()
         self.endpoint.send_message(buffer)
 
+    @Slot(list)  # type: ignore
+    def logging_bar_csv_loggers(self, csv_loggers) -> None:
+        Message = self.messages.Message
+        m = Message()
+        m.loggingBarCsvLoggersFront = m.init(Message.Union.LoggingBarCsvLoggersFront)
+        m.loggingBarCsvLoggersFront.csvLoggers = csv_loggers
+        buffer = m.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot(list)  # type: ignore
+    def logging_bar_track_formats(self, track_formats) -> None:
+        Message = self.messages.Message
+        m = Message()
+        m.loggingBarTrackFormatsFront = m.init(Message.Union.LoggingBarTrackFormatsFront)
+        m.loggingBarTrackFormatsFront.trackFormats = track_formats
+        buffer = m.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot(list)  # type: ignore
+    def logging_bar_sbp_json_options(self, sbp_json_options) -> None:
+        Message = self.messages.Message
+        m = Message()
+        m.loggingBarSbpJsonOptionsFront = m.init(Message.Union.LoggingBarSbpJsonOptionsFront)
+        m.loggingBarSbpJsonOptionsFront.sbpJsonOptions = sbp_json_options
+        buffer = m.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot(bool)  # type: ignore
+    def logging_bar_rinex(self, rinex_logging: bool) -> None:
+        Message = self.messages.Message
+        m = Message()
+        m.loggingBarRinexFront = m.init(Message.Union.LoggingBarRinexFront)
+        m.loggingBarRinexFront.rinexLogging = rinex_logging
+        buffer = m.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot(str)  # type: ignore
+    def logging_bar_annotation(self, text: str) -> None:
+        Message = self.messages.Message
+        m = Message()
+        m.loggingBarAnnotationFront = m.init(Message.Union.LoggingBarAnnotationFront)
+        m.loggingBarAnnotationFront.text = text
+        buffer = m.to_bytes()
+        self.endpoint.send_message(buffer)
+
     @Slot(str)  # type: ignore
     def log_level(self, log_level) -> None:
         Message = self.messages.Message
@@ -346,7 +391,7 @@ class BackendRequestBroker(QObject):  # pylint: disable=too-many-instance-attrib
         buffer = m.to_bytes()
         self.endpoint.send_message(buffer)
 
-    @Slot(str, str, str, int, QTKeys.QVARIANT, QTKeys.QVARIANT, QTKeys.QVARIANT, str)  # type: ignore
+    @Slot(str, str, str, int, QTKeys.QVARIANT, QTKeys.QVARIANT, QTKeys.QVARIANT, str, bool, str, str)  # type: ignore
     def ntrip_connect(
         self,
         url: str,
@@ -357,6 +402,9 @@ class BackendRequestBroker(QObject):  # pylint: disable=too-many-instance-attrib
         lon: Optional[float],
         alt: Optional[float],
         output_type: str,
+        auto_mountpoint: bool,
+        ntrip_version: str,
+        credential: str,
     ) -> None:
         Message = self.messages.Message
         msg = self.messages.Message()
@@ -366,6 +414,9 @@ class BackendRequestBroker(QObject):  # pylint: disable=too-many-instance-attrib
         msg.ntripConnect.password = password
         msg.ntripConnect.ggaPeriod = gga_period
         msg.ntripConnect.outputType = output_type
+        msg.ntripConnect.autoMountpoint = auto_mountpoint
+        msg.ntripConnect.ntripVersion = ntrip_version
+        msg.ntripConnect.credential = credential
         if lat is not None and lon is not None and alt is not None:
             msg.ntripConnect.position.pos.lat = float(lat)
             msg.ntripConnect.position.pos.lon = float(lon)
@@ -375,6 +426,18 @@ class BackendRequestBroker(QObject):  # pylint: disable=too-many-instance-attrib
         buffer = msg.to_bytes()
         self.endpoint.send_message(buffer)
 
+    @Slot(str, str, str, str)  # type: ignore
+    def ntrip_fetch_sourcetable(self, url: str, username: str, password: str, credential: str) -> None:
+        Message = self.messages.Message
+        msg = self.messages.Message()
+        msg.ntripSourcetableRequest = msg.init(Message.Union.NtripSourcetableRequest)
+        msg.ntripSourcetableRequest.url = url
+        msg.ntripSourcetableRequest.username = username
+        msg.ntripSourcetableRequest.password = password
+        msg.ntripSourcetableRequest.credential = credential
+        buffer = msg.to_bytes()
+        self.endpoint.send_message(buffer)
+
     @Slot()  # type: ignore
     def ntrip_disconnect(self):
         Message = self.messages.Message
@@ -382,3 +445,44 @@ class BackendRequestBroker(QObject):  # pylint: disable=too-many-instance-attrib
         msg.ntripDisconnect = msg.init(Message.Union.NtripDisconnect)
         buffer = msg.to_bytes()
         self.endpoint.send_message(buffer)
+
+    @Slot(str, str, str, str, str)  # type: ignore
+    def ntrip_server_connect(self, url: str, username: str, password: str, ntrip_version: str, credential: str) -> None:
+        Message = self.messages.Message
+        msg = self.messages.Message()
+        msg.ntripServerConnect = msg.init(Message.Union.NtripServerConnect)
+        msg.ntripServerConnect.url = url
+        msg.ntripServerConnect.username = username
+        msg.ntripServerConnect.password = password
+        msg.ntripServerConnect.ntripVersion = ntrip_version
+        msg.ntripServerConnect.credential = credential
+        buffer = msg.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot()  # type: ignore
+    def ntrip_server_disconnect(self):
+        Message = self.messages.Message
+        msg = self.messages.Message()
+        msg.ntripServerDisconnect = msg.init(Message.Union.NtripServerDisconnect)
+        buffer = msg.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot(str, str, str)  # type: ignore
+    def save_credential(self, name: str, username: str, password: str) -> None:
+        Message = self.messages.Message
+        msg = self.messages.Message()
+        msg.saveCredential = msg.init(Message.Union.SaveCredential)
+        msg.saveCredential.name = name
+        msg.saveCredential.username = username
+        msg.saveCredential.password = password
+        buffer = msg.to_bytes()
+        self.endpoint.send_message(buffer)
+
+    @Slot(str)  # type: ignore
+    def unlock_credentials(self, passphrase: str) -> None:
+        Message = self.messages.Message
+        msg = self.messages.Message()
+        msg.unlockCredentials = msg.init(Message.Union.UnlockCredentials)
+        msg.unlockCredentials.passphrase = passphrase
+        buffer = msg.to_bytes()
+        self.endpoint.send_message(buffer)
@@ -53,6 +53,33 @@ class CsvLogging(str, Enum):
     ON = "ON"
 
 
+class CsvLogger(str, Enum):
+    POSITION = "POSITION"
+    VELOCITY = "VELOCITY"
+    BASELINE = "BASELINE"
+    DOPS = "DOPS"
+    TRACKING_CN0 = "TRACKING_CN0"
+    OBSERVATIONS = "OBSERVATIONS"
+    IMU = "IMU"
+    MAG = "MAG"
+    INS = "INS"
+    PROTECTION_LEVEL = "PROTECTION_LEVEL"
+    ANNOTATIONS = "ANNOTATIONS"
+
+
+class TrackFormat(str, Enum):
+    GPX = "GPX"
+    KML = "KML"
+    GEOJSON = "GEOJSON"
+
+
+class SbpJsonOption(str, Enum):
+    COMPACT = "COMPACT"
+    PAYLOAD = "PAYLOAD"
+    HOST_TIME = "HOST_TIME"
+    PIKSI_TOOLS = "PIKSI_TOOLS"
+
+
 class LogLevel(str, Enum):
     ERROR = "ERROR"
     WARNING = "WARNING"
@@ -103,6 +130,11 @@ class Keys(str, Enum):
     SBP_LOGGING_FORMAT = "SBP_LOGGING_FORMAT"
     SBP_LOGGING_FORMAT_INDEX = "SBP_LOGGING_FORMAT_INDEX"
     CSV_LOGGING = "CSV_LOGGING"
+    CSV_LOGGERS = "CSV_LOGGERS"
+    TRACK_FORMATS = "TRACK_FORMATS"
+    RINEX_LOGGING = "RINEX_LOGGING"
+    LOG_SCHEDULE = "LOG_SCHEDULE"
+    SBP_JSON_OPTIONS = "SBP_JSON_OPTIONS"
     SBP_LOGGING_LABELS = "SBP_LOGGING_LABELS"
     LOG_LEVEL_LABELS = "LOG_LEVEL_LABELS"
     FIELDS_DATA = "FIELDS_DATA"
@@ -159,6 +191,16 @@ class Keys(str, Enum):
     NOTIFICATION = "NOTIFICATION"
     SOLUTION_LINE = "SOLUTION_LINE"
     NTRIP_DISPLAY = "NTRIP_DISPLAY"
+    RTCM_MESSAGES = "RTCM_MESSAGES"
+    CONSTELLATIONS = "CONSTELLATIONS"
+    STATION_ID = "STATION_ID"
+    BASE_POSITION = "BASE_POSITION"
+    LATENCY = "LATENCY"
+    AGE = "AGE"
+    GAPS = "GAPS"
+    LONGEST_GAP = "LONGEST_GAP"
+    CRC_ERRORS = "CRC_ERRORS"
+    CORRECTION_BYTES = "CORRECTION_BYTES"
 
 
 class ConnectionState(str, Enum):
@@ -0,0 +1,194 @@
+# Copyright (c) 2022 Swift Navigation
+#
+# Permission is hereby granted, free of charge, to any person obtaining a copy of
+# this software and associated documentation files (the "Software"), to deal in
+# the Software without restriction, including without limitation the rights to
+# use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+# the Software, and to permit persons to whom the Software is furnished to do so,
+# subject to the following conditions:
+#
+# The above copyright notice and this permission notice shall be included in all
+# copies or substantial portions of the Software.
+#
+# THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+# IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+# FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+# COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+# IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+# CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+"""Corrections Status QObjects.
+"""
+
+from typing import Dict, List, Any
+
+from PySide6.QtCore import Property, QObject, Signal, Slot
+
+from .constants import Keys, QTKeys
+
+
+def corrections_status_update() -> Dict[str, Any]:
+    return {
+        Keys.RTCM_MESSAGES: [],
+        Keys.CONSTELLATIONS: [],
+        Keys.STATION_ID: -1,
+        Keys.BASE_POSITION: [],
+        Keys.LATENCY: -1.0,
+        Keys.AGE: -1.0,
+        Keys.GAPS: 0,
+        Keys.LONGEST_GAP: 0.0,
+        Keys.CRC_ERRORS: 0,
+        Keys.CORRECTION_BYTES: 0,
+    }
+
+
+CORRECTIONS_STATUS: List[Dict[str, Any]] = [corrections_status_update()]
+
+
+class CorrectionsStatusData(QObject):  # pylint: disable=too-many-instance-attributes
+    _instance: "CorrectionsStatusData"
+    _rtcm_messages: List[List[Any]] = []
+    _constellations: List[List[Any]] = []
+    _station_id: int = -1
+    _base_position: List[float] = []
+    _latency: float = -1.0
+    _age: float = -1.0
+    _gaps: int = 0
+    _longest_gap: float = 0.0
+    _crc_errors: int = 0
+    _bytes: float = 0.0
+    _data_updated = Signal()
+    corrections_status: Dict[str, Any] = {}
+
+    def __init__(self):
+        super().__init__()
+        assert getattr(self.__class__, "_instance", None) is None
+        self.__class__._instance = self
+        self.corrections_status = CORRECTIONS_STATUS[0]
+        self._data_updated.connect(self.handle_data_updated)
+
+    @classmethod
+    def post_data_update(cls, update_data: Dict[str, Any]) -> None:
+        CORRECTIONS_STATUS[0] = update_data
+        cls._instance._data_updated.emit()  # pylint: disable=protected-access
+
+    @Slot()  # type: ignore
+    def handle_data_updated(self) -> None:
+        self.corrections_status = CORRECTIONS_STATUS[0]
+        self.update()  # type: ignore
+
+    def get_rtcm_messages(self) -> List[List[Any]]:
+        """Getter for _rtcm_messages."""
+        return self._rtcm_messages
+
+    def set_rtcm_messages(self, rtcm_messages: List[List[Any]]) -> None:
+        """Setter for _rtcm_messages."""
+        self._rtcm_messages = rtcm_messages
+
+    rtcm_messages = Property(QTKeys.QVARIANTLIST, get_rtcm_messages, set_rtcm_messages)  # type: ignore
+
+    def get_constellations(self) -> List[List[Any]]:
+        """Getter for _constellations."""
+        return self._constellations
+
+    def set_constellations(self, constellations: List[List[Any]]) -> None:
+        """Setter for _constellations."""
+        self._constellations = constellations
+
+    constellations = Property(QTKeys.QVARIANTLIST, get_constellations, set_constellations)  # type: ignore
+
+    def get_station_id(self) -> int:
+        """Getter for _station_id."""
+        return self._station_id
+
+    def set_station_id(self, station_id: int) -> None:
+        """Setter for _station_id."""
+        self._station_id = station_id
+
+    station_id = Property(int, get_station_id, set_station_id)
+
+    def get_base_position(self) -> List[float]:
+        """Getter for _base_position."""
+        return self._base_position
+
+    def set_base_position(self, base_position: List[float]) -> None:
+        """Setter for _base_position."""
+        self._base_position = base_position
+
+    base_position = Property(QTKeys.QVARIANTLIST, get_base_position, set_base_position)  # type: ignore
+
+    def get_latency(self) -> float:
+        """Getter for _latency."""
+        return self._latency
+
+    def set_latency(self, latency: float) -> None:
+        """Setter for _latency."""
+        self._latency = latency
+
+    latency = Property(float, get_latency, set_latency)
+
+    def get_age(self) -> float:
+        """Getter for _age."""
+        return self._age
+
+    def set_age(self, age: float) -> None:
+        """Setter for _age."""
+        self._age = age
+
+    age = Property(float, get_age, set_age)
+
+    def get_gaps(self) -> int:
+        """Getter for _gaps."""
+        return self._gaps
+
+    def set_gaps(self, gaps: int) -> None:
+        """Setter for _gaps."""
+        self._gaps = gaps
+
+    gaps = Property(int, get_gaps, set_gaps)
+
+    def get_longest_gap(self) -> float:
+        """Getter for _longest_gap."""
+        return self._longest_gap
+
+    def set_longest_gap(self, longest_gap: float) -> None:
+        """Setter for _longest_gap."""
+        self._longest_gap = longest_gap
+
+    longest_gap = Property(float, get_longest_gap, set_longest_gap)
+
+    def get_crc_errors(self) -> int:
+        """Getter for _crc_errors."""
+        return self._crc_errors
+
+    def set_crc_errors(self, crc_errors: int) -> None:
+        """Setter for _crc_errors."""
+        self._crc_errors = crc_errors
+
+    crc_errors = Property(int, get_crc_errors, set_crc_errors)
+
+    def get_bytes(self) -> float:
+        """Getter for _bytes."""
+        return self._bytes
+
+    def set_bytes(self, bytes_received: float) -> None:
+        """Setter for _bytes."""
+        self._bytes = bytes_received
+
+    bytes = Property(float, get_bytes, set_bytes)
+
+
+class CorrectionsStatusModel(QObject):  # pylint: disable=too-few-public-methods
+    @Slot(CorrectionsStatusData)  # type: ignore
+    def fill_console_points(self, cp: CorrectionsStatusData) -> CorrectionsStatusData:
+        cp.set_rtcm_messages(cp.corrections_status[Keys.RTCM_MESSAGES])
+        cp.set_constellations(cp.corrections_status[Keys.CONSTELLATIONS])
+        cp.set_station_id(cp.corrections_status[Keys.STATION_ID])
+        cp.set_base_position(cp.corrections_status[Keys.BASE_POSITION])
+        cp.set_latency(cp.corrections_status[Keys.LATENCY])
+        cp.set_age(cp.corrections_status[Keys.AGE])
+        cp.set_gaps(cp.corrections_status[Keys.GAPS])
+        cp.set_longest_gap(cp.corrections_status[Keys.LONGEST_GAP])
+        cp.set_crc_errors(cp.corrections_status[Keys.CRC_ERRORS])
+        cp.set_bytes(cp.corrections_status[Keys.CORRECTION_BYTES])
+        return cp
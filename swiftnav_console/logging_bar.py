@@ -26,7 +26,7 @@ import time
 from PySide6.QtCore import Property, QObject, QStringListModel, Signal, Slot, Qt
 from PySide6.QtQml import QmlElement
 
-from .constants import Keys, SbpLogging
+from .constants import CsvLogger, Keys, SbpJsonOption, SbpLogging, TrackFormat
 
 QML_IMPORT_NAME = "SwiftConsole"
 QML_IMPORT_MAJOR_VERSION = 1
@@ -39,6 +39,11 @@ def logging_bar_update() -> Dict[str, Any]:
     return {
         Keys.PREVIOUS_FOLDERS: [],
         Keys.CSV_LOGGING: False,
+        Keys.CSV_LOGGERS: [logger.value for logger in CsvLogger],
+        Keys.TRACK_FORMATS: [],
+        Keys.RINEX_LOGGING: False,
+        Keys.LOG_SCHEDULE: "",
+        Keys.SBP_JSON_OPTIONS: [],
         Keys.SBP_LOGGING: False,
         Keys.SBP_LOGGING_FORMAT: SbpLogging.SBP_JSON,
         Keys.SBP_LOGGING_FORMAT_INDEX: 0,
@@ -74,6 +79,14 @@ class SwiftStringListModel(QStringListModel):
 class LoggingBarData(QObject):  # pylint: disable=too-many-instance-attributes, too-many-public-methods
     _instance: "LoggingBarData"
     _csv_logging: bool = False
+    _csv_loggers: List[str] = [logger.value for logger in CsvLogger]
+    _csv_logger_labels: List[str] = [logger.value for logger in CsvLogger]
+    _track_formats: List[str] = []
+    _track_format_labels: List[str] = [track_format.value for track_format in TrackFormat]
+    _rinex_logging: bool = False
+    _log_schedule: str = ""
+    _sbp_json_options: List[str] = []
+    _sbp_json_option_labels: List[str] = [option.value for option in SbpJsonOption]
     _sbp_logging: bool = False
     _sbp_logging_format: str = SbpLogging.SBP_JSON
     _sbp_logging_format_index: int = 0
@@ -138,6 +151,61 @@ class LoggingBarData(QObject):  # pylint: disable=too-many-instance-attributes,
 
     csv_logging = Property(bool, get_csv_logging, set_csv_logging)
 
+    def get_csv_loggers(self) -> List[str]:
+        return self._csv_loggers
+
+    def set_csv_loggers(self, csv_loggers: List[str]) -> None:
+        self._csv_loggers = csv_loggers
+
+    csv_loggers = Property(list, get_csv_loggers, set_csv_loggers)  # type: ignore
+
+    def get_csv_logger_labels(self) -> List[str]:
+        return self._csv_logger_labels
+
+    csv_logger_labels = Property(list, get_csv_logger_labels, constant=True)  # type: ignore
+
+    def get_track_formats(self) -> List[str]:
+        return self._track_formats
+
+    def set_track_formats(self, track_formats: List[str]) -> None:
+        self._track_formats = track_formats
+
+    track_formats = Property(list, get_track_formats, set_track_formats)  # type: ignore
+
+    def get_track_format_labels(self) -> List[str]:
+        return self._track_format_labels
+
+    track_format_labels = Property(list, get_track_format_labels, constant=True)  # type: ignore
+
+    def get_rinex_logging(self) -> bool:
+        return self._rinex_logging
+
+    def set_rinex_logging(self, rinex_logging: bool) -> None:
+        self._rinex_logging = rinex_logging
+
+    rinex_logging = Property(bool, get_rinex_logging, set_rinex_logging)  # type: ignore
+
+    def get_log_schedule(self) -> str:
+        return self._log_schedule
+
+    def set_log_schedule(self, log_schedule: str) -> None:
+        self._log_schedule = log_schedule
+
+    log_schedule = Property(str, get_log_schedule, set_log_schedule)  # type: ignore
+
+    def get_sbp_json_options(self) -> List[str]:
+        return self._sbp_json_options
+
+    def set_sbp_json_options(self, sbp_json_options: List[str]) -> None:
+        self._sbp_json_options = sbp_json_options
+
+    sbp_json_options = Property(list, get_sbp_json_options, set_sbp_json_options)  # type: ignore
+
+    def get_sbp_json_option_labels(self) -> List[str]:
+        return self._sbp_json_option_labels
+
+    sbp_json_option_labels = Property(list, get_sbp_json_option_labels, constant=True)  # type: ignore
+
     def get_sbp_logging(self) -> bool:
         return self._sbp_logging
 
@@ -215,6 +283,11 @@ class LoggingBarModel(QObject):  # pylint: disable=too-few-public-methods
     @Slot(LoggingBarData)  # type: ignore
     def fill_data(self, cp: LoggingBarData) -> LoggingBarData:
         cp.set_csv_logging(cp.logging_bar[Keys.CSV_LOGGING])
+        cp.set_csv_loggers(cp.logging_bar[Keys.CSV_LOGGERS])
+        cp.set_track_formats(cp.logging_bar[Keys.TRACK_FORMATS])
+        cp.set_rinex_logging(cp.logging_bar[Keys.RINEX_LOGGING])
+        cp.set_log_schedule(cp.logging_bar[Keys.LOG_SCHEDULE])
+        cp.set_sbp_json_options(cp.logging_bar[Keys.SBP_JSON_OPTIONS])
         cp.set_sbp_logging(cp.logging_bar[Keys.SBP_LOGGING])
         cp.set_sbp_logging_format(cp.logging_bar[Keys.SBP_LOGGING_FORMAT])
         cp.set_sbp_logging_format_index(cp.logging_bar[Keys.SBP_LOGGING_FORMAT_INDEX])
@@ -63,7 +63,7 @@ from .backend_importer import BackendImporter
 
 from .backend_request_broker import BackendRequestBroker
 
-from .ntrip_status import NtripStatusData
+from .ntrip_status import NtripServerStatusData, NtripStatusData
 
 from .log_panel import (
     log_panel_update,
@@ -113,6 +113,12 @@ from .advanced_system_monitor_tab import (
     advanced_system_monitor_tab_update,
 )
 
+from .corrections_status import (
+    CorrectionsStatusModel,
+    CorrectionsStatusData,
+    corrections_status_update,
+)
+
 from .fusion_status_flags import (
     FusionStatusFlagsModel,
     FusionStatusFlagsData,
@@ -528,6 +534,11 @@ class BackendMessageReceiver(QObject):  # pylint: disable=too-many-instance-attr
                 data = logging_bar_update()
                 data[Keys.PREVIOUS_FOLDERS][:] = m.loggingBarStatus.previousFolders
                 data[Keys.CSV_LOGGING] = m.loggingBarStatus.csvLogging
+                data[Keys.CSV_LOGGERS][:] = m.loggingBarStatus.csvLoggers
+                data[Keys.TRACK_FORMATS][:] = m.loggingBarStatus.trackFormats
+                data[Keys.RINEX_LOGGING] = m.loggingBarStatus.rinexLogging
+                data[Keys.LOG_SCHEDULE] = m.loggingBarStatus.logSchedule
+                data[Keys.SBP_JSON_OPTIONS][:] = m.loggingBarStatus.sbpJsonOptions
                 data[Keys.SBP_LOGGING] = m.loggingBarStatus.sbpLogging
                 data[Keys.SBP_LOGGING_FORMAT] = m.loggingBarStatus.sbpLoggingFormat
                 LoggingBarData.post_data_update(data)
@@ -573,6 +584,60 @@ class BackendMessageReceiver(QObject):  # pylint: disable=too-many-instance-attr
                 SettingsTabData.post_import_status_update(m.settingsImportResponse.status)
             elif m.which == Message.Union.SettingsNotification:
                 SettingsTabData.post_notification_update(m.settingsNotification.message)
+            elif m.which == Message.Union.CorrectionsStatus:
+                status = m.correctionsStatus
+                data = corrections_status_update()
+                data[Keys.RTCM_MESSAGES][:] = [
+                    [entry.messageType, entry.count, entry.rate, entry.age] for entry in status.messages
+                ]
+                data[Keys.CONSTELLATIONS][:] = [
+                    [entry.name, entry.msm, entry.satellites, entry.signals, entry.age]
+                    for entry in status.constellations
+                ]
+                data[Keys.STATION_ID] = status.stationId
+                if status.basePosition.which() == "pos":
+                    pos = status.basePosition.pos
+                    data[Keys.BASE_POSITION][:] = [pos.lat, pos.lon, pos.alt]
+                data[Keys.LATENCY] = status.latency
+                data[Keys.AGE] = status.age
+                data[Keys.GAPS] = status.gaps
+                data[Keys.LONGEST_GAP] = status.longestGap
+                data[Keys.CRC_ERRORS] = status.crcErrors
+                data[Keys.CORRECTION_BYTES] = status.bytes
+                CorrectionsStatusData.post_data_update(data)
+            elif m.which == Message.Union.NtripConnectionState:
+                NtripStatusData.post_connection_state(m.ntripConnectionState.state, m.ntripConnectionState.detail)
+            elif m.which == Message.Union.CredentialsStatus:
+                NtripStatusData.post_credentials_status(m.credentialsStatus.unlocked, m.credentialsStatus.detail)
+            elif m.which == Message.Union.NtripServerStatus:
+                status = m.ntripServerStatus
+                NtripServerStatusData.post_status(
+                    {
+                        "state": status.state,
+                        "detail": status.detail,
+                        "bytesSent": status.bytesSent,
+                        "messagesSent": status.messagesSent,
+                        "dropped": status.dropped,
+                        "rate": status.rate,
+                        "reconnects": status.reconnects,
+                        "uptime": status.uptime,
+                    }
+                )
+            elif m.which == Message.Union.NtripSourcetable:
+                mountpoints = [
+                    {
+                        "name": entry.name,
+                        "identifier": entry.identifier,
+                        "format": entry.format,
+                        "navSystems": entry.navSystems,
+                        "lat": entry.lat,
+                        "lon": entry.lon,
+                        "nmea": entry.nmea,
+                        "distance": entry.distance,
+                    }
+                    for entry in m.ntripSourcetable.mountpoints
+                ]
+                NtripStatusData.post_sourcetable(mountpoints, m.ntripSourcetable.selected, m.ntripSourcetable.error)
             elif m.which == Message.Union.InsSettingsChangeResponse:
                 data = settings_ins_update()
                 data[Keys.RECOMMENDED_INS_SETTINGS][:] = [
@@ -809,6 +874,7 @@ def main(passed_args: Optional[Tuple[str, ...]] = None) -> int:
         AdvancedSpectrumAnalyzerPoints, "SwiftConsole", 1, 0, "AdvancedSpectrumAnalyzerPoints"  # type: ignore
     )
     qmlRegisterType(AdvancedSystemMonitorData, "SwiftConsole", 1, 0, "AdvancedSystemMonitorData")  # type: ignore
+    qmlRegisterType(CorrectionsStatusData, "SwiftConsole", 1, 0, "CorrectionsStatusData")  # type: ignore
     qmlRegisterType(FusionStatusFlagsData, "SwiftConsole", 1, 0, "FusionStatusFlagsData")  # type: ignore
     qmlRegisterType(BaselinePlotPoints, "SwiftConsole", 1, 0, "BaselinePlotPoints")  # type: ignore
     qmlRegisterType(BaselineTableEntries, "SwiftConsole", 1, 0, "BaselineTableEntries")  # type: ignore
@@ -820,6 +886,7 @@ def main(passed_args: Optional[Tuple[str, ...]] = None) -> int:
     qmlRegisterType(SolutionVelocityPoints, "SwiftConsole", 1, 0, "SolutionVelocityPoints")  # type: ignore
     qmlRegisterType(StatusBarData, "SwiftConsole", 1, 0, "StatusBarData")  # type: ignore
     qmlRegisterType(NtripStatusData, "SwiftConsole", 1, 0, "NtripStatusData")  # type: ignore
+    qmlRegisterType(NtripServerStatusData, "SwiftConsole", 1, 0, "NtripServerStatusData")  # type: ignore
     qmlRegisterType(TrackingSignalsPoints, "SwiftConsole", 1, 0, "TrackingSignalsPoints")  # type: ignore
     qmlRegisterType(TrackingSkyPlotPoints, "SwiftConsole", 1, 0, "TrackingSkyPlotPoints")  # type: ignore
     qmlRegisterType(ObservationRemoteTableModel, "SwiftConsole", 1, 0, "ObservationRemoteTableModel")  # type: ignore
@@ -867,6 +934,7 @@ def main(passed_args: Optional[Tuple[str, ...]] = None) -> int:
     advanced_networking_model = AdvancedNetworkingModel()
     advanced_spectrum_analyzer_model = AdvancedSpectrumAnalyzerModel()
     advanced_system_monitor_model = AdvancedSystemMonitorModel()
+    corrections_status_model = CorrectionsStatusModel()
     fusion_engine_flags_model = FusionStatusFlagsModel()
     baseline_plot_model = BaselinePlotModel()
     baseline_table_model = BaselineTableModel()
@@ -885,6 +953,7 @@ def main(passed_args: Optional[Tuple[str, ...]] = None) -> int:
     root_context.setContextProperty("advanced_networking_model", advanced_networking_model)
     root_context.setContextProperty("advanced_spectrum_analyzer_model", advanced_spectrum_analyzer_model)
     root_context.setContextProperty("advanced_system_monitor_model", advanced_system_monitor_model)
+    root_context.setContextProperty("corrections_status_model", corrections_status_model)
     root_context.setContextProperty("fusion_engine_flags_model", fusion_engine_flags_model)
     root_context.setContextProperty("baseline_plot_model", baseline_plot_model)
     root_context.setContextProperty("baseline_table_model", baseline_table_model)
@@ -7,6 +7,9 @@ from PySide6.QtCore import QObject, SignalInstance
 class NtripStatusData(QObject):  # pylint: disable=too-many-instance-attributes
     _instance: "NtripStatusData"
     ntrip_connected: SignalInstance
+    ntrip_sourcetable: SignalInstance
+    ntrip_connection_state: SignalInstance
+    credentials_status: SignalInstance
 
     def __init__(self):
         super().__init__()
@@ -16,3 +19,29 @@ class NtripStatusData(QObject):  # pylint: disable=too-many-instance-attributes
     @classmethod
     def post_connected(cls, connected: bool) -> None:
         cls._instance.ntrip_connected.emit(connected)
+
+    @classmethod
+    def post_connection_state(cls, state: str, detail: str) -> None:
+        cls._instance.ntrip_connection_state.emit(state, detail)
+
+    @classmethod
+    def post_sourcetable(cls, mountpoints: list, selected: str, error: str) -> None:
+        cls._instance.ntrip_sourcetable.emit(mountpoints, selected, error)
+
+    @classmethod
+    def post_credentials_status(cls, unlocked: bool, detail: str) -> None:
+        cls._instance.credentials_status.emit(unlocked, detail)
+
+
+class NtripServerStatusData(QObject):
+    _instance: "NtripServerStatusData"
+    ntrip_server_status: SignalInstance
+
+    def __init__(self):
+        super().__init__()
+        assert getattr(self.__class__, "_instance", None) is None
+        self.__class__._instance = self
+
+    @classmethod
+    def post_status(cls, status: dict) -> None:
+        cls._instance.ntrip_server_status.emit(status)